@@ -0,0 +1,905 @@
+//! Local price alerts
+//!
+//! Alerts evaluated in the backend against the live price stream, so they
+//! fire while the window is hidden and without waiting for the frontend's
+//! 60s poll of server alerts. Stored in `alerts.json` in the app data
+//! directory and delivered as desktop notifications.
+//!
+//! `above` and `below` fire when the condition becomes true and re-arm once
+//! it is false again; `crosses` fires when consecutive prices fall on
+//! opposite sides of the target. The last price is not persisted, so no
+//! crossing is inferred across a restart.
+//!
+//! Alerts can instead carry a compound [`rules`] expression. Those are
+//! evaluated every few seconds against data fetched from Haunt and behave
+//! like `above`/`below`: they fire when the rule becomes true.
+
+pub mod rules;
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{Emitter, Manager, State};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::haunt::models::AlertCondition;
+use crate::haunt::socket::WsMessage;
+use crate::haunt::{HauntClient, HauntSocket};
+use crate::notifications::{self, Notification, NotificationCategory};
+use crate::{new_id, now_millis};
+use rules::{Rule, RuleError, Snapshot, Source};
+
+const ALERTS_FILE: &str = "alerts.json";
+
+/// Event carrying a [`TriggeredAlert`] when a local alert fires
+pub const TRIGGERED_EVENT: &str = "local-alert-triggered";
+
+const DEFAULT_COOLDOWN_SECS: u64 = 5 * 60;
+/// How often rule alerts are re-evaluated
+const RULE_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Whether an alert stays active after firing
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertMode {
+    /// Disable after the first trigger
+    #[default]
+    Once,
+    /// Keep firing, at most once per cooldown
+    Repeat,
+}
+
+/// What an alert watches
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum AlertTrigger {
+    /// A price threshold checked on every price update
+    Price {
+        condition: AlertCondition,
+        #[serde(rename = "targetPrice")]
+        target_price: f64,
+    },
+    /// A [`rules`] expression
+    Rule { rule: String },
+}
+
+/// A locally evaluated alert
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalAlert {
+    pub id: String,
+    /// Lowercase, as on the price stream
+    pub symbol: String,
+    #[serde(flatten)]
+    pub trigger: AlertTrigger,
+    pub mode: AlertMode,
+    pub cooldown_secs: u64,
+    pub enabled: bool,
+    pub created_at: i64,
+    pub expires_at: Option<i64>,
+    pub triggered_at: Option<i64>,
+    #[serde(default)]
+    pub trigger_count: u32,
+    /// `above`/`below` only fire again after the condition has been false
+    #[serde(default = "armed_default")]
+    armed: bool,
+    #[serde(skip)]
+    last_price: Option<f64>,
+    /// Parsed form of a rule trigger, with its timers
+    #[serde(skip)]
+    compiled: Option<Rule>,
+}
+
+fn armed_default() -> bool {
+    true
+}
+
+/// Request to create a local alert
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateLocalAlert {
+    pub symbol: String,
+    #[serde(flatten)]
+    pub trigger: AlertTrigger,
+    #[serde(default)]
+    pub mode: AlertMode,
+    pub cooldown_secs: Option<u64>,
+    pub expires_at: Option<i64>,
+}
+
+/// Payload of [`TRIGGERED_EVENT`]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TriggeredAlert {
+    pub alert: LocalAlert,
+    /// Latest known price, if the trigger had one
+    pub price: Option<f64>,
+}
+
+impl LocalAlert {
+    fn new(request: CreateLocalAlert, id: String, now: i64) -> Result<Self, String> {
+        let symbol = request.symbol.trim().to_lowercase();
+        if symbol.is_empty() {
+            return Err("Alert symbol is required".to_string());
+        }
+        let compiled = match &request.trigger {
+            AlertTrigger::Price { target_price, .. } => {
+                if !target_price.is_finite() || *target_price <= 0.0 {
+                    return Err("Alert target price must be a positive number".to_string());
+                }
+                None
+            }
+            AlertTrigger::Rule { rule } => Some(Rule::parse(rule).map_err(|e| e.to_string())?),
+        };
+
+        Ok(Self {
+            id,
+            symbol,
+            trigger: request.trigger,
+            mode: request.mode,
+            cooldown_secs: request.cooldown_secs.unwrap_or(DEFAULT_COOLDOWN_SECS),
+            enabled: true,
+            created_at: now,
+            expires_at: request.expires_at,
+            triggered_at: None,
+            trigger_count: 0,
+            armed: true,
+            last_price: None,
+            compiled,
+        })
+    }
+
+    fn active(&self, now: i64) -> bool {
+        self.enabled && !self.expires_at.is_some_and(|at| now >= at)
+    }
+
+    fn cooling_down(&self, now: i64) -> bool {
+        let cooldown = self.cooldown_secs.saturating_mul(1000) as i64;
+        self.triggered_at
+            .is_some_and(|at| now.saturating_sub(at) < cooldown)
+    }
+
+    /// Feed a price; true if a price trigger fires
+    fn evaluate_price(&mut self, price: f64, now: i64) -> bool {
+        let previous = self.last_price.replace(price);
+        let AlertTrigger::Price {
+            condition,
+            target_price: target,
+        } = self.trigger
+        else {
+            return false;
+        };
+        if !self.active(now) {
+            return false;
+        }
+
+        match condition {
+            AlertCondition::Above => self.level(Some(price >= target), now),
+            AlertCondition::Below => self.level(Some(price <= target), now),
+            AlertCondition::Crosses => {
+                let crossed = previous.is_some_and(|previous| {
+                    (previous < target && price >= target) || (previous > target && price <= target)
+                });
+                // An edge inside the cooldown is dropped
+                crossed && self.fire(now)
+            }
+        }
+    }
+
+    /// Feed fetched market data; true if a rule trigger fires
+    fn evaluate_rule(&mut self, snapshot: &Snapshot, now: i64) -> bool {
+        if !self.active(now) {
+            return false;
+        }
+        let Some(rule) = self.compiled.as_mut() else {
+            return false;
+        };
+        let holds = rule.evaluate(snapshot, now);
+        self.level(holds, now)
+    }
+
+    /// Fire when a condition holds and the alert is armed; re-arm once it
+    /// is false. Armed alerts stay armed through a cooldown and fire when
+    /// it ends. Unknown leaves the arming alone.
+    fn level(&mut self, holds: Option<bool>, now: i64) -> bool {
+        match holds {
+            Some(true) if self.armed => self.fire(now),
+            Some(false) => {
+                self.armed = true;
+                false
+            }
+            _ => false,
+        }
+    }
+
+    fn fire(&mut self, now: i64) -> bool {
+        if self.cooling_down(now) {
+            return false;
+        }
+        self.armed = false;
+        self.triggered_at = Some(now);
+        self.trigger_count += 1;
+        if self.mode == AlertMode::Once {
+            self.enabled = false;
+        }
+        true
+    }
+}
+
+/// Local alerts and their evaluation
+#[derive(Debug, Default)]
+pub struct AlertEngine {
+    alerts: Vec<LocalAlert>,
+}
+
+impl AlertEngine {
+    pub fn alerts(&self) -> &[LocalAlert] {
+        &self.alerts
+    }
+
+    /// Replace the alerts, compiling rule triggers; alerts whose rule no
+    /// longer parses are disabled
+    fn load(&mut self, mut alerts: Vec<LocalAlert>) {
+        for alert in &mut alerts {
+            let AlertTrigger::Rule { rule } = &alert.trigger else {
+                continue;
+            };
+            match Rule::parse(rule) {
+                Ok(rule) => alert.compiled = Some(rule),
+                Err(e) => {
+                    eprintln!("Disabling alert {} with invalid rule: {}", alert.id, e);
+                    alert.enabled = false;
+                }
+            }
+        }
+        self.alerts = alerts;
+    }
+
+    /// Symbols that enabled price alerts need streamed prices for
+    pub fn symbols(&self) -> BTreeSet<String> {
+        self.alerts
+            .iter()
+            .filter(|alert| alert.enabled && matches!(alert.trigger, AlertTrigger::Price { .. }))
+            .map(|alert| alert.symbol.clone())
+            .collect()
+    }
+
+    /// Data enabled rule alerts need, by symbol
+    pub fn rule_sources(&self) -> HashMap<String, HashSet<Source>> {
+        let mut sources: HashMap<String, HashSet<Source>> = HashMap::new();
+        for alert in self.alerts.iter().filter(|alert| alert.enabled) {
+            if let Some(rule) = &alert.compiled {
+                sources
+                    .entry(alert.symbol.clone())
+                    .or_default()
+                    .extend(rule.sources());
+            }
+        }
+        sources
+    }
+
+    /// Feed a price from the stream; returns the alerts that fired
+    pub fn on_price(&mut self, symbol: &str, price: f64, now: i64) -> Vec<TriggeredAlert> {
+        let symbol = symbol.to_lowercase();
+        self.alerts
+            .iter_mut()
+            .filter(|alert| alert.symbol == symbol)
+            .filter_map(|alert| {
+                alert.evaluate_price(price, now).then(|| TriggeredAlert {
+                    alert: alert.clone(),
+                    price: Some(price),
+                })
+            })
+            .collect()
+    }
+
+    /// Feed fetched data for a symbol; returns the rule alerts that fired
+    pub fn on_snapshot(
+        &mut self,
+        symbol: &str,
+        snapshot: &Snapshot,
+        now: i64,
+    ) -> Vec<TriggeredAlert> {
+        self.alerts
+            .iter_mut()
+            .filter(|alert| alert.symbol == symbol)
+            .filter_map(|alert| {
+                alert.evaluate_rule(snapshot, now).then(|| TriggeredAlert {
+                    alert: alert.clone(),
+                    price: snapshot.asset.as_ref().map(|asset| asset.price),
+                })
+            })
+            .collect()
+    }
+
+    pub fn create(&mut self, request: CreateLocalAlert, now: i64) -> Result<LocalAlert, String> {
+        let alert = LocalAlert::new(request, new_id(), now)?;
+        self.alerts.push(alert.clone());
+        Ok(alert)
+    }
+
+    pub fn delete(&mut self, id: &str) -> Result<(), String> {
+        let before = self.alerts.len();
+        self.alerts.retain(|alert| alert.id != id);
+        if self.alerts.len() == before {
+            return Err(format!("No alert with id {}", id));
+        }
+        Ok(())
+    }
+
+    /// Enable or disable an alert; enabling re-arms it
+    pub fn set_enabled(&mut self, id: &str, enabled: bool) -> Result<LocalAlert, String> {
+        let alert = self
+            .alerts
+            .iter_mut()
+            .find(|alert| alert.id == id)
+            .ok_or_else(|| format!("No alert with id {}", id))?;
+        if enabled && !alert.enabled {
+            alert.armed = true;
+            alert.triggered_at = None;
+        }
+        alert.enabled = enabled;
+        Ok(alert.clone())
+    }
+}
+
+/// `$1,234.56`, with more decimals for small prices
+pub(crate) fn format_price(price: f64) -> String {
+    let decimals = if price.abs() >= 1.0 {
+        2
+    } else if price.abs() >= 0.01 {
+        4
+    } else {
+        8
+    };
+    let fixed = format!("{:.*}", decimals, price.abs());
+    let (whole, fraction) = fixed.split_once('.').unwrap_or((&fixed, ""));
+
+    let mut grouped = String::new();
+    for (i, digit) in whole.chars().enumerate() {
+        if i > 0 && (whole.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    let sign = if price < 0.0 { "-" } else { "" };
+    format!("{}${}.{}", sign, grouped, fraction)
+}
+
+fn notification_text(triggered: &TriggeredAlert) -> (String, String) {
+    let alert = &triggered.alert;
+    let symbol = alert.symbol.to_uppercase();
+    let title = match &alert.trigger {
+        AlertTrigger::Price {
+            condition,
+            target_price,
+        } => {
+            let verb = match condition {
+                AlertCondition::Above => "above",
+                AlertCondition::Below => "below",
+                AlertCondition::Crosses => "crossed",
+            };
+            format!("{} {} {}", symbol, verb, format_price(*target_price))
+        }
+        AlertTrigger::Rule { rule } => format!("{}: {}", symbol, rule),
+    };
+    let body = match triggered.price {
+        Some(price) => format!("{} is at {}", symbol, format_price(price)),
+        None => format!("{} alert rule matched", symbol),
+    };
+    (title, body)
+}
+
+/// Fetch the data a rule reads; anything that fails stays unknown
+async fn fetch_snapshot(client: &HauntClient, symbol: &str, sources: &HashSet<Source>) -> Snapshot {
+    let mut snapshot = Snapshot::default();
+    for source in sources {
+        match source {
+            Source::Asset => {
+                snapshot.asset = client.search(symbol, 10).await.ok().and_then(|response| {
+                    response
+                        .data
+                        .into_iter()
+                        .find(|asset| asset.symbol.eq_ignore_ascii_case(symbol))
+                });
+            }
+            Source::OrderBook => {
+                snapshot.book = client
+                    .get_order_book(symbol, 20)
+                    .await
+                    .ok()
+                    .map(|response| response.data);
+            }
+            Source::Funding => {
+                snapshot.funding = client
+                    .get_funding_rates(&[symbol.to_string()])
+                    .await
+                    .ok()
+                    .and_then(|response| {
+                        response
+                            .data
+                            .into_iter()
+                            .find(|rate| rate.symbol.eq_ignore_ascii_case(symbol))
+                    });
+            }
+            Source::Signals(timeframe) => {
+                if let Ok(response) = client.get_signals(symbol, *timeframe).await {
+                    snapshot.signals.push(response.data);
+                }
+            }
+        }
+    }
+    snapshot
+}
+
+/// Evaluate every rule alert against freshly fetched data
+async fn poll_rules(app: &tauri::AppHandle) {
+    let sources = app
+        .state::<AlertState>()
+        .engine
+        .lock()
+        .unwrap()
+        .rule_sources();
+    let mut triggered = Vec::new();
+    for (symbol, sources) in sources {
+        let snapshot = fetch_snapshot(&app.state::<HauntClient>(), &symbol, &sources).await;
+        let state = app.state::<AlertState>();
+        let mut engine = state.engine.lock().unwrap();
+        triggered.extend(engine.on_snapshot(&symbol, &snapshot, now_millis()));
+    }
+
+    if !triggered.is_empty() {
+        for alert in &triggered {
+            deliver(app, alert);
+        }
+        app.state::<AlertState>().changed(app);
+    }
+}
+
+/// Managed state for local alerts
+#[derive(Default)]
+pub struct AlertState {
+    engine: Mutex<AlertEngine>,
+    data_dir: Mutex<Option<PathBuf>>,
+    /// Symbols this module holds socket subscriptions for
+    subscribed: Mutex<BTreeSet<String>>,
+}
+
+impl AlertState {
+    fn save(&self) {
+        let Some(dir) = self.data_dir.lock().unwrap().clone() else {
+            return;
+        };
+        let alerts = self.engine.lock().unwrap().alerts().to_vec();
+        let written = fs::create_dir_all(&dir).and_then(|_| {
+            let json = serde_json::to_vec_pretty(&alerts)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+            fs::write(dir.join(ALERTS_FILE), json)
+        });
+        if let Err(e) = written {
+            eprintln!("Failed to save alerts: {}", e);
+        }
+    }
+
+    /// Hold socket subscriptions for exactly the symbols enabled alerts need
+    fn sync_subscriptions(&self, socket: &HauntSocket) {
+        let wanted = self.engine.lock().unwrap().symbols();
+        let mut subscribed = self.subscribed.lock().unwrap();
+
+        let added: Vec<String> = wanted.difference(&subscribed).cloned().collect();
+        let removed: Vec<String> = subscribed.difference(&wanted).cloned().collect();
+        if !added.is_empty() {
+            socket.subscribe(&added);
+        }
+        if !removed.is_empty() {
+            socket.unsubscribe(Some(&removed));
+        }
+        *subscribed = wanted;
+    }
+
+    /// Persist and resubscribe after the alert list changed
+    fn changed(&self, app: &tauri::AppHandle) {
+        self.save();
+        self.sync_subscriptions(&app.state::<HauntSocket>());
+    }
+}
+
+fn deliver(app: &tauri::AppHandle, triggered: &TriggeredAlert) {
+    let (title, body) = notification_text(triggered);
+    let notification = Notification {
+        category: NotificationCategory::Alert,
+        target: Some(format!(
+            "wraith://asset/{}",
+            triggered.alert.symbol.to_uppercase()
+        )),
+        ..Notification::new(title, body)
+    };
+    if let Err(e) = notifications::show(app, notification) {
+        eprintln!("Failed to show alert notification: {}", e);
+    }
+    let _ = app.emit(TRIGGERED_EVENT, triggered);
+}
+
+/// Load saved alerts and start evaluating them against the price stream
+pub fn init(app: &tauri::AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+    let data_dir = app.path().app_data_dir()?;
+    let alerts = match fs::read(data_dir.join(ALERTS_FILE)) {
+        Ok(bytes) => serde_json::from_slice(&bytes)?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(e.into()),
+    };
+
+    let state = app.state::<AlertState>();
+    state.engine.lock().unwrap().load(alerts);
+    *state.data_dir.lock().unwrap() = Some(data_dir);
+    state.sync_subscriptions(&app.state::<HauntSocket>());
+
+    let mut messages = app.state::<HauntSocket>().messages();
+    let handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let state = handle.state::<AlertState>();
+        loop {
+            let message = match messages.recv().await {
+                Ok(message) => message,
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            };
+            let WsMessage::PriceUpdate { data } = &*message else {
+                continue;
+            };
+
+            let triggered =
+                state
+                    .engine
+                    .lock()
+                    .unwrap()
+                    .on_price(&data.symbol, data.price, now_millis());
+            if triggered.is_empty() {
+                continue;
+            }
+            for alert in &triggered {
+                deliver(&handle, alert);
+            }
+            state.changed(&handle);
+        }
+    });
+
+    let handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(RULE_POLL_INTERVAL);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            poll_rules(&handle).await;
+        }
+    });
+
+    Ok(())
+}
+
+// ========== Commands ==========
+
+/// Tauri command: List local alerts
+#[tauri::command]
+pub fn local_alerts_list(state: State<'_, AlertState>) -> Vec<LocalAlert> {
+    state.engine.lock().unwrap().alerts().to_vec()
+}
+
+/// Tauri command: Create a local alert
+#[tauri::command]
+pub fn local_alerts_create(
+    app: tauri::AppHandle,
+    state: State<'_, AlertState>,
+    request: CreateLocalAlert,
+) -> Result<LocalAlert, String> {
+    let alert = state.engine.lock().unwrap().create(request, now_millis())?;
+    state.changed(&app);
+    Ok(alert)
+}
+
+/// Tauri command: Delete a local alert
+#[tauri::command]
+pub fn local_alerts_delete(
+    app: tauri::AppHandle,
+    state: State<'_, AlertState>,
+    id: String,
+) -> Result<(), String> {
+    state.engine.lock().unwrap().delete(&id)?;
+    state.changed(&app);
+    Ok(())
+}
+
+/// Tauri command: Enable or disable a local alert
+#[tauri::command]
+pub fn local_alerts_set_enabled(
+    app: tauri::AppHandle,
+    state: State<'_, AlertState>,
+    id: String,
+    enabled: bool,
+) -> Result<LocalAlert, String> {
+    let alert = state.engine.lock().unwrap().set_enabled(&id, enabled)?;
+    state.changed(&app);
+    Ok(alert)
+}
+
+/// Tauri command: Check a rule expression; `null` when it is valid
+#[tauri::command]
+pub fn local_alerts_check_rule(rule: String) -> Option<RuleError> {
+    Rule::parse(&rule).err()
+}
+
+/// Tauri command: List the fields rule expressions can use
+#[tauri::command]
+pub fn local_alerts_rule_fields() -> Vec<String> {
+    rules::field_names()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINUTE: i64 = 60 * 1000;
+
+    fn single(condition: AlertCondition, mode: AlertMode, cooldown_secs: u64) -> AlertEngine {
+        let mut engine = AlertEngine::default();
+        engine
+            .create(
+                CreateLocalAlert {
+                    symbol: "BTC".to_string(),
+                    trigger: AlertTrigger::Price {
+                        condition,
+                        target_price: 100.0,
+                    },
+                    mode,
+                    cooldown_secs: Some(cooldown_secs),
+                    expires_at: None,
+                },
+                0,
+            )
+            .unwrap();
+        engine
+    }
+
+    /// Feed prices a minute apart; returns the minutes at which it fired
+    fn fires(engine: &mut AlertEngine, prices: &[f64]) -> Vec<i64> {
+        prices
+            .iter()
+            .enumerate()
+            .filter(|(minute, price)| {
+                !engine
+                    .on_price("btc", **price, *minute as i64 * MINUTE)
+                    .is_empty()
+            })
+            .map(|(minute, _)| minute as i64)
+            .collect()
+    }
+
+    #[test]
+    fn crosses_fires_on_edges_only() {
+        let mut engine = single(AlertCondition::Crosses, AlertMode::Repeat, 0);
+        // The first price has nothing to cross from; landing on the target
+        // counts as crossing it, moving off it again does not
+        let prices = [105.0, 101.0, 99.0, 98.0, 100.0, 100.0, 102.0, 101.0];
+        assert_eq!(fires(&mut engine, &prices), [2, 4]);
+    }
+
+    #[test]
+    fn level_conditions_rearm_after_clearing() {
+        let mut engine = single(AlertCondition::Above, AlertMode::Repeat, 0);
+        let prices = [101.0, 102.0, 99.0, 100.0, 100.5];
+        assert_eq!(fires(&mut engine, &prices), [0, 3]);
+
+        let mut engine = single(AlertCondition::Below, AlertMode::Repeat, 0);
+        assert_eq!(fires(&mut engine, &[101.0, 99.0, 98.0]), [1]);
+    }
+
+    #[test]
+    fn cooldown_delays_level_and_drops_edges() {
+        // 3 minute cooldown: the crossing at minute 2 is lost, the one at 4
+        // is not
+        let mut engine = single(AlertCondition::Crosses, AlertMode::Repeat, 180);
+        let prices = [99.0, 101.0, 99.0, 98.0, 101.0];
+        assert_eq!(fires(&mut engine, &prices), [1, 4]);
+
+        // A level condition re-armed during the cooldown fires when it ends
+        let mut engine = single(AlertCondition::Above, AlertMode::Repeat, 180);
+        let prices = [101.0, 99.0, 101.0, 101.0, 101.0, 101.0];
+        assert_eq!(fires(&mut engine, &prices), [0, 3]);
+    }
+
+    #[test]
+    fn one_shot_disables_and_re_enable_rearms() {
+        let mut engine = single(AlertCondition::Above, AlertMode::Once, 0);
+        assert_eq!(fires(&mut engine, &[101.0, 99.0, 101.0]), [0]);
+        assert!(!engine.alerts()[0].enabled);
+        assert_eq!(engine.alerts()[0].trigger_count, 1);
+        assert!(engine.symbols().is_empty());
+
+        let id = engine.alerts()[0].id.clone();
+        engine.set_enabled(&id, true).unwrap();
+        assert_eq!(fires(&mut engine, &[101.0]), [0]);
+    }
+
+    #[test]
+    fn expired_alerts_stay_quiet() {
+        let mut engine = single(AlertCondition::Above, AlertMode::Repeat, 0);
+        engine.alerts[0].expires_at = Some(MINUTE);
+        assert_eq!(fires(&mut engine, &[99.0, 101.0]), Vec::<i64>::new());
+    }
+
+    #[test]
+    fn other_symbols_are_ignored() {
+        let mut engine = single(AlertCondition::Above, AlertMode::Repeat, 0);
+        assert!(engine.on_price("eth", 500.0, 0).is_empty());
+        assert_eq!(engine.on_price("BTC", 500.0, 0).len(), 1);
+    }
+
+    #[test]
+    fn persists_state_but_not_last_price() {
+        let mut engine = single(AlertCondition::Above, AlertMode::Repeat, 0);
+        engine.on_price("btc", 101.0, 0);
+
+        let json = serde_json::to_string(&engine.alerts).unwrap();
+        assert!(!json.contains("lastPrice"));
+        let restored: Vec<LocalAlert> = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored[0].triggered_at, Some(0));
+        assert!(!restored[0].armed);
+        assert_eq!(restored[0].last_price, None);
+    }
+
+    #[test]
+    fn validates_new_alerts() {
+        let mut engine = AlertEngine::default();
+        let request = |symbol: &str, target_price: f64| CreateLocalAlert {
+            symbol: symbol.to_string(),
+            trigger: AlertTrigger::Price {
+                condition: AlertCondition::Above,
+                target_price,
+            },
+            mode: AlertMode::Once,
+            cooldown_secs: None,
+            expires_at: None,
+        };
+        assert!(engine.create(request(" ", 1.0), 0).is_err());
+        assert!(engine.create(request("btc", f64::NAN), 0).is_err());
+        assert!(engine.create(request("btc", -1.0), 0).is_err());
+        let alert = engine.create(request(" Eth ", 1.0), 0).unwrap();
+        assert_eq!(alert.symbol, "eth");
+        assert_eq!(alert.cooldown_secs, DEFAULT_COOLDOWN_SECS);
+
+        let rule = CreateLocalAlert {
+            trigger: AlertTrigger::Rule {
+                rule: "price >".to_string(),
+            },
+            ..request("btc", 1.0)
+        };
+        assert_eq!(
+            engine.create(rule, 0).unwrap_err(),
+            "Expected a field or number at the end (column 8)"
+        );
+    }
+
+    fn rule_engine(rule: &str) -> AlertEngine {
+        let mut engine = AlertEngine::default();
+        let request: CreateLocalAlert = serde_json::from_value(serde_json::json!({
+            "symbol": "btc",
+            "rule": rule,
+            "mode": "repeat",
+            "cooldownSecs": 0,
+        }))
+        .unwrap();
+        engine.create(request, 0).unwrap();
+        engine
+    }
+
+    fn funding(rate: Option<f64>) -> Snapshot {
+        Snapshot {
+            funding: rate.map(|rate| crate::haunt::models::FundingRate {
+                symbol: "BTC".to_string(),
+                rate,
+                next_funding_time: 0,
+                predicted_rate: None,
+                interval: 8.0,
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn rule_alerts_fire_when_the_rule_becomes_true() {
+        let mut engine = rule_engine("funding.rate > 0.01%");
+        assert!(engine.symbols().is_empty());
+        assert_eq!(
+            engine.rule_sources()["btc"],
+            HashSet::from([Source::Funding])
+        );
+        // Price updates never trigger rule alerts
+        assert!(engine.on_price("btc", 1e9, 0).is_empty());
+
+        let fired = |engine: &mut AlertEngine, rate| {
+            !engine.on_snapshot("btc", &funding(rate), 0).is_empty()
+        };
+        assert!(fired(&mut engine, Some(0.0002)));
+        assert!(!fired(&mut engine, Some(0.0003)));
+        // Unknown data neither fires nor re-arms
+        assert!(!fired(&mut engine, None));
+        assert!(!fired(&mut engine, Some(0.0003)));
+        assert!(!fired(&mut engine, Some(0.0)));
+        assert!(fired(&mut engine, Some(0.0002)));
+
+        let triggered = engine.on_snapshot("eth", &funding(Some(1.0)), 0);
+        assert!(triggered.is_empty());
+    }
+
+    #[test]
+    fn triggers_round_trip_and_bad_rules_load_disabled() {
+        let mut engine = rule_engine("book.imbalance > 0.5 FOR 30s");
+        engine
+            .create(
+                CreateLocalAlert {
+                    symbol: "eth".to_string(),
+                    trigger: AlertTrigger::Price {
+                        condition: AlertCondition::Below,
+                        target_price: 10.0,
+                    },
+                    mode: AlertMode::Once,
+                    cooldown_secs: None,
+                    expires_at: None,
+                },
+                0,
+            )
+            .unwrap();
+
+        let json = serde_json::to_value(engine.alerts()).unwrap();
+        assert_eq!(json[0]["rule"], "book.imbalance > 0.5 FOR 30s");
+        assert_eq!(json[1]["condition"], "below");
+        assert_eq!(json[1]["targetPrice"], 10.0);
+
+        let mut alerts: Vec<LocalAlert> = serde_json::from_value(json).unwrap();
+        alerts[0].trigger = AlertTrigger::Rule {
+            rule: "book.imbalance >".to_string(),
+        };
+        let mut restored = AlertEngine::default();
+        restored.load(alerts);
+        assert!(!restored.alerts()[0].enabled);
+        assert!(restored.alerts()[1].enabled);
+        assert!(restored.rule_sources().is_empty());
+        assert_eq!(restored.symbols(), BTreeSet::from(["eth".to_string()]));
+    }
+
+    #[test]
+    fn formats_notifications() {
+        assert_eq!(format_price(65_000.0), "$65,000.00");
+        assert_eq!(format_price(1_234_567.891), "$1,234,567.89");
+        assert_eq!(format_price(0.5), "$0.5000");
+        assert_eq!(format_price(0.000_012_34), "$0.00001234");
+
+        let mut engine = single(AlertCondition::Crosses, AlertMode::Once, 0);
+        engine.on_price("btc", 99.0, 0);
+        let triggered = engine.on_price("btc", 100.25, 1).pop().unwrap();
+        assert_eq!(
+            notification_text(&triggered),
+            (
+                "BTC crossed $100.00".to_string(),
+                "BTC is at $100.25".to_string()
+            )
+        );
+
+        let mut engine = rule_engine("funding.rate > 0");
+        let triggered = engine
+            .on_snapshot("btc", &funding(Some(1.0)), 0)
+            .pop()
+            .unwrap();
+        assert_eq!(
+            notification_text(&triggered),
+            (
+                "BTC: funding.rate > 0".to_string(),
+                "BTC alert rule matched".to_string()
+            )
+        );
+    }
+}
@@ -0,0 +1,993 @@
+//! Compound alert rules
+//!
+//! A small expression language over market data for one symbol:
+//!
+//! ```text
+//! change_1h < -3% AND funding.rate > 0.01%
+//! book.imbalance > 0.5 FOR 30s
+//! (price > 70000 OR signals.day_trading.composite >= 60) AND NOT volume_24h < 1e9
+//! ```
+//!
+//! Comparisons (`<`, `<=`, `>`, `>=`, `==`, `!=`) combine with `AND`, `OR`,
+//! `NOT` and parentheses; keywords are case-insensitive and `AND` binds
+//! tighter than `OR`. A `%` literal is read in the field's own units, so
+//! `3%` is 3 against `change_1h` (already a percentage) and 0.03 against
+//! `funding.rate` (a fraction). `FOR <n>s|m|h` after a comparison or group
+//! requires it to hold continuously for that long.
+//!
+//! Data that could not be fetched makes a comparison unknown rather than
+//! false, and unknowns propagate through `AND`/`OR`/`NOT` the way SQL's
+//! `NULL` does, so a rule only fires when it is definitely true.
+
+use std::collections::HashSet;
+
+use serde::Serialize;
+
+use crate::haunt::models::{
+    AggregatedOrderBook, Asset, FundingRate, SymbolSignals, TradingTimeframe,
+};
+
+/// A rule that failed to parse, with the offending span for the UI
+#[derive(Debug, Clone, PartialEq, Serialize, thiserror::Error)]
+#[serde(rename_all = "camelCase")]
+#[error("{message} (column {})", .start + 1)]
+pub struct RuleError {
+    pub message: String,
+    /// Character offset where the problem starts
+    pub start: usize,
+    /// Character offset just past the problem
+    pub end: usize,
+}
+
+impl RuleError {
+    fn new(message: impl Into<String>, start: usize, end: usize) -> Self {
+        Self {
+            message: message.into(),
+            start,
+            end,
+        }
+    }
+}
+
+// ========== Fields ==========
+
+const TIMEFRAMES: [TradingTimeframe; 4] = [
+    TradingTimeframe::Scalping,
+    TradingTimeframe::DayTrading,
+    TradingTimeframe::SwingTrading,
+    TradingTimeframe::PositionTrading,
+];
+
+/// A composite score from [`SymbolSignals`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Score {
+    Composite,
+    Trend,
+    Momentum,
+    Volatility,
+    Volume,
+}
+
+const SCORES: [(&str, Score); 5] = [
+    ("composite", Score::Composite),
+    ("trend", Score::Trend),
+    ("momentum", Score::Momentum),
+    ("volatility", Score::Volatility),
+    ("volume", Score::Volume),
+];
+
+/// A value a rule can compare
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Price,
+    Change1h,
+    Change24h,
+    Change7d,
+    MarketCap,
+    Volume24h,
+    BookImbalance,
+    BookSpread,
+    BookSpreadPct,
+    BookMidPrice,
+    BookBidTotal,
+    BookAskTotal,
+    FundingRate,
+    FundingPredictedRate,
+    Signal(TradingTimeframe, Score),
+}
+
+const FIELDS: [(&str, Field); 14] = [
+    ("price", Field::Price),
+    ("change_1h", Field::Change1h),
+    ("change_24h", Field::Change24h),
+    ("change_7d", Field::Change7d),
+    ("market_cap", Field::MarketCap),
+    ("volume_24h", Field::Volume24h),
+    ("book.imbalance", Field::BookImbalance),
+    ("book.spread", Field::BookSpread),
+    ("book.spread_pct", Field::BookSpreadPct),
+    ("book.mid_price", Field::BookMidPrice),
+    ("book.bid_total", Field::BookBidTotal),
+    ("book.ask_total", Field::BookAskTotal),
+    ("funding.rate", Field::FundingRate),
+    ("funding.predicted_rate", Field::FundingPredictedRate),
+];
+
+/// How a field's numbers relate to a `%` literal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Unit {
+    Plain,
+    /// Already a percentage: 3% is 3
+    Percent,
+    /// A fraction: 3% is 0.03
+    Fraction,
+}
+
+/// Data a rule needs fetched
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Source {
+    Asset,
+    OrderBook,
+    Funding,
+    Signals(TradingTimeframe),
+}
+
+impl Field {
+    fn parse(name: &str) -> Option<Self> {
+        if let Some((_, field)) = FIELDS.iter().find(|(n, _)| *n == name) {
+            return Some(*field);
+        }
+        let (timeframe, score) = name.strip_prefix("signals.")?.split_once('.')?;
+        let timeframe = TIMEFRAMES.into_iter().find(|t| t.as_str() == timeframe)?;
+        let (_, score) = SCORES.iter().find(|(n, _)| *n == score)?;
+        Some(Self::Signal(timeframe, *score))
+    }
+
+    fn name(self) -> String {
+        if let Self::Signal(timeframe, score) = self {
+            let (score, _) = SCORES.iter().find(|(_, s)| *s == score).unwrap();
+            return format!("signals.{}.{}", timeframe.as_str(), score);
+        }
+        let (name, _) = FIELDS.iter().find(|(_, f)| *f == self).unwrap();
+        name.to_string()
+    }
+
+    fn unit(self) -> Unit {
+        match self {
+            Self::Change1h | Self::Change24h | Self::Change7d | Self::BookSpreadPct => {
+                Unit::Percent
+            }
+            Self::BookImbalance | Self::FundingRate | Self::FundingPredictedRate => Unit::Fraction,
+            _ => Unit::Plain,
+        }
+    }
+
+    fn source(self) -> Source {
+        match self {
+            Self::Price
+            | Self::Change1h
+            | Self::Change24h
+            | Self::Change7d
+            | Self::MarketCap
+            | Self::Volume24h => Source::Asset,
+            Self::BookImbalance
+            | Self::BookSpread
+            | Self::BookSpreadPct
+            | Self::BookMidPrice
+            | Self::BookBidTotal
+            | Self::BookAskTotal => Source::OrderBook,
+            Self::FundingRate | Self::FundingPredictedRate => Source::Funding,
+            Self::Signal(timeframe, _) => Source::Signals(timeframe),
+        }
+    }
+
+    fn value(self, snapshot: &Snapshot) -> Option<f64> {
+        let asset = snapshot.asset.as_ref();
+        let book = snapshot.book.as_ref();
+        let funding = snapshot.funding.as_ref();
+        match self {
+            Self::Price => asset.map(|a| a.price),
+            Self::Change1h => asset.map(|a| a.change_1h),
+            Self::Change24h => asset.map(|a| a.change_24h),
+            Self::Change7d => asset.map(|a| a.change_7d),
+            Self::MarketCap => asset.map(|a| a.market_cap),
+            Self::Volume24h => asset.map(|a| a.volume_24h),
+            Self::BookImbalance => book.map(|b| b.imbalance),
+            Self::BookSpread => book.map(|b| b.spread),
+            Self::BookSpreadPct => book.map(|b| b.spread_pct),
+            Self::BookMidPrice => book.map(|b| b.mid_price),
+            Self::BookBidTotal => book.map(|b| b.bid_total),
+            Self::BookAskTotal => book.map(|b| b.ask_total),
+            Self::FundingRate => funding.map(|f| f.rate),
+            Self::FundingPredictedRate => funding.and_then(|f| f.predicted_rate),
+            Self::Signal(timeframe, score) => {
+                let signals = snapshot.signals.iter().find(|s| s.timeframe == timeframe)?;
+                Some(match score {
+                    Score::Composite => signals.composite_score,
+                    Score::Trend => signals.trend_score,
+                    Score::Momentum => signals.momentum_score,
+                    Score::Volatility => signals.volatility_score,
+                    Score::Volume => signals.volume_score,
+                })
+            }
+        }
+    }
+}
+
+/// Every field name, for autocompletion
+pub fn field_names() -> Vec<String> {
+    let mut names: Vec<String> = FIELDS.iter().map(|(name, _)| name.to_string()).collect();
+    for timeframe in TIMEFRAMES {
+        for (_, score) in SCORES {
+            names.push(Field::Signal(timeframe, score).name());
+        }
+    }
+    names
+}
+
+/// Market data for one symbol; missing parts make their fields unknown
+#[derive(Debug, Clone, Default)]
+pub struct Snapshot {
+    pub asset: Option<Asset>,
+    pub book: Option<AggregatedOrderBook>,
+    pub funding: Option<FundingRate>,
+    pub signals: Vec<SymbolSignals>,
+}
+
+// ========== Lexer ==========
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CmpOp {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+impl CmpOp {
+    fn apply(self, left: f64, right: f64) -> bool {
+        match self {
+            Self::Lt => left < right,
+            Self::Le => left <= right,
+            Self::Gt => left > right,
+            Self::Ge => left >= right,
+            Self::Eq => left == right,
+            Self::Ne => left != right,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(f64),
+    Percent,
+    Minus,
+    Cmp(CmpOp),
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    For,
+}
+
+#[derive(Debug, Clone)]
+struct Spanned {
+    token: Token,
+    start: usize,
+    end: usize,
+}
+
+fn lex(source: &str) -> Result<Vec<Spanned>, RuleError> {
+    let chars: Vec<char> = source.chars().collect();
+    let digit_at = |i: usize| chars.get(i).is_some_and(|c| c.is_ascii_digit());
+
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        let token = if c.is_ascii_alphabetic() || c == '_' {
+            while chars
+                .get(i)
+                .is_some_and(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '.')
+            {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            match word.to_ascii_uppercase().as_str() {
+                "AND" => Token::And,
+                "OR" => Token::Or,
+                "NOT" => Token::Not,
+                "FOR" => Token::For,
+                _ => Token::Ident(word.to_ascii_lowercase()),
+            }
+        } else if c.is_ascii_digit() || (c == '.' && digit_at(i + 1)) {
+            while chars
+                .get(i)
+                .is_some_and(|c| c.is_ascii_digit() || *c == '.')
+            {
+                i += 1;
+            }
+            // An exponent only if digits follow, so `30s` and `2e` stay apart
+            if matches!(chars.get(i), Some('e' | 'E')) {
+                let sign = usize::from(matches!(chars.get(i + 1), Some('+' | '-')));
+                if digit_at(i + 1 + sign) {
+                    i += 1 + sign;
+                    while digit_at(i) {
+                        i += 1;
+                    }
+                }
+            }
+            let text: String = chars[start..i].iter().collect();
+            let value = text
+                .parse()
+                .map_err(|_| RuleError::new(format!("Invalid number `{}`", text), start, i))?;
+            Token::Number(value)
+        } else {
+            i += 1;
+            match c {
+                '%' => Token::Percent,
+                '-' => Token::Minus,
+                '(' => Token::LParen,
+                ')' => Token::RParen,
+                '<' | '>' | '=' | '!' => {
+                    let equals = chars.get(i) == Some(&'=');
+                    if equals {
+                        i += 1;
+                    }
+                    Token::Cmp(match (c, equals) {
+                        ('<', false) => CmpOp::Lt,
+                        ('<', true) => CmpOp::Le,
+                        ('>', false) => CmpOp::Gt,
+                        ('>', true) => CmpOp::Ge,
+                        ('=', _) => CmpOp::Eq,
+                        _ if equals => CmpOp::Ne,
+                        _ => return Err(RuleError::new("Use NOT instead of `!`", start, i)),
+                    })
+                }
+                '&' | '|' => {
+                    return Err(RuleError::new(
+                        "Use AND and OR to combine conditions",
+                        start,
+                        i,
+                    ))
+                }
+                _ => {
+                    return Err(RuleError::new(
+                        format!("Unexpected character `{}`", c),
+                        start,
+                        i,
+                    ))
+                }
+            }
+        };
+        tokens.push(Spanned {
+            token,
+            start,
+            end: i,
+        });
+    }
+    Ok(tokens)
+}
+
+// ========== Parser ==========
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operand {
+    Field(Field),
+    Value(f64),
+}
+
+impl Operand {
+    fn value(self, snapshot: &Snapshot) -> Option<f64> {
+        match self {
+            Self::Field(field) => field.value(snapshot),
+            Self::Value(value) => Some(value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Compare {
+        left: Operand,
+        op: CmpOp,
+        right: Operand,
+    },
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    /// True once `expr` has held for `duration_ms`; `slot` indexes the
+    /// rule's timers
+    Sustained {
+        expr: Box<Expr>,
+        duration_ms: i64,
+        slot: usize,
+    },
+}
+
+/// An operand before `%` literals are resolved against the field they are
+/// compared with
+#[derive(Debug, Clone, Copy)]
+enum RawOperand {
+    Field(Field),
+    Number { value: f64, percent: bool },
+}
+
+struct Parser {
+    tokens: Vec<Spanned>,
+    pos: usize,
+    /// Length of the source in characters
+    len: usize,
+    slots: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|t| &t.token)
+    }
+
+    fn next(&mut self) -> Option<Spanned> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        let found = self.peek() == Some(token);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    /// Error at the current token, or at the end of the input
+    fn error(&self, message: impl Into<String>) -> RuleError {
+        match self.tokens.get(self.pos) {
+            Some(token) => RuleError::new(message, token.start, token.end),
+            None => RuleError::new(message, self.len, self.len),
+        }
+    }
+
+    fn expected(&self, what: &str) -> RuleError {
+        match self.tokens.get(self.pos) {
+            Some(_) => self.error(format!("Expected {}", what)),
+            None => self.error(format!("Expected {} at the end", what)),
+        }
+    }
+
+    /// End offset of the last consumed token
+    fn last_end(&self) -> usize {
+        self.pos
+            .checked_sub(1)
+            .and_then(|i| self.tokens.get(i))
+            .map_or(0, |t| t.end)
+    }
+
+    fn or(&mut self) -> Result<Expr, RuleError> {
+        let mut left = self.and()?;
+        while self.eat(&Token::Or) {
+            left = Expr::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Expr, RuleError> {
+        let mut left = self.unary()?;
+        while self.eat(&Token::And) {
+            left = Expr::And(Box::new(left), Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, RuleError> {
+        if self.eat(&Token::Not) {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+
+        let expr = self.primary()?;
+        if !self.eat(&Token::For) {
+            return Ok(expr);
+        }
+        let duration_ms = self.duration()?;
+        let slot = self.slots;
+        self.slots += 1;
+        Ok(Expr::Sustained {
+            expr: Box::new(expr),
+            duration_ms,
+            slot,
+        })
+    }
+
+    fn primary(&mut self) -> Result<Expr, RuleError> {
+        let Some(Token::LParen) = self.peek() else {
+            return self.comparison();
+        };
+        let open = self.next().unwrap();
+        let expr = self.or()?;
+        if !self.eat(&Token::RParen) {
+            return Err(match self.peek() {
+                Some(_) => self.expected("`)`"),
+                None => RuleError::new("Unclosed `(`", open.start, open.end),
+            });
+        }
+        Ok(expr)
+    }
+
+    fn comparison(&mut self) -> Result<Expr, RuleError> {
+        let start = self.tokens.get(self.pos).map_or(self.len, |t| t.start);
+        let left = self.operand()?;
+        let op = match self.peek() {
+            Some(Token::Cmp(op)) => {
+                let op = *op;
+                self.pos += 1;
+                op
+            }
+            _ => return Err(self.expected("a comparison such as `>`")),
+        };
+        let right = self.operand()?;
+        let end = self.last_end();
+
+        let resolve = |number: f64, percent: bool, field: Field| match (percent, field.unit()) {
+            (false, _) | (true, Unit::Percent) => Ok(Operand::Value(number)),
+            (true, Unit::Fraction) => Ok(Operand::Value(number / 100.0)),
+            (true, Unit::Plain) => Err(RuleError::new(
+                format!("`{}` is not a percentage; drop the `%`", field.name()),
+                start,
+                end,
+            )),
+        };
+        let (left, right) = match (left, right) {
+            (RawOperand::Field(left), RawOperand::Field(right)) => {
+                (Operand::Field(left), Operand::Field(right))
+            }
+            (RawOperand::Field(field), RawOperand::Number { value, percent }) => {
+                (Operand::Field(field), resolve(value, percent, field)?)
+            }
+            (RawOperand::Number { value, percent }, RawOperand::Field(field)) => {
+                (resolve(value, percent, field)?, Operand::Field(field))
+            }
+            (RawOperand::Number { .. }, RawOperand::Number { .. }) => {
+                return Err(RuleError::new(
+                    "Comparison needs a field, not two numbers",
+                    start,
+                    end,
+                ))
+            }
+        };
+        Ok(Expr::Compare { left, op, right })
+    }
+
+    fn operand(&mut self) -> Result<RawOperand, RuleError> {
+        let negative = self.eat(&Token::Minus);
+        let Some(token) = self.tokens.get(self.pos).cloned() else {
+            return Err(self.expected("a field or number"));
+        };
+        match token.token {
+            Token::Number(value) => {
+                self.pos += 1;
+                let percent = self.eat(&Token::Percent);
+                let value = if negative { -value } else { value };
+                Ok(RawOperand::Number { value, percent })
+            }
+            Token::Ident(name) if !negative => {
+                self.pos += 1;
+                Field::parse(&name).map(RawOperand::Field).ok_or_else(|| {
+                    RuleError::new(format!("Unknown field `{}`", name), token.start, token.end)
+                })
+            }
+            _ if negative => Err(self.expected("a number after `-`")),
+            _ => Err(self.expected("a field or number")),
+        }
+    }
+
+    fn duration(&mut self) -> Result<i64, RuleError> {
+        let start = self.tokens.get(self.pos).map_or(self.len, |t| t.start);
+        let value = match self.peek() {
+            Some(Token::Number(value)) if *value > 0.0 => *value,
+            _ => return Err(self.expected("a duration such as `30s`")),
+        };
+        self.pos += 1;
+        let unit_ms = match self.peek() {
+            Some(Token::Ident(unit)) => match unit.as_str() {
+                "s" | "sec" | "secs" => 1_000.0,
+                "m" | "min" | "mins" => 60_000.0,
+                "h" | "hr" | "hrs" => 3_600_000.0,
+                _ => return Err(self.error("Duration unit must be s, m or h")),
+            },
+            _ => return Err(self.expected("a duration unit (s, m or h)")),
+        };
+        self.pos += 1;
+
+        let duration = value * unit_ms;
+        if duration > 7.0 * 24.0 * 3_600_000.0 {
+            return Err(RuleError::new(
+                "Duration is longer than a week",
+                start,
+                self.last_end(),
+            ));
+        }
+        Ok(duration as i64)
+    }
+}
+
+impl Expr {
+    fn evaluate(
+        &self,
+        snapshot: &Snapshot,
+        now: i64,
+        held_since: &mut [Option<i64>],
+    ) -> Option<bool> {
+        match self {
+            Self::Compare { left, op, right } => {
+                Some(op.apply(left.value(snapshot)?, right.value(snapshot)?))
+            }
+            // Both sides are always evaluated so every timer sees every tick
+            Self::And(left, right) => {
+                let left = left.evaluate(snapshot, now, held_since);
+                let right = right.evaluate(snapshot, now, held_since);
+                match (left, right) {
+                    (Some(false), _) | (_, Some(false)) => Some(false),
+                    (Some(true), Some(true)) => Some(true),
+                    _ => None,
+                }
+            }
+            Self::Or(left, right) => {
+                let left = left.evaluate(snapshot, now, held_since);
+                let right = right.evaluate(snapshot, now, held_since);
+                match (left, right) {
+                    (Some(true), _) | (_, Some(true)) => Some(true),
+                    (Some(false), Some(false)) => Some(false),
+                    _ => None,
+                }
+            }
+            Self::Not(expr) => expr.evaluate(snapshot, now, held_since).map(|v| !v),
+            Self::Sustained {
+                expr,
+                duration_ms,
+                slot,
+            } => {
+                let value = expr.evaluate(snapshot, now, held_since);
+                if value != Some(true) {
+                    held_since[*slot] = None;
+                    return value;
+                }
+                let since = *held_since[*slot].get_or_insert(now);
+                Some(now - since >= *duration_ms)
+            }
+        }
+    }
+
+    fn sources(&self, sources: &mut HashSet<Source>) {
+        match self {
+            Self::Compare { left, right, .. } => {
+                for operand in [left, right] {
+                    if let Operand::Field(field) = operand {
+                        sources.insert(field.source());
+                    }
+                }
+            }
+            Self::And(left, right) | Self::Or(left, right) => {
+                left.sources(sources);
+                right.sources(sources);
+            }
+            Self::Not(expr) | Self::Sustained { expr, .. } => expr.sources(sources),
+        }
+    }
+}
+
+/// A parsed rule and the timers for its `FOR` clauses
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    expr: Expr,
+    held_since: Vec<Option<i64>>,
+}
+
+impl Rule {
+    pub fn parse(source: &str) -> Result<Self, RuleError> {
+        let tokens = lex(source)?;
+        let len = source.chars().count();
+        if tokens.is_empty() {
+            return Err(RuleError::new("Rule is empty", 0, len));
+        }
+
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            len,
+            slots: 0,
+        };
+        let expr = parser.or()?;
+        if parser.peek().is_some() {
+            return Err(match parser.peek() {
+                Some(Token::RParen) => parser.error("Unmatched `)`"),
+                _ => parser.error("Expected AND or OR"),
+            });
+        }
+        Ok(Self {
+            expr,
+            held_since: vec![None; parser.slots],
+        })
+    }
+
+    /// Data the rule reads
+    pub fn sources(&self) -> HashSet<Source> {
+        let mut sources = HashSet::new();
+        self.expr.sources(&mut sources);
+        sources
+    }
+
+    /// Evaluate against fresh data; `None` when it depends on missing data
+    pub fn evaluate(&mut self, snapshot: &Snapshot, now: i64) -> Option<bool> {
+        self.expr.evaluate(snapshot, now, &mut self.held_since)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::haunt::models::SignalDirection;
+
+    fn asset(price: f64, change_1h: f64) -> Asset {
+        Asset::from_value(serde_json::json!({
+            "id": 1,
+            "name": "Bitcoin",
+            "symbol": "BTC",
+            "price": price,
+            "change1h": change_1h,
+        }))
+        .unwrap()
+    }
+
+    fn book(imbalance: f64) -> AggregatedOrderBook {
+        serde_json::from_value(serde_json::json!({
+            "symbol": "btc",
+            "bids": [],
+            "asks": [],
+            "bidTotal": 1.0,
+            "askTotal": 1.0,
+            "imbalance": imbalance,
+            "bestBid": 1.0,
+            "bestAsk": 1.0,
+            "spread": 0.0,
+            "spreadPct": 0.0,
+            "midPrice": 1.0,
+            "exchangeCount": 1,
+            "timestamp": 0,
+        }))
+        .unwrap()
+    }
+
+    fn funding(rate: f64) -> FundingRate {
+        FundingRate {
+            symbol: "btc".to_string(),
+            rate,
+            next_funding_time: 0,
+            predicted_rate: None,
+            interval: 8.0,
+        }
+    }
+
+    fn signals(timeframe: TradingTimeframe, composite_score: f64) -> SymbolSignals {
+        SymbolSignals {
+            symbol: "btc".to_string(),
+            timeframe,
+            signals: vec![],
+            trend_score: 0.0,
+            momentum_score: 0.0,
+            volatility_score: 0.0,
+            volume_score: 0.0,
+            composite_score,
+            direction: SignalDirection::Neutral,
+            timestamp: 0,
+        }
+    }
+
+    fn snapshot(price: f64, change_1h: f64) -> Snapshot {
+        Snapshot {
+            asset: Some(asset(price, change_1h)),
+            ..Default::default()
+        }
+    }
+
+    fn eval(rule: &str, snapshot: &Snapshot) -> Option<bool> {
+        Rule::parse(rule).unwrap().evaluate(snapshot, 0)
+    }
+
+    fn error(rule: &str) -> (String, usize, usize) {
+        let e = Rule::parse(rule).unwrap_err();
+        (e.message, e.start, e.end)
+    }
+
+    #[test]
+    fn example_rules() {
+        let mut rule = Rule::parse("change_1h < -3% AND funding.rate > 0.01%").unwrap();
+        assert_eq!(
+            rule.sources(),
+            HashSet::from([Source::Asset, Source::Funding])
+        );
+
+        let mut data = Snapshot {
+            funding: Some(funding(0.0002)),
+            ..snapshot(60_000.0, -3.5)
+        };
+        assert_eq!(rule.evaluate(&data, 0), Some(true));
+        data.funding = Some(funding(0.00005));
+        assert_eq!(rule.evaluate(&data, 0), Some(false));
+
+        let mut rule = Rule::parse("book.imbalance > 0.5 for 30s").unwrap();
+        assert_eq!(rule.sources(), HashSet::from([Source::OrderBook]));
+        let strong = Snapshot {
+            book: Some(book(0.6)),
+            ..Default::default()
+        };
+        assert_eq!(rule.evaluate(&strong, 0), Some(false));
+        assert_eq!(rule.evaluate(&strong, 29_999), Some(false));
+        assert_eq!(rule.evaluate(&strong, 30_000), Some(true));
+    }
+
+    #[test]
+    fn precedence_and_grouping() {
+        let data = snapshot(100.0, 1.0);
+        // AND binds tighter: false OR (true AND true)
+        assert_eq!(
+            eval("price > 200 OR price > 50 AND change_1h > 0", &data),
+            Some(true)
+        );
+        assert_eq!(
+            eval("(price > 200 OR price > 50) AND change_1h > 5", &data),
+            Some(false)
+        );
+        assert_eq!(
+            eval("NOT price > 200 AND NOT NOT price < 200", &data),
+            Some(true)
+        );
+        assert_eq!(
+            eval("not (price >= 100 and price <= 100)", &data),
+            Some(false)
+        );
+        assert_eq!(eval("100 == price", &data), Some(true));
+        assert_eq!(eval("price != 100", &data), Some(false));
+    }
+
+    #[test]
+    fn percent_literals_follow_field_units() {
+        let data = Snapshot {
+            book: Some(book(0.3)),
+            funding: Some(funding(0.00015)),
+            ..snapshot(100.0, 2.5)
+        };
+        assert_eq!(eval("change_1h > 2%", &data), Some(true));
+        assert_eq!(eval("change_1h > 2", &data), Some(true));
+        assert_eq!(eval("book.imbalance < 50%", &data), Some(true));
+        assert_eq!(eval("book.imbalance < 0.25", &data), Some(false));
+        assert_eq!(eval("funding.rate >= 0.01%", &data), Some(true));
+        assert_eq!(eval("funding.rate > 0.02%", &data), Some(false));
+        assert_eq!(eval("price > 1e1 AND price < 1.5E+2", &data), Some(true));
+    }
+
+    #[test]
+    fn signal_fields() {
+        let data = Snapshot {
+            signals: vec![signals(TradingTimeframe::SwingTrading, 70.0)],
+            ..Default::default()
+        };
+        let mut rule = Rule::parse("signals.swing_trading.composite >= 60").unwrap();
+        assert_eq!(
+            rule.sources(),
+            HashSet::from([Source::Signals(TradingTimeframe::SwingTrading)])
+        );
+        assert_eq!(rule.evaluate(&data, 0), Some(true));
+        assert_eq!(eval("signals.scalping.composite >= 60", &data), None);
+        assert!(field_names().contains(&"signals.position_trading.volatility".to_string()));
+        assert_eq!(
+            field_names().len(),
+            FIELDS.len() + TIMEFRAMES.len() * SCORES.len()
+        );
+    }
+
+    #[test]
+    fn missing_data_is_unknown() {
+        let data = snapshot(100.0, 0.0);
+        assert_eq!(eval("funding.rate > 0", &data), None);
+        assert_eq!(eval("NOT funding.rate > 0", &data), None);
+        assert_eq!(eval("funding.rate > 0 AND price > 200", &data), Some(false));
+        assert_eq!(eval("funding.rate > 0 AND price > 50", &data), None);
+        assert_eq!(eval("funding.rate > 0 OR price > 50", &data), Some(true));
+        assert_eq!(eval("funding.rate > 0 OR price > 200", &data), None);
+    }
+
+    #[test]
+    fn sustained_conditions_reset() {
+        let mut rule =
+            Rule::parse("(price > 100 OR change_1h > 5) FOR 1m AND price < 1000").unwrap();
+        let high = snapshot(150.0, 0.0);
+        let low = snapshot(50.0, 0.0);
+
+        assert_eq!(rule.evaluate(&high, 0), Some(false));
+        assert_eq!(rule.evaluate(&high, 59_000), Some(false));
+        // Dropping below resets the timer
+        assert_eq!(rule.evaluate(&low, 60_000), Some(false));
+        assert_eq!(rule.evaluate(&high, 61_000), Some(false));
+        assert_eq!(rule.evaluate(&high, 121_000), Some(true));
+        // So does missing data
+        assert_eq!(rule.evaluate(&Snapshot::default(), 122_000), None);
+        assert_eq!(rule.evaluate(&high, 123_000), Some(false));
+    }
+
+    #[test]
+    fn reports_errors_with_spans() {
+        assert_eq!(error("   "), ("Rule is empty".to_string(), 0, 3));
+        assert_eq!(
+            error("prise > 5"),
+            ("Unknown field `prise`".to_string(), 0, 5)
+        );
+        assert_eq!(
+            error("price > "),
+            ("Expected a field or number at the end".to_string(), 8, 8)
+        );
+        assert_eq!(
+            error("price 5"),
+            ("Expected a comparison such as `>`".to_string(), 6, 7)
+        );
+        assert_eq!(error("(price > 5"), ("Unclosed `(`".to_string(), 0, 1));
+        assert_eq!(error("price > 5)"), ("Unmatched `)`".to_string(), 9, 10));
+        assert_eq!(
+            error("price > 5 change_1h > 1"),
+            ("Expected AND or OR".to_string(), 10, 19)
+        );
+        assert_eq!(
+            error("price > 5%"),
+            (
+                "`price` is not a percentage; drop the `%`".to_string(),
+                0,
+                10
+            )
+        );
+        assert_eq!(
+            error("1 < 2"),
+            (
+                "Comparison needs a field, not two numbers".to_string(),
+                0,
+                5
+            )
+        );
+        assert_eq!(
+            error("price > 5 FOR 30"),
+            (
+                "Expected a duration unit (s, m or h) at the end".to_string(),
+                16,
+                16
+            )
+        );
+        assert_eq!(
+            error("price > 5 FOR 3d"),
+            ("Duration unit must be s, m or h".to_string(), 15, 16)
+        );
+        assert_eq!(
+            error("price > 5 && change_1h > 0"),
+            ("Use AND and OR to combine conditions".to_string(), 10, 11)
+        );
+        assert_eq!(
+            error("price > 1.2.3"),
+            ("Invalid number `1.2.3`".to_string(), 8, 13)
+        );
+        assert_eq!(
+            error("price > 5 — x"),
+            ("Unexpected character `—`".to_string(), 10, 11)
+        );
+        assert_eq!(
+            Rule::parse("price >").unwrap_err().to_string(),
+            "Expected a field or number at the end (column 8)"
+        );
+    }
+}
@@ -0,0 +1,757 @@
+//! Strategy backtesting
+//!
+//! Replays a [`Strategy`] over OHLC candles from the chart API or the local
+//! cache, so rules can be tried before they run in the sandbox. Conditions
+//! are evaluated at each candle's close and acted on at the next candle's
+//! open, so a signal never trades on the price that produced it.
+//!
+//! Fills are simulated as follows:
+//! - entries, condition exits and stops are market fills and move
+//!   `slippage_pct` against the position; take profits fill at their price
+//!   like a resting limit order
+//! - a candle that opens past a stop or target fills at the open
+//! - when a candle's range reaches both the stop and the target, the stop is
+//!   assumed to have filled first
+//! - fees are charged on the notional of every fill
+//! - a position still open at the last candle is closed at its close
+//!
+//! Positions are unleveraged: no entry is larger than current equity.
+
+pub mod strategy;
+
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::cache::CacheState;
+use crate::haunt::models::{
+    DrawdownHistoryPoint, OhlcPoint, OrderSide, PerformancePoint, PositionSide, Trade,
+};
+use crate::haunt::HauntClient;
+use crate::indicators;
+use crate::new_id;
+use strategy::{Compiled, Sizing, Strategy, StrategyError};
+
+/// Chart range tested when none is given
+const DEFAULT_RANGE: &str = "1m";
+
+/// Errors produced by a backtest
+#[derive(Debug, thiserror::Error)]
+pub enum BacktestError {
+    #[error(transparent)]
+    Strategy(#[from] StrategyError),
+    #[error("No price history to test against")]
+    NoHistory,
+    #[error("Starting balance must be positive, and fees and slippage non-negative")]
+    InvalidConfig,
+}
+
+/// Account and execution assumptions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BacktestConfig {
+    pub initial_balance: f64,
+    /// Percent of notional charged on every fill
+    pub fee_pct: f64,
+    /// Percent market fills move against the position
+    pub slippage_pct: f64,
+}
+
+impl BacktestConfig {
+    pub fn validate(&self) -> Result<(), BacktestError> {
+        let valid = self.initial_balance.is_finite()
+            && self.initial_balance > 0.0
+            && self.fee_pct >= 0.0
+            && self.slippage_pct >= 0.0;
+        if valid {
+            Ok(())
+        } else {
+            Err(BacktestError::InvalidConfig)
+        }
+    }
+}
+
+impl Default for BacktestConfig {
+    fn default() -> Self {
+        Self {
+            initial_balance: 10_000.0,
+            fee_pct: 0.1,
+            slippage_pct: 0.05,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BacktestSummary {
+    pub start_value: f64,
+    pub end_value: f64,
+    pub total_pnl: f64,
+    pub total_pnl_percent: f64,
+    /// Round trips, entry to exit
+    pub total_trades: u64,
+    pub winning_trades: u64,
+    pub losing_trades: u64,
+    pub win_rate: f64,
+    pub max_drawdown_percent: f64,
+    pub total_fees: f64,
+    /// Gross profit over gross loss; `None` without a losing trade
+    pub profit_factor: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BacktestResult {
+    pub symbol: String,
+    /// Equity marked at each candle's close
+    pub equity: Vec<PerformancePoint>,
+    /// Drawdown from the running equity peak at each candle's close
+    pub drawdowns: Vec<DrawdownHistoryPoint>,
+    /// Every fill; exits carry the round trip's P&L net of both fees
+    pub trades: Vec<Trade>,
+    pub summary: BacktestSummary,
+}
+
+/// Price after slippage for a market order
+pub(crate) fn slip(price: f64, side: OrderSide, slippage_pct: f64) -> f64 {
+    match side {
+        OrderSide::Buy => price * (1.0 + slippage_pct / 100.0),
+        OrderSide::Sell => price * (1.0 - slippage_pct / 100.0),
+    }
+}
+
+struct Position {
+    side: PositionSide,
+    size: f64,
+    entry: f64,
+    entry_fee: f64,
+    stop: Option<f64>,
+    target: Option<f64>,
+}
+
+impl Position {
+    fn direction(&self) -> f64 {
+        match self.side {
+            PositionSide::Long => 1.0,
+            PositionSide::Short => -1.0,
+        }
+    }
+
+    fn exit_side(&self) -> OrderSide {
+        match self.side {
+            PositionSide::Long => OrderSide::Sell,
+            PositionSide::Short => OrderSide::Buy,
+        }
+    }
+
+    fn unrealized(&self, price: f64) -> f64 {
+        (price - self.entry) * self.size * self.direction()
+    }
+
+    /// Fill price if the candle reaches the stop or the target
+    fn triggered(&self, candle: &OhlcPoint, slippage_pct: f64) -> Option<f64> {
+        let long = self.side == PositionSide::Long;
+        // The worst and best prices the candle reached for this position
+        let (worst, best) = if long {
+            (candle.low, candle.high)
+        } else {
+            (candle.high, candle.low)
+        };
+        let beyond = |price: f64, level: f64, against: bool| {
+            if against == long {
+                price <= level
+            } else {
+                price >= level
+            }
+        };
+        if let Some(stop) = self.stop {
+            if beyond(candle.open, stop, true) {
+                return Some(slip(candle.open, self.exit_side(), slippage_pct));
+            }
+            if beyond(worst, stop, true) {
+                return Some(slip(stop, self.exit_side(), slippage_pct));
+            }
+        }
+        let target = self.target?;
+        if beyond(candle.open, target, false) {
+            Some(candle.open)
+        } else if beyond(best, target, false) {
+            Some(target)
+        } else {
+            None
+        }
+    }
+}
+
+/// Cash, the open position and everything filled so far
+struct Book<'a> {
+    symbol: &'a str,
+    strategy: &'a Strategy,
+    config: &'a BacktestConfig,
+    cash: f64,
+    position: Option<Position>,
+    trades: Vec<Trade>,
+    fees: f64,
+    /// Net P&L of each round trip
+    results: Vec<f64>,
+}
+
+impl Book<'_> {
+    fn equity(&self, price: f64) -> f64 {
+        self.cash + self.position.as_ref().map_or(0.0, |p| p.unrealized(price))
+    }
+
+    fn fee(&self, notional: f64) -> f64 {
+        notional * self.config.fee_pct / 100.0
+    }
+
+    fn open(&mut self, price: f64, time: i64) {
+        let equity = self.cash;
+        if equity <= 0.0 {
+            return;
+        }
+        let side = self.strategy.side;
+        let (order_side, direction) = match side {
+            PositionSide::Long => (OrderSide::Buy, 1.0),
+            PositionSide::Short => (OrderSide::Sell, -1.0),
+        };
+        let fill = slip(price, order_side, self.config.slippage_pct);
+        let notional = match self.strategy.sizing {
+            Sizing::Units { size } => size * fill,
+            Sizing::Notional { amount } => amount,
+            Sizing::Equity { percent } => equity * percent / 100.0,
+            // Validation guarantees a stop for risk sizing
+            Sizing::Risk { percent } => {
+                let stop_pct = self.strategy.stop_loss_pct.unwrap_or(100.0);
+                equity * percent / stop_pct
+            }
+        }
+        .min(equity);
+        if !(notional > 0.0 && fill > 0.0) {
+            return;
+        }
+
+        let size = notional / fill;
+        let fee = self.fee(notional);
+        self.cash -= fee;
+        self.fees += fee;
+        self.position = Some(Position {
+            side,
+            size,
+            entry: fill,
+            entry_fee: fee,
+            stop: self
+                .strategy
+                .stop_loss_pct
+                .map(|pct| fill * (1.0 - direction * pct / 100.0)),
+            target: self
+                .strategy
+                .take_profit_pct
+                .map(|pct| fill * (1.0 + direction * pct / 100.0)),
+        });
+        self.trades.push(Trade {
+            id: new_id(),
+            symbol: self.symbol.to_string(),
+            side: order_side,
+            size,
+            price: fill,
+            fee,
+            pnl: 0.0,
+            executed_at: time,
+        });
+    }
+
+    /// Close the open position at an already slipped fill price
+    fn close(&mut self, fill: f64, time: i64) {
+        let Some(position) = self.position.take() else {
+            return;
+        };
+        let gross = position.unrealized(fill);
+        let fee = self.fee(fill * position.size);
+        let pnl = gross - position.entry_fee - fee;
+        self.cash += gross - fee;
+        self.fees += fee;
+        self.results.push(pnl);
+        self.trades.push(Trade {
+            id: new_id(),
+            symbol: self.symbol.to_string(),
+            side: position.exit_side(),
+            size: position.size,
+            price: fill,
+            fee,
+            pnl,
+            executed_at: time,
+        });
+    }
+}
+
+/// Equity and drawdown marked through a run
+pub(crate) struct Curve {
+    initial: f64,
+    peak: f64,
+    max_drawdown: f64,
+    equity: Vec<PerformancePoint>,
+    drawdowns: Vec<DrawdownHistoryPoint>,
+}
+
+impl Curve {
+    pub fn new(initial: f64) -> Self {
+        Self {
+            initial,
+            peak: initial,
+            max_drawdown: 0.0,
+            equity: Vec::new(),
+            drawdowns: Vec::new(),
+        }
+    }
+
+    /// Record account value at `time`, in milliseconds
+    pub fn mark(&mut self, time: i64, value: f64) {
+        let pnl = value - self.initial;
+        self.equity.push(PerformancePoint {
+            timestamp: time,
+            value,
+            pnl,
+            pnl_percent: pnl / self.initial * 100.0,
+        });
+        self.peak = self.peak.max(value);
+        let drawdown = if self.peak > 0.0 {
+            (self.peak - value) / self.peak * 100.0
+        } else {
+            0.0
+        };
+        self.max_drawdown = self.max_drawdown.max(drawdown);
+        self.drawdowns.push(DrawdownHistoryPoint {
+            timestamp: time,
+            drawdown_percent: drawdown,
+            portfolio_value: value,
+        });
+    }
+
+    /// Summarise the run; `results` holds the net P&L of each closed trade
+    pub fn finish(
+        self,
+        symbol: &str,
+        end_value: f64,
+        trades: Vec<Trade>,
+        results: &[f64],
+        fees: f64,
+    ) -> BacktestResult {
+        let winning = results.iter().filter(|pnl| **pnl > 0.0).count() as u64;
+        let losing = results.iter().filter(|pnl| **pnl < 0.0).count() as u64;
+        let total = results.len() as u64;
+        let gross_profit: f64 = results.iter().filter(|pnl| **pnl > 0.0).sum();
+        let gross_loss: f64 = -results.iter().filter(|pnl| **pnl < 0.0).sum::<f64>();
+        let total_pnl = end_value - self.initial;
+        BacktestResult {
+            symbol: symbol.to_string(),
+            equity: self.equity,
+            drawdowns: self.drawdowns,
+            trades,
+            summary: BacktestSummary {
+                start_value: self.initial,
+                end_value,
+                total_pnl,
+                total_pnl_percent: total_pnl / self.initial * 100.0,
+                total_trades: total,
+                winning_trades: winning,
+                losing_trades: losing,
+                win_rate: if total > 0 {
+                    winning as f64 / total as f64 * 100.0
+                } else {
+                    0.0
+                },
+                max_drawdown_percent: self.max_drawdown,
+                total_fees: fees,
+                profit_factor: (gross_loss > 0.0).then(|| gross_profit / gross_loss),
+            },
+        }
+    }
+}
+
+/// Run a strategy over candles, oldest first
+pub fn run(
+    symbol: &str,
+    candles: &[OhlcPoint],
+    strategy: &Strategy,
+    config: &BacktestConfig,
+) -> Result<BacktestResult, BacktestError> {
+    strategy.validate()?;
+    config.validate()?;
+    if candles.is_empty() {
+        return Err(BacktestError::NoHistory);
+    }
+
+    let entry: Vec<Compiled> = strategy
+        .entry
+        .iter()
+        .map(|c| Compiled::new(c, candles))
+        .collect();
+    let exit: Vec<Compiled> = strategy
+        .exit
+        .iter()
+        .map(|c| Compiled::new(c, candles))
+        .collect();
+
+    let mut book = Book {
+        symbol,
+        strategy,
+        config,
+        cash: config.initial_balance,
+        position: None,
+        trades: Vec::new(),
+        fees: 0.0,
+        results: Vec::new(),
+    };
+    let mut curve = Curve::new(config.initial_balance);
+    // Decided at one close, filled at the next open
+    let mut enter = false;
+    let mut leave = false;
+
+    for (i, candle) in candles.iter().enumerate() {
+        // Candle times are Unix seconds
+        let time = candle.time * 1000;
+        let last = i + 1 == candles.len();
+
+        if std::mem::take(&mut enter) {
+            book.open(candle.open, time);
+        } else if std::mem::take(&mut leave) {
+            if let Some(side) = book.position.as_ref().map(Position::exit_side) {
+                book.close(slip(candle.open, side, config.slippage_pct), time);
+            }
+        }
+        let triggered = book
+            .position
+            .as_ref()
+            .and_then(|p| p.triggered(candle, config.slippage_pct));
+        if let Some(fill) = triggered {
+            book.close(fill, time);
+        }
+        if last {
+            if let Some(side) = book.position.as_ref().map(Position::exit_side) {
+                book.close(slip(candle.close, side, config.slippage_pct), time);
+            }
+        }
+
+        curve.mark(time, book.equity(candle.close));
+
+        if last {
+            break;
+        }
+        if book.position.is_none() {
+            enter = entry.iter().all(|c| c.holds(i));
+        } else {
+            leave = exit.iter().any(|c| c.holds(i));
+        }
+    }
+
+    let end_value = book.cash;
+    Ok(curve.finish(symbol, end_value, book.trades, &book.results, book.fees))
+}
+
+// ========== Commands ==========
+
+/// Tauri command: Backtest a strategy over candles supplied by the webview
+#[tauri::command]
+pub fn backtest_run(
+    symbol: String,
+    candles: Vec<OhlcPoint>,
+    strategy: Strategy,
+    config: Option<BacktestConfig>,
+) -> Result<BacktestResult, String> {
+    run(&symbol, &candles, &strategy, &config.unwrap_or_default()).map_err(|e| e.to_string())
+}
+
+/// Tauri command: Backtest a strategy over an asset's chart
+///
+/// Fetches the range from Haunt, falling back to the cached series when it
+/// cannot be reached; `cached` skips the fetch.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn backtest_run_for_asset(
+    client: State<'_, HauntClient>,
+    cache: State<'_, CacheState>,
+    id: i64,
+    symbol: String,
+    range: Option<String>,
+    strategy: Strategy,
+    config: Option<BacktestConfig>,
+    cached: Option<bool>,
+) -> Result<BacktestResult, String> {
+    let range = range.as_deref().unwrap_or(DEFAULT_RANGE);
+    let candles = if cached.unwrap_or(false) {
+        cache
+            .cached_chart(id, range)
+            .ok_or_else(|| BacktestError::NoHistory.to_string())?
+    } else {
+        indicators::load_candles(&client, &cache, id, range).await?
+    };
+    run(&symbol, &candles, &strategy, &config.unwrap_or_default()).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::strategy::{Comparison, Condition, Source, Threshold};
+    use super::*;
+
+    fn candle(time: i64, open: f64, high: f64, low: f64, close: f64) -> OhlcPoint {
+        OhlcPoint {
+            time,
+            open,
+            high,
+            low,
+            close,
+            volume: None,
+        }
+    }
+
+    /// Candles that open, peak and close at one price
+    fn flat(closes: &[f64]) -> Vec<OhlcPoint> {
+        closes
+            .iter()
+            .enumerate()
+            .map(|(i, &c)| candle(i as i64, c, c, c, c))
+            .collect()
+    }
+
+    fn close_vs(op: Comparison, value: f64) -> Condition {
+        Condition {
+            indicator: Source::Close,
+            op,
+            threshold: Threshold::Value(value),
+        }
+    }
+
+    fn strategy(side: PositionSide, entry: Condition, exit: Vec<Condition>) -> Strategy {
+        Strategy {
+            side,
+            entry: vec![entry],
+            exit,
+            stop_loss_pct: None,
+            take_profit_pct: None,
+            sizing: Sizing::Equity { percent: 100.0 },
+        }
+    }
+
+    fn frictionless() -> BacktestConfig {
+        BacktestConfig {
+            initial_balance: 1000.0,
+            fee_pct: 0.0,
+            slippage_pct: 0.0,
+        }
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn signals_fill_at_next_open() {
+        // Entry holds at the close of candle 1, exit at the close of candle 3
+        let candles = flat(&[100.0, 105.0, 110.0, 120.0, 130.0]);
+        let strategy = strategy(
+            PositionSide::Long,
+            close_vs(Comparison::CrossesAbove, 102.0),
+            vec![close_vs(Comparison::Above, 115.0)],
+        );
+        let result = run("BTC", &candles, &strategy, &frictionless()).unwrap();
+
+        assert_eq!(result.trades.len(), 2);
+        let (buy, sell) = (&result.trades[0], &result.trades[1]);
+        assert_eq!(
+            (buy.side, buy.price, buy.executed_at),
+            (OrderSide::Buy, 110.0, 2000)
+        );
+        assert_eq!(
+            (sell.side, sell.price, sell.executed_at),
+            (OrderSide::Sell, 130.0, 4000)
+        );
+        assert!(close(buy.size, 1000.0 / 110.0));
+        assert!(close(sell.pnl, 1000.0 / 110.0 * 20.0));
+
+        assert_eq!(result.equity.len(), 5);
+        assert!(close(result.equity[3].value, 1000.0 / 110.0 * 120.0));
+        assert!(close(result.summary.end_value, 1000.0 + sell.pnl));
+        assert_eq!(result.summary.total_trades, 1);
+        assert_eq!(result.summary.win_rate, 100.0);
+        assert_eq!(result.summary.profit_factor, None);
+    }
+
+    #[test]
+    fn fees_and_slippage() {
+        let candles = flat(&[100.0, 100.0, 110.0]);
+        let strategy = strategy(
+            PositionSide::Long,
+            close_vs(Comparison::Above, 0.0),
+            Vec::new(),
+        );
+        let config = BacktestConfig {
+            initial_balance: 1000.0,
+            fee_pct: 0.1,
+            slippage_pct: 1.0,
+        };
+        let result = run("BTC", &candles, &strategy, &config).unwrap();
+
+        // Bought at 101 after slippage with the full 1000; sold at the last
+        // close, 110 less 1%
+        let (buy, sell) = (&result.trades[0], &result.trades[1]);
+        assert!(close(buy.price, 101.0));
+        assert!(close(buy.fee, 1.0));
+        let size = 1000.0 / 101.0;
+        assert!(close(sell.price, 108.9));
+        assert!(close(sell.fee, 108.9 * size * 0.001));
+        let gross = (108.9 - 101.0) * size;
+        assert!(close(sell.pnl, gross - buy.fee - sell.fee));
+        assert!(close(result.summary.end_value, 1000.0 + sell.pnl));
+        assert!(close(result.summary.total_fees, buy.fee + sell.fee));
+        // The last point reflects the closing fill
+        assert!(close(result.equity[2].value, result.summary.end_value));
+    }
+
+    #[test]
+    fn stop_loss_and_take_profit() {
+        let mut strategy = strategy(
+            PositionSide::Long,
+            close_vs(Comparison::CrossesAbove, 99.0),
+            Vec::new(),
+        );
+        strategy.stop_loss_pct = Some(5.0);
+        strategy.take_profit_pct = Some(10.0);
+
+        // Enters at 100; the candle after touches 94 and the stop fills at 95
+        let candles = vec![
+            candle(0, 90.0, 90.0, 90.0, 90.0),
+            candle(1, 100.0, 100.0, 100.0, 100.0),
+            candle(2, 100.0, 100.0, 100.0, 100.0),
+            candle(3, 100.0, 101.0, 94.0, 98.0),
+            candle(4, 98.0, 98.0, 98.0, 98.0),
+        ];
+        let result = run("BTC", &candles, &strategy, &frictionless()).unwrap();
+        assert_eq!(result.trades.len(), 2);
+        assert!(close(result.trades[0].price, 100.0));
+        assert!(close(result.trades[1].price, 95.0));
+        assert_eq!(result.trades[1].executed_at, 3000);
+
+        // Reaching both in one candle counts as the stop
+        let mut both = candles.clone();
+        both[3] = candle(3, 100.0, 111.0, 94.0, 105.0);
+        let result = run("BTC", &both, &strategy, &frictionless()).unwrap();
+        assert!(close(result.trades[1].price, 95.0));
+
+        // Target reached
+        let mut target = candles.clone();
+        target[3] = candle(3, 100.0, 112.0, 99.0, 111.0);
+        let result = run("BTC", &target, &strategy, &frictionless()).unwrap();
+        assert!(close(result.trades[1].price, 110.0));
+
+        // Gapping through the stop fills at the open
+        let mut gap = candles;
+        gap[3] = candle(3, 90.0, 91.0, 89.0, 90.0);
+        let result = run("BTC", &gap, &strategy, &frictionless()).unwrap();
+        assert!(close(result.trades[1].price, 90.0));
+    }
+
+    #[test]
+    fn short_profits_from_decline() {
+        let candles = flat(&[100.0, 100.0, 80.0]);
+        let mut strategy = strategy(
+            PositionSide::Short,
+            close_vs(Comparison::Above, 0.0),
+            Vec::new(),
+        );
+        strategy.stop_loss_pct = Some(10.0);
+        let result = run("BTC", &candles, &strategy, &frictionless()).unwrap();
+        let (sell, buy) = (&result.trades[0], &result.trades[1]);
+        assert_eq!((sell.side, buy.side), (OrderSide::Sell, OrderSide::Buy));
+        assert!(close(buy.pnl, 10.0 * 20.0));
+        assert!(close(result.summary.end_value, 1200.0));
+
+        // A rise through the stop above the entry
+        let rising = vec![
+            candle(0, 100.0, 100.0, 100.0, 100.0),
+            candle(1, 100.0, 100.0, 100.0, 100.0),
+            candle(2, 100.0, 115.0, 100.0, 112.0),
+        ];
+        let result = run("BTC", &rising, &strategy, &frictionless()).unwrap();
+        assert!(close(result.trades[1].price, 110.0));
+        assert!(close(result.summary.end_value, 900.0));
+    }
+
+    #[test]
+    fn drawdown_and_win_rate() {
+        // Two round trips: the first loses, the second wins
+        let candles = flat(&[100.0, 100.0, 80.0, 80.0, 80.0, 120.0]);
+        let strategy = Strategy {
+            exit: vec![close_vs(Comparison::Below, 90.0)],
+            ..strategy(
+                PositionSide::Long,
+                close_vs(Comparison::Above, 0.0),
+                Vec::new(),
+            )
+        };
+        let result = run("BTC", &candles, &strategy, &frictionless()).unwrap();
+
+        // Buy at 100, exit at 80, buy at 80, close at 120
+        let prices: Vec<f64> = result.trades.iter().map(|t| t.price).collect();
+        assert_eq!(prices, [100.0, 80.0, 80.0, 120.0]);
+        assert_eq!(result.summary.total_trades, 2);
+        assert_eq!(result.summary.winning_trades, 1);
+        assert_eq!(result.summary.losing_trades, 1);
+        assert_eq!(result.summary.win_rate, 50.0);
+        assert!(close(result.summary.max_drawdown_percent, 20.0));
+        assert!(close(result.drawdowns[2].drawdown_percent, 20.0));
+        assert!(close(result.summary.end_value, 1200.0));
+        // 400 gained against 200 lost
+        assert!(close(result.summary.profit_factor.unwrap(), 2.0));
+    }
+
+    #[test]
+    fn sizing_rules() {
+        let candles = flat(&[100.0, 100.0, 100.0]);
+        let mut strategy = strategy(
+            PositionSide::Long,
+            close_vs(Comparison::Above, 0.0),
+            Vec::new(),
+        );
+        let size = |strategy: &Strategy| {
+            run("BTC", &candles, strategy, &frictionless())
+                .unwrap()
+                .trades[0]
+                .size
+        };
+
+        strategy.sizing = Sizing::Units { size: 2.0 };
+        assert!(close(size(&strategy), 2.0));
+        strategy.sizing = Sizing::Notional { amount: 300.0 };
+        assert!(close(size(&strategy), 3.0));
+        strategy.sizing = Sizing::Equity { percent: 50.0 };
+        assert!(close(size(&strategy), 5.0));
+        // 1% of equity at risk over a 2% stop: 500 notional
+        strategy.sizing = Sizing::Risk { percent: 1.0 };
+        strategy.stop_loss_pct = Some(2.0);
+        assert!(close(size(&strategy), 5.0));
+        // Never more than equity
+        strategy.sizing = Sizing::Units { size: 50.0 };
+        assert!(close(size(&strategy), 10.0));
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        let strategy = strategy(
+            PositionSide::Long,
+            close_vs(Comparison::Above, 0.0),
+            Vec::new(),
+        );
+        assert!(matches!(
+            run("BTC", &[], &strategy, &frictionless()),
+            Err(BacktestError::NoHistory)
+        ));
+        let config = BacktestConfig {
+            fee_pct: -1.0,
+            ..frictionless()
+        };
+        assert!(matches!(
+            run("BTC", &flat(&[1.0]), &strategy, &config),
+            Err(BacktestError::InvalidConfig)
+        ));
+    }
+}
@@ -0,0 +1,377 @@
+//! Backtest strategies
+//!
+//! A strategy enters when every entry condition holds at a candle's close
+//! and exits when any exit condition does, or when its stop loss or take
+//! profit is reached. Conditions compare an indicator with a fixed
+//! threshold or with another indicator, e.g. RSI below 30 or the close
+//! crossing above its 50-period SMA.
+
+use serde::{Deserialize, Serialize};
+
+use crate::haunt::models::{OhlcPoint, PositionSide};
+use crate::indicators;
+
+/// Errors in a strategy definition
+#[derive(Debug, thiserror::Error)]
+pub enum StrategyError {
+    #[error("Strategy needs at least one entry condition")]
+    NoEntry,
+    #[error("Indicator periods must be at least 1")]
+    InvalidPeriod,
+    #[error("Stop loss and take profit must be positive percentages")]
+    InvalidExit,
+    #[error("Position size must be positive")]
+    InvalidSize,
+    #[error("Risk-based sizing needs a stop loss")]
+    RiskWithoutStop,
+}
+
+/// A series a condition can test, computed over the whole backtest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Source {
+    Close,
+    Sma {
+        period: usize,
+    },
+    Ema {
+        period: usize,
+    },
+    Rsi {
+        period: usize,
+    },
+    /// MACD histogram
+    Macd {
+        fast: usize,
+        slow: usize,
+        signal: usize,
+    },
+    /// Stochastic %K
+    Stochastic {
+        period: usize,
+    },
+    Cci {
+        period: usize,
+    },
+    Mfi {
+        period: usize,
+    },
+    /// Bollinger %B: 0 at the lower band, 1 at the upper
+    Bollinger {
+        period: usize,
+        width: f64,
+    },
+    Atr {
+        period: usize,
+    },
+    Adx {
+        period: usize,
+    },
+}
+
+impl Source {
+    fn periods(&self) -> Vec<usize> {
+        match *self {
+            Self::Close => Vec::new(),
+            Self::Macd { fast, slow, signal } => vec![fast, slow, signal],
+            Self::Sma { period }
+            | Self::Ema { period }
+            | Self::Rsi { period }
+            | Self::Stochastic { period }
+            | Self::Cci { period }
+            | Self::Mfi { period }
+            | Self::Bollinger { period, .. }
+            | Self::Atr { period }
+            | Self::Adx { period } => vec![period],
+        }
+    }
+
+    /// One value per candle, `None` until the indicator has history
+    pub fn series(&self, candles: &[OhlcPoint]) -> Vec<Option<f64>> {
+        let closes: Vec<f64> = candles.iter().map(|c| c.close).collect();
+        match *self {
+            Self::Close => closes.into_iter().map(Some).collect(),
+            Self::Sma { period } => indicators::sma(&closes, period),
+            Self::Ema { period } => indicators::ema(&closes, period),
+            Self::Rsi { period } => indicators::rsi(&closes, period),
+            Self::Macd { fast, slow, signal } => {
+                indicators::macd(&closes, fast, slow, signal).histogram
+            }
+            Self::Stochastic { period } => indicators::stochastic(candles, period, 1).k,
+            Self::Cci { period } => indicators::cci(candles, period),
+            Self::Mfi { period } => indicators::mfi(candles, period),
+            Self::Bollinger { period, width } => {
+                let bands = indicators::bollinger(&closes, period, width);
+                closes
+                    .iter()
+                    .zip(bands.upper.iter().zip(&bands.lower))
+                    .map(|(close, bands)| match bands {
+                        (Some(upper), Some(lower)) if upper > lower => {
+                            Some((close - lower) / (upper - lower))
+                        }
+                        (Some(_), Some(_)) => Some(0.5),
+                        _ => None,
+                    })
+                    .collect()
+            }
+            Self::Atr { period } => indicators::atr(candles, period),
+            Self::Adx { period } => indicators::adx(candles, period).adx,
+        }
+    }
+}
+
+/// What a condition compares its indicator with
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Threshold {
+    Value(f64),
+    Source(Source),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Comparison {
+    Above,
+    Below,
+    CrossesAbove,
+    CrossesBelow,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Condition {
+    pub indicator: Source,
+    pub op: Comparison,
+    pub threshold: Threshold,
+}
+
+/// How large each position is
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Sizing {
+    /// A fixed quantity of the asset
+    Units { size: f64 },
+    /// A fixed amount of the quote currency
+    Notional { amount: f64 },
+    /// A share of current equity
+    Equity { percent: f64 },
+    /// Sized so hitting the stop loss costs this share of equity
+    Risk { percent: f64 },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Strategy {
+    pub side: PositionSide,
+    pub entry: Vec<Condition>,
+    #[serde(default)]
+    pub exit: Vec<Condition>,
+    /// Percent from the entry fill
+    pub stop_loss_pct: Option<f64>,
+    /// Percent from the entry fill
+    pub take_profit_pct: Option<f64>,
+    pub sizing: Sizing,
+}
+
+impl Strategy {
+    pub fn validate(&self) -> Result<(), StrategyError> {
+        if self.entry.is_empty() {
+            return Err(StrategyError::NoEntry);
+        }
+        let sources = self.entry.iter().chain(&self.exit).flat_map(|c| {
+            let threshold = match &c.threshold {
+                Threshold::Source(source) => Some(source),
+                Threshold::Value(_) => None,
+            };
+            std::iter::once(&c.indicator).chain(threshold)
+        });
+        for source in sources {
+            if source.periods().contains(&0) {
+                return Err(StrategyError::InvalidPeriod);
+            }
+        }
+        let exits = [self.stop_loss_pct, self.take_profit_pct];
+        if exits
+            .iter()
+            .flatten()
+            .any(|pct| !(pct.is_finite() && *pct > 0.0))
+        {
+            return Err(StrategyError::InvalidExit);
+        }
+        let amount = match self.sizing {
+            Sizing::Units { size } => size,
+            Sizing::Notional { amount } => amount,
+            Sizing::Equity { percent } | Sizing::Risk { percent } => percent,
+        };
+        if !(amount.is_finite() && amount > 0.0) {
+            return Err(StrategyError::InvalidSize);
+        }
+        if matches!(self.sizing, Sizing::Risk { .. }) && self.stop_loss_pct.is_none() {
+            return Err(StrategyError::RiskWithoutStop);
+        }
+        Ok(())
+    }
+}
+
+/// A condition with its series computed, tested candle by candle
+pub(super) struct Compiled {
+    indicator: Vec<Option<f64>>,
+    op: Comparison,
+    threshold: Vec<Option<f64>>,
+}
+
+impl Compiled {
+    pub fn new(condition: &Condition, candles: &[OhlcPoint]) -> Self {
+        let threshold = match &condition.threshold {
+            Threshold::Value(value) => vec![Some(*value); candles.len()],
+            Threshold::Source(source) => source.series(candles),
+        };
+        Self {
+            indicator: condition.indicator.series(candles),
+            op: condition.op,
+            threshold,
+        }
+    }
+
+    /// Whether the condition holds at the close of candle `i`; crossings
+    /// compare with the candle before
+    pub fn holds(&self, i: usize) -> bool {
+        let at = |i: usize| Some((self.indicator[i]?, self.threshold[i]?));
+        let Some((value, threshold)) = at(i) else {
+            return false;
+        };
+        let previous = i.checked_sub(1).and_then(at);
+        match self.op {
+            Comparison::Above => value > threshold,
+            Comparison::Below => value < threshold,
+            Comparison::CrossesAbove => value > threshold && previous.is_some_and(|(v, t)| v <= t),
+            Comparison::CrossesBelow => value < threshold && previous.is_some_and(|(v, t)| v >= t),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candles(closes: &[f64]) -> Vec<OhlcPoint> {
+        closes
+            .iter()
+            .enumerate()
+            .map(|(i, &close)| OhlcPoint {
+                time: i as i64,
+                open: close,
+                high: close,
+                low: close,
+                close,
+                volume: None,
+            })
+            .collect()
+    }
+
+    fn strategy(json: serde_json::Value) -> Strategy {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn parses_thresholds_and_sources() {
+        let strategy = strategy(serde_json::json!({
+            "side": "long",
+            "entry": [
+                { "indicator": { "type": "rsi", "period": 14 }, "op": "below", "threshold": 30 },
+                {
+                    "indicator": { "type": "close" },
+                    "op": "crosses_above",
+                    "threshold": { "type": "sma", "period": 50 }
+                }
+            ],
+            "stopLossPct": 2,
+            "sizing": { "type": "risk", "percent": 1 }
+        }));
+        assert!(strategy.validate().is_ok());
+        assert!(matches!(strategy.entry[0].threshold, Threshold::Value(v) if v == 30.0));
+        assert!(matches!(
+            strategy.entry[1].threshold,
+            Threshold::Source(Source::Sma { period: 50 })
+        ));
+        assert!(strategy.exit.is_empty());
+    }
+
+    #[test]
+    fn validates_strategies() {
+        let base = serde_json::json!({
+            "side": "long",
+            "entry": [{ "indicator": { "type": "close" }, "op": "above", "threshold": 1 }],
+            "sizing": { "type": "equity", "percent": 100 }
+        });
+        assert!(strategy(base.clone()).validate().is_ok());
+
+        let mut no_entry = strategy(base.clone());
+        no_entry.entry.clear();
+        assert!(matches!(no_entry.validate(), Err(StrategyError::NoEntry)));
+
+        let mut zero_period = strategy(base.clone());
+        zero_period.entry[0].threshold = Threshold::Source(Source::Ema { period: 0 });
+        assert!(matches!(
+            zero_period.validate(),
+            Err(StrategyError::InvalidPeriod)
+        ));
+
+        let mut risk = strategy(base.clone());
+        risk.sizing = Sizing::Risk { percent: 1.0 };
+        assert!(matches!(
+            risk.validate(),
+            Err(StrategyError::RiskWithoutStop)
+        ));
+
+        let mut negative = strategy(base);
+        negative.take_profit_pct = Some(-1.0);
+        assert!(matches!(
+            negative.validate(),
+            Err(StrategyError::InvalidExit)
+        ));
+    }
+
+    #[test]
+    fn evaluates_conditions() {
+        let candles = candles(&[1.0, 3.0, 2.0, 4.0]);
+        let compiled = |op, threshold| {
+            Compiled::new(
+                &Condition {
+                    indicator: Source::Close,
+                    op,
+                    threshold,
+                },
+                &candles,
+            )
+        };
+
+        let above = compiled(Comparison::Above, Threshold::Value(2.5));
+        assert_eq!(
+            (0..4).map(|i| above.holds(i)).collect::<Vec<_>>(),
+            [false, true, false, true]
+        );
+
+        let crosses = compiled(Comparison::CrossesAbove, Threshold::Value(2.5));
+        // No candle before the first, so it can't cross there
+        assert_eq!(
+            (0..4).map(|i| crosses.holds(i)).collect::<Vec<_>>(),
+            [false, true, false, true]
+        );
+        let crosses = compiled(Comparison::CrossesBelow, Threshold::Value(2.5));
+        assert_eq!(
+            (0..4).map(|i| crosses.holds(i)).collect::<Vec<_>>(),
+            [false, false, true, false]
+        );
+
+        // Against a two-candle SMA, which has no value for the first candle
+        let sma = compiled(
+            Comparison::CrossesAbove,
+            Threshold::Source(Source::Sma { period: 2 }),
+        );
+        assert_eq!(
+            (0..4).map(|i| sma.holds(i)).collect::<Vec<_>>(),
+            [false, false, false, true]
+        );
+    }
+}
@@ -0,0 +1,587 @@
+//! Local market-data cache
+//!
+//! SQLite store for the latest listings, sparklines and OHLC chart series,
+//! so the dashboard has something to draw on a cold start before Haunt
+//! responds. Everything served from here carries its age and a `stale` flag;
+//! the frontend shows it and refreshes in the background.
+
+use std::path::Path;
+use std::sync::Mutex;
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use tauri::{Manager, State};
+
+use crate::haunt::models::{Asset, OhlcPoint};
+use crate::now_millis;
+
+const CACHE_FILE: &str = "market-cache.sqlite3";
+
+/// Schema migrations, applied in order; `PRAGMA user_version` records how
+/// many have run. Only ever append to this list.
+const MIGRATIONS: &[&str] = &[
+    // 1: listings, sparklines and chart series
+    "CREATE TABLE listings (
+        id INTEGER PRIMARY KEY,
+        rank INTEGER NOT NULL,
+        symbol TEXT NOT NULL,
+        asset TEXT NOT NULL,
+        updated_at INTEGER NOT NULL
+    );
+    CREATE TABLE sparklines (
+        asset_id INTEGER PRIMARY KEY,
+        points TEXT NOT NULL,
+        updated_at INTEGER NOT NULL
+    );
+    CREATE TABLE charts (
+        asset_id INTEGER NOT NULL,
+        range TEXT NOT NULL,
+        points TEXT NOT NULL,
+        updated_at INTEGER NOT NULL,
+        accessed_at INTEGER NOT NULL,
+        PRIMARY KEY (asset_id, range)
+    );
+    CREATE INDEX charts_accessed_at ON charts (accessed_at);",
+];
+
+/// Listings older than this are dropped
+const LISTING_MAX_AGE_MS: i64 = 7 * 24 * 60 * 60 * 1000;
+/// Most listings kept, by rank
+const MAX_LISTINGS: i64 = 1000;
+/// Most chart series kept, least recently read evicted first
+const MAX_CHART_SERIES: i64 = 200;
+/// Listings older than this are served as stale
+const LISTINGS_STALE_MS: i64 = 60 * 1000;
+
+/// Errors produced by the cache
+#[derive(Debug, thiserror::Error)]
+pub enum CacheError {
+    #[error("Market cache is not open")]
+    NotOpen,
+    #[error("Market cache was written by a newer version (schema {0})")]
+    FutureSchema(i64),
+    #[error("Market cache error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error("Market cache contains invalid data: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Cached data with its age
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Cached<T> {
+    pub data: T,
+    /// Unix milliseconds of the oldest entry in `data`
+    pub updated_at: i64,
+    pub stale: bool,
+}
+
+/// Chart series go stale at roughly one candle's width
+fn chart_stale_after_ms(range: &str) -> i64 {
+    let minutes = match range {
+        "1h" => 1,
+        "4h" => 5,
+        "1d" => 15,
+        "1w" => 60,
+        _ => 6 * 60,
+    };
+    minutes * 60 * 1000
+}
+
+/// Bring a database up to the latest schema in `migrations`
+///
+/// `newer` builds the error for a database written by a later release,
+/// from the schema version it records.
+pub(crate) fn migrate<E: From<rusqlite::Error>>(
+    conn: &mut Connection,
+    migrations: &[&str],
+    newer: impl FnOnce(i64) -> E,
+) -> Result<(), E> {
+    let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    if version > migrations.len() as i64 {
+        return Err(newer(version));
+    }
+
+    for (index, migration) in migrations.iter().enumerate().skip(version as usize) {
+        let tx = conn.transaction()?;
+        tx.execute_batch(migration)?;
+        tx.pragma_update(None, "user_version", index as i64 + 1)?;
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+/// The cache database
+pub struct MarketCache {
+    conn: Connection,
+}
+
+impl MarketCache {
+    pub fn open(path: &Path) -> Result<Self, CacheError> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    fn with_connection(mut conn: Connection) -> Result<Self, CacheError> {
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        migrate(&mut conn, MIGRATIONS, CacheError::FutureSchema)?;
+        Ok(Self { conn })
+    }
+
+    /// Upsert listings and their sparklines, then evict old rows
+    pub fn store_listings(&mut self, assets: &[Asset], now: i64) -> Result<(), CacheError> {
+        let tx = self.conn.transaction()?;
+        {
+            let mut listing = tx.prepare_cached(
+                "INSERT OR REPLACE INTO listings (id, rank, symbol, asset, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?;
+            let mut sparkline = tx.prepare_cached(
+                "INSERT OR REPLACE INTO sparklines (asset_id, points, updated_at)
+                 VALUES (?1, ?2, ?3)",
+            )?;
+
+            for asset in assets {
+                // Sparklines live in their own table; don't store them twice
+                let mut stored = asset.clone();
+                stored.sparkline.clear();
+                listing.execute(params![
+                    asset.id,
+                    asset.rank,
+                    asset.symbol,
+                    serde_json::to_string(&stored)?,
+                    now
+                ])?;
+                if !asset.sparkline.is_empty() {
+                    sparkline.execute(params![
+                        asset.id,
+                        serde_json::to_string(&asset.sparkline)?,
+                        now
+                    ])?;
+                }
+            }
+        }
+        Self::evict_listings(&tx, now)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn evict_listings(conn: &Connection, now: i64) -> Result<(), CacheError> {
+        conn.execute(
+            "DELETE FROM listings WHERE updated_at < ?1",
+            params![now - LISTING_MAX_AGE_MS],
+        )?;
+        conn.execute(
+            "DELETE FROM listings WHERE id NOT IN (
+                SELECT id FROM listings ORDER BY updated_at DESC, rank ASC LIMIT ?1
+            )",
+            params![MAX_LISTINGS],
+        )?;
+        conn.execute(
+            "DELETE FROM sparklines WHERE asset_id NOT IN (SELECT id FROM listings)",
+            [],
+        )?;
+        Ok(())
+    }
+
+    /// Cached listings by rank, with sparklines attached
+    pub fn listings(
+        &self,
+        limit: Option<u32>,
+        now: i64,
+    ) -> Result<Option<Cached<Vec<Asset>>>, CacheError> {
+        let mut statement = self.conn.prepare_cached(
+            "SELECT l.asset, s.points, l.updated_at FROM listings l
+             LEFT JOIN sparklines s ON s.asset_id = l.id
+             ORDER BY l.rank ASC, l.id ASC LIMIT ?1",
+        )?;
+        let limit = limit.map_or(-1, i64::from);
+        let rows = statement.query_map(params![limit], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<String>>(1)?,
+                row.get::<_, i64>(2)?,
+            ))
+        })?;
+
+        let mut assets = Vec::new();
+        let mut oldest = i64::MAX;
+        for row in rows {
+            let (asset, sparkline, updated_at) = row?;
+            let mut asset: Asset = serde_json::from_str(&asset)?;
+            if let Some(points) = sparkline {
+                asset.sparkline = serde_json::from_str(&points)?;
+            }
+            oldest = oldest.min(updated_at);
+            assets.push(asset);
+        }
+
+        if assets.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Cached {
+            data: assets,
+            updated_at: oldest,
+            stale: now - oldest > LISTINGS_STALE_MS,
+        }))
+    }
+
+    /// Replace the series for an asset and range, then evict old series
+    pub fn store_chart(
+        &mut self,
+        asset_id: i64,
+        range: &str,
+        points: &[OhlcPoint],
+        now: i64,
+    ) -> Result<(), CacheError> {
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "INSERT OR REPLACE INTO charts (asset_id, range, points, updated_at, accessed_at)
+             VALUES (?1, ?2, ?3, ?4, ?4)",
+            params![asset_id, range, serde_json::to_string(points)?, now],
+        )?;
+        tx.execute(
+            "DELETE FROM charts WHERE rowid NOT IN (
+                SELECT rowid FROM charts ORDER BY accessed_at DESC LIMIT ?1
+            )",
+            params![MAX_CHART_SERIES],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Cached series for an asset and range, marking it recently used
+    pub fn chart(
+        &self,
+        asset_id: i64,
+        range: &str,
+        now: i64,
+    ) -> Result<Option<Cached<Vec<OhlcPoint>>>, CacheError> {
+        let row: Option<(String, i64)> = self
+            .conn
+            .query_row(
+                "SELECT points, updated_at FROM charts WHERE asset_id = ?1 AND range = ?2",
+                params![asset_id, range],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        let Some((points, updated_at)) = row else {
+            return Ok(None);
+        };
+
+        self.conn.execute(
+            "UPDATE charts SET accessed_at = ?3 WHERE asset_id = ?1 AND range = ?2",
+            params![asset_id, range, now],
+        )?;
+        Ok(Some(Cached {
+            data: serde_json::from_str(&points)?,
+            updated_at,
+            stale: now - updated_at > chart_stale_after_ms(range),
+        }))
+    }
+
+    pub fn clear(&self) -> Result<(), CacheError> {
+        self.conn
+            .execute_batch("DELETE FROM listings; DELETE FROM sparklines; DELETE FROM charts;")?;
+        Ok(())
+    }
+}
+
+/// Managed state holding the open cache
+#[derive(Default)]
+pub struct CacheState {
+    cache: Mutex<Option<MarketCache>>,
+}
+
+impl CacheState {
+    fn with<T>(
+        &self,
+        f: impl FnOnce(&mut MarketCache) -> Result<T, CacheError>,
+    ) -> Result<T, CacheError> {
+        let mut guard = self.cache.lock().unwrap();
+        f(guard.as_mut().ok_or(CacheError::NotOpen)?)
+    }
+
+    /// Write fresh listings through to the cache, logging failures
+    pub fn record_listings(&self, assets: &[Asset]) {
+        if let Err(e) = self.with(|cache| cache.store_listings(assets, now_millis())) {
+            eprintln!("Failed to cache listings: {}", e);
+        }
+    }
+
+    /// Write a fresh chart series through to the cache, logging failures
+    pub fn record_chart(&self, asset_id: i64, range: &str, points: &[OhlcPoint]) {
+        if let Err(e) = self.with(|cache| cache.store_chart(asset_id, range, points, now_millis()))
+        {
+            eprintln!("Failed to cache chart: {}", e);
+        }
+    }
+
+    /// Read a cached chart series however stale, logging failures
+    pub fn cached_chart(&self, asset_id: i64, range: &str) -> Option<Vec<OhlcPoint>> {
+        match self.with(|cache| cache.chart(asset_id, range, now_millis())) {
+            Ok(cached) => cached.map(|c| c.data),
+            Err(e) => {
+                eprintln!("Failed to read cached chart: {}", e);
+                None
+            }
+        }
+    }
+}
+
+/// Open the cache in the app data directory
+///
+/// A cache that can't be opened or migrated is deleted and rebuilt; it only
+/// holds data that can be fetched again.
+pub fn init(app: &tauri::AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+    let data_dir = app.path().app_data_dir()?;
+    std::fs::create_dir_all(&data_dir)?;
+    let path = data_dir.join(CACHE_FILE);
+
+    let cache = match MarketCache::open(&path) {
+        Ok(cache) => cache,
+        Err(e) => {
+            eprintln!("Rebuilding market cache: {}", e);
+            for suffix in ["", "-wal", "-shm"] {
+                let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+            }
+            MarketCache::open(&path)?
+        }
+    };
+    *app.state::<CacheState>().cache.lock().unwrap() = Some(cache);
+    Ok(())
+}
+
+/// Tauri command: Get cached listings for an instant first paint
+#[tauri::command]
+pub fn cache_get_listings(
+    state: State<'_, CacheState>,
+    limit: Option<u32>,
+) -> Result<Option<Cached<Vec<Asset>>>, String> {
+    state
+        .with(|cache| cache.listings(limit, now_millis()))
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Get a cached chart series
+#[tauri::command]
+pub fn cache_get_chart(
+    state: State<'_, CacheState>,
+    id: i64,
+    range: String,
+) -> Result<Option<Cached<Vec<OhlcPoint>>>, String> {
+    state
+        .with(|cache| cache.chart(id, &range, now_millis()))
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Store listings fetched by the webview
+#[tauri::command]
+pub fn cache_store_listings(
+    state: State<'_, CacheState>,
+    assets: Vec<Asset>,
+) -> Result<(), String> {
+    state
+        .with(|cache| cache.store_listings(&assets, now_millis()))
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Store a chart series fetched by the webview
+#[tauri::command]
+pub fn cache_store_chart(
+    state: State<'_, CacheState>,
+    id: i64,
+    range: String,
+    points: Vec<OhlcPoint>,
+) -> Result<(), String> {
+    state
+        .with(|cache| cache.store_chart(id, &range, &points, now_millis()))
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Delete all cached market data
+#[tauri::command]
+pub fn cache_clear(state: State<'_, CacheState>) -> Result<(), String> {
+    state.with(|cache| cache.clear()).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn memory_cache() -> MarketCache {
+        MarketCache::with_connection(Connection::open_in_memory().unwrap()).unwrap()
+    }
+
+    fn asset(id: i64, rank: u32) -> Asset {
+        serde_json::from_value(serde_json::json!({
+            "id": id, "rank": rank, "name": format!("Coin {id}"), "symbol": format!("C{id}"),
+            "price": 1.0, "change1h": 0.0, "change24h": 0.0, "change7d": 0.0,
+            "marketCap": 0.0, "volume24h": 0.0, "circulatingSupply": 0.0,
+            "sparkline": [1.0, 2.0, id as f64]
+        }))
+        .unwrap()
+    }
+
+    fn candle(time: i64) -> OhlcPoint {
+        OhlcPoint {
+            time,
+            open: 1.0,
+            high: 2.0,
+            low: 0.5,
+            close: 1.5,
+            volume: Some(10.0),
+        }
+    }
+
+    fn count(cache: &MarketCache, table: &str) -> i64 {
+        cache
+            .conn
+            .query_row(&format!("SELECT COUNT(*) FROM {table}"), [], |row| {
+                row.get(0)
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn listings_round_trip_with_sparklines() {
+        let mut cache = memory_cache();
+        assert!(cache.listings(None, 0).unwrap().is_none());
+
+        cache
+            .store_listings(&[asset(2, 2), asset(1, 1)], 1_000)
+            .unwrap();
+        let cached = cache.listings(None, 1_000).unwrap().unwrap();
+        assert_eq!(cached.data[0].id, 1);
+        assert_eq!(cached.data[1].sparkline, vec![1.0, 2.0, 2.0]);
+        assert!(!cached.stale);
+
+        let later = cache
+            .listings(Some(1), 1_000 + LISTINGS_STALE_MS + 1)
+            .unwrap()
+            .unwrap();
+        assert_eq!(later.data.len(), 1);
+        assert!(later.stale);
+    }
+
+    #[test]
+    fn old_listings_are_evicted_with_their_sparklines() {
+        let mut cache = memory_cache();
+        cache.store_listings(&[asset(1, 1)], 0).unwrap();
+        cache
+            .store_listings(&[asset(2, 2)], LISTING_MAX_AGE_MS + 1)
+            .unwrap();
+
+        let cached = cache
+            .listings(None, LISTING_MAX_AGE_MS + 1)
+            .unwrap()
+            .unwrap();
+        assert_eq!(cached.data.len(), 1);
+        assert_eq!(cached.data[0].id, 2);
+        assert_eq!(count(&cache, "sparklines"), 1);
+    }
+
+    #[test]
+    fn listings_are_capped() {
+        let mut cache = memory_cache();
+        let assets: Vec<Asset> = (1..=MAX_LISTINGS + 5)
+            .map(|id| asset(id, id as u32))
+            .collect();
+        cache.store_listings(&assets, 1).unwrap();
+
+        assert_eq!(count(&cache, "listings"), MAX_LISTINGS);
+        assert_eq!(count(&cache, "sparklines"), MAX_LISTINGS);
+        let cached = cache.listings(None, 1).unwrap().unwrap();
+        assert_eq!(cached.data.last().unwrap().rank as i64, MAX_LISTINGS);
+    }
+
+    #[test]
+    fn charts_are_per_range_and_stale_by_range() {
+        let mut cache = memory_cache();
+        cache.store_chart(1, "1h", &[candle(1)], 0).unwrap();
+        cache
+            .store_chart(1, "1w", &[candle(1), candle(2)], 0)
+            .unwrap();
+
+        let hour = cache.chart(1, "1h", 2 * 60 * 1000).unwrap().unwrap();
+        assert_eq!(hour.data, vec![candle(1)]);
+        assert!(hour.stale);
+
+        let week = cache.chart(1, "1w", 2 * 60 * 1000).unwrap().unwrap();
+        assert_eq!(week.data.len(), 2);
+        assert!(!week.stale);
+        assert!(cache.chart(1, "1d", 0).unwrap().is_none());
+    }
+
+    #[test]
+    fn least_recently_read_charts_are_evicted() {
+        let mut cache = memory_cache();
+        for id in 0..MAX_CHART_SERIES {
+            cache.store_chart(id, "1d", &[candle(id)], id).unwrap();
+        }
+        // Reading the oldest series keeps it alive
+        cache.chart(0, "1d", 10_000).unwrap().unwrap();
+        cache.store_chart(999, "1d", &[candle(0)], 10_001).unwrap();
+
+        assert_eq!(count(&cache, "charts"), MAX_CHART_SERIES);
+        assert!(cache.chart(0, "1d", 10_002).unwrap().is_some());
+        assert!(cache.chart(1, "1d", 10_002).unwrap().is_none());
+    }
+
+    #[test]
+    fn migrations_run_once_and_keep_data() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        let v1 = &["CREATE TABLE t (a INTEGER);", "INSERT INTO t VALUES (1);"][..1];
+        migrate(&mut conn, v1, CacheError::FutureSchema).unwrap();
+        conn.execute("INSERT INTO t VALUES (7)", []).unwrap();
+
+        let v2 = &[
+            "CREATE TABLE t (a INTEGER);",
+            "ALTER TABLE t ADD COLUMN b TEXT;",
+        ];
+        migrate(&mut conn, v2, CacheError::FutureSchema).unwrap();
+        migrate(&mut conn, v2, CacheError::FutureSchema).unwrap();
+
+        let version: i64 = conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, 2);
+        let (a, b): (i64, Option<String>) = conn
+            .query_row("SELECT a, b FROM t", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!((a, b), (7, None));
+
+        assert!(matches!(
+            migrate(&mut conn, v1, CacheError::FutureSchema),
+            Err(CacheError::FutureSchema(2))
+        ));
+    }
+
+    #[test]
+    fn failed_migration_rolls_back() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        let broken = &["CREATE TABLE t (a INTEGER);", "CREATE TABLE u (; nonsense"];
+        assert!(migrate(&mut conn, broken, CacheError::FutureSchema).is_err());
+
+        let version: i64 = conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, 1);
+    }
+
+    #[test]
+    fn latest_schema_applies_to_a_file() {
+        let dir = std::env::temp_dir().join(format!("wraith-cache-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(CACHE_FILE);
+
+        let mut cache = MarketCache::open(&path).unwrap();
+        cache.store_chart(1, "1d", &[candle(1)], 0).unwrap();
+        drop(cache);
+
+        let cache = MarketCache::open(&path).unwrap();
+        assert!(cache.chart(1, "1d", 0).unwrap().is_some());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,1018 @@
+//! `wraith://` deep link router
+//!
+//! Links are parsed into typed [`DeepLink`] routes before anything reaches
+//! the frontend. Parsing is strict: the scheme, route and parameter names
+//! must be known, every parameter must be valid for its type, and links
+//! carrying credentials, ports, fragments or repeated parameters are
+//! rejected. Valid links are emitted as [`ROUTE_EVENT`] and, in canonical
+//! form, as the string [`LINK_EVENT`] older listeners expect; rejected
+//! ones as [`REJECTED_EVENT`]. Trade links are the exception: they never
+//! reach the frontend and instead wait for approval in
+//! [`trade_confirm`](crate::trade_confirm).
+//!
+//! A link Wraith was launched with arrives before the frontend listens, so
+//! it is kept until the frontend collects it with
+//! `deep_link_launch_routes`.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_deep_link::DeepLinkExt;
+use url::Url;
+
+use crate::haunt::models::{AlertCondition, AssetClass, OrderSide, OrderType, PlaceOrderRequest};
+use crate::trade_confirm;
+
+/// Event carrying a routed [`DeepLink`]
+pub const ROUTE_EVENT: &str = "deep-link-route";
+/// Event carrying the canonical link string of a routed [`DeepLink`]
+pub const LINK_EVENT: &str = "deep-link";
+/// Event carrying a [`RejectedLink`]
+pub const REJECTED_EVENT: &str = "deep-link-rejected";
+
+const SCHEME: &str = "wraith";
+const MAX_LINK_LEN: usize = 2048;
+const MAX_SYMBOL_LEN: usize = 20;
+const MAX_ID_LEN: usize = 64;
+const MAX_CODE_LEN: usize = 512;
+const MAX_STATE_LEN: usize = 128;
+const MAX_SERVER_URL_LEN: usize = 256;
+const MAX_NAME_LEN: usize = 64;
+/// Largest size or price a link may carry
+const MAX_AMOUNT: f64 = 1e12;
+const MAX_LEVERAGE: f64 = 125.0;
+/// Characters of a rejected link echoed back in [`RejectedLink`]
+const REJECTED_ECHO_LEN: usize = 256;
+
+#[derive(Debug, PartialEq, thiserror::Error)]
+pub enum DeepLinkError {
+    #[error("Link is too long")]
+    TooLong,
+    #[error("Not a valid link: {0}")]
+    Malformed(String),
+    #[error("Not a wraith:// link")]
+    Scheme,
+    #[error("Links can't carry credentials, ports or fragments")]
+    Authority,
+    #[error("Unknown route \"{0}\"")]
+    UnknownRoute(String),
+    #[error("Unknown parameter \"{0}\"")]
+    UnknownParam(String),
+    #[error("Parameter \"{0}\" is repeated")]
+    RepeatedParam(String),
+    #[error("Missing parameter \"{0}\"")]
+    MissingParam(&'static str),
+    #[error("Invalid {0}")]
+    Invalid(&'static str),
+}
+
+/// A validated `wraith://` link
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "route", rename_all = "camelCase")]
+pub enum DeepLink {
+    /// `wraith://asset/{symbol}`
+    Asset { symbol: String },
+    /// `wraith://portfolio/{id}`
+    Portfolio { id: String },
+    /// `wraith://trade?symbol=&side=&size=`, see [`TradeLink`]
+    Trade(TradeLink),
+    /// `wraith://alert/new` with optional `symbol`, `condition` and `price`
+    NewAlert {
+        symbol: Option<String>,
+        condition: Option<AlertCondition>,
+        price: Option<f64>,
+    },
+    /// `wraith://auth/callback?code=` with an optional `state`
+    AuthCallback { code: String, state: Option<String> },
+    /// `wraith://server/add?url=` with an optional `name`
+    AddServer { url: String, name: Option<String> },
+}
+
+/// An order carried by a `wraith://trade` link
+///
+/// Besides `symbol`, `side` and `size` a link may set `type` (`market`,
+/// `limit` or `stop_limit`), `price` and `stop` as the type requires,
+/// `class` (`spot`, `perp`, `stock` or `etf`), `leverage` for perps, and
+/// `sl`/`tp`. Leverage implies a perp when no class is given.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TradeLink {
+    pub symbol: String,
+    pub asset_class: AssetClass,
+    pub side: OrderSide,
+    pub order_type: OrderType,
+    pub size: f64,
+    pub price: Option<f64>,
+    pub stop_price: Option<f64>,
+    pub leverage: Option<f64>,
+    pub stop_loss: Option<f64>,
+    pub take_profit: Option<f64>,
+}
+
+impl TradeLink {
+    fn parse(params: &mut Params) -> Result<Self, DeepLinkError> {
+        let symbol = parse_symbol(&params.require("symbol")?)?;
+        let side = parse_side(&params.require("side")?)?;
+        let size = parse_amount(&params.require("size")?, "size")?;
+        let order_type = params
+            .take("type")
+            .map(|t| parse_order_type(&t))
+            .transpose()?
+            .unwrap_or(OrderType::Market);
+        let price = params
+            .take("price")
+            .map(|p| parse_amount(&p, "price"))
+            .transpose()?;
+        let stop_price = params
+            .take("stop")
+            .map(|p| parse_amount(&p, "stop price"))
+            .transpose()?;
+        let leverage = params
+            .take("leverage")
+            .map(|l| parse_leverage(&l))
+            .transpose()?;
+        let asset_class = match params.take("class") {
+            Some(class) => parse_asset_class(&class)?,
+            None if leverage.is_some() => AssetClass::Perp,
+            None => AssetClass::CryptoSpot,
+        };
+        let stop_loss = params
+            .take("sl")
+            .map(|p| parse_amount(&p, "stop loss"))
+            .transpose()?;
+        let take_profit = params
+            .take("tp")
+            .map(|p| parse_amount(&p, "take profit"))
+            .transpose()?;
+
+        let (needs_price, needs_stop) = match order_type {
+            OrderType::Limit => (true, false),
+            OrderType::StopLimit => (true, true),
+            _ => (false, false),
+        };
+        match (needs_price, price) {
+            (true, None) => return Err(DeepLinkError::MissingParam("price")),
+            (false, Some(_)) => return Err(DeepLinkError::UnknownParam("price".to_string())),
+            _ => {}
+        }
+        match (needs_stop, stop_price) {
+            (true, None) => return Err(DeepLinkError::MissingParam("stop")),
+            (false, Some(_)) => return Err(DeepLinkError::UnknownParam("stop".to_string())),
+            _ => {}
+        }
+        if leverage.is_some() && asset_class != AssetClass::Perp {
+            return Err(DeepLinkError::Invalid("leverage"));
+        }
+        // With a known entry, protective orders must sit on the right side
+        // of it; a link setting them the other way round is a trap
+        if let Some(price) = price {
+            let buy = side == OrderSide::Buy;
+            let wrong_side = |level: Option<f64>, below: bool| {
+                level.is_some_and(|level| {
+                    if below {
+                        level >= price
+                    } else {
+                        level <= price
+                    }
+                })
+            };
+            if wrong_side(stop_loss, buy) {
+                return Err(DeepLinkError::Invalid("stop loss"));
+            }
+            if wrong_side(take_profit, !buy) {
+                return Err(DeepLinkError::Invalid("take profit"));
+            }
+        }
+
+        Ok(Self {
+            symbol,
+            asset_class,
+            side,
+            order_type,
+            size,
+            price,
+            stop_price,
+            leverage,
+            stop_loss,
+            take_profit,
+        })
+    }
+
+    fn append_query(&self, query: &mut url::form_urlencoded::Serializer<'_, String>) {
+        let side = match self.side {
+            OrderSide::Buy => "buy",
+            OrderSide::Sell => "sell",
+        };
+        let order_type = match self.order_type {
+            OrderType::Limit => "limit",
+            OrderType::StopLimit => "stop_limit",
+            _ => "market",
+        };
+        let class = match self.asset_class {
+            AssetClass::Perp => "perp",
+            AssetClass::Stock => "stock",
+            AssetClass::Etf => "etf",
+            _ => "spot",
+        };
+        query
+            .append_pair("symbol", &self.symbol)
+            .append_pair("side", side)
+            .append_pair("size", &self.size.to_string())
+            .append_pair("type", order_type)
+            .append_pair("class", class);
+        let optional = [
+            ("price", self.price),
+            ("stop", self.stop_price),
+            ("leverage", self.leverage),
+            ("sl", self.stop_loss),
+            ("tp", self.take_profit),
+        ];
+        for (name, value) in optional {
+            if let Some(value) = value {
+                query.append_pair(name, &value.to_string());
+            }
+        }
+    }
+
+    /// The order this link asks for, placed in `portfolio_id`
+    pub fn order(&self, portfolio_id: &str) -> PlaceOrderRequest {
+        PlaceOrderRequest {
+            portfolio_id: portfolio_id.to_string(),
+            symbol: self.symbol.clone(),
+            asset_class: self.asset_class,
+            side: self.side,
+            order_type: self.order_type,
+            quantity: self.size,
+            price: self.price,
+            stop_price: self.stop_price,
+            trail_amount: None,
+            trail_percent: None,
+            time_in_force: None,
+            leverage: self.leverage,
+            stop_loss: self.stop_loss,
+            take_profit: self.take_profit,
+            reduce_only: None,
+            post_only: None,
+            margin_mode: None,
+            bypass_drawdown: None,
+        }
+    }
+}
+
+/// Payload of [`REJECTED_EVENT`]
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RejectedLink {
+    /// The start of the link as received
+    pub link: String,
+    pub error: String,
+}
+
+/// Query parameters, each of which a route must claim
+struct Params(BTreeMap<String, String>);
+
+impl Params {
+    fn parse(url: &Url) -> Result<Self, DeepLinkError> {
+        let mut params = BTreeMap::new();
+        for (name, value) in url.query_pairs() {
+            if params.contains_key(name.as_ref()) {
+                return Err(DeepLinkError::RepeatedParam(name.into_owned()));
+            }
+            params.insert(name.into_owned(), value.into_owned());
+        }
+        Ok(Self(params))
+    }
+
+    fn take(&mut self, name: &'static str) -> Option<String> {
+        self.0.remove(name)
+    }
+
+    fn require(&mut self, name: &'static str) -> Result<String, DeepLinkError> {
+        self.take(name).ok_or(DeepLinkError::MissingParam(name))
+    }
+
+    /// Fail on any parameter the route didn't claim
+    fn finish(self) -> Result<(), DeepLinkError> {
+        match self.0.into_keys().next() {
+            Some(name) => Err(DeepLinkError::UnknownParam(name)),
+            None => Ok(()),
+        }
+    }
+}
+
+fn parse_symbol(value: &str) -> Result<String, DeepLinkError> {
+    let valid = (1..=MAX_SYMBOL_LEN).contains(&value.len())
+        && value.chars().all(|c| c.is_ascii_alphanumeric());
+    if !valid {
+        return Err(DeepLinkError::Invalid("symbol"));
+    }
+    Ok(value.to_ascii_uppercase())
+}
+
+fn parse_id(value: &str) -> Result<String, DeepLinkError> {
+    let valid = (1..=MAX_ID_LEN).contains(&value.len())
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(DeepLinkError::Invalid("portfolio id"));
+    }
+    Ok(value.to_string())
+}
+
+fn parse_side(value: &str) -> Result<OrderSide, DeepLinkError> {
+    match value.to_ascii_lowercase().as_str() {
+        "buy" => Ok(OrderSide::Buy),
+        "sell" => Ok(OrderSide::Sell),
+        _ => Err(DeepLinkError::Invalid("side")),
+    }
+}
+
+fn parse_order_type(value: &str) -> Result<OrderType, DeepLinkError> {
+    match value.to_ascii_lowercase().as_str() {
+        "market" => Ok(OrderType::Market),
+        "limit" => Ok(OrderType::Limit),
+        "stop_limit" => Ok(OrderType::StopLimit),
+        _ => Err(DeepLinkError::Invalid("order type")),
+    }
+}
+
+fn parse_asset_class(value: &str) -> Result<AssetClass, DeepLinkError> {
+    match value.to_ascii_lowercase().as_str() {
+        "spot" => Ok(AssetClass::CryptoSpot),
+        "perp" => Ok(AssetClass::Perp),
+        "stock" => Ok(AssetClass::Stock),
+        "etf" => Ok(AssetClass::Etf),
+        _ => Err(DeepLinkError::Invalid("asset class")),
+    }
+}
+
+/// Leverage between 1x and [`MAX_LEVERAGE`]
+fn parse_leverage(value: &str) -> Result<f64, DeepLinkError> {
+    let leverage = parse_amount(value, "leverage")?;
+    if !(1.0..=MAX_LEVERAGE).contains(&leverage) {
+        return Err(DeepLinkError::Invalid("leverage"));
+    }
+    Ok(leverage)
+}
+
+fn parse_condition(value: &str) -> Result<AlertCondition, DeepLinkError> {
+    match value.to_ascii_lowercase().as_str() {
+        "above" => Ok(AlertCondition::Above),
+        "below" => Ok(AlertCondition::Below),
+        "crosses" => Ok(AlertCondition::Crosses),
+        _ => Err(DeepLinkError::Invalid("condition")),
+    }
+}
+
+/// A positive, finite amount no larger than [`MAX_AMOUNT`]
+fn parse_amount(value: &str, what: &'static str) -> Result<f64, DeepLinkError> {
+    // Plain decimals only; f64 parsing also accepts "inf" and "NaN"
+    let plain = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '-' | '+'));
+    let amount: f64 = value
+        .parse()
+        .ok()
+        .filter(|_| plain)
+        .ok_or(DeepLinkError::Invalid(what))?;
+    if !(amount > 0.0 && amount <= MAX_AMOUNT) {
+        return Err(DeepLinkError::Invalid(what));
+    }
+    Ok(amount)
+}
+
+/// Opaque URL-safe token such as an OAuth code or state
+fn parse_token(value: &str, max_len: usize, what: &'static str) -> Result<String, DeepLinkError> {
+    let valid = (1..=max_len).contains(&value.len())
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '~'));
+    if !valid {
+        return Err(DeepLinkError::Invalid(what));
+    }
+    Ok(value.to_string())
+}
+
+/// An http(s) server base URL without credentials, query or fragment
+fn parse_server_url(value: &str) -> Result<String, DeepLinkError> {
+    let invalid = DeepLinkError::Invalid("server url");
+    if value.len() > MAX_SERVER_URL_LEN {
+        return Err(invalid);
+    }
+    let url = Url::parse(value).map_err(|_| DeepLinkError::Invalid("server url"))?;
+    let valid = matches!(url.scheme(), "http" | "https")
+        && url.host_str().is_some_and(|host| !host.is_empty())
+        && url.username().is_empty()
+        && url.password().is_none()
+        && url.query().is_none()
+        && url.fragment().is_none();
+    if !valid {
+        return Err(invalid);
+    }
+    let url = url.as_str().trim_end_matches('/');
+    if url.len() > MAX_SERVER_URL_LEN {
+        return Err(invalid);
+    }
+    Ok(url.to_string())
+}
+
+/// Display name with control and bidi override characters removed
+fn parse_name(value: &str) -> Result<String, DeepLinkError> {
+    let name: String = value
+        .chars()
+        .filter(|c| {
+            !c.is_control() && !matches!(c, '\u{202a}'..='\u{202e}' | '\u{2066}'..='\u{2069}')
+        })
+        .collect();
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LEN {
+        return Err(DeepLinkError::Invalid("server name"));
+    }
+    Ok(name.to_string())
+}
+
+impl DeepLink {
+    pub fn parse(link: &str) -> Result<Self, DeepLinkError> {
+        if link.len() > MAX_LINK_LEN {
+            return Err(DeepLinkError::TooLong);
+        }
+        let url = Url::parse(link).map_err(|e| DeepLinkError::Malformed(e.to_string()))?;
+        if url.scheme() != SCHEME {
+            return Err(DeepLinkError::Scheme);
+        }
+        if !url.username().is_empty()
+            || url.password().is_some()
+            || url.port().is_some()
+            || url.fragment().is_some()
+        {
+            return Err(DeepLinkError::Authority);
+        }
+
+        // wraith://asset/BTC parses with "asset" as the host
+        let host = url.host_str().unwrap_or_default();
+        let path = url.path();
+        let path = path.strip_suffix('/').unwrap_or(path);
+        let segments: Vec<&str> = match path.strip_prefix('/') {
+            Some(rest) => rest.split('/').collect(),
+            None if path.is_empty() => Vec::new(),
+            None => return Err(DeepLinkError::UnknownRoute(path.to_string())),
+        };
+
+        let mut params = Params::parse(&url)?;
+        let route = match (host, segments.as_slice()) {
+            ("asset", [symbol]) => Self::Asset {
+                symbol: parse_symbol(symbol)?,
+            },
+            ("portfolio", [id]) => Self::Portfolio { id: parse_id(id)? },
+            ("trade", []) => Self::Trade(TradeLink::parse(&mut params)?),
+            ("alert", ["new"]) => Self::NewAlert {
+                symbol: params
+                    .take("symbol")
+                    .map(|s| parse_symbol(&s))
+                    .transpose()?,
+                condition: params
+                    .take("condition")
+                    .map(|c| parse_condition(&c))
+                    .transpose()?,
+                price: params
+                    .take("price")
+                    .map(|p| parse_amount(&p, "price"))
+                    .transpose()?,
+            },
+            ("auth", ["callback"]) => Self::AuthCallback {
+                code: parse_token(&params.require("code")?, MAX_CODE_LEN, "code")?,
+                state: params
+                    .take("state")
+                    .map(|s| parse_token(&s, MAX_STATE_LEN, "state"))
+                    .transpose()?,
+            },
+            ("server", ["add"]) => Self::AddServer {
+                url: parse_server_url(&params.require("url")?)?,
+                name: params.take("name").map(|n| parse_name(&n)).transpose()?,
+            },
+            _ => {
+                let route: String = format!("{}{}", host, path)
+                    .chars()
+                    .take(MAX_ID_LEN)
+                    .collect();
+                return Err(DeepLinkError::UnknownRoute(route));
+            }
+        };
+        params.finish()?;
+        Ok(route)
+    }
+
+    /// Canonical link for this route; parses back to an equal route
+    pub fn to_url(&self) -> String {
+        let mut query = url::form_urlencoded::Serializer::new(String::new());
+        let path = match self {
+            Self::Asset { symbol } => return format!("{}://asset/{}", SCHEME, symbol),
+            Self::Portfolio { id } => return format!("{}://portfolio/{}", SCHEME, id),
+            Self::Trade(trade) => {
+                trade.append_query(&mut query);
+                "trade"
+            }
+            Self::NewAlert {
+                symbol,
+                condition,
+                price,
+            } => {
+                if let Some(symbol) = symbol {
+                    query.append_pair("symbol", symbol);
+                }
+                if let Some(condition) = condition {
+                    let condition = match condition {
+                        AlertCondition::Above => "above",
+                        AlertCondition::Below => "below",
+                        AlertCondition::Crosses => "crosses",
+                    };
+                    query.append_pair("condition", condition);
+                }
+                if let Some(price) = price {
+                    query.append_pair("price", &price.to_string());
+                }
+                "alert/new"
+            }
+            Self::AuthCallback { code, state } => {
+                query.append_pair("code", code);
+                if let Some(state) = state {
+                    query.append_pair("state", state);
+                }
+                "auth/callback"
+            }
+            Self::AddServer { url, name } => {
+                query.append_pair("url", url);
+                if let Some(name) = name {
+                    query.append_pair("name", name);
+                }
+                "server/add"
+            }
+        };
+        let query = query.finish();
+        if query.is_empty() {
+            format!("{}://{}", SCHEME, path)
+        } else {
+            format!("{}://{}?{}", SCHEME, path, query)
+        }
+    }
+}
+
+/// Parse a link and emit it as a route, or report why it was rejected;
+/// trade links go to confirmation instead
+pub fn route(app: &AppHandle, link: &str) {
+    match DeepLink::parse(link) {
+        Ok(DeepLink::Trade(trade)) => trade_confirm::request(app, link, trade),
+        Ok(route) => {
+            let _ = app.emit(LINK_EVENT, route.to_url());
+            let _ = app.emit(ROUTE_EVENT, &route);
+        }
+        Err(e) => {
+            eprintln!("Rejected deep link: {}", e);
+            if is_trade_link(link) {
+                trade_confirm::audit_invalid(app, link, &e);
+            }
+            let _ = app.emit(
+                REJECTED_EVENT,
+                RejectedLink {
+                    link: link.chars().take(REJECTED_ECHO_LEN).collect(),
+                    error: e.to_string(),
+                },
+            );
+        }
+    }
+}
+
+/// Whether a link that failed to parse was meant to place an order
+fn is_trade_link(link: &str) -> bool {
+    Url::parse(link).is_ok_and(|url| url.scheme() == SCHEME && url.host_str() == Some("trade"))
+}
+
+/// Managed state for deep links
+#[derive(Default)]
+pub struct DeepLinkState {
+    /// Routes from the launch link, until the frontend collects them
+    launch: Mutex<Vec<DeepLink>>,
+}
+
+/// Route links opened while running and keep the one Wraith was launched
+/// with
+pub fn init(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+    let handle = app.clone();
+    app.deep_link().on_open_url(move |event| {
+        for url in event.urls() {
+            route(&handle, url.as_str());
+        }
+    });
+
+    let Some(urls) = app.deep_link().get_current()? else {
+        return Ok(());
+    };
+    let state = app.state::<DeepLinkState>();
+    let mut launch = state.launch.lock().unwrap();
+    for url in urls {
+        match DeepLink::parse(url.as_str()) {
+            Ok(DeepLink::Trade(trade)) => trade_confirm::request(app, url.as_str(), trade),
+            Ok(route) => launch.push(route),
+            Err(e) => {
+                eprintln!("Rejected deep link: {}", e);
+                if is_trade_link(url.as_str()) {
+                    trade_confirm::audit_invalid(app, url.as_str(), &e);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// ========== Commands ==========
+
+/// Tauri command: Parse and validate a `wraith://` link
+#[tauri::command]
+pub fn deep_link_parse(link: String) -> Result<DeepLink, String> {
+    DeepLink::parse(&link).map_err(|e| e.to_string())
+}
+
+/// Tauri command: Take the routes of the link Wraith was launched with
+#[tauri::command]
+pub fn deep_link_launch_routes(state: State<'_, DeepLinkState>) -> Vec<DeepLink> {
+    std::mem::take(&mut *state.launch.lock().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck::{Arbitrary, Gen, QuickCheck};
+
+    fn parse(link: &str) -> Result<DeepLink, DeepLinkError> {
+        DeepLink::parse(link)
+    }
+
+    #[test]
+    fn parses_every_route() {
+        assert_eq!(
+            parse("wraith://asset/btc").unwrap(),
+            DeepLink::Asset {
+                symbol: "BTC".to_string()
+            }
+        );
+        assert_eq!(
+            parse("wraith://portfolio/p_01-a/").unwrap(),
+            DeepLink::Portfolio {
+                id: "p_01-a".to_string()
+            }
+        );
+        assert_eq!(
+            parse("wraith://trade?symbol=eth&side=SELL&size=0.25").unwrap(),
+            DeepLink::Trade(TradeLink {
+                symbol: "ETH".to_string(),
+                asset_class: AssetClass::CryptoSpot,
+                side: OrderSide::Sell,
+                order_type: OrderType::Market,
+                size: 0.25,
+                price: None,
+                stop_price: None,
+                leverage: None,
+                stop_loss: None,
+                take_profit: None,
+            })
+        );
+        assert_eq!(
+            parse(
+                "wraith://trade?symbol=btc&side=buy&size=1&type=stop_limit&price=100&stop=99\
+                 &leverage=5&sl=90&tp=120"
+            )
+            .unwrap(),
+            DeepLink::Trade(TradeLink {
+                symbol: "BTC".to_string(),
+                asset_class: AssetClass::Perp,
+                side: OrderSide::Buy,
+                order_type: OrderType::StopLimit,
+                size: 1.0,
+                price: Some(100.0),
+                stop_price: Some(99.0),
+                leverage: Some(5.0),
+                stop_loss: Some(90.0),
+                take_profit: Some(120.0),
+            })
+        );
+        assert_eq!(
+            parse("wraith://alert/new").unwrap(),
+            DeepLink::NewAlert {
+                symbol: None,
+                condition: None,
+                price: None,
+            }
+        );
+        assert_eq!(
+            parse("wraith://alert/new?symbol=sol&condition=above&price=150").unwrap(),
+            DeepLink::NewAlert {
+                symbol: Some("SOL".to_string()),
+                condition: Some(AlertCondition::Above),
+                price: Some(150.0),
+            }
+        );
+        assert_eq!(
+            parse("wraith://auth/callback?code=abc.DEF-123&state=xyz").unwrap(),
+            DeepLink::AuthCallback {
+                code: "abc.DEF-123".to_string(),
+                state: Some("xyz".to_string()),
+            }
+        );
+        assert_eq!(
+            parse("wraith://server/add?url=https%3A%2F%2Fhaunt.example.com%2F&name=%E2%80%AEEU%0A")
+                .unwrap(),
+            DeepLink::AddServer {
+                url: "https://haunt.example.com".to_string(),
+                name: Some("EU".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_links() {
+        let cases: &[(&str, DeepLinkError)] = &[
+            ("https://asset/BTC", DeepLinkError::Scheme),
+            ("wraith://user@asset/BTC", DeepLinkError::Authority),
+            ("wraith://asset:80/BTC", DeepLinkError::Authority),
+            ("wraith://asset/BTC#x", DeepLinkError::Authority),
+            (
+                "wraith://settings",
+                DeepLinkError::UnknownRoute("settings".to_string()),
+            ),
+            (
+                "wraith://asset/BTC/chart",
+                DeepLinkError::UnknownRoute("asset/BTC/chart".to_string()),
+            ),
+            ("wraith://asset/BT%43", DeepLinkError::Invalid("symbol")),
+            ("wraith://asset/<script>", DeepLinkError::Invalid("symbol")),
+            (
+                "wraith://asset/BTC?x=1",
+                DeepLinkError::UnknownParam("x".to_string()),
+            ),
+            (
+                "wraith://trade?symbol=BTC&side=buy",
+                DeepLinkError::MissingParam("size"),
+            ),
+            (
+                "wraith://trade?symbol=BTC&side=hold&size=1",
+                DeepLinkError::Invalid("side"),
+            ),
+            (
+                "wraith://trade?symbol=BTC&side=buy&size=1&size=2",
+                DeepLinkError::RepeatedParam("size".to_string()),
+            ),
+            (
+                "wraith://trade?symbol=BTC&side=buy&size=-1",
+                DeepLinkError::Invalid("size"),
+            ),
+            (
+                "wraith://trade?symbol=BTC&side=buy&size=inf",
+                DeepLinkError::Invalid("size"),
+            ),
+            (
+                "wraith://trade?symbol=BTC&side=buy&size=1e400",
+                DeepLinkError::Invalid("size"),
+            ),
+            (
+                "wraith://trade?symbol=BTC&side=buy&size=1&type=limit",
+                DeepLinkError::MissingParam("price"),
+            ),
+            (
+                "wraith://trade?symbol=BTC&side=buy&size=1&price=100",
+                DeepLinkError::UnknownParam("price".to_string()),
+            ),
+            (
+                "wraith://trade?symbol=BTC&side=buy&size=1&type=trailing_stop",
+                DeepLinkError::Invalid("order type"),
+            ),
+            (
+                "wraith://trade?symbol=BTC&side=buy&size=1&leverage=500",
+                DeepLinkError::Invalid("leverage"),
+            ),
+            (
+                "wraith://trade?symbol=BTC&side=buy&size=1&class=spot&leverage=2",
+                DeepLinkError::Invalid("leverage"),
+            ),
+            (
+                "wraith://trade?symbol=BTC&side=buy&size=1&type=limit&price=100&sl=110",
+                DeepLinkError::Invalid("stop loss"),
+            ),
+            (
+                "wraith://trade?symbol=BTC&side=sell&size=1&type=limit&price=100&tp=110",
+                DeepLinkError::Invalid("take profit"),
+            ),
+            (
+                "wraith://alert/new?price=NaN",
+                DeepLinkError::Invalid("price"),
+            ),
+            (
+                "wraith://auth/callback?code=a%20b",
+                DeepLinkError::Invalid("code"),
+            ),
+            (
+                "wraith://server/add?url=javascript%3Aalert(1)",
+                DeepLinkError::Invalid("server url"),
+            ),
+            (
+                "wraith://server/add?url=https%3A%2F%2Fu%3Ap%40evil.com",
+                DeepLinkError::Invalid("server url"),
+            ),
+            (
+                "wraith://server/add?url=https%3A%2F%2Fa.com&name=%0A",
+                DeepLinkError::Invalid("server name"),
+            ),
+        ];
+        for (link, error) in cases {
+            assert_eq!(parse(link).as_ref(), Err(error), "{}", link);
+        }
+        assert_eq!(
+            parse(&format!("wraith://asset/{}", "A".repeat(MAX_LINK_LEN))),
+            Err(DeepLinkError::TooLong)
+        );
+        assert!(matches!(
+            parse("not a link"),
+            Err(DeepLinkError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn serializes_tagged_routes() {
+        let route = parse("wraith://trade?symbol=BTC&side=buy&size=2").unwrap();
+        assert_eq!(
+            serde_json::to_value(&route).unwrap(),
+            serde_json::json!({
+                "route": "trade",
+                "symbol": "BTC",
+                "assetClass": "crypto_spot",
+                "side": "buy",
+                "orderType": "market",
+                "size": 2.0,
+                "price": null,
+                "stopPrice": null,
+                "leverage": null,
+                "stopLoss": null,
+                "takeProfit": null,
+            })
+        );
+        let route = parse("wraith://server/add?url=http%3A%2F%2Flocalhost%3A3001").unwrap();
+        assert_eq!(
+            serde_json::to_value(&route).unwrap(),
+            serde_json::json!({ "route": "addServer", "url": "http://localhost:3001", "name": null })
+        );
+    }
+
+    // ========== Property tests ==========
+
+    fn pick(g: &mut Gen, alphabet: &str, min: usize, max: usize) -> String {
+        let chars: Vec<char> = alphabet.chars().collect();
+        let len = min + usize::arbitrary(g) % (max - min + 1);
+        (0..len).map(|_| *g.choose(&chars).unwrap()).collect()
+    }
+
+    fn amount(g: &mut Gen) -> f64 {
+        let mantissa = (u32::arbitrary(g) % 1_000_000 + 1) as f64;
+        let scale = *g.choose(&[1e-8, 1e-4, 1e-2, 1.0, 1e3]).unwrap();
+        mantissa * scale
+    }
+
+    const ALNUM: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+    const UPPER: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+
+    impl Arbitrary for DeepLink {
+        fn arbitrary(g: &mut Gen) -> Self {
+            let symbol = |g: &mut Gen| pick(g, UPPER, 1, MAX_SYMBOL_LEN);
+            match u8::arbitrary(g) % 6 {
+                0 => Self::Asset { symbol: symbol(g) },
+                1 => Self::Portfolio {
+                    id: pick(g, &format!("{}-_", ALNUM), 1, MAX_ID_LEN),
+                },
+                2 => Self::Trade(TradeLink::arbitrary(g)),
+                3 => Self::NewAlert {
+                    symbol: bool::arbitrary(g).then(|| symbol(g)),
+                    condition: g
+                        .choose(&[
+                            None,
+                            Some(AlertCondition::Above),
+                            Some(AlertCondition::Below),
+                            Some(AlertCondition::Crosses),
+                        ])
+                        .copied()
+                        .unwrap(),
+                    price: bool::arbitrary(g).then(|| amount(g)),
+                },
+                4 => Self::AuthCallback {
+                    code: pick(g, &format!("{}-_.~", ALNUM), 1, 64),
+                    state: bool::arbitrary(g).then(|| pick(g, ALNUM, 1, 32)),
+                },
+                _ => Self::AddServer {
+                    url: format!(
+                        "{}://{}.example.com",
+                        g.choose(&["http", "https"]).unwrap(),
+                        pick(g, "abcdefghij", 1, 12)
+                    ),
+                    name: bool::arbitrary(g).then(|| {
+                        let name = pick(g, "Server Ünïcødé 東京 -_", 1, MAX_NAME_LEN);
+                        let name = name.trim().to_string();
+                        if name.is_empty() {
+                            "EU".to_string()
+                        } else {
+                            name
+                        }
+                    }),
+                },
+            }
+        }
+    }
+
+    impl Arbitrary for TradeLink {
+        fn arbitrary(g: &mut Gen) -> Self {
+            let side = *g.choose(&[OrderSide::Buy, OrderSide::Sell]).unwrap();
+            let order_type = *g
+                .choose(&[OrderType::Market, OrderType::Limit, OrderType::StopLimit])
+                .unwrap();
+            let asset_class = *g
+                .choose(&[
+                    AssetClass::CryptoSpot,
+                    AssetClass::Perp,
+                    AssetClass::Stock,
+                    AssetClass::Etf,
+                ])
+                .unwrap();
+            let price = (order_type != OrderType::Market).then(|| amount(g));
+            // Protective levels on the valid side of a known entry
+            let (low, high) = match price {
+                Some(price) => (price / 2.0, price * 2.0),
+                None => (amount(g), amount(g)),
+            };
+            let (stop_loss, take_profit) = match side {
+                OrderSide::Buy => (low, high),
+                OrderSide::Sell => (high, low),
+            };
+            Self {
+                symbol: pick(g, UPPER, 1, MAX_SYMBOL_LEN),
+                asset_class,
+                side,
+                order_type,
+                size: amount(g),
+                price,
+                stop_price: (order_type == OrderType::StopLimit).then(|| amount(g)),
+                leverage: (asset_class == AssetClass::Perp && bool::arbitrary(g))
+                    .then(|| (u8::arbitrary(g) % 125 + 1) as f64),
+                stop_loss: bool::arbitrary(g).then_some(stop_loss),
+                take_profit: bool::arbitrary(g).then_some(take_profit),
+            }
+        }
+    }
+
+    #[test]
+    fn canonical_links_round_trip() {
+        fn prop(route: DeepLink) -> bool {
+            parse(&route.to_url()).as_ref() == Ok(&route)
+        }
+        QuickCheck::new()
+            .tests(2000)
+            .quickcheck(prop as fn(DeepLink) -> bool);
+    }
+
+    #[test]
+    fn never_panics_on_arbitrary_input() {
+        fn prop(input: String) -> bool {
+            let _ = parse(&input);
+            let _ = parse(&format!("wraith://{}", input));
+            let _ = parse(&format!("wraith://trade?{}", input));
+            true
+        }
+        QuickCheck::new()
+            .tests(2000)
+            .quickcheck(prop as fn(String) -> bool);
+    }
+
+    #[test]
+    fn mutated_links_parse_to_canonical_routes() {
+        // Whatever survives a random edit must still be a valid route whose
+        // canonical form parses back to itself
+        fn prop(route: DeepLink, at: usize, byte: u8, delete: bool) -> bool {
+            let mut link = route.to_url().into_bytes();
+            let at = at % link.len();
+            if delete {
+                link.remove(at);
+            } else {
+                link[at] = byte;
+            }
+            let Ok(link) = String::from_utf8(link) else {
+                return true;
+            };
+            match parse(&link) {
+                Ok(mutated) => parse(&mutated.to_url()).as_ref() == Ok(&mutated),
+                Err(_) => true,
+            }
+        }
+        QuickCheck::new()
+            .tests(5000)
+            .quickcheck(prop as fn(DeepLink, usize, u8, bool) -> bool);
+    }
+}
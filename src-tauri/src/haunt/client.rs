@@ -0,0 +1,1426 @@
+//! Haunt REST client
+//!
+//! Mirrors `HauntClient` in `src/services/haunt.ts`: `fetch` for public
+//! endpoints, `fetch_with_auth` for endpoints that need a session token.
+
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+use reqwest::{Method, RequestBuilder};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use super::models::*;
+
+/// Default server used until the frontend or server selection picks one
+pub const DEFAULT_BASE_URL: &str = "http://localhost:3001";
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Errors produced by Haunt requests
+#[derive(Debug, thiserror::Error)]
+pub enum HauntError {
+    /// Non-2xx response, carrying the server's error code when present
+    #[error("{message}")]
+    Api {
+        status: u16,
+        code: String,
+        message: String,
+    },
+    #[error("Haunt request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("Unexpected Haunt response: {0}")]
+    Decode(#[from] serde_json::Error),
+}
+
+pub type Result<T> = std::result::Result<T, HauntError>;
+
+/// Typed client for the Haunt API
+pub struct HauntClient {
+    http: reqwest::Client,
+    base_url: RwLock<String>,
+}
+
+impl HauntClient {
+    pub fn new(base_url: &str) -> Self {
+        super::install_crypto_provider();
+
+        let http = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .user_agent(concat!("wraith-desktop/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .expect("failed to build HTTP client");
+
+        Self {
+            http,
+            base_url: RwLock::new(normalize_base_url(base_url)),
+        }
+    }
+
+    pub fn base_url(&self) -> String {
+        self.base_url.read().unwrap().clone()
+    }
+
+    /// Point the client at another server
+    pub fn set_base_url(&self, base_url: &str) {
+        *self.base_url.write().unwrap() = normalize_base_url(base_url);
+    }
+
+    fn request(&self, method: Method, endpoint: &str) -> RequestBuilder {
+        self.http
+            .request(method, format!("{}{}", self.base_url(), endpoint))
+    }
+
+    async fn send<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        request: RequestBuilder,
+    ) -> Result<T> {
+        let response = request.send().await?;
+        let status = response.status();
+        let bytes = response.bytes().await?;
+
+        if !status.is_success() {
+            let body: ApiErrorBody = serde_json::from_slice(&bytes).unwrap_or_default();
+            let mut message = body.error.or(body.message).unwrap_or_else(|| {
+                format!(
+                    "Haunt API error: {} {}",
+                    status.as_u16(),
+                    status.canonical_reason().unwrap_or("")
+                )
+            });
+            if status == reqwest::StatusCode::FORBIDDEN
+                && (endpoint.contains("/orders") || endpoint.contains("/positions"))
+            {
+                message = "Portfolio access denied. Please try logging out and back in.".into();
+            }
+
+            return Err(HauntError::Api {
+                status: status.as_u16(),
+                code: body.code.unwrap_or_default(),
+                message,
+            });
+        }
+
+        // Some endpoints (logout) reply with an empty body
+        let bytes = if bytes.is_empty() {
+            "null".into()
+        } else {
+            bytes
+        };
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    async fn fetch<T: DeserializeOwned>(&self, endpoint: &str) -> Result<T> {
+        self.send(endpoint, self.request(Method::GET, endpoint))
+            .await
+    }
+
+    async fn fetch_with_auth<T: DeserializeOwned>(&self, endpoint: &str, token: &str) -> Result<T> {
+        self.send(
+            endpoint,
+            self.request(Method::GET, endpoint).bearer_auth(token),
+        )
+        .await
+    }
+
+    async fn send_with_auth<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        endpoint: &str,
+        token: Option<&str>,
+        body: Option<&(impl Serialize + ?Sized)>,
+    ) -> Result<T> {
+        let mut request = self.request(method, endpoint);
+        if let Some(token) = token {
+            request = request.bearer_auth(token);
+        }
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+        self.send(endpoint, request).await
+    }
+
+    // ========== Mesh ==========
+
+    /// Check another server's health without switching to it, returning
+    /// the round-trip time
+    pub async fn probe(&self, base_url: &str) -> Result<Duration> {
+        let endpoint = "/api/health";
+        let request = self
+            .http
+            .get(format!("{}{}", normalize_base_url(base_url), endpoint))
+            .timeout(PROBE_TIMEOUT);
+
+        let started = Instant::now();
+        let _: HealthResponse = self.send(endpoint, request).await?;
+        Ok(started.elapsed())
+    }
+
+    /// Ask a server for the mesh it belongs to
+    pub async fn discover(&self, base_url: &str) -> Result<MeshDiscoveryResponse> {
+        let endpoint = "/api/mesh/servers";
+        let request = self
+            .http
+            .get(format!("{}{}", normalize_base_url(base_url), endpoint))
+            .timeout(PROBE_TIMEOUT);
+        self.send(endpoint, request).await
+    }
+
+    // ========== Market ==========
+
+    /// Get listings with optional filtering and sorting
+    pub async fn get_listings(&self, params: &ListingsParams) -> Result<ApiResponse<Vec<Asset>>> {
+        let mut query = Vec::new();
+        if let Some(start) = params.start.filter(|s| *s > 0) {
+            query.push(("start", start.to_string()));
+        }
+        if let Some(limit) = params.limit.filter(|l| *l > 0) {
+            query.push(("limit", limit.to_string()));
+        }
+        if let Some(sort) = &params.sort {
+            query.push(("sort", sort.clone()));
+        }
+        if let Some(sort_dir) = &params.sort_dir {
+            query.push(("sort_dir", sort_dir.clone()));
+        }
+        if let Some(filter) = params.filter.as_ref().filter(|f| *f != "all") {
+            query.push(("filter", filter.clone()));
+        }
+        if let Some(asset_type) = params.asset_type.as_ref().filter(|t| *t != "all") {
+            query.push(("asset_type", asset_type.clone()));
+        }
+        if let Some(min_change) = params.min_change {
+            query.push(("min_change", min_change.to_string()));
+        }
+        if let Some(max_change) = params.max_change {
+            query.push(("max_change", max_change.to_string()));
+        }
+
+        let endpoint = with_query("/api/crypto/listings", &query);
+        self.fetch(&endpoint).await
+    }
+
+    /// Get a single asset by ID
+    pub async fn get_asset(&self, id: i64) -> Result<ApiResponse<Asset>> {
+        let response: ApiResponse<Value> = self.fetch(&format!("/api/crypto/{id}")).await?;
+        Ok(ApiResponse {
+            data: Asset::from_value(response.data)?,
+            meta: response.meta,
+        })
+    }
+
+    /// Get latest quotes for an asset
+    pub async fn get_quotes(&self, id: i64) -> Result<ApiResponse<Asset>> {
+        self.fetch(&format!("/api/crypto/{id}/quotes")).await
+    }
+
+    /// Search assets by name or symbol
+    pub async fn search(&self, query: &str, limit: u32) -> Result<ApiResponse<Vec<Asset>>> {
+        let endpoint = with_query(
+            "/api/crypto/search",
+            &[("q", query.to_string()), ("limit", limit.to_string())],
+        );
+        self.fetch(&endpoint).await
+    }
+
+    pub async fn get_global_metrics(&self) -> Result<ApiResponse<GlobalMetrics>> {
+        self.fetch("/api/market/global").await
+    }
+
+    pub async fn get_fear_greed(&self) -> Result<ApiResponse<FearGreedData>> {
+        self.fetch("/api/market/fear-greed").await
+    }
+
+    /// Get OHLC chart data; `range` is "1h", "4h", "1d", "1w" or "1m"
+    pub async fn get_chart(&self, id: i64, range: &str) -> Result<ApiResponse<ChartData>> {
+        let endpoint = with_query(
+            &format!("/api/crypto/{id}/chart"),
+            &[("range", range.to_string())],
+        );
+        self.fetch(&endpoint).await
+    }
+
+    /// Trigger historical data seeding for a symbol
+    pub async fn seed_symbol(&self, symbol: &str) -> Result<ApiResponse<SeedResponse>> {
+        self.send_with_auth(
+            Method::POST,
+            "/api/crypto/seed",
+            None,
+            Some(&json!({ "symbol": symbol })),
+        )
+        .await
+    }
+
+    pub async fn get_symbol_source_stats(
+        &self,
+        symbol: &str,
+    ) -> Result<ApiResponse<SymbolSourceStats>> {
+        self.fetch(&format!(
+            "/api/market/source-stats/{}",
+            path_segment(&symbol.to_lowercase())
+        ))
+        .await
+    }
+
+    /// Get top movers; `timeframe` is "1m", "5m", "15m", "1h", "4h" or "24h"
+    pub async fn get_movers(
+        &self,
+        timeframe: &str,
+        limit: u32,
+        asset_type: Option<&str>,
+    ) -> Result<ApiResponse<MoversResponse>> {
+        let mut query = vec![
+            ("timeframe", timeframe.to_string()),
+            ("limit", limit.to_string()),
+        ];
+        if let Some(asset_type) = asset_type.filter(|t| *t != "all") {
+            query.push(("asset_type", asset_type.to_string()));
+        }
+        self.fetch(&with_query("/api/market/movers", &query)).await
+    }
+
+    pub async fn get_stats(&self) -> Result<ApiResponse<ApiStats>> {
+        self.fetch("/api/market/stats").await
+    }
+
+    pub async fn get_confidence(&self, symbol: &str) -> Result<ApiResponse<Opaque>> {
+        self.fetch(&format!(
+            "/api/market/confidence/{}",
+            path_segment(&symbol.to_lowercase())
+        ))
+        .await
+    }
+
+    pub async fn get_exchange_dominance(&self, symbol: &str) -> Result<ApiResponse<Opaque>> {
+        self.fetch(&format!(
+            "/api/market/exchange-dominance/{}",
+            path_segment(&symbol.to_lowercase())
+        ))
+        .await
+    }
+
+    pub async fn health(&self) -> Result<HealthResponse> {
+        self.fetch("/api/health").await
+    }
+
+    /// Get the aggregated order book; `depth` is capped at 100 by the server
+    pub async fn get_order_book(
+        &self,
+        symbol: &str,
+        depth: u32,
+    ) -> Result<DataResponse<AggregatedOrderBook>> {
+        let endpoint = with_query(
+            &format!("/api/orderbook/{}", path_segment(&symbol.to_lowercase())),
+            &[("depth", depth.to_string())],
+        );
+        self.fetch(&endpoint).await
+    }
+
+    /// Get current funding rates, optionally for a subset of symbols
+    pub async fn get_funding_rates(
+        &self,
+        symbols: &[String],
+    ) -> Result<ApiResponse<Vec<FundingRate>>> {
+        let query = if symbols.is_empty() {
+            vec![]
+        } else {
+            vec![("symbols", symbols.join(","))]
+        };
+        self.fetch(&with_query("/api/market/funding", &query)).await
+    }
+
+    pub async fn get_funding_history(
+        &self,
+        symbol: &str,
+        limit: u32,
+    ) -> Result<ApiResponse<Vec<FundingHistory>>> {
+        let endpoint = with_query(
+            &format!(
+                "/api/market/funding/{}/history",
+                path_segment(&symbol.to_lowercase())
+            ),
+            &[("limit", limit.to_string())],
+        );
+        self.fetch(&endpoint).await
+    }
+
+    // ========== Signals ==========
+
+    pub async fn get_signals(
+        &self,
+        symbol: &str,
+        timeframe: TradingTimeframe,
+    ) -> Result<ApiResponse<SymbolSignals>> {
+        let endpoint = with_query(
+            &format!("/api/signals/{}", path_segment(&symbol.to_lowercase())),
+            &[("timeframe", timeframe.as_str().to_string())],
+        );
+        self.fetch(&endpoint).await
+    }
+
+    pub async fn get_signal_accuracy(&self, symbol: &str) -> Result<ApiResponse<AccuracyResponse>> {
+        self.fetch(&format!(
+            "/api/signals/{}/accuracy",
+            path_segment(&symbol.to_lowercase())
+        ))
+        .await
+    }
+
+    /// Get predictions; `status` is "all", "validated" or "pending"
+    pub async fn get_signal_predictions(
+        &self,
+        symbol: &str,
+        status: Option<&str>,
+        limit: Option<u32>,
+    ) -> Result<ApiResponse<PredictionsResponse>> {
+        let mut query = Vec::new();
+        if let Some(status) = status {
+            query.push(("status", status.to_string()));
+        }
+        if let Some(limit) = limit {
+            query.push(("limit", limit.to_string()));
+        }
+        let endpoint = with_query(
+            &format!(
+                "/api/signals/{}/predictions",
+                path_segment(&symbol.to_lowercase())
+            ),
+            &query,
+        );
+        self.fetch(&endpoint).await
+    }
+
+    pub async fn get_indicator_accuracy(
+        &self,
+        indicator: &str,
+    ) -> Result<ApiResponse<Vec<SignalAccuracy>>> {
+        self.fetch(&format!(
+            "/api/signals/accuracy/{}",
+            path_segment(&indicator.to_lowercase())
+        ))
+        .await
+    }
+
+    pub async fn get_recommendation(
+        &self,
+        symbol: &str,
+        timeframe: TradingTimeframe,
+    ) -> Result<ApiResponse<Recommendation>> {
+        let endpoint = with_query(
+            &format!(
+                "/api/signals/{}/recommendation",
+                path_segment(&symbol.to_lowercase())
+            ),
+            &[("timeframe", timeframe.as_str().to_string())],
+        );
+        self.fetch(&endpoint).await
+    }
+
+    /// Force fresh signal computation and prediction generation
+    pub async fn generate_predictions(
+        &self,
+        symbol: &str,
+        timeframe: TradingTimeframe,
+    ) -> Result<ApiResponse<SymbolSignals>> {
+        let endpoint = with_query(
+            &format!(
+                "/api/signals/{}/generate",
+                path_segment(&symbol.to_lowercase())
+            ),
+            &[("timeframe", timeframe.as_str().to_string())],
+        );
+        self.send_with_auth(Method::POST, &endpoint, None, None::<&Value>)
+            .await
+    }
+
+    // ========== Portfolio ==========
+
+    pub async fn list_portfolios(
+        &self,
+        token: &str,
+        user_id: Option<&str>,
+    ) -> Result<ApiResponse<Vec<Portfolio>>> {
+        let query: Vec<_> = user_id
+            .map(|id| ("user_id", id.to_string()))
+            .into_iter()
+            .collect();
+        self.fetch_with_auth(&with_query("/api/trading/portfolios", &query), token)
+            .await
+    }
+
+    pub async fn get_portfolio(
+        &self,
+        token: &str,
+        portfolio_id: &str,
+    ) -> Result<ApiResponse<Portfolio>> {
+        self.fetch_with_auth(
+            &format!("/api/trading/portfolios/{}", path_segment(portfolio_id)),
+            token,
+        )
+        .await
+    }
+
+    pub async fn get_portfolio_summary(
+        &self,
+        token: &str,
+        portfolio_id: &str,
+    ) -> Result<ApiResponse<Portfolio>> {
+        self.fetch_with_auth(
+            &format!(
+                "/api/trading/portfolios/{}/summary",
+                path_segment(portfolio_id)
+            ),
+            token,
+        )
+        .await
+    }
+
+    pub async fn create_portfolio(
+        &self,
+        token: &str,
+        request: &CreatePortfolioRequest,
+    ) -> Result<ApiResponse<Portfolio>> {
+        // This endpoint takes snake_case fields
+        let body = json!({
+            "name": request.name,
+            "initial_balance": request.initial_balance,
+            "user_id": request.user_id,
+        });
+        self.send_with_auth(
+            Method::POST,
+            "/api/trading/portfolios",
+            Some(token),
+            Some(&body),
+        )
+        .await
+    }
+
+    /// Restore balance, clear positions and unstop a stopped portfolio
+    pub async fn reset_portfolio(
+        &self,
+        token: &str,
+        portfolio_id: &str,
+    ) -> Result<ApiResponse<Portfolio>> {
+        self.send_with_auth(
+            Method::POST,
+            &format!(
+                "/api/trading/portfolios/{}/reset",
+                path_segment(portfolio_id)
+            ),
+            Some(token),
+            None::<&Value>,
+        )
+        .await
+    }
+
+    pub async fn get_holdings(
+        &self,
+        token: &str,
+        portfolio_id: &str,
+    ) -> Result<ApiResponse<HoldingsResponse>> {
+        self.fetch_with_auth(
+            &format!(
+                "/api/trading/portfolios/{}/holdings",
+                path_segment(portfolio_id)
+            ),
+            token,
+        )
+        .await
+    }
+
+    /// Get performance history; `range` is "1d", "1w", "1m", "3m", "1y" or "all"
+    pub async fn get_performance(
+        &self,
+        token: &str,
+        portfolio_id: &str,
+        range: &str,
+    ) -> Result<ApiResponse<PerformanceResponse>> {
+        let endpoint = with_query(
+            &format!(
+                "/api/trading/portfolios/{}/performance",
+                path_segment(portfolio_id)
+            ),
+            &[("range", range.to_string())],
+        );
+        self.fetch_with_auth(&endpoint, token).await
+    }
+
+    pub async fn get_portfolio_settings(
+        &self,
+        token: &str,
+        portfolio_id: &str,
+    ) -> Result<ApiResponse<PortfolioSettings>> {
+        self.fetch_with_auth(
+            &format!(
+                "/api/trading/portfolios/{}/settings",
+                path_segment(portfolio_id)
+            ),
+            token,
+        )
+        .await
+    }
+
+    /// Update portfolio settings; `settings` may be a partial object
+    pub async fn update_portfolio_settings(
+        &self,
+        token: &str,
+        portfolio_id: &str,
+        settings: &Value,
+    ) -> Result<ApiResponse<PortfolioSettings>> {
+        self.send_with_auth(
+            Method::PUT,
+            &format!(
+                "/api/trading/portfolios/{}/settings",
+                path_segment(portfolio_id)
+            ),
+            Some(token),
+            Some(settings),
+        )
+        .await
+    }
+
+    pub async fn get_portfolio_stats(
+        &self,
+        token: &str,
+        portfolio_id: &str,
+    ) -> Result<ApiResponse<Opaque>> {
+        self.fetch_with_auth(
+            &format!(
+                "/api/trading/portfolios/{}/stats",
+                path_segment(portfolio_id)
+            ),
+            token,
+        )
+        .await
+    }
+
+    /// Get drawdown history; `range` is "1d", "1w", "1m", "3m" or "all"
+    pub async fn get_drawdown_history(
+        &self,
+        token: &str,
+        portfolio_id: &str,
+        range: &str,
+    ) -> Result<ApiResponse<Vec<DrawdownHistoryPoint>>> {
+        let endpoint = with_query(
+            &format!(
+                "/api/trading/portfolios/{}/drawdown-history",
+                path_segment(portfolio_id)
+            ),
+            &[("range", range.to_string())],
+        );
+        self.fetch_with_auth(&endpoint, token).await
+    }
+
+    // ========== Positions & orders ==========
+
+    pub async fn get_positions(
+        &self,
+        token: &str,
+        portfolio_id: &str,
+    ) -> Result<ApiResponse<Vec<Position>>> {
+        let endpoint = with_query(
+            "/api/trading/positions",
+            &[("portfolio_id", portfolio_id.to_string())],
+        );
+        self.fetch_with_auth(&endpoint, token).await
+    }
+
+    pub async fn get_position(
+        &self,
+        token: &str,
+        position_id: &str,
+    ) -> Result<ApiResponse<Position>> {
+        self.fetch_with_auth(
+            &format!("/api/trading/positions/{}", path_segment(position_id)),
+            token,
+        )
+        .await
+    }
+
+    /// Get orders; `status` is "open", "filled" or "cancelled"
+    pub async fn get_orders(
+        &self,
+        token: &str,
+        portfolio_id: &str,
+        status: &str,
+    ) -> Result<ApiResponse<Vec<Order>>> {
+        let endpoint = with_query(
+            "/api/trading/orders",
+            &[
+                ("portfolio_id", portfolio_id.to_string()),
+                ("status", status.to_string()),
+            ],
+        );
+        self.fetch_with_auth(&endpoint, token).await
+    }
+
+    pub async fn get_trades(
+        &self,
+        token: &str,
+        portfolio_id: &str,
+        limit: u32,
+    ) -> Result<ApiResponse<Vec<Trade>>> {
+        let endpoint = with_query(
+            "/api/trading/trades",
+            &[
+                ("portfolio_id", portfolio_id.to_string()),
+                ("limit", limit.to_string()),
+            ],
+        );
+        self.fetch_with_auth(&endpoint, token).await
+    }
+
+    pub async fn place_order(
+        &self,
+        token: &str,
+        order: &PlaceOrderRequest,
+    ) -> Result<ApiResponse<Order>> {
+        self.send_with_auth(
+            Method::POST,
+            "/api/trading/orders",
+            Some(token),
+            Some(order),
+        )
+        .await
+    }
+
+    pub async fn cancel_order(
+        &self,
+        token: &str,
+        order_id: &str,
+    ) -> Result<ApiResponse<SuccessResponse>> {
+        self.send_with_auth(
+            Method::DELETE,
+            &format!("/api/trading/orders/{}", path_segment(order_id)),
+            Some(token),
+            None::<&Value>,
+        )
+        .await
+    }
+
+    pub async fn modify_order(
+        &self,
+        token: &str,
+        order_id: &str,
+        changes: &ModifyOrderRequest,
+    ) -> Result<ApiResponse<Order>> {
+        self.send_with_auth(
+            Method::PUT,
+            &format!("/api/trading/orders/{}", path_segment(order_id)),
+            Some(token),
+            Some(changes),
+        )
+        .await
+    }
+
+    /// Cancel all pending orders, optionally only for one symbol
+    pub async fn cancel_all_orders(
+        &self,
+        token: &str,
+        portfolio_id: &str,
+        symbol: Option<&str>,
+    ) -> Result<ApiResponse<CancelAllOrdersResponse>> {
+        let mut query = vec![("portfolio_id", portfolio_id.to_string())];
+        if let Some(symbol) = symbol {
+            query.push(("symbol", symbol.to_string()));
+        }
+        self.send_with_auth(
+            Method::DELETE,
+            &with_query("/api/trading/orders", &query),
+            Some(token),
+            None::<&Value>,
+        )
+        .await
+    }
+
+    /// Close a position, optionally at a limit price
+    pub async fn close_position(
+        &self,
+        token: &str,
+        position_id: &str,
+        price: Option<f64>,
+    ) -> Result<ApiResponse<Trade>> {
+        let query: Vec<_> = price
+            .filter(|p| *p > 0.0)
+            .map(|p| ("price", p.to_string()))
+            .into_iter()
+            .collect();
+        self.send_with_auth(
+            Method::DELETE,
+            &with_query(
+                &format!("/api/trading/positions/{}", path_segment(position_id)),
+                &query,
+            ),
+            Some(token),
+            None::<&Value>,
+        )
+        .await
+    }
+
+    pub async fn modify_position(
+        &self,
+        token: &str,
+        position_id: &str,
+        changes: &ModifyPositionRequest,
+    ) -> Result<ApiResponse<Position>> {
+        self.send_with_auth(
+            Method::PUT,
+            &format!("/api/trading/positions/{}", path_segment(position_id)),
+            Some(token),
+            Some(changes),
+        )
+        .await
+    }
+
+    /// Add margin to an isolated position
+    pub async fn add_margin(
+        &self,
+        token: &str,
+        position_id: &str,
+        amount: f64,
+    ) -> Result<ApiResponse<Position>> {
+        self.send_with_auth(
+            Method::POST,
+            &format!(
+                "/api/trading/positions/{}/margin",
+                path_segment(position_id)
+            ),
+            Some(token),
+            Some(&json!({ "amount": amount })),
+        )
+        .await
+    }
+
+    // ========== Auth ==========
+
+    pub async fn get_challenge(&self) -> Result<ApiResponse<AuthChallenge>> {
+        self.fetch("/api/auth/challenge").await
+    }
+
+    /// Verify a signed challenge and create a session
+    pub async fn verify(&self, request: &AuthRequest) -> Result<ApiResponse<AuthResponse>> {
+        self.send_with_auth(Method::POST, "/api/auth/verify", None, Some(request))
+            .await
+    }
+
+    pub async fn get_me(&self, token: &str) -> Result<ApiResponse<Profile>> {
+        self.fetch_with_auth("/api/auth/me", token).await
+    }
+
+    pub async fn update_profile(
+        &self,
+        token: &str,
+        settings: &ProfileSettings,
+    ) -> Result<ApiResponse<Profile>> {
+        self.send_with_auth(
+            Method::PUT,
+            "/api/auth/profile",
+            Some(token),
+            Some(settings),
+        )
+        .await
+    }
+
+    pub async fn update_username(
+        &self,
+        token: &str,
+        username: &str,
+    ) -> Result<ApiResponse<Profile>> {
+        self.send_with_auth(
+            Method::PUT,
+            "/api/auth/profile/username",
+            Some(token),
+            Some(&json!({ "username": username })),
+        )
+        .await
+    }
+
+    /// Update leaderboard visibility; opting in requires a signed consent message
+    pub async fn update_leaderboard_visibility(
+        &self,
+        token: &str,
+        show_on_leaderboard: bool,
+        signature: Option<&str>,
+        timestamp: u64,
+    ) -> Result<ApiResponse<Profile>> {
+        self.send_with_auth(
+            Method::POST,
+            "/api/auth/profile/leaderboard",
+            Some(token),
+            Some(&json!({
+                "showOnLeaderboard": show_on_leaderboard,
+                "signature": signature,
+                "timestamp": timestamp,
+            })),
+        )
+        .await
+    }
+
+    /// Invalidate the session
+    pub async fn logout(&self, token: &str) -> Result<()> {
+        let _: Value = self
+            .send_with_auth(
+                Method::POST,
+                "/api/auth/logout",
+                Some(token),
+                None::<&Value>,
+            )
+            .await?;
+        Ok(())
+    }
+
+    // ========== Peers & leaderboard ==========
+
+    pub async fn get_peers(&self) -> Result<ApiResponse<PeerMeshResponse>> {
+        self.fetch("/api/peers").await
+    }
+
+    pub async fn get_peer(&self, peer_id: &str) -> Result<ApiResponse<Option<PeerStatus>>> {
+        self.fetch(&format!("/api/peers/{}", path_segment(peer_id)))
+            .await
+    }
+
+    pub async fn get_sync_health(&self) -> Result<Opaque> {
+        self.fetch("/api/sync/health").await
+    }
+
+    /// Get the leaderboard; `timeframe` is "daily", "weekly", "monthly" or "all_time"
+    pub async fn get_leaderboard(
+        &self,
+        timeframe: &str,
+        limit: u32,
+    ) -> Result<ApiResponse<Vec<LeaderboardEntry>>> {
+        let endpoint = with_query(
+            "/api/trading/leaderboard",
+            &[
+                ("timeframe", timeframe.to_string()),
+                ("limit", limit.to_string()),
+            ],
+        );
+        self.fetch(&endpoint).await
+    }
+
+    pub async fn get_trader_stats(&self, trader_id: &str) -> Result<ApiResponse<LeaderboardEntry>> {
+        self.fetch(&format!(
+            "/api/trading/leaderboard/{}",
+            path_segment(trader_id)
+        ))
+        .await
+    }
+
+    pub async fn get_my_rank(&self, token: &str) -> Result<ApiResponse<LeaderboardEntry>> {
+        self.fetch_with_auth("/api/trading/leaderboard/me", token)
+            .await
+    }
+
+    // ========== Alerts ==========
+
+    pub async fn get_alerts(&self, token: &str) -> Result<ApiResponse<Vec<Alert>>> {
+        self.fetch_with_auth("/api/alerts", token).await
+    }
+
+    pub async fn create_alert(
+        &self,
+        token: &str,
+        request: &CreateAlertRequest,
+    ) -> Result<ApiResponse<Alert>> {
+        self.send_with_auth(Method::POST, "/api/alerts", Some(token), Some(request))
+            .await
+    }
+
+    pub async fn delete_alert(
+        &self,
+        token: &str,
+        alert_id: &str,
+    ) -> Result<ApiResponse<SuccessResponse>> {
+        self.send_with_auth(
+            Method::DELETE,
+            &format!("/api/alerts/{}", path_segment(alert_id)),
+            Some(token),
+            None::<&Value>,
+        )
+        .await
+    }
+
+    // ========== Account ==========
+
+    pub async fn get_account_summary(&self, token: &str) -> Result<ApiResponse<AccountSummary>> {
+        self.fetch_with_auth("/api/account/summary", token).await
+    }
+
+    pub async fn get_transactions(
+        &self,
+        token: &str,
+        params: &TransactionHistoryParams,
+    ) -> Result<ApiResponse<Vec<Transaction>>> {
+        let mut query = Vec::new();
+        if let Some(kind) = &params.kind {
+            query.push(("type", kind.clone()));
+        }
+        if let Some(status) = &params.status {
+            query.push(("status", status.clone()));
+        }
+        if let Some(start_time) = params.start_time {
+            query.push(("startTime", start_time.to_string()));
+        }
+        if let Some(end_time) = params.end_time {
+            query.push(("endTime", end_time.to_string()));
+        }
+        if let Some(limit) = params.limit {
+            query.push(("limit", limit.to_string()));
+        }
+        self.fetch_with_auth(&with_query("/api/account/transactions", &query), token)
+            .await
+    }
+
+    /// Get trading stats; `timeframe` is "today", "week", "month", "year" or "all"
+    pub async fn get_trading_stats(
+        &self,
+        token: &str,
+        timeframe: &str,
+    ) -> Result<ApiResponse<Opaque>> {
+        let endpoint = with_query(
+            "/api/account/stats",
+            &[("timeframe", timeframe.to_string())],
+        );
+        self.fetch_with_auth(&endpoint, token).await
+    }
+
+    pub async fn get_trade_history(
+        &self,
+        token: &str,
+        params: &TradeHistoryParams,
+    ) -> Result<ApiResponse<Vec<Trade>>> {
+        let mut query = Vec::new();
+        if let Some(symbol) = &params.symbol {
+            query.push(("symbol", symbol.clone()));
+        }
+        if let Some(side) = params.side {
+            let side = match side {
+                OrderSide::Buy => "buy",
+                OrderSide::Sell => "sell",
+            };
+            query.push(("side", side.to_string()));
+        }
+        if let Some(start_time) = params.start_time {
+            query.push(("startTime", start_time.to_string()));
+        }
+        if let Some(end_time) = params.end_time {
+            query.push(("endTime", end_time.to_string()));
+        }
+        if let Some(limit) = params.limit {
+            query.push(("limit", limit.to_string()));
+        }
+        if let Some(offset) = params.offset {
+            query.push(("offset", offset.to_string()));
+        }
+        self.fetch_with_auth(&with_query("/api/trades", &query), token)
+            .await
+    }
+
+    // ========== RAT (Random Auto Trader) ==========
+
+    pub async fn start_rat(
+        &self,
+        token: &str,
+        portfolio_id: &str,
+        config: Option<&Value>,
+    ) -> Result<ApiResponse<Opaque>> {
+        self.send_with_auth(
+            Method::POST,
+            "/api/developer/rat/start",
+            Some(token),
+            Some(&json!({ "portfolioId": portfolio_id, "config": config })),
+        )
+        .await
+    }
+
+    pub async fn stop_rat(&self, token: &str, portfolio_id: &str) -> Result<ApiResponse<Opaque>> {
+        self.send_with_auth(
+            Method::POST,
+            "/api/developer/rat/stop",
+            Some(token),
+            Some(&json!({ "portfolioId": portfolio_id })),
+        )
+        .await
+    }
+
+    pub async fn get_rat_status(
+        &self,
+        token: &str,
+        portfolio_id: &str,
+    ) -> Result<ApiResponse<Opaque>> {
+        let endpoint = with_query(
+            "/api/developer/rat/status",
+            &[("portfolio_id", portfolio_id.to_string())],
+        );
+        self.fetch_with_auth(&endpoint, token).await
+    }
+
+    pub async fn update_rat_config(
+        &self,
+        token: &str,
+        portfolio_id: &str,
+        config: &Value,
+    ) -> Result<ApiResponse<Opaque>> {
+        self.send_with_auth(
+            Method::PUT,
+            "/api/developer/rat/config",
+            Some(token),
+            Some(&json!({ "portfolioId": portfolio_id, "config": config })),
+        )
+        .await
+    }
+
+    // ========== Tap trading (grid) ==========
+
+    pub async fn get_grid_config(&self, symbol: &str) -> Result<ApiResponse<Opaque>> {
+        self.fetch(&format!("/api/grid/config/{}", path_segment(symbol)))
+            .await
+    }
+
+    pub async fn get_grid_state(
+        &self,
+        symbol: &str,
+        row_count: Option<u32>,
+        col_count: Option<u32>,
+        portfolio_id: Option<&str>,
+    ) -> Result<ApiResponse<Opaque>> {
+        let mut query = Vec::new();
+        if let Some(row_count) = row_count {
+            query.push(("row_count", row_count.to_string()));
+        }
+        if let Some(col_count) = col_count {
+            query.push(("col_count", col_count.to_string()));
+        }
+        if let Some(portfolio_id) = portfolio_id {
+            query.push(("portfolio_id", portfolio_id.to_string()));
+        }
+        let endpoint = with_query(&format!("/api/grid/state/{}", path_segment(symbol)), &query);
+        self.fetch(&endpoint).await
+    }
+
+    pub async fn get_grid_positions(
+        &self,
+        token: &str,
+        portfolio_id: &str,
+    ) -> Result<ApiResponse<Opaque>> {
+        self.fetch_with_auth(
+            &format!("/api/grid/positions/{}", path_segment(portfolio_id)),
+            token,
+        )
+        .await
+    }
+
+    pub async fn get_grid_stats(
+        &self,
+        token: &str,
+        portfolio_id: &str,
+        symbol: &str,
+    ) -> Result<ApiResponse<Opaque>> {
+        self.fetch_with_auth(
+            &format!(
+                "/api/grid/stats/{}/{}",
+                path_segment(portfolio_id),
+                path_segment(symbol)
+            ),
+            token,
+        )
+        .await
+    }
+
+    /// Place a grid trade; the server is authoritative for the cell's bounds
+    pub async fn place_grid_trade(
+        &self,
+        token: &str,
+        params: &Value,
+    ) -> Result<ApiResponse<Opaque>> {
+        self.send_with_auth(Method::POST, "/api/grid/trade", Some(token), Some(params))
+            .await
+    }
+
+    // ========== Notifications ==========
+
+    pub async fn get_notifications(
+        &self,
+        token: &str,
+        page: u32,
+        page_size: u32,
+        unread_only: bool,
+    ) -> Result<DataResponse<NotificationListResponse>> {
+        let mut query = vec![
+            ("page", page.to_string()),
+            ("pageSize", page_size.to_string()),
+        ];
+        if unread_only {
+            query.push(("unreadOnly", "true".to_string()));
+        }
+        self.fetch_with_auth(&with_query("/api/notifications", &query), token)
+            .await
+    }
+
+    pub async fn mark_all_notifications_read(
+        &self,
+        token: &str,
+    ) -> Result<DataResponse<AffectedResponse>> {
+        self.send_with_auth(
+            Method::POST,
+            "/api/notifications/read-all",
+            Some(token),
+            None::<&Value>,
+        )
+        .await
+    }
+
+    pub async fn mark_notifications_read(
+        &self,
+        token: &str,
+        ids: &[String],
+    ) -> Result<DataResponse<AffectedResponse>> {
+        self.send_with_auth(
+            Method::POST,
+            "/api/notifications/read",
+            Some(token),
+            Some(&json!({ "ids": ids })),
+        )
+        .await
+    }
+
+    pub async fn clear_notifications(&self, token: &str) -> Result<DataResponse<AffectedResponse>> {
+        self.send_with_auth(
+            Method::DELETE,
+            "/api/notifications",
+            Some(token),
+            None::<&Value>,
+        )
+        .await
+    }
+}
+
+impl Default for HauntClient {
+    fn default() -> Self {
+        Self::new(DEFAULT_BASE_URL)
+    }
+}
+
+fn normalize_base_url(base_url: &str) -> String {
+    base_url.trim().trim_end_matches('/').to_string()
+}
+
+/// Percent-encode a value used as a single path segment
+fn path_segment(value: &str) -> String {
+    url::form_urlencoded::byte_serialize(value.as_bytes())
+        .collect::<String>()
+        .replace('+', "%20")
+}
+
+/// Append an encoded query string to an endpoint
+fn with_query(endpoint: &str, query: &[(&str, String)]) -> String {
+    if query.is_empty() {
+        return endpoint.to_string();
+    }
+    let encoded = url::form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(query)
+        .finish();
+    format!("{endpoint}?{encoded}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_json, header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn client_for(server: &MockServer) -> HauntClient {
+        HauntClient::new(&server.uri())
+    }
+
+    fn asset_json(symbol: &str, price: f64) -> Value {
+        json!({
+            "id": 1, "rank": 1, "name": "Bitcoin", "symbol": symbol,
+            "image": "", "price": price, "change1h": 0.1, "change24h": 1.5,
+            "change7d": -2.0, "marketCap": 1.0e12, "volume24h": 3.0e10,
+            "circulatingSupply": 1.9e7, "sparkline": [1.0, 2.0, 3.0]
+        })
+    }
+
+    #[tokio::test]
+    async fn listings_send_filters_as_query() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/crypto/listings"))
+            .and(query_param("limit", "2"))
+            .and(query_param("filter", "gainers"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": [asset_json("BTC", 65000.0), asset_json("ETH", 3200.0)],
+                "meta": { "cached": true, "total": 2 }
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let params = ListingsParams {
+            limit: Some(2),
+            filter: Some("gainers".into()),
+            asset_type: Some("all".into()),
+            ..Default::default()
+        };
+        let response = client_for(&server).get_listings(&params).await.unwrap();
+
+        assert_eq!(response.data.len(), 2);
+        assert_eq!(response.data[1].symbol, "ETH");
+        assert_eq!(response.data[0].change_24h, 1.5);
+        assert!(response.meta.cached);
+    }
+
+    #[tokio::test]
+    async fn nested_asset_is_flattened() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/crypto/42"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": {
+                    "id": 42, "name": "Solana", "symbol": "SOL",
+                    "quote": { "price": 150.0, "percentChange24h": 4.2, "marketCap": 7.0e10 }
+                },
+                "meta": { "cached": false }
+            })))
+            .mount(&server)
+            .await;
+
+        let asset = client_for(&server).get_asset(42).await.unwrap().data;
+        assert_eq!(asset.symbol, "SOL");
+        assert_eq!(asset.price, 150.0);
+        assert_eq!(asset.change_24h, 4.2);
+        assert!(asset.image.ends_with("/42.png"));
+    }
+
+    #[tokio::test]
+    async fn authenticated_requests_send_bearer_token_and_camel_case_body() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/trading/orders"))
+            .and(header("authorization", "Bearer session-123"))
+            .and(body_json(json!({
+                "portfolioId": "p1", "symbol": "BTC", "assetClass": "perp",
+                "side": "buy", "orderType": "market", "quantity": 0.5, "leverage": 10.0
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": {
+                    "id": "o1", "symbol": "BTC", "type": "market", "side": "buy",
+                    "size": 0.5, "filledSize": 0.5, "status": "filled", "createdAt": 1
+                }
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let order = PlaceOrderRequest {
+            portfolio_id: "p1".into(),
+            symbol: "BTC".into(),
+            asset_class: AssetClass::Perp,
+            side: OrderSide::Buy,
+            order_type: OrderType::Market,
+            quantity: 0.5,
+            price: None,
+            stop_price: None,
+            trail_amount: None,
+            trail_percent: None,
+            time_in_force: None,
+            leverage: Some(10.0),
+            stop_loss: None,
+            take_profit: None,
+            reduce_only: None,
+            post_only: None,
+            margin_mode: None,
+            bypass_drawdown: None,
+        };
+        let placed = client_for(&server)
+            .place_order("session-123", &order)
+            .await
+            .unwrap()
+            .data;
+        assert_eq!(placed.status, OrderStatus::Filled);
+    }
+
+    #[tokio::test]
+    async fn api_errors_keep_code_and_status() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/alerts"))
+            .respond_with(ResponseTemplate::new(401).set_body_json(json!({
+                "error": "Session expired", "code": "SESSION_EXPIRED"
+            })))
+            .mount(&server)
+            .await;
+
+        let err = client_for(&server).get_alerts("stale").await.unwrap_err();
+        assert_eq!(err.to_string(), "Session expired");
+        assert!(matches!(
+            err,
+            HauntError::Api { status: 401, code, .. } if code == "SESSION_EXPIRED"
+        ));
+    }
+
+    #[tokio::test]
+    async fn forbidden_trading_errors_get_helpful_message() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/trading/positions"))
+            .respond_with(ResponseTemplate::new(403).set_body_string("nope"))
+            .mount(&server)
+            .await;
+
+        let err = client_for(&server)
+            .get_positions("token", "p1")
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Portfolio access denied. Please try logging out and back in."
+        );
+    }
+
+    #[tokio::test]
+    async fn logout_accepts_empty_body() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/auth/logout"))
+            .respond_with(ResponseTemplate::new(204))
+            .mount(&server)
+            .await;
+
+        client_for(&server).logout("token").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn base_url_can_be_switched() {
+        let first = MockServer::start().await;
+        let second = MockServer::start().await;
+        for (server, status) in [(&first, "a"), (&second, "b")] {
+            Mock::given(method("GET"))
+                .and(path("/api/health"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "status": status, "timestamp": "", "uptime": 1.0
+                })))
+                .mount(server)
+                .await;
+        }
+
+        let client = client_for(&first);
+        assert_eq!(client.health().await.unwrap().status, "a");
+        client.set_base_url(&format!("{}/", second.uri()));
+        assert_eq!(client.health().await.unwrap().status, "b");
+    }
+
+    #[test]
+    fn path_segments_are_encoded() {
+        assert_eq!(path_segment("btc"), "btc");
+        assert_eq!(path_segment("../admin"), "..%2Fadmin");
+        assert_eq!(path_segment("a b"), "a%20b");
+        assert_eq!(with_query("/x", &[("q", "a&b".into())]), "/x?q=a%26b");
+    }
+}
@@ -0,0 +1,1021 @@
+//! Tauri commands wrapping [`HauntClient`]
+//!
+//! Names follow the `haunt.ts` methods with a `haunt_` prefix. Authenticated
+//! commands take the session token explicitly so any identity's session can
+//! be used.
+
+use serde_json::Value;
+use tauri::State;
+
+use super::client::HauntClient;
+use super::models::*;
+use crate::cache::CacheState;
+
+type CmdResult<T> = Result<T, String>;
+
+/// Tauri command: Get the Haunt server the native client talks to
+#[tauri::command]
+pub fn haunt_base_url(client: State<'_, HauntClient>) -> String {
+    client.base_url()
+}
+
+/// Tauri command: Point the native client at another Haunt server
+#[tauri::command]
+pub fn haunt_set_base_url(client: State<'_, HauntClient>, base_url: String) -> CmdResult<()> {
+    let parsed = url::Url::parse(base_url.trim()).map_err(|e| e.to_string())?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err("Haunt server URL must use http or https".into());
+    }
+    client.set_base_url(&base_url);
+    Ok(())
+}
+
+// ========== Market ==========
+
+/// Tauri command: Get listings with optional filtering and sorting
+///
+/// Results are written through to the market cache.
+#[tauri::command]
+pub async fn haunt_get_listings(
+    client: State<'_, HauntClient>,
+    cache: State<'_, CacheState>,
+    params: Option<ListingsParams>,
+) -> CmdResult<ApiResponse<Vec<Asset>>> {
+    let response = client
+        .get_listings(&params.unwrap_or_default())
+        .await
+        .map_err(|e| e.to_string())?;
+    cache.record_listings(&response.data);
+    Ok(response)
+}
+
+/// Tauri command: Get a single asset by ID
+#[tauri::command]
+pub async fn haunt_get_asset(
+    client: State<'_, HauntClient>,
+    id: i64,
+) -> CmdResult<ApiResponse<Asset>> {
+    client.get_asset(id).await.map_err(|e| e.to_string())
+}
+
+/// Tauri command: Get latest quotes for an asset
+#[tauri::command]
+pub async fn haunt_get_quotes(
+    client: State<'_, HauntClient>,
+    id: i64,
+) -> CmdResult<ApiResponse<Asset>> {
+    client.get_quotes(id).await.map_err(|e| e.to_string())
+}
+
+/// Tauri command: Search assets by name or symbol
+#[tauri::command]
+pub async fn haunt_search(
+    client: State<'_, HauntClient>,
+    query: String,
+    limit: Option<u32>,
+) -> CmdResult<ApiResponse<Vec<Asset>>> {
+    client
+        .search(&query, limit.unwrap_or(10))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Get global market metrics
+#[tauri::command]
+pub async fn haunt_get_global_metrics(
+    client: State<'_, HauntClient>,
+) -> CmdResult<ApiResponse<GlobalMetrics>> {
+    client.get_global_metrics().await.map_err(|e| e.to_string())
+}
+
+/// Tauri command: Get the Fear & Greed index
+#[tauri::command]
+pub async fn haunt_get_fear_greed(
+    client: State<'_, HauntClient>,
+) -> CmdResult<ApiResponse<FearGreedData>> {
+    client.get_fear_greed().await.map_err(|e| e.to_string())
+}
+
+/// Tauri command: Get OHLC chart data for an asset
+///
+/// Non-empty series are written through to the market cache.
+#[tauri::command]
+pub async fn haunt_get_chart(
+    client: State<'_, HauntClient>,
+    cache: State<'_, CacheState>,
+    id: i64,
+    range: Option<String>,
+) -> CmdResult<ApiResponse<ChartData>> {
+    let range = range.as_deref().unwrap_or("1d");
+    let response = client
+        .get_chart(id, range)
+        .await
+        .map_err(|e| e.to_string())?;
+    if !response.data.data.is_empty() {
+        cache.record_chart(id, range, &response.data.data);
+    }
+    Ok(response)
+}
+
+/// Tauri command: Trigger historical data seeding for a symbol
+#[tauri::command]
+pub async fn haunt_seed_symbol(
+    client: State<'_, HauntClient>,
+    symbol: String,
+) -> CmdResult<ApiResponse<SeedResponse>> {
+    client.seed_symbol(&symbol).await.map_err(|e| e.to_string())
+}
+
+/// Tauri command: Get per-exchange update counts for a symbol
+#[tauri::command]
+pub async fn haunt_get_symbol_source_stats(
+    client: State<'_, HauntClient>,
+    symbol: String,
+) -> CmdResult<ApiResponse<SymbolSourceStats>> {
+    client
+        .get_symbol_source_stats(&symbol)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Get top gainers and losers
+#[tauri::command]
+pub async fn haunt_get_movers(
+    client: State<'_, HauntClient>,
+    timeframe: Option<String>,
+    limit: Option<u32>,
+    asset_type: Option<String>,
+) -> CmdResult<ApiResponse<MoversResponse>> {
+    client
+        .get_movers(
+            timeframe.as_deref().unwrap_or("1h"),
+            limit.unwrap_or(10),
+            asset_type.as_deref(),
+        )
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Get API and feed statistics
+#[tauri::command]
+pub async fn haunt_get_stats(client: State<'_, HauntClient>) -> CmdResult<ApiResponse<ApiStats>> {
+    client.get_stats().await.map_err(|e| e.to_string())
+}
+
+/// Tauri command: Get the price confidence breakdown for a symbol
+#[tauri::command]
+pub async fn haunt_get_confidence(
+    client: State<'_, HauntClient>,
+    symbol: String,
+) -> CmdResult<ApiResponse<Value>> {
+    client
+        .get_confidence(&symbol)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Get volume dominance by exchange for a symbol
+#[tauri::command]
+pub async fn haunt_get_exchange_dominance(
+    client: State<'_, HauntClient>,
+    symbol: String,
+) -> CmdResult<ApiResponse<Value>> {
+    client
+        .get_exchange_dominance(&symbol)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Check server health
+#[tauri::command]
+pub async fn haunt_health(client: State<'_, HauntClient>) -> CmdResult<HealthResponse> {
+    client.health().await.map_err(|e| e.to_string())
+}
+
+/// Tauri command: Get the aggregated order book for a symbol
+#[tauri::command]
+pub async fn haunt_get_order_book(
+    client: State<'_, HauntClient>,
+    symbol: String,
+    depth: Option<u32>,
+) -> CmdResult<DataResponse<AggregatedOrderBook>> {
+    client
+        .get_order_book(&symbol, depth.unwrap_or(50))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Get current funding rates
+#[tauri::command]
+pub async fn haunt_get_funding_rates(
+    client: State<'_, HauntClient>,
+    symbols: Option<Vec<String>>,
+) -> CmdResult<ApiResponse<Vec<FundingRate>>> {
+    client
+        .get_funding_rates(&symbols.unwrap_or_default())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Get funding rate history for a symbol
+#[tauri::command]
+pub async fn haunt_get_funding_history(
+    client: State<'_, HauntClient>,
+    symbol: String,
+    limit: Option<u32>,
+) -> CmdResult<ApiResponse<Vec<FundingHistory>>> {
+    client
+        .get_funding_history(&symbol, limit.unwrap_or(100))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// ========== Signals ==========
+
+/// Tauri command: Get trading signals for a symbol
+#[tauri::command]
+pub async fn haunt_get_signals(
+    client: State<'_, HauntClient>,
+    symbol: String,
+    timeframe: Option<TradingTimeframe>,
+) -> CmdResult<ApiResponse<SymbolSignals>> {
+    client
+        .get_signals(&symbol, timeframe.unwrap_or(TradingTimeframe::DayTrading))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Get signal accuracy for a symbol
+#[tauri::command]
+pub async fn haunt_get_signal_accuracy(
+    client: State<'_, HauntClient>,
+    symbol: String,
+) -> CmdResult<ApiResponse<AccuracyResponse>> {
+    client
+        .get_signal_accuracy(&symbol)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Get signal predictions for a symbol
+#[tauri::command]
+pub async fn haunt_get_signal_predictions(
+    client: State<'_, HauntClient>,
+    symbol: String,
+    status: Option<String>,
+    limit: Option<u32>,
+) -> CmdResult<ApiResponse<PredictionsResponse>> {
+    client
+        .get_signal_predictions(&symbol, status.as_deref(), limit)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Get accuracy for an indicator across symbols
+#[tauri::command]
+pub async fn haunt_get_indicator_accuracy(
+    client: State<'_, HauntClient>,
+    indicator: String,
+) -> CmdResult<ApiResponse<Vec<SignalAccuracy>>> {
+    client
+        .get_indicator_accuracy(&indicator)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Get an accuracy-weighted recommendation for a symbol
+#[tauri::command]
+pub async fn haunt_get_recommendation(
+    client: State<'_, HauntClient>,
+    symbol: String,
+    timeframe: Option<TradingTimeframe>,
+) -> CmdResult<ApiResponse<Recommendation>> {
+    client
+        .get_recommendation(&symbol, timeframe.unwrap_or(TradingTimeframe::DayTrading))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Force fresh signal computation for a symbol
+#[tauri::command]
+pub async fn haunt_generate_predictions(
+    client: State<'_, HauntClient>,
+    symbol: String,
+    timeframe: Option<TradingTimeframe>,
+) -> CmdResult<ApiResponse<SymbolSignals>> {
+    client
+        .generate_predictions(&symbol, timeframe.unwrap_or(TradingTimeframe::DayTrading))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// ========== Portfolio ==========
+
+/// Tauri command: List the user's portfolios
+#[tauri::command]
+pub async fn haunt_list_portfolios(
+    client: State<'_, HauntClient>,
+    token: String,
+    user_id: Option<String>,
+) -> CmdResult<ApiResponse<Vec<Portfolio>>> {
+    client
+        .list_portfolios(&token, user_id.as_deref())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Get a portfolio by ID
+#[tauri::command]
+pub async fn haunt_get_portfolio(
+    client: State<'_, HauntClient>,
+    token: String,
+    portfolio_id: String,
+) -> CmdResult<ApiResponse<Portfolio>> {
+    client
+        .get_portfolio(&token, &portfolio_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Get a portfolio summary
+#[tauri::command]
+pub async fn haunt_get_portfolio_summary(
+    client: State<'_, HauntClient>,
+    token: String,
+    portfolio_id: String,
+) -> CmdResult<ApiResponse<Portfolio>> {
+    client
+        .get_portfolio_summary(&token, &portfolio_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Create a portfolio
+#[tauri::command]
+pub async fn haunt_create_portfolio(
+    client: State<'_, HauntClient>,
+    token: String,
+    request: CreatePortfolioRequest,
+) -> CmdResult<ApiResponse<Portfolio>> {
+    client
+        .create_portfolio(&token, &request)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Reset a portfolio to its starting balance
+#[tauri::command]
+pub async fn haunt_reset_portfolio(
+    client: State<'_, HauntClient>,
+    token: String,
+    portfolio_id: String,
+) -> CmdResult<ApiResponse<Portfolio>> {
+    client
+        .reset_portfolio(&token, &portfolio_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Get portfolio holdings
+#[tauri::command]
+pub async fn haunt_get_holdings(
+    client: State<'_, HauntClient>,
+    token: String,
+    portfolio_id: String,
+) -> CmdResult<ApiResponse<HoldingsResponse>> {
+    client
+        .get_holdings(&token, &portfolio_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Get portfolio performance history
+#[tauri::command]
+pub async fn haunt_get_performance(
+    client: State<'_, HauntClient>,
+    token: String,
+    portfolio_id: String,
+    range: Option<String>,
+) -> CmdResult<ApiResponse<PerformanceResponse>> {
+    client
+        .get_performance(&token, &portfolio_id, range.as_deref().unwrap_or("1m"))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Get portfolio settings
+#[tauri::command]
+pub async fn haunt_get_portfolio_settings(
+    client: State<'_, HauntClient>,
+    token: String,
+    portfolio_id: String,
+) -> CmdResult<ApiResponse<PortfolioSettings>> {
+    client
+        .get_portfolio_settings(&token, &portfolio_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Update portfolio settings
+#[tauri::command]
+pub async fn haunt_update_portfolio_settings(
+    client: State<'_, HauntClient>,
+    token: String,
+    portfolio_id: String,
+    settings: Value,
+) -> CmdResult<ApiResponse<PortfolioSettings>> {
+    client
+        .update_portfolio_settings(&token, &portfolio_id, &settings)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Get portfolio trading statistics
+#[tauri::command]
+pub async fn haunt_get_portfolio_stats(
+    client: State<'_, HauntClient>,
+    token: String,
+    portfolio_id: String,
+) -> CmdResult<ApiResponse<Value>> {
+    client
+        .get_portfolio_stats(&token, &portfolio_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Get drawdown history for a portfolio
+#[tauri::command]
+pub async fn haunt_get_drawdown_history(
+    client: State<'_, HauntClient>,
+    token: String,
+    portfolio_id: String,
+    range: Option<String>,
+) -> CmdResult<ApiResponse<Vec<DrawdownHistoryPoint>>> {
+    client
+        .get_drawdown_history(&token, &portfolio_id, range.as_deref().unwrap_or("1w"))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// ========== Positions & orders ==========
+
+/// Tauri command: Get open positions for a portfolio
+#[tauri::command]
+pub async fn haunt_get_positions(
+    client: State<'_, HauntClient>,
+    token: String,
+    portfolio_id: String,
+) -> CmdResult<ApiResponse<Vec<Position>>> {
+    client
+        .get_positions(&token, &portfolio_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Get a position by ID
+#[tauri::command]
+pub async fn haunt_get_position(
+    client: State<'_, HauntClient>,
+    token: String,
+    position_id: String,
+) -> CmdResult<ApiResponse<Position>> {
+    client
+        .get_position(&token, &position_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Get orders for a portfolio
+#[tauri::command]
+pub async fn haunt_get_orders(
+    client: State<'_, HauntClient>,
+    token: String,
+    portfolio_id: String,
+    status: Option<String>,
+) -> CmdResult<ApiResponse<Vec<Order>>> {
+    client
+        .get_orders(&token, &portfolio_id, status.as_deref().unwrap_or("open"))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Get recent trades for a portfolio
+#[tauri::command]
+pub async fn haunt_get_trades(
+    client: State<'_, HauntClient>,
+    token: String,
+    portfolio_id: String,
+    limit: Option<u32>,
+) -> CmdResult<ApiResponse<Vec<Trade>>> {
+    client
+        .get_trades(&token, &portfolio_id, limit.unwrap_or(50))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Place an order
+#[tauri::command]
+pub async fn haunt_place_order(
+    client: State<'_, HauntClient>,
+    token: String,
+    order: PlaceOrderRequest,
+) -> CmdResult<ApiResponse<Order>> {
+    client
+        .place_order(&token, &order)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Cancel an order
+#[tauri::command]
+pub async fn haunt_cancel_order(
+    client: State<'_, HauntClient>,
+    token: String,
+    order_id: String,
+) -> CmdResult<ApiResponse<SuccessResponse>> {
+    client
+        .cancel_order(&token, &order_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Modify a pending order
+#[tauri::command]
+pub async fn haunt_modify_order(
+    client: State<'_, HauntClient>,
+    token: String,
+    order_id: String,
+    changes: ModifyOrderRequest,
+) -> CmdResult<ApiResponse<Order>> {
+    client
+        .modify_order(&token, &order_id, &changes)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Cancel all pending orders for a portfolio
+#[tauri::command]
+pub async fn haunt_cancel_all_orders(
+    client: State<'_, HauntClient>,
+    token: String,
+    portfolio_id: String,
+    symbol: Option<String>,
+) -> CmdResult<ApiResponse<CancelAllOrdersResponse>> {
+    client
+        .cancel_all_orders(&token, &portfolio_id, symbol.as_deref())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Close a position
+#[tauri::command]
+pub async fn haunt_close_position(
+    client: State<'_, HauntClient>,
+    token: String,
+    position_id: String,
+    price: Option<f64>,
+) -> CmdResult<ApiResponse<Trade>> {
+    client
+        .close_position(&token, &position_id, price)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Update a position's stop loss, take profit or trailing stop
+#[tauri::command]
+pub async fn haunt_modify_position(
+    client: State<'_, HauntClient>,
+    token: String,
+    position_id: String,
+    changes: ModifyPositionRequest,
+) -> CmdResult<ApiResponse<Position>> {
+    client
+        .modify_position(&token, &position_id, &changes)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Add margin to an isolated position
+#[tauri::command]
+pub async fn haunt_add_margin(
+    client: State<'_, HauntClient>,
+    token: String,
+    position_id: String,
+    amount: f64,
+) -> CmdResult<ApiResponse<Position>> {
+    client
+        .add_margin(&token, &position_id, amount)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// ========== Auth ==========
+
+/// Tauri command: Request an authentication challenge
+#[tauri::command]
+pub async fn haunt_get_challenge(
+    client: State<'_, HauntClient>,
+) -> CmdResult<ApiResponse<AuthChallenge>> {
+    client.get_challenge().await.map_err(|e| e.to_string())
+}
+
+/// Tauri command: Verify a signed challenge and create a session
+#[tauri::command]
+pub async fn haunt_verify(
+    client: State<'_, HauntClient>,
+    request: AuthRequest,
+) -> CmdResult<ApiResponse<AuthResponse>> {
+    client.verify(&request).await.map_err(|e| e.to_string())
+}
+
+/// Tauri command: Get the current user's profile
+#[tauri::command]
+pub async fn haunt_get_me(
+    client: State<'_, HauntClient>,
+    token: String,
+) -> CmdResult<ApiResponse<Profile>> {
+    client.get_me(&token).await.map_err(|e| e.to_string())
+}
+
+/// Tauri command: Update profile settings
+#[tauri::command]
+pub async fn haunt_update_profile(
+    client: State<'_, HauntClient>,
+    token: String,
+    settings: ProfileSettings,
+) -> CmdResult<ApiResponse<Profile>> {
+    client
+        .update_profile(&token, &settings)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Change the username
+#[tauri::command]
+pub async fn haunt_update_username(
+    client: State<'_, HauntClient>,
+    token: String,
+    username: String,
+) -> CmdResult<ApiResponse<Profile>> {
+    client
+        .update_username(&token, &username)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Opt in or out of the leaderboard
+#[tauri::command]
+pub async fn haunt_update_leaderboard_visibility(
+    client: State<'_, HauntClient>,
+    token: String,
+    show_on_leaderboard: bool,
+    signature: Option<String>,
+    timestamp: u64,
+) -> CmdResult<ApiResponse<Profile>> {
+    client
+        .update_leaderboard_visibility(&token, show_on_leaderboard, signature.as_deref(), timestamp)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Invalidate a session
+#[tauri::command]
+pub async fn haunt_logout(client: State<'_, HauntClient>, token: String) -> CmdResult<()> {
+    client.logout(&token).await.map_err(|e| e.to_string())
+}
+
+// ========== Peers & leaderboard ==========
+
+/// Tauri command: Get the peer mesh status
+#[tauri::command]
+pub async fn haunt_get_peers(
+    client: State<'_, HauntClient>,
+) -> CmdResult<ApiResponse<PeerMeshResponse>> {
+    client.get_peers().await.map_err(|e| e.to_string())
+}
+
+/// Tauri command: Get a single peer's status
+#[tauri::command]
+pub async fn haunt_get_peer(
+    client: State<'_, HauntClient>,
+    peer_id: String,
+) -> CmdResult<ApiResponse<Option<PeerStatus>>> {
+    client.get_peer(&peer_id).await.map_err(|e| e.to_string())
+}
+
+/// Tauri command: Get data sync health
+#[tauri::command]
+pub async fn haunt_get_sync_health(client: State<'_, HauntClient>) -> CmdResult<Value> {
+    client.get_sync_health().await.map_err(|e| e.to_string())
+}
+
+/// Tauri command: Get the leaderboard
+#[tauri::command]
+pub async fn haunt_get_leaderboard(
+    client: State<'_, HauntClient>,
+    timeframe: Option<String>,
+    limit: Option<u32>,
+) -> CmdResult<ApiResponse<Vec<LeaderboardEntry>>> {
+    client
+        .get_leaderboard(
+            timeframe.as_deref().unwrap_or("all_time"),
+            limit.unwrap_or(50),
+        )
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Get a trader's leaderboard stats
+#[tauri::command]
+pub async fn haunt_get_trader_stats(
+    client: State<'_, HauntClient>,
+    trader_id: String,
+) -> CmdResult<ApiResponse<LeaderboardEntry>> {
+    client
+        .get_trader_stats(&trader_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Get the current user's leaderboard rank
+#[tauri::command]
+pub async fn haunt_get_my_rank(
+    client: State<'_, HauntClient>,
+    token: String,
+) -> CmdResult<ApiResponse<LeaderboardEntry>> {
+    client.get_my_rank(&token).await.map_err(|e| e.to_string())
+}
+
+// ========== Alerts ==========
+
+/// Tauri command: Get the user's price alerts
+#[tauri::command]
+pub async fn haunt_get_alerts(
+    client: State<'_, HauntClient>,
+    token: String,
+) -> CmdResult<ApiResponse<Vec<Alert>>> {
+    client.get_alerts(&token).await.map_err(|e| e.to_string())
+}
+
+/// Tauri command: Create a price alert
+#[tauri::command]
+pub async fn haunt_create_alert(
+    client: State<'_, HauntClient>,
+    token: String,
+    request: CreateAlertRequest,
+) -> CmdResult<ApiResponse<Alert>> {
+    client
+        .create_alert(&token, &request)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Delete a price alert
+#[tauri::command]
+pub async fn haunt_delete_alert(
+    client: State<'_, HauntClient>,
+    token: String,
+    alert_id: String,
+) -> CmdResult<ApiResponse<SuccessResponse>> {
+    client
+        .delete_alert(&token, &alert_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// ========== Account ==========
+
+/// Tauri command: Get the account summary
+#[tauri::command]
+pub async fn haunt_get_account_summary(
+    client: State<'_, HauntClient>,
+    token: String,
+) -> CmdResult<ApiResponse<AccountSummary>> {
+    client
+        .get_account_summary(&token)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Get account transaction history
+#[tauri::command]
+pub async fn haunt_get_transactions(
+    client: State<'_, HauntClient>,
+    token: String,
+    params: Option<TransactionHistoryParams>,
+) -> CmdResult<ApiResponse<Vec<Transaction>>> {
+    client
+        .get_transactions(&token, &params.unwrap_or_default())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Get account trading statistics
+#[tauri::command]
+pub async fn haunt_get_trading_stats(
+    client: State<'_, HauntClient>,
+    token: String,
+    timeframe: Option<String>,
+) -> CmdResult<ApiResponse<Value>> {
+    client
+        .get_trading_stats(&token, timeframe.as_deref().unwrap_or("all"))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Get trade history across portfolios
+#[tauri::command]
+pub async fn haunt_get_trade_history(
+    client: State<'_, HauntClient>,
+    token: String,
+    params: Option<TradeHistoryParams>,
+) -> CmdResult<ApiResponse<Vec<Trade>>> {
+    client
+        .get_trade_history(&token, &params.unwrap_or_default())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// ========== RAT ==========
+
+/// Tauri command: Start the Random Auto Trader
+#[tauri::command]
+pub async fn haunt_start_rat(
+    client: State<'_, HauntClient>,
+    token: String,
+    portfolio_id: String,
+    config: Option<Value>,
+) -> CmdResult<ApiResponse<Value>> {
+    client
+        .start_rat(&token, &portfolio_id, config.as_ref())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Stop the Random Auto Trader
+#[tauri::command]
+pub async fn haunt_stop_rat(
+    client: State<'_, HauntClient>,
+    token: String,
+    portfolio_id: String,
+) -> CmdResult<ApiResponse<Value>> {
+    client
+        .stop_rat(&token, &portfolio_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Get Random Auto Trader status
+#[tauri::command]
+pub async fn haunt_get_rat_status(
+    client: State<'_, HauntClient>,
+    token: String,
+    portfolio_id: String,
+) -> CmdResult<ApiResponse<Value>> {
+    client
+        .get_rat_status(&token, &portfolio_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Update Random Auto Trader configuration
+#[tauri::command]
+pub async fn haunt_update_rat_config(
+    client: State<'_, HauntClient>,
+    token: String,
+    portfolio_id: String,
+    config: Value,
+) -> CmdResult<ApiResponse<Value>> {
+    client
+        .update_rat_config(&token, &portfolio_id, &config)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// ========== Tap trading (grid) ==========
+
+/// Tauri command: Get grid configuration for a symbol
+#[tauri::command]
+pub async fn haunt_get_grid_config(
+    client: State<'_, HauntClient>,
+    symbol: String,
+) -> CmdResult<ApiResponse<Value>> {
+    client
+        .get_grid_config(&symbol)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Get the grid state for a symbol
+#[tauri::command]
+pub async fn haunt_get_grid_state(
+    client: State<'_, HauntClient>,
+    symbol: String,
+    row_count: Option<u32>,
+    col_count: Option<u32>,
+    portfolio_id: Option<String>,
+) -> CmdResult<ApiResponse<Value>> {
+    client
+        .get_grid_state(&symbol, row_count, col_count, portfolio_id.as_deref())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Get active grid positions
+#[tauri::command]
+pub async fn haunt_get_grid_positions(
+    client: State<'_, HauntClient>,
+    token: String,
+    portfolio_id: String,
+) -> CmdResult<ApiResponse<Value>> {
+    client
+        .get_grid_positions(&token, &portfolio_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Get grid trading statistics
+#[tauri::command]
+pub async fn haunt_get_grid_stats(
+    client: State<'_, HauntClient>,
+    token: String,
+    portfolio_id: String,
+    symbol: String,
+) -> CmdResult<ApiResponse<Value>> {
+    client
+        .get_grid_stats(&token, &portfolio_id, &symbol)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Place a grid trade
+#[tauri::command]
+pub async fn haunt_place_grid_trade(
+    client: State<'_, HauntClient>,
+    token: String,
+    params: Value,
+) -> CmdResult<ApiResponse<Value>> {
+    client
+        .place_grid_trade(&token, &params)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// ========== Notifications ==========
+
+/// Tauri command: Get server-side notifications
+#[tauri::command]
+pub async fn haunt_get_notifications(
+    client: State<'_, HauntClient>,
+    token: String,
+    page: Option<u32>,
+    page_size: Option<u32>,
+    unread_only: Option<bool>,
+) -> CmdResult<DataResponse<NotificationListResponse>> {
+    client
+        .get_notifications(
+            &token,
+            page.unwrap_or(1),
+            page_size.unwrap_or(50),
+            unread_only.unwrap_or(false),
+        )
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Mark all server-side notifications read
+#[tauri::command]
+pub async fn haunt_mark_all_notifications_read(
+    client: State<'_, HauntClient>,
+    token: String,
+) -> CmdResult<DataResponse<AffectedResponse>> {
+    client
+        .mark_all_notifications_read(&token)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Mark specific server-side notifications read
+#[tauri::command]
+pub async fn haunt_mark_notifications_read(
+    client: State<'_, HauntClient>,
+    token: String,
+    ids: Vec<String>,
+) -> CmdResult<DataResponse<AffectedResponse>> {
+    client
+        .mark_notifications_read(&token, &ids)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Delete all server-side notifications
+#[tauri::command]
+pub async fn haunt_clear_notifications(
+    client: State<'_, HauntClient>,
+    token: String,
+) -> CmdResult<DataResponse<AffectedResponse>> {
+    client
+        .clear_notifications(&token)
+        .await
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,748 @@
+//! Haunt server selection
+//!
+//! Native port of `ApiServerContext.tsx`: discovers the server mesh, probes
+//! every known server on an interval and keeps rolling latency and health
+//! stats. The active server drives both [`HauntClient`] and [`HauntSocket`],
+//! so switching servers never needs a frontend reload.
+//!
+//! With "autoFastest" on, the fastest healthy server is used. Otherwise the
+//! user's preferred server is used, with automatic failover while it is down
+//! and a switch back once it recovers.
+
+use std::collections::VecDeque;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{Emitter, Manager, State};
+use tokio::sync::Notify;
+
+use super::client::{HauntClient, DEFAULT_BASE_URL};
+use super::models::{MeshDiscoveryResponse, PeerMeshResponse};
+use super::socket::{HauntSocket, DEFAULT_WS_URL};
+
+use crate::now_millis;
+
+/// Event carrying [`MeshStatus`] after every probe round
+pub const STATUS_EVENT: &str = "mesh-status";
+/// Event carrying [`ServerChanged`] when the active server switches
+pub const SERVER_CHANGED_EVENT: &str = "mesh-server-changed";
+
+const MESH_FILE: &str = "mesh.json";
+const PROBE_INTERVAL: Duration = Duration::from_secs(30);
+/// Rounds between mesh re-discovery (5 minutes at the probe interval)
+const DISCOVERY_EVERY: u32 = 10;
+/// Probe results kept for rolling stats
+const SAMPLE_WINDOW: usize = 10;
+/// Consecutive failed probes before the active server is abandoned
+const FAILOVER_AFTER: u32 = 2;
+/// How much faster another server must be before auto mode moves to it
+const SWITCH_MARGIN: f64 = 0.2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ServerStatus {
+    Online,
+    Offline,
+    #[default]
+    Checking,
+}
+
+/// A known Haunt server with its rolling probe stats
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiServer {
+    pub id: String,
+    pub name: String,
+    pub region: String,
+    pub url: String,
+    pub ws_url: String,
+    #[serde(default)]
+    pub is_local: bool,
+    #[serde(default)]
+    pub is_discovered: bool,
+    #[serde(default)]
+    pub status: ServerStatus,
+    /// Latest successful round trip
+    #[serde(default)]
+    pub latency_ms: Option<u64>,
+    /// Mean of successful round trips in the sample window
+    #[serde(default)]
+    pub avg_latency_ms: Option<u64>,
+    /// Share of successful probes in the sample window, 0.0-1.0
+    #[serde(default)]
+    pub success_rate: f64,
+    #[serde(default)]
+    pub consecutive_failures: u32,
+    #[serde(default)]
+    pub last_checked: Option<u64>,
+    /// Recent probe results; `None` marks a failure
+    #[serde(skip)]
+    samples: VecDeque<Option<u64>>,
+}
+
+impl ApiServer {
+    fn new(id: &str, name: &str, region: &str, url: &str, ws_url: &str) -> Self {
+        Self {
+            id: id.into(),
+            name: name.into(),
+            region: region.into(),
+            url: url.into(),
+            ws_url: ws_url.into(),
+            is_local: id == "local",
+            is_discovered: false,
+            status: ServerStatus::Checking,
+            latency_ms: None,
+            avg_latency_ms: None,
+            success_rate: 0.0,
+            consecutive_failures: 0,
+            last_checked: None,
+            samples: VecDeque::new(),
+        }
+    }
+
+    /// Record a probe result (round trip in ms, or `None` on failure)
+    fn record(&mut self, latency_ms: Option<u64>, now_ms: u64) {
+        if self.samples.len() == SAMPLE_WINDOW {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(latency_ms);
+        self.last_checked = Some(now_ms);
+
+        let successes: Vec<u64> = self.samples.iter().flatten().copied().collect();
+        self.success_rate = successes.len() as f64 / self.samples.len() as f64;
+        self.avg_latency_ms =
+            (!successes.is_empty()).then(|| successes.iter().sum::<u64>() / successes.len() as u64);
+
+        match latency_ms {
+            Some(latency) => {
+                self.status = ServerStatus::Online;
+                self.latency_ms = Some(latency);
+                self.consecutive_failures = 0;
+            }
+            None => {
+                self.status = ServerStatus::Offline;
+                self.latency_ms = None;
+                self.consecutive_failures += 1;
+            }
+        }
+    }
+
+    fn is_healthy(&self) -> bool {
+        self.status == ServerStatus::Online && self.consecutive_failures == 0
+    }
+
+    fn rank_latency(&self) -> u64 {
+        self.avg_latency_ms.or(self.latency_ms).unwrap_or(u64::MAX)
+    }
+}
+
+/// Servers used until discovery succeeds
+fn fallback_servers() -> Vec<ApiServer> {
+    vec![
+        ApiServer::new("local", "Local", "Local", DEFAULT_BASE_URL, DEFAULT_WS_URL),
+        ApiServer::new(
+            "osaka",
+            "Osaka",
+            "Asia Pacific",
+            "https://osaka.haunt.st",
+            "wss://osaka.haunt.st/ws",
+        ),
+        ApiServer::new(
+            "seoul",
+            "Seoul",
+            "Asia Pacific",
+            "https://seoul.haunt.st",
+            "wss://seoul.haunt.st/ws",
+        ),
+        ApiServer::new(
+            "nyc",
+            "New York",
+            "North America",
+            "https://nyc.haunt.st",
+            "wss://nyc.haunt.st/ws",
+        ),
+    ]
+}
+
+/// Convert a discovery response, keeping the local server available
+fn servers_from_discovery(discovery: &MeshDiscoveryResponse) -> Vec<ApiServer> {
+    let mut servers: Vec<ApiServer> = discovery
+        .servers
+        .iter()
+        .map(|s| {
+            let mut name = s.id.replace('-', " ");
+            if let Some(first) = name.get(..1) {
+                name = first.to_uppercase() + &name[1..];
+            }
+            let mut server = ApiServer::new(&s.id, &name, &s.region, &s.api_url, &s.ws_url);
+            server.is_local = s.id == "local" || s.api_url.contains("localhost");
+            server.is_discovered = true;
+            server
+        })
+        .collect();
+
+    if !servers.iter().any(|s| s.is_local) {
+        servers.insert(0, fallback_servers().remove(0));
+    }
+    servers
+}
+
+/// Why the active server changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SwitchReason {
+    Manual,
+    Fastest,
+    Failover,
+    Recovered,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerChanged {
+    pub previous: Option<String>,
+    pub current: String,
+    pub reason: SwitchReason,
+}
+
+/// Settings persisted across launches
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MeshSettings {
+    #[serde(default)]
+    auto_fastest: bool,
+    preferred_server: Option<String>,
+    /// Last known servers, used until the next discovery
+    #[serde(default)]
+    servers: Vec<ApiServer>,
+}
+
+/// Server selection state
+struct Mesh {
+    servers: Vec<ApiServer>,
+    active_id: String,
+    preferred_id: String,
+    auto_fastest: bool,
+    peer_mesh: Option<PeerMeshResponse>,
+}
+
+impl Default for Mesh {
+    fn default() -> Self {
+        Self {
+            servers: fallback_servers(),
+            active_id: "local".into(),
+            preferred_id: "local".into(),
+            auto_fastest: false,
+            peer_mesh: None,
+        }
+    }
+}
+
+impl Mesh {
+    fn from_settings(settings: MeshSettings) -> Self {
+        let mut mesh = Self {
+            auto_fastest: settings.auto_fastest,
+            ..Default::default()
+        };
+        if !settings.servers.is_empty() {
+            // Stats from the last run are stale; start from scratch
+            mesh.servers = settings
+                .servers
+                .iter()
+                .map(|s| ApiServer {
+                    is_local: s.is_local,
+                    is_discovered: s.is_discovered,
+                    ..ApiServer::new(&s.id, &s.name, &s.region, &s.url, &s.ws_url)
+                })
+                .collect();
+        }
+        if let Some(preferred) = settings.preferred_server {
+            if mesh.server(&preferred).is_some() {
+                mesh.preferred_id = preferred.clone();
+                mesh.active_id = preferred;
+            }
+        }
+        mesh
+    }
+
+    fn settings(&self) -> MeshSettings {
+        MeshSettings {
+            auto_fastest: self.auto_fastest,
+            preferred_server: Some(self.preferred_id.clone()),
+            servers: self.servers.clone(),
+        }
+    }
+
+    fn server(&self, id: &str) -> Option<&ApiServer> {
+        self.servers.iter().find(|s| s.id == id)
+    }
+
+    fn active(&self) -> Option<&ApiServer> {
+        self.server(&self.active_id)
+    }
+
+    fn fastest(&self) -> Option<&ApiServer> {
+        self.servers
+            .iter()
+            .filter(|s| s.is_healthy())
+            .min_by_key(|s| s.rank_latency())
+    }
+
+    /// Replace the server list, carrying stats over for known servers
+    fn set_servers(&mut self, servers: Vec<ApiServer>) {
+        let previous = std::mem::replace(&mut self.servers, servers);
+        for server in &mut self.servers {
+            if let Some(old) = previous.iter().find(|s| s.id == server.id) {
+                server.status = old.status;
+                server.latency_ms = old.latency_ms;
+                server.avg_latency_ms = old.avg_latency_ms;
+                server.success_rate = old.success_rate;
+                server.consecutive_failures = old.consecutive_failures;
+                server.last_checked = old.last_checked;
+                server.samples = old.samples.clone();
+            }
+        }
+        // Keep the active server reachable even if the mesh dropped it
+        if self.active().is_none() {
+            if let Some(old) = previous.into_iter().find(|s| s.id == self.active_id) {
+                self.servers.push(old);
+            }
+        }
+    }
+
+    /// Decide whether to move off the active server
+    fn evaluate(&self) -> Option<(String, SwitchReason)> {
+        let active = self.active();
+        let active_failed = active.map_or(true, |s| s.consecutive_failures >= FAILOVER_AFTER);
+
+        if self.auto_fastest {
+            let fastest = self.fastest()?;
+            if fastest.id == self.active_id {
+                return None;
+            }
+            match active {
+                Some(active) if active.is_healthy() => {
+                    let threshold = active.rank_latency() as f64 * (1.0 - SWITCH_MARGIN);
+                    ((fastest.rank_latency() as f64) < threshold)
+                        .then(|| (fastest.id.clone(), SwitchReason::Fastest))
+                }
+                // Still checking, or a single failed probe: wait for more data
+                Some(_) if !active_failed => None,
+                _ => Some((fastest.id.clone(), SwitchReason::Failover)),
+            }
+        } else {
+            if self.active_id != self.preferred_id {
+                if let Some(preferred) = self.server(&self.preferred_id) {
+                    if preferred.is_healthy() {
+                        return Some((preferred.id.clone(), SwitchReason::Recovered));
+                    }
+                }
+            }
+            if !active_failed {
+                return None;
+            }
+            self.fastest()
+                .filter(|s| s.id != self.active_id)
+                .map(|s| (s.id.clone(), SwitchReason::Failover))
+        }
+    }
+}
+
+/// Snapshot reported to the frontend
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MeshStatus {
+    pub servers: Vec<ApiServer>,
+    pub active_server: Option<ApiServer>,
+    pub preferred_server: String,
+    pub auto_fastest: bool,
+    pub fastest_server: Option<ApiServer>,
+    pub peer_mesh: Option<PeerMeshResponse>,
+}
+
+impl From<&Mesh> for MeshStatus {
+    fn from(mesh: &Mesh) -> Self {
+        Self {
+            servers: mesh.servers.clone(),
+            active_server: mesh.active().cloned(),
+            preferred_server: mesh.preferred_id.clone(),
+            auto_fastest: mesh.auto_fastest,
+            fastest_server: mesh.fastest().cloned(),
+            peer_mesh: mesh.peer_mesh.clone(),
+        }
+    }
+}
+
+/// Managed state for server selection
+#[derive(Default)]
+pub struct MeshState {
+    mesh: Mutex<Mesh>,
+    data_dir: Mutex<Option<PathBuf>>,
+    /// Wakes the probe loop early
+    wake: Arc<Notify>,
+}
+
+impl MeshState {
+    pub fn status(&self) -> MeshStatus {
+        MeshStatus::from(&*self.mesh.lock().unwrap())
+    }
+
+    fn save(&self) {
+        let Some(dir) = self.data_dir.lock().unwrap().clone() else {
+            return;
+        };
+        let settings = self.mesh.lock().unwrap().settings();
+        let written = fs::create_dir_all(&dir).and_then(|_| {
+            let json = serde_json::to_vec_pretty(&settings)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+            fs::write(dir.join(MESH_FILE), json)
+        });
+        if let Err(e) = written {
+            eprintln!("Failed to save server settings: {}", e);
+        }
+    }
+}
+
+/// Point the REST and WebSocket clients at the active server
+fn apply_active(app: &tauri::AppHandle, server: &ApiServer) {
+    app.state::<HauntClient>().set_base_url(&server.url);
+    app.state::<HauntSocket>().set_url(&server.ws_url);
+}
+
+fn switch_to(app: &tauri::AppHandle, id: &str, reason: SwitchReason) {
+    let state = app.state::<MeshState>();
+    let (previous, server) = {
+        let mut mesh = state.mesh.lock().unwrap();
+        let Some(server) = mesh.server(id).cloned() else {
+            return;
+        };
+        if mesh.active_id == id {
+            return;
+        }
+        let previous = std::mem::replace(&mut mesh.active_id, id.to_string());
+        if reason == SwitchReason::Manual {
+            mesh.preferred_id = id.to_string();
+        }
+        (previous, server)
+    };
+
+    apply_active(app, &server);
+    let _ = app.emit(
+        SERVER_CHANGED_EVENT,
+        ServerChanged {
+            previous: Some(previous),
+            current: server.id,
+            reason,
+        },
+    );
+}
+
+/// Refresh the server list from any reachable mesh member
+async fn discover(app: &tauri::AppHandle) {
+    let client = app.state::<HauntClient>();
+    let state = app.state::<MeshState>();
+    let candidates: Vec<String> = {
+        let mesh = state.mesh.lock().unwrap();
+        let mut urls: Vec<String> = mesh.active().map(|s| s.url.clone()).into_iter().collect();
+        for server in mesh.servers.iter().chain(fallback_servers().iter()) {
+            if !urls.contains(&server.url) {
+                urls.push(server.url.clone());
+            }
+        }
+        urls
+    };
+
+    for url in candidates {
+        if let Ok(discovery) = client.discover(&url).await {
+            state
+                .mesh
+                .lock()
+                .unwrap()
+                .set_servers(servers_from_discovery(&discovery));
+            state.save();
+            return;
+        }
+    }
+}
+
+/// Probe every server, then switch if the selection rules say so
+async fn probe_all(app: &tauri::AppHandle) {
+    let client = app.state::<HauntClient>();
+    let state = app.state::<MeshState>();
+    let targets: Vec<(String, String)> = state
+        .mesh
+        .lock()
+        .unwrap()
+        .servers
+        .iter()
+        .map(|s| (s.id.clone(), s.url.clone()))
+        .collect();
+
+    let results = futures_util::future::join_all(targets.iter().map(|(id, url)| {
+        let client = &client;
+        async move {
+            let latency = client.probe(url).await.ok();
+            (id.clone(), latency.map(|d| d.as_millis() as u64))
+        }
+    }))
+    .await;
+
+    let decision = {
+        let mut mesh = state.mesh.lock().unwrap();
+        let now = now_millis() as u64;
+        for (id, latency) in results {
+            if let Some(server) = mesh.servers.iter_mut().find(|s| s.id == id) {
+                server.record(latency, now);
+            }
+        }
+        mesh.evaluate()
+    };
+    if let Some((id, reason)) = decision {
+        switch_to(app, &id, reason);
+    }
+
+    let peer_mesh = client.get_peers().await.ok().map(|r| r.data);
+    let status = {
+        let mut mesh = state.mesh.lock().unwrap();
+        if peer_mesh.is_some() {
+            mesh.peer_mesh = peer_mesh;
+        }
+        MeshStatus::from(&*mesh)
+    };
+    let _ = app.emit(STATUS_EVENT, status);
+}
+
+/// Load saved settings, point the clients at the saved server and start
+/// probing
+pub fn init(app: &tauri::AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+    let data_dir = app.path().app_data_dir()?;
+    let state = app.state::<MeshState>();
+
+    let settings = match fs::read(data_dir.join(MESH_FILE)) {
+        Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+            eprintln!("Failed to parse mesh settings: {}", e);
+            MeshSettings::default()
+        }),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => MeshSettings::default(),
+        Err(e) => return Err(e.into()),
+    };
+    *state.data_dir.lock().unwrap() = Some(data_dir);
+    *state.mesh.lock().unwrap() = Mesh::from_settings(settings);
+
+    if let Some(active) = state.mesh.lock().unwrap().active().cloned() {
+        apply_active(app, &active);
+    }
+
+    let handle = app.clone();
+    let wake = state.wake.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut round = 0u32;
+        loop {
+            if round % DISCOVERY_EVERY == 0 {
+                discover(&handle).await;
+            }
+            probe_all(&handle).await;
+            round = round.wrapping_add(1);
+
+            tokio::select! {
+                _ = tokio::time::sleep(PROBE_INTERVAL) => {}
+                _ = wake.notified() => {}
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Tauri command: Get known servers, the active server and probe stats
+#[tauri::command]
+pub fn mesh_status(state: State<'_, MeshState>) -> MeshStatus {
+    state.status()
+}
+
+/// Tauri command: Select a server manually, turning off autoFastest
+#[tauri::command]
+pub fn mesh_set_active(
+    app: tauri::AppHandle,
+    state: State<'_, MeshState>,
+    server_id: String,
+) -> Result<MeshStatus, String> {
+    {
+        let mut mesh = state.mesh.lock().unwrap();
+        if mesh.server(&server_id).is_none() {
+            return Err(format!("Unknown server \"{}\"", server_id));
+        }
+        mesh.auto_fastest = false;
+        mesh.preferred_id = server_id.clone();
+    }
+    switch_to(&app, &server_id, SwitchReason::Manual);
+    state.save();
+    Ok(state.status())
+}
+
+/// Tauri command: Turn automatic fastest-server selection on or off
+#[tauri::command]
+pub fn mesh_set_auto_fastest(
+    app: tauri::AppHandle,
+    state: State<'_, MeshState>,
+    enabled: bool,
+) -> MeshStatus {
+    let decision = {
+        let mut mesh = state.mesh.lock().unwrap();
+        mesh.auto_fastest = enabled;
+        if !enabled {
+            mesh.preferred_id = mesh.active_id.clone();
+        }
+        mesh.evaluate()
+    };
+    if let Some((id, reason)) = decision {
+        switch_to(&app, &id, reason);
+    }
+    state.save();
+    state.status()
+}
+
+/// Tauri command: Probe all servers now
+#[tauri::command]
+pub async fn mesh_refresh(app: tauri::AppHandle) -> Result<MeshStatus, String> {
+    probe_all(&app).await;
+    Ok(app.state::<MeshState>().status())
+}
+
+/// Tauri command: Re-discover the mesh and probe the result
+#[tauri::command]
+pub async fn mesh_discover(app: tauri::AppHandle) -> Result<MeshStatus, String> {
+    discover(&app).await;
+    app.state::<MeshState>().wake.notify_one();
+    Ok(app.state::<MeshState>().status())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    /// Mesh with the fallback servers and the given latencies (None = down)
+    fn mesh_with(latencies: &[(&str, Option<u64>)]) -> Mesh {
+        let mut mesh = Mesh::default();
+        for (id, latency) in latencies {
+            let server = mesh.servers.iter_mut().find(|s| s.id == *id).unwrap();
+            server.record(*latency, 1);
+        }
+        mesh
+    }
+
+    #[test]
+    fn rolling_stats_track_window() {
+        let mut server = fallback_servers().remove(1);
+        for latency in [Some(100), Some(200), None] {
+            server.record(latency, 1);
+        }
+        assert_eq!(server.avg_latency_ms, Some(150));
+        assert_eq!(server.status, ServerStatus::Offline);
+        assert_eq!(server.consecutive_failures, 1);
+        assert!((server.success_rate - 2.0 / 3.0).abs() < 1e-9);
+
+        for _ in 0..SAMPLE_WINDOW {
+            server.record(Some(50), 2);
+        }
+        assert_eq!(server.samples.len(), SAMPLE_WINDOW);
+        assert_eq!(server.avg_latency_ms, Some(50));
+        assert_eq!(server.success_rate, 1.0);
+        assert!(server.is_healthy());
+    }
+
+    #[test]
+    fn auto_fastest_needs_a_clear_win() {
+        let mut mesh = mesh_with(&[
+            ("local", Some(100)),
+            ("osaka", Some(90)),
+            ("seoul", None),
+            ("nyc", Some(150)),
+        ]);
+        mesh.auto_fastest = true;
+        assert_eq!(mesh.evaluate(), None);
+
+        mesh.servers[1].record(Some(20), 2);
+        assert_eq!(
+            mesh.evaluate(),
+            Some(("osaka".into(), SwitchReason::Fastest))
+        );
+    }
+
+    #[test]
+    fn fails_over_and_recovers_preferred_server() {
+        let mut mesh = mesh_with(&[("local", None), ("osaka", Some(80)), ("nyc", Some(40))]);
+        assert_eq!(mesh.evaluate(), None, "one failure is not enough");
+
+        mesh.servers[0].record(None, 2);
+        assert_eq!(
+            mesh.evaluate(),
+            Some(("nyc".into(), SwitchReason::Failover))
+        );
+        mesh.active_id = "nyc".into();
+        assert_eq!(mesh.evaluate(), None);
+
+        mesh.servers[0].record(Some(5), 3);
+        assert_eq!(
+            mesh.evaluate(),
+            Some(("local".into(), SwitchReason::Recovered))
+        );
+    }
+
+    #[test]
+    fn discovery_keeps_local_and_stats() {
+        let discovery: MeshDiscoveryResponse = serde_json::from_value(json!({
+            "selfId": "osaka", "selfRegion": "Asia Pacific",
+            "selfApiUrl": "https://osaka.haunt.st", "selfWsUrl": "wss://osaka.haunt.st/ws",
+            "servers": [
+                { "id": "osaka", "region": "Asia Pacific", "apiUrl": "https://osaka.haunt.st",
+                  "wsUrl": "wss://osaka.haunt.st/ws", "status": "online" },
+                { "id": "eu-west", "region": "Europe", "apiUrl": "https://eu.haunt.st",
+                  "wsUrl": "wss://eu.haunt.st/ws", "status": "online", "latencyMs": 12.5 }
+            ],
+            "meshKeyHash": "abc", "timestamp": 1
+        }))
+        .unwrap();
+
+        let mut mesh = mesh_with(&[("osaka", Some(70))]);
+        mesh.active_id = "nyc".into();
+        mesh.set_servers(servers_from_discovery(&discovery));
+
+        let ids: Vec<_> = mesh.servers.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, ["local", "osaka", "eu-west", "nyc"]);
+        assert_eq!(mesh.servers[2].name, "Eu west");
+        assert_eq!(mesh.server("osaka").unwrap().latency_ms, Some(70));
+
+        let settings = mesh.settings();
+        let restored = Mesh::from_settings(serde_json::from_value(json!(settings)).unwrap());
+        assert_eq!(restored.servers.len(), 4);
+        assert!(restored.servers[2].is_discovered);
+        assert_eq!(restored.servers[1].status, ServerStatus::Checking);
+        assert_eq!(restored.active_id, "local");
+    }
+
+    #[tokio::test]
+    async fn probes_measure_round_trip() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/health"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({ "status": "ok", "timestamp": "", "uptime": 1.0 }))
+                    .set_delay(Duration::from_millis(50)),
+            )
+            .mount(&server)
+            .await;
+
+        let client = HauntClient::default();
+        let latency = client.probe(&server.uri()).await.unwrap();
+        assert!(latency >= Duration::from_millis(50));
+        assert!(client.probe("http://127.0.0.1:9").await.is_err());
+    }
+}
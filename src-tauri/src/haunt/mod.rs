@@ -0,0 +1,25 @@
+//! Native Haunt API client
+//!
+//! A typed port of `src/services/haunt.ts` so background tasks (alerts,
+//! tray updates, exports) can reach Haunt while the window is hidden, plus
+//! the backend-owned WebSocket stream and server selection.
+
+mod client;
+pub mod commands;
+pub mod mesh;
+pub mod models;
+pub mod socket;
+
+pub use client::HauntClient;
+pub use mesh::MeshState;
+pub use socket::HauntSocket;
+
+/// Install ring as the process-wide rustls provider
+///
+/// reqwest and tungstenite are built without a bundled provider; ring is the
+/// one the updater already links.
+pub(crate) fn install_crypto_provider() {
+    if rustls::crypto::CryptoProvider::get_default().is_none() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+    }
+}
@@ -0,0 +1,998 @@
+//! Haunt API models
+//!
+//! Serde mirrors of the types in `src/services/haunt.ts` and `src/types/`.
+//! Field names follow the API's camelCase; optional fields stay optional so
+//! older servers that omit them still deserialize.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Standard `{ data, meta }` response envelope
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiResponse<T> {
+    pub data: T,
+    #[serde(default)]
+    pub meta: ApiMeta,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiMeta {
+    #[serde(default)]
+    pub cached: bool,
+    pub total: Option<u64>,
+    pub start: Option<u64>,
+    pub limit: Option<u64>,
+    pub query: Option<String>,
+}
+
+/// `{ data }` envelope used by endpoints without metadata
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataResponse<T> {
+    pub data: T,
+}
+
+/// Error body returned by Haunt on non-2xx responses
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ApiErrorBody {
+    pub error: Option<String>,
+    pub message: Option<String>,
+    pub code: Option<String>,
+}
+
+// ========== Market ==========
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TradeDirection {
+    Up,
+    Down,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Asset {
+    pub id: i64,
+    #[serde(default)]
+    pub rank: u32,
+    pub name: String,
+    pub symbol: String,
+    #[serde(default)]
+    pub image: String,
+    pub price: f64,
+    #[serde(default)]
+    pub change_1h: f64,
+    #[serde(default)]
+    pub change_24h: f64,
+    #[serde(default)]
+    pub change_7d: f64,
+    #[serde(default)]
+    pub market_cap: f64,
+    #[serde(default)]
+    pub volume_24h: f64,
+    #[serde(default)]
+    pub circulating_supply: f64,
+    pub max_supply: Option<f64>,
+    #[serde(default)]
+    pub sparkline: Vec<f64>,
+    pub trade_direction: Option<TradeDirection>,
+    pub asset_type: Option<String>,
+    pub exchange: Option<String>,
+    pub sector: Option<String>,
+}
+
+impl Asset {
+    /// Build an asset from either the flat listings shape or the nested
+    /// `quote` shape returned by `/api/crypto/{id}`
+    pub fn from_value(value: Value) -> Result<Self, serde_json::Error> {
+        if value.get("price").is_some() {
+            return serde_json::from_value(value);
+        }
+
+        let quote = value.get("quote").cloned().unwrap_or(Value::Null);
+        let num = |v: &Value, key: &str| v.get(key).and_then(Value::as_f64).unwrap_or(0.0);
+        let id = value.get("id").and_then(Value::as_i64).unwrap_or(0);
+        let image = value
+            .get("image")
+            .or_else(|| value.get("logo"))
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .unwrap_or_else(|| {
+                format!("https://s2.coinmarketcap.com/static/img/coins/64x64/{id}.png")
+            });
+
+        Ok(Self {
+            id,
+            rank: value.get("rank").and_then(Value::as_u64).unwrap_or(0) as u32,
+            name: serde_json::from_value(value.get("name").cloned().unwrap_or_default())?,
+            symbol: serde_json::from_value(value.get("symbol").cloned().unwrap_or_default())?,
+            image,
+            price: num(&quote, "price"),
+            change_1h: num(&quote, "percentChange1h"),
+            change_24h: num(&quote, "percentChange24h"),
+            change_7d: num(&quote, "percentChange7d"),
+            market_cap: num(&quote, "marketCap"),
+            volume_24h: num(&quote, "volume24h"),
+            circulating_supply: num(&quote, "circulatingSupply"),
+            max_supply: quote.get("maxSupply").and_then(Value::as_f64),
+            sparkline: value
+                .get("sparkline")
+                .and_then(|s| serde_json::from_value(s.clone()).ok())
+                .unwrap_or_default(),
+            trade_direction: None,
+            asset_type: value
+                .get("assetType")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            exchange: None,
+            sector: None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ListingsParams {
+    pub start: Option<u32>,
+    pub limit: Option<u32>,
+    pub sort: Option<String>,
+    pub sort_dir: Option<String>,
+    pub filter: Option<String>,
+    pub asset_type: Option<String>,
+    pub min_change: Option<f64>,
+    pub max_change: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GlobalMetrics {
+    pub total_market_cap: f64,
+    pub total_volume_24h: f64,
+    pub btc_dominance: f64,
+    pub eth_dominance: f64,
+    pub active_cryptocurrencies: u64,
+    pub active_exchanges: u64,
+    pub market_cap_change_24h: f64,
+    pub volume_change_24h: f64,
+    pub last_updated: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FearGreedData {
+    pub value: f64,
+    pub classification: String,
+    pub timestamp: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OhlcPoint {
+    pub time: i64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChartData {
+    pub symbol: String,
+    pub range: String,
+    pub data: Vec<OhlcPoint>,
+    pub seeding: Option<bool>,
+    pub seeding_status: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SeedResponse {
+    pub symbol: String,
+    pub status: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceStat {
+    pub source: String,
+    pub update_count: u64,
+    pub update_percent: f64,
+    pub online: bool,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SymbolSourceStats {
+    pub symbol: String,
+    pub sources: Vec<SourceStat>,
+    pub total_updates: u64,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Mover {
+    pub symbol: String,
+    pub price: f64,
+    pub change_percent: f64,
+    pub volume_24h: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MoversResponse {
+    pub timeframe: String,
+    pub gainers: Vec<Mover>,
+    pub losers: Vec<Mover>,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiStats {
+    pub total_updates: u64,
+    pub tps: f64,
+    pub uptime_secs: u64,
+    pub active_symbols: u64,
+    pub online_sources: u64,
+    pub total_sources: u64,
+    pub exchanges: Vec<SourceStat>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthResponse {
+    pub status: String,
+    pub timestamp: String,
+    pub uptime: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AggregatedLevel {
+    pub price: f64,
+    pub total_quantity: f64,
+    #[serde(default)]
+    pub exchanges: std::collections::HashMap<String, f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AggregatedOrderBook {
+    pub symbol: String,
+    pub bids: Vec<AggregatedLevel>,
+    pub asks: Vec<AggregatedLevel>,
+    pub bid_total: f64,
+    pub ask_total: f64,
+    pub imbalance: f64,
+    pub best_bid: f64,
+    pub best_ask: f64,
+    pub spread: f64,
+    pub spread_pct: f64,
+    pub mid_price: f64,
+    pub exchange_count: u32,
+    #[serde(default)]
+    pub exchanges: Vec<String>,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FundingRate {
+    pub symbol: String,
+    pub rate: f64,
+    pub next_funding_time: i64,
+    pub predicted_rate: Option<f64>,
+    /// Funding interval in hours
+    pub interval: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FundingHistory {
+    pub symbol: String,
+    pub rate: f64,
+    pub timestamp: i64,
+    pub payment: Option<f64>,
+}
+
+// ========== Signals ==========
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TradingTimeframe {
+    Scalping,
+    DayTrading,
+    SwingTrading,
+    PositionTrading,
+}
+
+impl TradingTimeframe {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Scalping => "scalping",
+            Self::DayTrading => "day_trading",
+            Self::SwingTrading => "swing_trading",
+            Self::PositionTrading => "position_trading",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignalDirection {
+    StrongBuy,
+    Buy,
+    Neutral,
+    Sell,
+    StrongSell,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignalCategory {
+    Trend,
+    Momentum,
+    Volatility,
+    Volume,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignalOutput {
+    pub name: String,
+    pub category: SignalCategory,
+    pub value: f64,
+    pub score: f64,
+    pub direction: SignalDirection,
+    pub accuracy: Option<f64>,
+    pub sample_size: Option<u64>,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SymbolSignals {
+    pub symbol: String,
+    pub timeframe: TradingTimeframe,
+    pub signals: Vec<SignalOutput>,
+    pub trend_score: f64,
+    pub momentum_score: f64,
+    pub volatility_score: f64,
+    pub volume_score: f64,
+    pub composite_score: f64,
+    pub direction: SignalDirection,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PredictionOutcome {
+    Correct,
+    Incorrect,
+    Neutral,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignalPrediction {
+    pub id: String,
+    pub symbol: String,
+    pub indicator: String,
+    pub direction: SignalDirection,
+    pub score: f64,
+    pub price_at_prediction: f64,
+    pub timestamp: i64,
+    pub validated: bool,
+    pub price_after_5m: Option<f64>,
+    pub price_after_1h: Option<f64>,
+    pub price_after_4h: Option<f64>,
+    pub price_after_24h: Option<f64>,
+    pub outcome_5m: Option<PredictionOutcome>,
+    pub outcome_1h: Option<PredictionOutcome>,
+    pub outcome_4h: Option<PredictionOutcome>,
+    pub outcome_24h: Option<PredictionOutcome>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignalAccuracy {
+    pub indicator: String,
+    pub symbol: String,
+    pub timeframe: String,
+    pub total_predictions: u64,
+    pub correct_predictions: u64,
+    pub incorrect_predictions: u64,
+    pub neutral_predictions: u64,
+    pub accuracy_pct: f64,
+    pub last_updated: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccuracyResponse {
+    pub symbol: String,
+    pub accuracies: Vec<SignalAccuracy>,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PredictionsResponse {
+    pub symbol: String,
+    pub predictions: Vec<SignalPrediction>,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Recommendation {
+    pub symbol: String,
+    /// "buy", "sell" or "hold"
+    pub action: String,
+    pub confidence: f64,
+    pub weighted_score: f64,
+    pub indicators_with_accuracy: u32,
+    pub total_indicators: u32,
+    pub average_accuracy: f64,
+    pub description: String,
+    pub timestamp: i64,
+}
+
+// ========== Portfolio & trading ==========
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RiskSettings {
+    pub max_position_size_pct: f64,
+    pub daily_loss_limit_pct: f64,
+    pub max_open_positions: u32,
+    pub risk_per_trade_pct: f64,
+    pub portfolio_stop_pct: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Portfolio {
+    pub id: String,
+    pub user_id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub base_currency: String,
+    pub starting_balance: f64,
+    pub cash_balance: f64,
+    pub margin_used: f64,
+    pub margin_available: f64,
+    pub unrealized_pnl: f64,
+    pub realized_pnl: f64,
+    pub total_value: f64,
+    pub total_trades: u64,
+    pub winning_trades: u64,
+    pub cost_basis_method: Option<String>,
+    pub risk_settings: Option<RiskSettings>,
+    pub is_competition: Option<bool>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatePortfolioRequest {
+    pub name: Option<String>,
+    pub initial_balance: Option<f64>,
+    pub user_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Holding {
+    pub id: String,
+    pub symbol: String,
+    pub name: String,
+    pub image: Option<String>,
+    pub quantity: f64,
+    pub avg_price: f64,
+    pub current_price: f64,
+    pub value: f64,
+    pub allocation: f64,
+    pub pnl: f64,
+    pub pnl_percent: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HoldingsResponse {
+    pub holdings: Vec<Holding>,
+    pub total_value: f64,
+    pub total_pnl: f64,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PerformancePoint {
+    pub timestamp: i64,
+    pub value: f64,
+    pub pnl: f64,
+    pub pnl_percent: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PerformanceResponse {
+    pub range: String,
+    pub data: Vec<PerformancePoint>,
+    pub start_value: f64,
+    pub end_value: f64,
+    pub total_pnl: f64,
+    pub total_pnl_percent: f64,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PositionSide {
+    Long,
+    Short,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MarginMode {
+    Isolated,
+    Cross,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Position {
+    pub id: String,
+    pub symbol: String,
+    pub side: PositionSide,
+    pub quantity: f64,
+    pub entry_price: f64,
+    pub current_price: f64,
+    pub leverage: f64,
+    pub margin_mode: MarginMode,
+    pub liquidation_price: Option<f64>,
+    pub unrealized_pnl: f64,
+    pub unrealized_pnl_pct: f64,
+    pub margin_used: f64,
+    pub stop_loss: Option<f64>,
+    pub take_profit: Option<f64>,
+    pub trailing_stop: Option<f64>,
+    pub created_at: i64,
+}
+
+/// Position changes; `Some(None)` serializes as `null` to remove a level
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModifyPositionRequest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_loss: Option<Option<f64>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub take_profit: Option<Option<f64>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trailing_stop: Option<Option<f64>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OrderSide {
+    Buy,
+    Sell,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderType {
+    Market,
+    Limit,
+    StopLoss,
+    TakeProfit,
+    StopLimit,
+    TrailingStop,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OrderStatus {
+    Pending,
+    Partial,
+    Filled,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Order {
+    pub id: String,
+    pub symbol: String,
+    #[serde(rename = "type")]
+    pub order_type: OrderType,
+    pub side: OrderSide,
+    pub price: Option<f64>,
+    pub size: f64,
+    pub filled_size: f64,
+    pub status: OrderStatus,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Trade {
+    pub id: String,
+    pub symbol: String,
+    pub side: OrderSide,
+    pub size: f64,
+    pub price: f64,
+    pub fee: f64,
+    pub pnl: f64,
+    pub executed_at: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AssetClass {
+    CryptoSpot,
+    Stock,
+    Etf,
+    Perp,
+    Option,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeInForce {
+    Gtc,
+    Ioc,
+    Fok,
+    Gtd,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaceOrderRequest {
+    pub portfolio_id: String,
+    pub symbol: String,
+    pub asset_class: AssetClass,
+    pub side: OrderSide,
+    pub order_type: OrderType,
+    pub quantity: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub price: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_price: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trail_amount: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trail_percent: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_in_force: Option<TimeInForce>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub leverage: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_loss: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub take_profit: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reduce_only: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_only: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub margin_mode: Option<MarginMode>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bypass_drawdown: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModifyOrderRequest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub price: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_loss: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub take_profit: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CancelAllOrdersResponse {
+    pub cancelled: u64,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SuccessResponse {
+    pub success: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AffectedResponse {
+    pub success: bool,
+    pub affected: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DrawdownProtection {
+    pub enabled: bool,
+    pub max_drawdown_percent: f64,
+    pub calculation_method: String,
+    pub allow_bypass: bool,
+    pub auto_reset_after: String,
+    pub warning_threshold_percent: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortfolioSettings {
+    pub drawdown_protection: DrawdownProtection,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DrawdownHistoryPoint {
+    pub timestamp: i64,
+    pub drawdown_percent: f64,
+    pub portfolio_value: f64,
+}
+
+// ========== Alerts ==========
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertCondition {
+    Above,
+    Below,
+    Crosses,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Alert {
+    pub id: String,
+    pub symbol: String,
+    pub condition: AlertCondition,
+    pub target_price: f64,
+    pub current_price: Option<f64>,
+    pub triggered: bool,
+    pub triggered_at: Option<i64>,
+    pub created_at: i64,
+    pub expires_at: Option<i64>,
+    pub notify_email: Option<bool>,
+    pub notify_push: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateAlertRequest {
+    pub symbol: String,
+    pub condition: AlertCondition,
+    pub target_price: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notify_email: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notify_push: Option<bool>,
+}
+
+// ========== Account ==========
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountSummary {
+    pub balance: f64,
+    pub equity: f64,
+    pub margin_used: f64,
+    pub margin_available: f64,
+    pub unrealized_pnl: f64,
+    pub realized_pnl: f64,
+    pub today_pnl: f64,
+    pub today_pnl_percent: f64,
+    pub open_positions: u32,
+    pub pending_orders: u32,
+    pub leverage: f64,
+    pub margin_level: f64,
+    /// "low", "medium", "high" or "critical"
+    pub liquidation_risk: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Transaction {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub amount: f64,
+    pub balance: f64,
+    pub description: String,
+    pub status: String,
+    pub created_at: i64,
+    pub completed_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionHistoryParams {
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+    pub status: Option<String>,
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TradeHistoryParams {
+    pub symbol: Option<String>,
+    pub side: Option<OrderSide>,
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LeaderboardEntry {
+    pub portfolio_id: String,
+    pub name: String,
+    pub user_id: String,
+    pub total_value: f64,
+    pub starting_balance: f64,
+    pub realized_pnl: f64,
+    pub unrealized_pnl: f64,
+    pub total_return_pct: f64,
+    pub total_trades: u64,
+    pub winning_trades: u64,
+    pub win_rate: f64,
+    pub open_positions: u32,
+    pub rank: Option<u32>,
+}
+
+// ========== Auth ==========
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthChallenge {
+    pub challenge: String,
+    pub timestamp: u64,
+    pub expires_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthRequest {
+    pub public_key: String,
+    pub challenge: String,
+    pub signature: String,
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileSettings {
+    pub default_timeframe: String,
+    pub preferred_indicators: Vec<String>,
+    pub notifications_enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Profile {
+    pub id: String,
+    pub public_key: String,
+    pub username: String,
+    pub created_at: i64,
+    pub last_seen: i64,
+    pub show_on_leaderboard: bool,
+    pub leaderboard_signature: Option<String>,
+    pub leaderboard_consent_at: Option<i64>,
+    pub settings: ProfileSettings,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthResponse {
+    pub authenticated: bool,
+    pub public_key: String,
+    pub session_token: String,
+    pub expires_at: u64,
+    pub profile: Profile,
+}
+
+// ========== Peers ==========
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerStatus {
+    pub id: String,
+    pub region: String,
+    /// "connected", "connecting", "disconnected" or "failed"
+    pub status: String,
+    pub latency_ms: Option<f64>,
+    pub avg_latency_ms: Option<f64>,
+    pub min_latency_ms: Option<f64>,
+    pub max_latency_ms: Option<f64>,
+    pub ping_count: u64,
+    pub failed_pings: u64,
+    pub uptime_percent: f64,
+    pub last_ping_at: Option<i64>,
+    pub last_attempt_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerMeshResponse {
+    pub server_id: String,
+    pub server_region: String,
+    pub peers: Vec<PeerStatus>,
+    pub connected_count: u32,
+    pub total_peers: u32,
+    pub timestamp: i64,
+}
+
+/// Server entry from `/api/mesh/servers`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MeshServer {
+    pub id: String,
+    pub region: String,
+    pub api_url: String,
+    pub ws_url: String,
+    pub status: String,
+    pub latency_ms: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MeshDiscoveryResponse {
+    pub self_id: String,
+    pub self_region: String,
+    pub self_api_url: String,
+    pub self_ws_url: String,
+    pub servers: Vec<MeshServer>,
+    pub mesh_key_hash: String,
+    pub timestamp: i64,
+}
+
+// ========== Notifications ==========
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackendNotification {
+    pub id: String,
+    pub user_id: String,
+    /// "success", "error", "warning" or "info"
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub title: String,
+    pub message: Option<String>,
+    pub read: bool,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationListResponse {
+    pub notifications: Vec<BackendNotification>,
+    pub total: u64,
+    pub page: u32,
+    pub page_size: u32,
+    pub total_pages: u32,
+    pub unread_count: u64,
+}
+
+/// Shapes the desktop app doesn't inspect yet are passed through untouched
+pub type Opaque = Value;
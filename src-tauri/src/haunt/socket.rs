@@ -0,0 +1,1165 @@
+//! Haunt WebSocket stream
+//!
+//! One connection owned by the backend, so prices and trading updates keep
+//! flowing while the window is hidden to the tray. Incoming [`WsMessage`]s
+//! are re-emitted as Tauri events to every window; subscriptions are
+//! tracked here and replayed after each reconnect.
+//!
+//! Reconnects back off exponentially. Timers do not advance while the
+//! machine sleeps, so waits are measured against the wall clock and a
+//! heartbeat gap larger than expected is treated as a resume: the old
+//! connection is dropped and a new one opened straight away.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use futures_util::{SinkExt, StreamExt};
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{Emitter, Manager, State};
+use tokio::sync::{broadcast, mpsc};
+use tokio_tungstenite::tungstenite::Message;
+
+use super::models::{PeerStatus, TradeDirection};
+
+/// Default stream used until a server is selected
+pub const DEFAULT_WS_URL: &str = "ws://localhost:3001/ws";
+
+/// Event carrying [`SocketStatus`] whenever the connection state changes
+pub const STATUS_EVENT: &str = "haunt-socket-status";
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+/// Drop the connection if nothing has arrived for this long
+const STALE_AFTER: Duration = Duration::from_secs(45);
+/// Heartbeat lateness that indicates the machine was asleep
+const RESUME_GAP: Duration = Duration::from_secs(30);
+const BACKOFF_BASE: Duration = Duration::from_secs(1);
+const BACKOFF_MAX: Duration = Duration::from_secs(30);
+/// Messages buffered per in-process listener before it starts missing some
+const TAP_CAPACITY: usize = 1024;
+
+// ========== Wire types ==========
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PriceUpdate {
+    pub id: i64,
+    pub symbol: String,
+    pub price: f64,
+    pub previous_price: Option<f64>,
+    pub change_24h: Option<f64>,
+    pub volume_24h: Option<f64>,
+    pub trade_direction: Option<TradeDirection>,
+    pub source: Option<String>,
+    pub sources: Option<Vec<String>>,
+    pub timestamp: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MarketUpdate {
+    pub total_market_cap: f64,
+    pub total_volume_24h: f64,
+    pub btc_dominance: f64,
+    pub timestamp: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerUpdate {
+    pub server_id: String,
+    pub server_region: String,
+    pub peers: Vec<PeerStatus>,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortfolioUpdate {
+    pub balance: f64,
+    pub margin_used: f64,
+    pub margin_available: f64,
+    pub unrealized_pnl: f64,
+    pub realized_pnl: f64,
+    pub total_value: f64,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PositionUpdate {
+    pub id: String,
+    pub symbol: String,
+    /// "long" or "short"
+    pub side: String,
+    pub size: f64,
+    pub entry_price: f64,
+    pub mark_price: f64,
+    pub unrealized_pnl: f64,
+    pub unrealized_pnl_percent: f64,
+    pub liquidation_price: f64,
+    /// "opened", "updated", "closed" or "liquidated"
+    pub event: String,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderUpdate {
+    pub id: String,
+    pub symbol: String,
+    #[serde(rename = "type")]
+    pub order_type: String,
+    pub side: String,
+    pub price: Option<f64>,
+    pub size: f64,
+    pub filled_size: f64,
+    pub status: String,
+    /// "created", "filled", "partial", "cancelled" or "rejected"
+    pub event: String,
+    pub execution_price: Option<f64>,
+    pub fee: Option<f64>,
+    pub pnl: Option<f64>,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertUpdate {
+    pub id: String,
+    pub symbol: String,
+    /// "above" or "below"
+    pub condition: String,
+    pub target_price: f64,
+    pub current_price: f64,
+    pub triggered: bool,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GridConfig {
+    pub symbol: String,
+    pub price_high: f64,
+    pub price_low: f64,
+    pub row_count: u32,
+    pub col_count: u32,
+    pub interval_ms: u64,
+    pub row_height: f64,
+    pub max_leverage: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GridMultiplierUpdate {
+    pub symbol: String,
+    pub multipliers: Vec<Vec<f64>>,
+    pub config: GridConfig,
+    #[serde(default, alias = "current_price")]
+    pub current_price: f64,
+    #[serde(
+        default,
+        rename = "current_col_index",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub current_col_index: Option<i64>,
+    pub timestamp: i64,
+}
+
+/// Grid position as sent by the server (snake_case)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GridPosition {
+    pub id: String,
+    pub portfolio_id: String,
+    pub symbol: String,
+    pub row_index: u32,
+    pub col_index: i64,
+    pub amount: f64,
+    pub leverage: f64,
+    pub multiplier: f64,
+    pub price_low: f64,
+    pub price_high: f64,
+    pub time_start: i64,
+    pub time_end: i64,
+    pub status: String,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GridTradePlaced {
+    pub position: GridPosition,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResolvedGridPosition {
+    pub id: String,
+    /// "won" or "lost"
+    pub status: String,
+    pub result_pnl: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GridTradeResolved {
+    pub position: ResolvedGridPosition,
+    pub won: bool,
+    pub payout: f64,
+    pub pnl: f64,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GridColumnResult {
+    pub position_id: String,
+    pub won: bool,
+    pub payout: f64,
+    pub pnl: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GridColumnExpired {
+    pub symbol: String,
+    pub col_index: i64,
+    pub time_end: i64,
+    pub results: Vec<GridColumnResult>,
+    pub timestamp: i64,
+}
+
+/// Server-to-client messages (`WSMessage` in `useHauntSocket.tsx`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WsMessage {
+    PriceUpdate {
+        data: PriceUpdate,
+    },
+    MarketUpdate {
+        data: MarketUpdate,
+    },
+    PeerUpdate {
+        data: PeerUpdate,
+    },
+    PortfolioUpdate {
+        data: PortfolioUpdate,
+    },
+    PositionUpdate {
+        data: PositionUpdate,
+    },
+    OrderUpdate {
+        data: OrderUpdate,
+    },
+    AlertTriggered {
+        data: AlertUpdate,
+    },
+    GridMultiplierUpdate {
+        data: GridMultiplierUpdate,
+    },
+    GridlineTradePlaced {
+        data: GridTradePlaced,
+    },
+    GridlineTradeResolved {
+        data: GridTradeResolved,
+    },
+    GridColumnExpired {
+        data: GridColumnExpired,
+    },
+    GridlineSubscribed,
+    GridlineUnsubscribed,
+    Subscribed {
+        assets: Vec<String>,
+    },
+    Unsubscribed {
+        assets: Vec<String>,
+    },
+    ThrottleSet {
+        throttle_ms: u64,
+    },
+    PeersSubscribed,
+    PeersUnsubscribed,
+    PortfolioSubscribed,
+    PortfolioUnsubscribed,
+    Error {
+        error: String,
+    },
+    /// Message types added to the server after this build
+    #[serde(other)]
+    Unknown,
+}
+
+impl WsMessage {
+    /// Tauri event name and payload for data-carrying messages
+    pub fn event(&self) -> Option<(&'static str, Value)> {
+        let (name, payload) = match self {
+            Self::PriceUpdate { data } => ("haunt-price-update", serde_json::to_value(data)),
+            Self::MarketUpdate { data } => ("haunt-market-update", serde_json::to_value(data)),
+            Self::PeerUpdate { data } => ("haunt-peer-update", serde_json::to_value(data)),
+            Self::PortfolioUpdate { data } => {
+                ("haunt-portfolio-update", serde_json::to_value(data))
+            }
+            Self::PositionUpdate { data } => ("haunt-position-update", serde_json::to_value(data)),
+            Self::OrderUpdate { data } => ("haunt-order-update", serde_json::to_value(data)),
+            Self::AlertTriggered { data } => ("haunt-alert-triggered", serde_json::to_value(data)),
+            Self::GridMultiplierUpdate { data } => {
+                ("haunt-grid-multiplier-update", serde_json::to_value(data))
+            }
+            Self::GridlineTradePlaced { data } => {
+                ("haunt-gridline-trade-placed", serde_json::to_value(data))
+            }
+            Self::GridlineTradeResolved { data } => {
+                ("haunt-gridline-trade-resolved", serde_json::to_value(data))
+            }
+            Self::GridColumnExpired { data } => {
+                ("haunt-grid-column-expired", serde_json::to_value(data))
+            }
+            _ => return None,
+        };
+        payload.ok().map(|payload| (name, payload))
+    }
+}
+
+/// Client-to-server messages
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientMessage {
+    Subscribe {
+        assets: Vec<String>,
+    },
+    Unsubscribe {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        assets: Option<Vec<String>>,
+    },
+    SetThrottle {
+        throttle_ms: u64,
+    },
+    SubscribePeers,
+    UnsubscribePeers,
+    SubscribePortfolio {
+        token: String,
+    },
+    UnsubscribePortfolio,
+    SubscribeGridline {
+        symbol: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        portfolio_id: Option<String>,
+    },
+    UnsubscribeGridline {
+        symbol: String,
+    },
+}
+
+// ========== State ==========
+
+/// Subscriptions requested by the app, replayed after every reconnect
+///
+/// Assets are reference counted so one window unsubscribing does not cut
+/// off another window watching the same symbol.
+#[derive(Debug, Default)]
+struct Subscriptions {
+    assets: BTreeMap<String, usize>,
+    throttle_ms: u64,
+    peers: bool,
+    portfolio_token: Option<String>,
+    gridlines: BTreeMap<String, Option<String>>,
+}
+
+impl Subscriptions {
+    /// Add references, returning assets that were not subscribed before
+    fn add_assets(&mut self, assets: &[String]) -> Vec<String> {
+        let mut added = Vec::new();
+        for asset in assets {
+            let count = self.assets.entry(asset.clone()).or_insert(0);
+            *count += 1;
+            if *count == 1 && !added.contains(asset) {
+                added.push(asset.clone());
+            }
+        }
+        added
+    }
+
+    /// Drop references, returning assets with none left; `None` drops all
+    fn remove_assets(&mut self, assets: Option<&[String]>) -> Vec<String> {
+        let Some(assets) = assets else {
+            return std::mem::take(&mut self.assets).into_keys().collect();
+        };
+
+        let mut removed = Vec::new();
+        for asset in assets {
+            if let Some(count) = self.assets.get_mut(asset) {
+                *count -= 1;
+                if *count == 0 {
+                    self.assets.remove(asset);
+                    removed.push(asset.clone());
+                }
+            }
+        }
+        removed
+    }
+
+    /// Messages that restore these subscriptions on a fresh connection
+    fn replay(&self) -> Vec<ClientMessage> {
+        let mut messages = Vec::new();
+        if !self.assets.is_empty() {
+            messages.push(ClientMessage::Subscribe {
+                assets: self.assets.keys().cloned().collect(),
+            });
+        }
+        if self.throttle_ms > 0 {
+            messages.push(ClientMessage::SetThrottle {
+                throttle_ms: self.throttle_ms,
+            });
+        }
+        if self.peers {
+            messages.push(ClientMessage::SubscribePeers);
+        }
+        if let Some(token) = &self.portfolio_token {
+            messages.push(ClientMessage::SubscribePortfolio {
+                token: token.clone(),
+            });
+        }
+        for (symbol, portfolio_id) in &self.gridlines {
+            messages.push(ClientMessage::SubscribeGridline {
+                symbol: symbol.clone(),
+                portfolio_id: portfolio_id.clone(),
+            });
+        }
+        messages
+    }
+}
+
+/// Connection state reported to the frontend
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SocketStatus {
+    pub url: String,
+    pub connected: bool,
+    /// Assets the server has confirmed
+    pub subscriptions: Vec<String>,
+    pub error: Option<String>,
+    pub update_count: u64,
+    pub peers_subscribed: bool,
+    pub portfolio_subscribed: bool,
+    pub gridline_subscribed: bool,
+    /// Consecutive failed attempts since the last successful connection
+    pub reconnect_attempt: u32,
+}
+
+impl SocketStatus {
+    /// Clear acknowledgements that belonged to a closed connection
+    fn disconnected(&mut self) {
+        self.connected = false;
+        self.subscriptions.clear();
+        self.peers_subscribed = false;
+        self.portfolio_subscribed = false;
+        self.gridline_subscribed = false;
+    }
+
+    /// Apply an acknowledgement, returning whether anything changed
+    fn apply(&mut self, message: &WsMessage) -> bool {
+        match message {
+            WsMessage::PriceUpdate { .. } => {
+                self.update_count += 1;
+                return false;
+            }
+            WsMessage::Subscribed { assets } => {
+                for asset in assets {
+                    if !self.subscriptions.contains(asset) {
+                        self.subscriptions.push(asset.clone());
+                    }
+                }
+            }
+            WsMessage::Unsubscribed { assets } => {
+                self.subscriptions.retain(|a| !assets.contains(a));
+            }
+            WsMessage::PeersSubscribed => self.peers_subscribed = true,
+            WsMessage::PeersUnsubscribed => self.peers_subscribed = false,
+            WsMessage::PortfolioSubscribed => self.portfolio_subscribed = true,
+            WsMessage::PortfolioUnsubscribed => self.portfolio_subscribed = false,
+            WsMessage::GridlineSubscribed => self.gridline_subscribed = true,
+            WsMessage::GridlineUnsubscribed => self.gridline_subscribed = false,
+            WsMessage::Error { error } => self.error = Some(error.clone()),
+            _ => return false,
+        }
+        true
+    }
+}
+
+struct Shared {
+    desired: Subscriptions,
+    status: SocketStatus,
+    /// Data-carrying messages for backend listeners
+    tap: broadcast::Sender<Arc<WsMessage>>,
+    /// Data messages are not forwarded to the windows
+    muted: bool,
+}
+
+impl Default for Shared {
+    fn default() -> Self {
+        Self {
+            desired: Subscriptions::default(),
+            status: SocketStatus::default(),
+            tap: broadcast::channel(TAP_CAPACITY).0,
+            muted: false,
+        }
+    }
+}
+
+enum Command {
+    Send(ClientMessage),
+    Reconnect,
+}
+
+/// Managed state for the backend WebSocket connection
+pub struct HauntSocket {
+    shared: Arc<Mutex<Shared>>,
+    commands: mpsc::UnboundedSender<Command>,
+    receiver: Mutex<Option<mpsc::UnboundedReceiver<Command>>>,
+}
+
+impl HauntSocket {
+    pub fn new(url: &str) -> Self {
+        let (commands, receiver) = mpsc::unbounded_channel();
+        let shared = Shared {
+            status: SocketStatus {
+                url: url.to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        Self {
+            shared: Arc::new(Mutex::new(shared)),
+            commands,
+            receiver: Mutex::new(Some(receiver)),
+        }
+    }
+
+    pub fn status(&self) -> SocketStatus {
+        self.shared.lock().unwrap().status.clone()
+    }
+
+    /// Receive every data-carrying message as it arrives, for backend
+    /// consumers such as the tick recorder
+    pub fn messages(&self) -> broadcast::Receiver<Arc<WsMessage>> {
+        self.shared.lock().unwrap().tap.subscribe()
+    }
+
+    /// Stop forwarding data messages to the windows, e.g. while a replay
+    /// stands in for the live stream; backend listeners still get them
+    pub fn set_muted(&self, muted: bool) {
+        self.shared.lock().unwrap().muted = muted;
+    }
+
+    fn send(&self, message: ClientMessage) {
+        let _ = self.commands.send(Command::Send(message));
+    }
+
+    pub fn subscribe(&self, assets: &[String]) {
+        let assets = normalize_assets(assets);
+        let added = self.shared.lock().unwrap().desired.add_assets(&assets);
+        if !added.is_empty() {
+            self.send(ClientMessage::Subscribe { assets: added });
+        }
+    }
+
+    /// Release assets; `None` unsubscribes from everything
+    pub fn unsubscribe(&self, assets: Option<&[String]>) {
+        let assets = assets.map(normalize_assets);
+        let removed = self
+            .shared
+            .lock()
+            .unwrap()
+            .desired
+            .remove_assets(assets.as_deref());
+        if !removed.is_empty() {
+            self.send(ClientMessage::Unsubscribe {
+                assets: Some(removed),
+            });
+        }
+    }
+
+    pub fn set_throttle(&self, throttle_ms: u64) {
+        self.shared.lock().unwrap().desired.throttle_ms = throttle_ms;
+        self.send(ClientMessage::SetThrottle { throttle_ms });
+    }
+
+    pub fn set_peers(&self, subscribed: bool) {
+        self.shared.lock().unwrap().desired.peers = subscribed;
+        self.send(if subscribed {
+            ClientMessage::SubscribePeers
+        } else {
+            ClientMessage::UnsubscribePeers
+        });
+    }
+
+    /// Subscribe to trading updates with a session token, or stop with `None`
+    pub fn set_portfolio(&self, token: Option<String>) {
+        self.shared.lock().unwrap().desired.portfolio_token = token.clone();
+        self.send(match token {
+            Some(token) => ClientMessage::SubscribePortfolio { token },
+            None => ClientMessage::UnsubscribePortfolio,
+        });
+    }
+
+    /// Token the portfolio subscription was made with, if any
+    pub fn portfolio_token(&self) -> Option<String> {
+        self.shared.lock().unwrap().desired.portfolio_token.clone()
+    }
+
+    pub fn subscribe_gridline(&self, symbol: String, portfolio_id: Option<String>) {
+        self.shared
+            .lock()
+            .unwrap()
+            .desired
+            .gridlines
+            .insert(symbol.clone(), portfolio_id.clone());
+        self.send(ClientMessage::SubscribeGridline {
+            symbol,
+            portfolio_id,
+        });
+    }
+
+    pub fn unsubscribe_gridline(&self, symbol: String) {
+        self.shared
+            .lock()
+            .unwrap()
+            .desired
+            .gridlines
+            .remove(&symbol);
+        self.send(ClientMessage::UnsubscribeGridline { symbol });
+    }
+
+    /// Switch servers; subscriptions carry over to the new connection
+    pub fn set_url(&self, url: &str) {
+        self.shared.lock().unwrap().status.url = url.to_string();
+        self.reconnect();
+    }
+
+    /// Drop the current connection and open a new one immediately
+    pub fn reconnect(&self) {
+        let _ = self.commands.send(Command::Reconnect);
+    }
+}
+
+impl Default for HauntSocket {
+    fn default() -> Self {
+        Self::new(DEFAULT_WS_URL)
+    }
+}
+
+fn normalize_assets(assets: &[String]) -> Vec<String> {
+    assets
+        .iter()
+        .map(|a| a.trim().to_lowercase())
+        .filter(|a| !a.is_empty())
+        .collect()
+}
+
+// ========== Connection task ==========
+
+/// Delay before reconnect attempt `attempt` (1-based); `jitter` in `[0, 1)`
+/// spreads clients by ±20% so a server restart isn't hit all at once
+fn backoff_delay(attempt: u32, jitter: f64) -> Duration {
+    let exp = BACKOFF_BASE.saturating_mul(1 << attempt.saturating_sub(1).min(16));
+    exp.min(BACKOFF_MAX).mul_f64(0.8 + 0.4 * jitter)
+}
+
+fn random_jitter() -> f64 {
+    OsRng.next_u32() as f64 / (u32::MAX as f64 + 1.0)
+}
+
+/// Why a connection ended
+enum Ended {
+    Dropped(Option<String>),
+    Reconnect,
+    Shutdown,
+}
+
+/// Run the connection loop until the command channel closes
+async fn run<F>(shared: Arc<Mutex<Shared>>, mut commands: mpsc::UnboundedReceiver<Command>, emit: F)
+where
+    F: Fn(&str, Value) + Send + 'static,
+{
+    let emit_status = |shared: &Mutex<Shared>| {
+        let status = shared.lock().unwrap().status.clone();
+        if let Ok(status) = serde_json::to_value(status) {
+            emit(STATUS_EVENT, status);
+        }
+    };
+
+    let mut attempt = 0u32;
+    loop {
+        let url = shared.lock().unwrap().status.url.clone();
+        let connected =
+            tokio::time::timeout(CONNECT_TIMEOUT, tokio_tungstenite::connect_async(&url)).await;
+
+        let error = match connected {
+            Ok(Ok((stream, _))) => {
+                attempt = 0;
+                {
+                    let mut shared = shared.lock().unwrap();
+                    shared.status.connected = true;
+                    shared.status.error = None;
+                    shared.status.reconnect_attempt = 0;
+                }
+                emit_status(&shared);
+
+                let ended = run_connection(stream, &shared, &mut commands, &emit).await;
+                shared.lock().unwrap().status.disconnected();
+                match ended {
+                    Ended::Shutdown => return,
+                    Ended::Reconnect => {
+                        emit_status(&shared);
+                        continue;
+                    }
+                    Ended::Dropped(error) => error,
+                }
+            }
+            Ok(Err(e)) => Some(format!("Failed to connect to {}: {}", url, e)),
+            Err(_) => Some(format!("Timed out connecting to {}", url)),
+        };
+
+        attempt += 1;
+        {
+            let mut shared = shared.lock().unwrap();
+            shared.status.reconnect_attempt = attempt;
+            if error.is_some() {
+                shared.status.error = error;
+            }
+        }
+        emit_status(&shared);
+
+        if !wait_for_retry(backoff_delay(attempt, random_jitter()), &mut commands).await {
+            return;
+        }
+    }
+}
+
+async fn run_connection<S, F>(
+    stream: tokio_tungstenite::WebSocketStream<S>,
+    shared: &Mutex<Shared>,
+    commands: &mut mpsc::UnboundedReceiver<Command>,
+    emit: &F,
+) -> Ended
+where
+    S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
+    F: Fn(&str, Value),
+{
+    let (mut sink, mut stream) = stream.split();
+
+    // Anything queued while disconnected is already covered by the replay,
+    // but a reconnect asked for mid-connect (say a new URL) still applies
+    let mut reconnect = false;
+    while let Ok(command) = commands.try_recv() {
+        reconnect |= matches!(command, Command::Reconnect);
+    }
+    if reconnect {
+        let _ = sink.close().await;
+        return Ended::Reconnect;
+    }
+
+    let replay = shared.lock().unwrap().desired.replay();
+    for message in replay {
+        if let Err(e) = sink.send(encode(&message)).await {
+            return Ended::Dropped(Some(e.to_string()));
+        }
+    }
+
+    let mut heartbeat = tokio::time::interval(HEARTBEAT_INTERVAL);
+    heartbeat.tick().await;
+    let mut last_frame = Instant::now();
+    let mut last_tick = SystemTime::now();
+
+    loop {
+        tokio::select! {
+            frame = stream.next() => {
+                last_frame = Instant::now();
+                match frame {
+                    Some(Ok(Message::Text(text))) => handle_text(shared, &text, emit),
+                    Some(Ok(Message::Close(_))) | None => return Ended::Dropped(None),
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => return Ended::Dropped(Some(e.to_string())),
+                }
+            }
+            command = commands.recv() => match command {
+                Some(Command::Send(message)) => {
+                    if let Err(e) = sink.send(encode(&message)).await {
+                        return Ended::Dropped(Some(e.to_string()));
+                    }
+                }
+                Some(Command::Reconnect) => {
+                    let _ = sink.close().await;
+                    return Ended::Reconnect;
+                }
+                None => {
+                    let _ = sink.close().await;
+                    return Ended::Shutdown;
+                }
+            },
+            _ = heartbeat.tick() => {
+                let now = SystemTime::now();
+                let gap = now.duration_since(last_tick).unwrap_or_default();
+                last_tick = now;
+                if gap > HEARTBEAT_INTERVAL + RESUME_GAP {
+                    return Ended::Reconnect;
+                }
+                if last_frame.elapsed() > STALE_AFTER {
+                    return Ended::Dropped(Some("Connection timed out".into()));
+                }
+                if sink.send(Message::Ping(Default::default())).await.is_err() {
+                    return Ended::Dropped(None);
+                }
+            }
+        }
+    }
+}
+
+fn encode(message: &ClientMessage) -> Message {
+    Message::text(serde_json::to_string(message).unwrap_or_default())
+}
+
+fn handle_text<F: Fn(&str, Value)>(shared: &Mutex<Shared>, text: &str, emit: &F) {
+    let message: WsMessage = match serde_json::from_str(text) {
+        Ok(message) => message,
+        Err(e) => {
+            eprintln!("Failed to parse Haunt WS message: {}", e);
+            return;
+        }
+    };
+
+    let status = {
+        let mut shared = shared.lock().unwrap();
+        shared.status.apply(&message).then(|| shared.status.clone())
+    };
+    if let Some(status) = status.and_then(|s| serde_json::to_value(s).ok()) {
+        emit(STATUS_EVENT, status);
+    }
+    if let Some((event, payload)) = message.event() {
+        let (muted, tap) = {
+            let shared = shared.lock().unwrap();
+            (shared.muted, shared.tap.clone())
+        };
+        if !muted {
+            emit(event, payload);
+        }
+        let _ = tap.send(Arc::new(message));
+    }
+}
+
+/// Wait out a backoff delay against the wall clock so time spent asleep
+/// counts; returns false once the app is shutting down
+async fn wait_for_retry(delay: Duration, commands: &mut mpsc::UnboundedReceiver<Command>) -> bool {
+    let deadline = SystemTime::now() + delay;
+    loop {
+        let remaining = match deadline.duration_since(SystemTime::now()) {
+            Ok(remaining) if !remaining.is_zero() => remaining,
+            _ => return true,
+        };
+
+        tokio::select! {
+            _ = tokio::time::sleep(remaining.min(Duration::from_secs(1))) => {}
+            command = commands.recv() => match command {
+                Some(Command::Reconnect) => return true,
+                Some(Command::Send(_)) => {}
+                None => return false,
+            },
+        }
+    }
+}
+
+/// Start the connection task
+pub fn init(app: &tauri::AppHandle) {
+    let socket = app.state::<HauntSocket>();
+    let Some(receiver) = socket.receiver.lock().unwrap().take() else {
+        return;
+    };
+
+    let handle = app.clone();
+    tauri::async_runtime::spawn(run(
+        socket.shared.clone(),
+        receiver,
+        move |event, payload| {
+            let _ = handle.emit(event, payload);
+        },
+    ));
+}
+
+// ========== Commands ==========
+
+/// Tauri command: Get the WebSocket connection status
+#[tauri::command]
+pub fn haunt_ws_status(socket: State<'_, HauntSocket>) -> SocketStatus {
+    socket.status()
+}
+
+/// Tauri command: Subscribe to price updates for assets
+#[tauri::command]
+pub fn haunt_ws_subscribe(socket: State<'_, HauntSocket>, assets: Vec<String>) {
+    socket.subscribe(&assets);
+}
+
+/// Tauri command: Unsubscribe from assets, or from everything when omitted
+#[tauri::command]
+pub fn haunt_ws_unsubscribe(socket: State<'_, HauntSocket>, assets: Option<Vec<String>>) {
+    socket.unsubscribe(assets.as_deref());
+}
+
+/// Tauri command: Set the server-side price update throttle
+#[tauri::command]
+pub fn haunt_ws_set_throttle(socket: State<'_, HauntSocket>, throttle_ms: u64) {
+    socket.set_throttle(throttle_ms);
+}
+
+/// Tauri command: Subscribe to or unsubscribe from peer mesh updates
+#[tauri::command]
+pub fn haunt_ws_set_peers(socket: State<'_, HauntSocket>, subscribed: bool) {
+    socket.set_peers(subscribed);
+}
+
+/// Tauri command: Subscribe to trading updates, or stop when `token` is omitted
+#[tauri::command]
+pub fn haunt_ws_set_portfolio(socket: State<'_, HauntSocket>, token: Option<String>) {
+    socket.set_portfolio(token);
+}
+
+/// Tauri command: Subscribe to tap trading updates for a symbol
+#[tauri::command]
+pub fn haunt_ws_subscribe_gridline(
+    socket: State<'_, HauntSocket>,
+    symbol: String,
+    portfolio_id: Option<String>,
+) {
+    socket.subscribe_gridline(symbol, portfolio_id);
+}
+
+/// Tauri command: Unsubscribe from tap trading updates for a symbol
+#[tauri::command]
+pub fn haunt_ws_unsubscribe_gridline(socket: State<'_, HauntSocket>, symbol: String) {
+    socket.unsubscribe_gridline(symbol);
+}
+
+/// Tauri command: Point the stream at another server
+#[tauri::command]
+pub fn haunt_ws_set_url(socket: State<'_, HauntSocket>, url: String) -> Result<(), String> {
+    let parsed = url::Url::parse(url.trim()).map_err(|e| e.to_string())?;
+    if !matches!(parsed.scheme(), "ws" | "wss") {
+        return Err("WebSocket URL must use ws or wss".into());
+    }
+    socket.set_url(url.trim());
+    Ok(())
+}
+
+/// Tauri command: Reconnect immediately
+#[tauri::command]
+pub fn haunt_ws_reconnect(socket: State<'_, HauntSocket>) {
+    socket.reconnect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tokio::net::TcpListener;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_server_messages() {
+        let message: WsMessage = serde_json::from_value(json!({
+            "type": "price_update",
+            "data": {
+                "id": 1, "symbol": "btc", "price": 65000.5, "tradeDirection": "up",
+                "sources": ["binance", "kraken"], "timestamp": "2024-01-01T00:00:00Z"
+            }
+        }))
+        .unwrap();
+        let (event, payload) = message.event().unwrap();
+        assert_eq!(event, "haunt-price-update");
+        assert_eq!(payload["price"], 65000.5);
+        assert_eq!(payload["tradeDirection"], "up");
+
+        let message: WsMessage =
+            serde_json::from_str(r#"{"type":"throttle_set","throttle_ms":250}"#).unwrap();
+        assert!(matches!(
+            message,
+            WsMessage::ThrottleSet { throttle_ms: 250 }
+        ));
+        assert!(message.event().is_none());
+
+        let message: WsMessage = serde_json::from_value(json!({
+            "type": "grid_multiplier_update",
+            "data": {
+                "symbol": "btc", "multipliers": [[1.5, 2.0]],
+                "config": {
+                    "symbol": "btc", "priceHigh": 2.0, "priceLow": 1.0, "rowCount": 1,
+                    "colCount": 2, "intervalMs": 5000, "rowHeight": 1.0, "maxLeverage": 10.0
+                },
+                "current_price": 1.5, "current_col_index": 3, "timestamp": 1
+            }
+        }))
+        .unwrap();
+        let (_, payload) = message.event().unwrap();
+        assert_eq!(payload["currentPrice"], 1.5);
+        assert_eq!(payload["current_col_index"], 3);
+
+        let message: WsMessage = serde_json::from_str(r#"{"type":"something_new"}"#).unwrap();
+        assert!(matches!(message, WsMessage::Unknown));
+    }
+
+    #[test]
+    fn client_messages_match_wire_format() {
+        let encoded = |m: &ClientMessage| serde_json::to_value(m).unwrap();
+        assert_eq!(
+            encoded(&ClientMessage::SetThrottle { throttle_ms: 100 }),
+            json!({ "type": "set_throttle", "throttle_ms": 100 })
+        );
+        assert_eq!(
+            encoded(&ClientMessage::Unsubscribe { assets: None }),
+            json!({ "type": "unsubscribe" })
+        );
+        assert_eq!(
+            encoded(&ClientMessage::SubscribeGridline {
+                symbol: "btc".into(),
+                portfolio_id: Some("p1".into())
+            }),
+            json!({ "type": "subscribe_gridline", "symbol": "btc", "portfolio_id": "p1" })
+        );
+    }
+
+    #[test]
+    fn asset_subscriptions_are_reference_counted() {
+        let mut subs = Subscriptions::default();
+        assert_eq!(
+            subs.add_assets(&strings(&["btc", "eth"])),
+            strings(&["btc", "eth"])
+        );
+        assert!(subs.add_assets(&strings(&["btc"])).is_empty());
+
+        assert!(subs.remove_assets(Some(&strings(&["btc"]))).is_empty());
+        assert_eq!(
+            subs.remove_assets(Some(&strings(&["btc"]))),
+            strings(&["btc"])
+        );
+        assert!(subs.remove_assets(Some(&strings(&["sol"]))).is_empty());
+        assert_eq!(subs.remove_assets(None), strings(&["eth"]));
+    }
+
+    #[test]
+    fn backoff_grows_and_caps() {
+        assert_eq!(backoff_delay(1, 0.5), Duration::from_secs(1));
+        assert_eq!(backoff_delay(3, 0.5), Duration::from_secs(4));
+        assert_eq!(backoff_delay(50, 0.5), BACKOFF_MAX);
+        assert!(backoff_delay(1, 0.0) >= Duration::from_millis(800));
+        assert!(backoff_delay(1, 0.999) < Duration::from_millis(1200));
+    }
+
+    async fn next_json<S>(ws: &mut tokio_tungstenite::WebSocketStream<S>) -> Value
+    where
+        S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
+    {
+        loop {
+            match ws.next().await.unwrap().unwrap() {
+                Message::Text(text) => return serde_json::from_str(&text).unwrap(),
+                _ => continue,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn replays_subscriptions_after_reconnect() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}/ws", listener.local_addr().unwrap());
+
+        let socket = HauntSocket::new(&url);
+        socket.subscribe(&strings(&["BTC", "eth"]));
+        socket.set_throttle(250);
+
+        let mut messages = socket.messages();
+        let events = Arc::new(Mutex::new(Vec::<(String, Value)>::new()));
+        let sink = events.clone();
+        let receiver = socket.receiver.lock().unwrap().take().unwrap();
+        tokio::spawn(run(
+            socket.shared.clone(),
+            receiver,
+            move |event, payload| {
+                sink.lock().unwrap().push((event.to_string(), payload));
+            },
+        ));
+
+        // First connection: expect the replay, then push an update and drop
+        let (tcp, _) = listener.accept().await.unwrap();
+        let mut ws = tokio_tungstenite::accept_async(tcp).await.unwrap();
+        assert_eq!(
+            next_json(&mut ws).await,
+            json!({ "type": "subscribe", "assets": ["btc", "eth"] })
+        );
+        assert_eq!(
+            next_json(&mut ws).await,
+            json!({ "type": "set_throttle", "throttle_ms": 250 })
+        );
+        let ack = json!({ "type": "subscribed", "assets": ["btc", "eth"] });
+        ws.send(Message::text(ack.to_string())).await.unwrap();
+        let update = json!({
+            "type": "price_update",
+            "data": { "id": 1, "symbol": "btc", "price": 1.0, "timestamp": "t" }
+        });
+        ws.send(Message::text(update.to_string())).await.unwrap();
+        let _ = ws.close(None).await;
+        drop(ws);
+
+        // Second connection: the same subscriptions come back
+        socket.unsubscribe(Some(&strings(&["ETH"])));
+        let (tcp, _) = listener.accept().await.unwrap();
+        let mut ws = tokio_tungstenite::accept_async(tcp).await.unwrap();
+        assert_eq!(
+            next_json(&mut ws).await,
+            json!({ "type": "subscribe", "assets": ["btc"] })
+        );
+        assert!(socket.status().connected);
+        assert!(socket.status().subscriptions.is_empty());
+
+        let events = events.lock().unwrap();
+        assert!(events
+            .iter()
+            .any(|(name, payload)| name == "haunt-price-update" && payload["symbol"] == "btc"));
+        assert!(events.iter().any(|(name, payload)| name == STATUS_EVENT
+            && payload["subscriptions"] == json!(["btc", "eth"])));
+        assert_eq!(socket.status().update_count, 1);
+        assert!(matches!(
+            messages.try_recv().as_deref(),
+            Ok(WsMessage::PriceUpdate { data }) if data.symbol == "btc"
+        ));
+        assert!(messages.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn url_change_during_connect_moves_to_the_new_server() {
+        let old = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let new = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let new_url = format!("ws://{}/ws", new.local_addr().unwrap());
+
+        let socket = HauntSocket::new(&format!("ws://{}/ws", old.local_addr().unwrap()));
+        socket.subscribe(&strings(&["btc"]));
+        let receiver = socket.receiver.lock().unwrap().take().unwrap();
+        tokio::spawn(run(socket.shared.clone(), receiver, |_, _| {}));
+
+        // Switch servers before the old one finishes its handshake
+        let (tcp, _) = old.accept().await.unwrap();
+        socket.set_url(&new_url);
+        let _old_ws = tokio_tungstenite::accept_async(tcp).await.unwrap();
+
+        let (tcp, _) = tokio::time::timeout(Duration::from_secs(5), new.accept())
+            .await
+            .expect("never reconnected to the new URL")
+            .unwrap();
+        let mut ws = tokio_tungstenite::accept_async(tcp).await.unwrap();
+        assert_eq!(
+            next_json(&mut ws).await,
+            json!({ "type": "subscribe", "assets": ["btc"] })
+        );
+        assert_eq!(socket.status().url, new_url);
+    }
+}
@@ -0,0 +1,413 @@
+//! Wallet identities
+//!
+//! The vault can hold several named keypairs, e.g. separate test and
+//! production identities on a shared desktop. One identity is active at a
+//! time and is used by the signing commands. Each identity keeps its own
+//! Haunt session token, held in memory for the life of the process.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::{Emitter, Manager, State};
+use zeroize::Zeroizing;
+
+use crate::now_millis;
+use crate::tray;
+use crate::vault::{VaultContents, VaultError, VaultState};
+use crate::wallet::{Keypair, WalletError};
+
+/// Event emitted when the active identity changes
+pub const IDENTITY_CHANGED_EVENT: &str = "identity-changed";
+
+const MAX_NAME_LEN: usize = 32;
+
+/// Errors produced by identity operations
+#[derive(Debug, thiserror::Error)]
+pub enum IdentityError {
+    #[error("Identity names must be 1-{MAX_NAME_LEN} letters, digits, spaces, '-' or '_'")]
+    InvalidName,
+    #[error("An identity named \"{0}\" already exists")]
+    Duplicate(String),
+    #[error("No identity named \"{0}\"")]
+    NotFound(String),
+    #[error(transparent)]
+    Wallet(#[from] WalletError),
+    #[error(transparent)]
+    Vault(#[from] VaultError),
+}
+
+/// An identity as reported to the frontend
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Identity {
+    pub name: String,
+    pub public_key: String,
+    pub created_at: u64,
+    pub active: bool,
+    /// Expiry of the identity's Haunt session, if it has a live one
+    pub session_expires_at: Option<u64>,
+}
+
+/// A Haunt session obtained from `/api/auth/verify`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HauntSession {
+    pub session_token: String,
+    /// Unix milliseconds, as returned in the verify response
+    pub expires_at: u64,
+}
+
+impl HauntSession {
+    fn is_expired(&self, now_ms: u64) -> bool {
+        now_ms >= self.expires_at
+    }
+}
+
+/// Managed state holding per-identity Haunt sessions
+#[derive(Default)]
+pub struct IdentityState {
+    sessions: Mutex<HashMap<String, HauntSession>>,
+}
+
+impl IdentityState {
+    /// Live session for `name`, dropping it if it has expired
+    pub fn session(&self, name: &str) -> Option<HauntSession> {
+        let mut sessions = self.sessions.lock().unwrap();
+        match sessions.get(name) {
+            Some(session) if session.is_expired(now_millis() as u64) => {
+                sessions.remove(name);
+                None
+            }
+            session => session.cloned(),
+        }
+    }
+}
+
+/// Name and live Haunt session of the active identity
+pub(crate) fn active_session(app: &tauri::AppHandle) -> Option<(String, HauntSession)> {
+    let name = app
+        .state::<VaultState>()
+        .read(|contents| contents.active().map(str::to_string))
+        .ok()??;
+    let session = app.state::<IdentityState>().session(&name)?;
+    Some((name, session))
+}
+
+/// Trim and validate an identity name
+fn validate_name(name: &str) -> Result<String, IdentityError> {
+    let name = name.trim();
+    let valid_chars = name
+        .chars()
+        .all(|c| c.is_alphanumeric() || c == ' ' || c == '-' || c == '_');
+
+    if name.is_empty() || name.chars().count() > MAX_NAME_LEN || !valid_chars {
+        return Err(IdentityError::InvalidName);
+    }
+    Ok(name.to_string())
+}
+
+/// List identities from decrypted vault contents
+fn list_identities(
+    contents: &VaultContents,
+    state: &IdentityState,
+) -> Result<Vec<Identity>, IdentityError> {
+    contents
+        .keys()
+        .iter()
+        .map(|key| {
+            Ok(Identity {
+                name: key.name.clone(),
+                public_key: Keypair::from_secret_hex(&key.secret_key)?.public_key_hex(),
+                created_at: key.created_at,
+                active: contents.active() == Some(key.name.as_str()),
+                session_expires_at: state.session(&key.name).map(|s| s.expires_at),
+            })
+        })
+        .collect()
+}
+
+/// Store a new keypair under `name`, making it active if it is the first
+fn add_identity(
+    vault: &VaultState,
+    state: &IdentityState,
+    name: &str,
+    keypair: Keypair,
+) -> Result<Identity, IdentityError> {
+    let name = validate_name(name)?;
+    vault.update(|contents| {
+        if contents.key(&name).is_some() {
+            return Err(IdentityError::Duplicate(name.clone()));
+        }
+        contents.set_key(&name, &keypair.secret_hex());
+        if contents.active().is_none() {
+            contents.set_active(&name);
+        }
+        Ok(())
+    })?;
+
+    find_identity(vault, state, &name)
+}
+
+fn find_identity(
+    vault: &VaultState,
+    state: &IdentityState,
+    name: &str,
+) -> Result<Identity, IdentityError> {
+    vault
+        .read(|contents| list_identities(contents, state))??
+        .into_iter()
+        .find(|identity| identity.name == name)
+        .ok_or_else(|| IdentityError::NotFound(name.to_string()))
+}
+
+fn active_identity(
+    vault: &VaultState,
+    state: &IdentityState,
+) -> Result<Option<Identity>, IdentityError> {
+    Ok(vault
+        .read(|contents| list_identities(contents, state))??
+        .into_iter()
+        .find(|identity| identity.active))
+}
+
+fn emit_identity_changed(app: &tauri::AppHandle, vault: &VaultState, state: &IdentityState) {
+    if let Ok(identity) = active_identity(vault, state) {
+        let _ = app.emit(IDENTITY_CHANGED_EVENT, identity);
+    }
+}
+
+/// Tauri command: List identities in the vault
+#[tauri::command]
+pub fn identity_list(
+    vault: State<'_, VaultState>,
+    state: State<'_, IdentityState>,
+) -> Result<Vec<Identity>, String> {
+    vault
+        .read(|contents| list_identities(contents, &state))
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Generate a new named identity
+#[tauri::command]
+pub fn identity_create(
+    app: tauri::AppHandle,
+    vault: State<'_, VaultState>,
+    state: State<'_, IdentityState>,
+    name: String,
+) -> Result<Identity, String> {
+    let identity =
+        add_identity(&vault, &state, &name, Keypair::generate()).map_err(|e| e.to_string())?;
+    if identity.active {
+        emit_identity_changed(&app, &vault, &state);
+    }
+    Ok(identity)
+}
+
+/// Tauri command: Import a hex secret key as a named identity
+///
+/// Used to migrate keys created by older builds out of localStorage.
+#[tauri::command]
+pub fn identity_import(
+    app: tauri::AppHandle,
+    vault: State<'_, VaultState>,
+    state: State<'_, IdentityState>,
+    name: String,
+    secret_key: String,
+) -> Result<Identity, String> {
+    let secret_key = Zeroizing::new(secret_key);
+    let keypair = Keypair::from_secret_hex(&secret_key).map_err(|e| e.to_string())?;
+    let identity = add_identity(&vault, &state, &name, keypair).map_err(|e| e.to_string())?;
+    if identity.active {
+        emit_identity_changed(&app, &vault, &state);
+    }
+    Ok(identity)
+}
+
+/// Tauri command: Rename an identity, carrying its session over
+#[tauri::command]
+pub fn identity_rename(
+    app: tauri::AppHandle,
+    vault: State<'_, VaultState>,
+    state: State<'_, IdentityState>,
+    name: String,
+    new_name: String,
+) -> Result<Identity, String> {
+    let new_name = validate_name(&new_name).map_err(|e| e.to_string())?;
+    vault
+        .update(|contents| {
+            if contents.key(&new_name).is_some() {
+                return Err(IdentityError::Duplicate(new_name.clone()));
+            }
+            if !contents.rename_key(&name, &new_name) {
+                return Err(IdentityError::NotFound(name.clone()));
+            }
+            Ok(())
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut sessions = state.sessions.lock().unwrap();
+    if let Some(session) = sessions.remove(&name) {
+        sessions.insert(new_name.clone(), session);
+    }
+    drop(sessions);
+
+    let identity = find_identity(&vault, &state, &new_name).map_err(|e| e.to_string())?;
+    if identity.active {
+        let _ = app.emit(IDENTITY_CHANGED_EVENT, Some(&identity));
+    }
+    Ok(identity)
+}
+
+/// Tauri command: Delete an identity and its session
+#[tauri::command]
+pub fn identity_remove(
+    app: tauri::AppHandle,
+    vault: State<'_, VaultState>,
+    state: State<'_, IdentityState>,
+    name: String,
+) -> Result<(), String> {
+    let was_active = vault
+        .update(|contents| {
+            let was_active = contents.active() == Some(name.as_str());
+            if !contents.remove_key(&name) {
+                return Err(IdentityError::NotFound(name.clone()));
+            }
+            Ok(was_active)
+        })
+        .map_err(|e| e.to_string())?;
+
+    state.sessions.lock().unwrap().remove(&name);
+    if was_active {
+        tray::sign_out(&app);
+        emit_identity_changed(&app, &vault, &state);
+    }
+    Ok(())
+}
+
+/// Tauri command: Make an identity active and notify every window
+#[tauri::command]
+pub fn identity_switch(
+    app: tauri::AppHandle,
+    vault: State<'_, VaultState>,
+    state: State<'_, IdentityState>,
+    name: String,
+) -> Result<Identity, String> {
+    vault
+        .update(|contents| {
+            if !contents.set_active(&name) {
+                return Err(IdentityError::NotFound(name.clone()));
+            }
+            Ok(())
+        })
+        .map_err(|e| e.to_string())?;
+
+    let identity = find_identity(&vault, &state, &name).map_err(|e| e.to_string())?;
+    // The tray's session belonged to the previous identity
+    tray::sign_out(&app);
+    let _ = app.emit(IDENTITY_CHANGED_EVENT, Some(&identity));
+    Ok(identity)
+}
+
+/// Tauri command: Store the Haunt session from `/api/auth/verify` for an identity
+#[tauri::command]
+pub fn identity_set_session(
+    vault: State<'_, VaultState>,
+    state: State<'_, IdentityState>,
+    name: String,
+    session_token: String,
+    expires_at: u64,
+) -> Result<(), String> {
+    let exists = vault
+        .read(|contents| contents.key(&name).is_some())
+        .map_err(|e| e.to_string())?;
+    if !exists {
+        return Err(IdentityError::NotFound(name).to_string());
+    }
+
+    state.sessions.lock().unwrap().insert(
+        name,
+        HauntSession {
+            session_token,
+            expires_at,
+        },
+    );
+    Ok(())
+}
+
+/// Tauri command: Get an identity's Haunt session if it hasn't expired
+#[tauri::command]
+pub fn identity_get_session(state: State<'_, IdentityState>, name: String) -> Option<HauntSession> {
+    state.session(&name)
+}
+
+/// Tauri command: Forget an identity's Haunt session
+#[tauri::command]
+pub fn identity_clear_session(
+    app: tauri::AppHandle,
+    vault: State<'_, VaultState>,
+    state: State<'_, IdentityState>,
+    name: String,
+) {
+    state.sessions.lock().unwrap().remove(&name);
+    let active = vault.read(|contents| contents.active() == Some(name.as_str()));
+    if active.unwrap_or(false) {
+        tray::sign_out(&app);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_names() {
+        assert_eq!(validate_name("  Production ").unwrap(), "Production");
+        assert_eq!(validate_name("test_2-b").unwrap(), "test_2-b");
+        assert!(validate_name("").is_err());
+        assert!(validate_name("   ").is_err());
+        assert!(validate_name("../etc").is_err());
+        assert!(validate_name(&"a".repeat(MAX_NAME_LEN + 1)).is_err());
+    }
+
+    #[test]
+    fn expired_sessions_are_dropped() {
+        let state = IdentityState::default();
+        state.sessions.lock().unwrap().insert(
+            "stale".into(),
+            HauntSession {
+                session_token: "t1".into(),
+                expires_at: 1,
+            },
+        );
+        state.sessions.lock().unwrap().insert(
+            "live".into(),
+            HauntSession {
+                session_token: "t2".into(),
+                expires_at: u64::MAX,
+            },
+        );
+
+        assert!(state.session("stale").is_none());
+        assert!(!state.sessions.lock().unwrap().contains_key("stale"));
+        assert_eq!(state.session("live").unwrap().session_token, "t2");
+    }
+
+    #[test]
+    fn listing_marks_active_identity() {
+        let state = IdentityState::default();
+        let mut contents = VaultContents::default();
+        contents.set_key("test", &Keypair::generate().secret_hex());
+        contents.set_key("prod", &Keypair::generate().secret_hex());
+        contents.set_active("prod");
+
+        let identities = list_identities(&contents, &state).unwrap();
+        assert_eq!(identities.len(), 2);
+        assert!(!identities[0].active);
+        assert!(identities[1].active);
+
+        contents.remove_key("prod");
+        assert_eq!(contents.active(), Some("test"));
+    }
+}
@@ -0,0 +1,1150 @@
+//! Local signal accuracy
+//!
+//! Every directional signal the local engine produces is recorded as a
+//! prediction and validated against the price once its horizon has passed,
+//! giving per-indicator and per-symbol accuracy without Haunt. Predictions
+//! live in `signal-accuracy.sqlite3` in the app data directory; unlike the
+//! market cache this history cannot be fetched again, so it is never
+//! rebuilt behind the user's back.
+//!
+//! Prices come from candles handed to the engine and from the live price
+//! stream, which is kept subscribed to every symbol with an open
+//! prediction. A prediction is settled by the first price seen after it falls
+//! due; one with no price within half a horizon of that is dropped rather
+//! than settled against a stale price. Only one prediction per symbol,
+//! indicator and timeframe is open at a time, so recomputing signals every
+//! few seconds doesn't record the same call over and over.
+//!
+//! A move within the neutral band either side of the prediction price is a
+//! neutral outcome and counts toward neither side of the accuracy
+//! percentage.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use rusqlite::{params, Connection};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tauri::{Manager, State};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::cache::migrate;
+use crate::haunt::models::{
+    AccuracyResponse, OhlcPoint, PredictionOutcome, PredictionsResponse, SignalAccuracy,
+    SignalDirection, SignalPrediction, SymbolSignals, TradingTimeframe,
+};
+use crate::haunt::socket::WsMessage;
+use crate::haunt::HauntSocket;
+use crate::{new_id, now_millis};
+
+const DB_FILE: &str = "signal-accuracy.sqlite3";
+const SETTINGS_FILE: &str = "signal-accuracy.json";
+
+/// Schema migrations, applied in order; `PRAGMA user_version` records how
+/// many have run. Only ever append to this list.
+const MIGRATIONS: &[&str] = &[
+    // 1: predictions and their outcomes
+    "CREATE TABLE predictions (
+        id TEXT PRIMARY KEY,
+        symbol TEXT NOT NULL,
+        indicator TEXT NOT NULL,
+        timeframe TEXT NOT NULL,
+        direction TEXT NOT NULL,
+        score REAL NOT NULL,
+        price REAL NOT NULL,
+        created_at INTEGER NOT NULL,
+        horizon_ms INTEGER NOT NULL,
+        due_at INTEGER NOT NULL,
+        price_after REAL,
+        outcome TEXT,
+        validated_at INTEGER
+    );
+    CREATE INDEX predictions_pending ON predictions (symbol, due_at) WHERE outcome IS NULL;
+    CREATE INDEX predictions_symbol ON predictions (symbol, created_at);",
+];
+
+/// Predictions listed when no limit is given
+const DEFAULT_PREDICTION_LIMIT: u32 = 50;
+/// Most predictions listed at once
+const MAX_PREDICTION_LIMIT: u32 = 500;
+
+/// Errors produced by the accuracy store
+#[derive(Debug, thiserror::Error)]
+pub enum AccuracyError {
+    #[error("Signal accuracy store is not open")]
+    NotOpen,
+    #[error("Signal accuracy store was written by a newer version (schema {0})")]
+    FutureSchema(i64),
+    #[error("Signal accuracy store error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error("Unknown prediction status: {0}")]
+    UnknownStatus(String),
+}
+
+/// How long after a prediction its price is checked
+///
+/// The four horizons Haunt validates at, so local predictions fill the
+/// same fields of [`SignalPrediction`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Horizon {
+    #[serde(rename = "5m")]
+    FiveMinutes,
+    #[serde(rename = "1h")]
+    OneHour,
+    #[serde(rename = "4h")]
+    FourHours,
+    #[serde(rename = "24h")]
+    OneDay,
+}
+
+impl Horizon {
+    pub fn millis(self) -> i64 {
+        let minutes = match self {
+            Self::FiveMinutes => 5,
+            Self::OneHour => 60,
+            Self::FourHours => 4 * 60,
+            Self::OneDay => 24 * 60,
+        };
+        minutes * 60 * 1000
+    }
+
+    fn from_millis(millis: i64) -> Option<Self> {
+        [
+            Self::FiveMinutes,
+            Self::OneHour,
+            Self::FourHours,
+            Self::OneDay,
+        ]
+        .into_iter()
+        .find(|h| h.millis() == millis)
+    }
+}
+
+/// Horizon used for each trading timeframe
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Horizons {
+    pub scalping: Horizon,
+    pub day_trading: Horizon,
+    pub swing_trading: Horizon,
+    pub position_trading: Horizon,
+}
+
+impl Default for Horizons {
+    fn default() -> Self {
+        Self {
+            scalping: Horizon::FiveMinutes,
+            day_trading: Horizon::OneHour,
+            swing_trading: Horizon::FourHours,
+            position_trading: Horizon::OneDay,
+        }
+    }
+}
+
+impl Horizons {
+    pub fn get(&self, timeframe: TradingTimeframe) -> Horizon {
+        match timeframe {
+            TradingTimeframe::Scalping => self.scalping,
+            TradingTimeframe::DayTrading => self.day_trading,
+            TradingTimeframe::SwingTrading => self.swing_trading,
+            TradingTimeframe::PositionTrading => self.position_trading,
+        }
+    }
+}
+
+/// Persisted accuracy settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AccuracySettings {
+    pub horizons: Horizons,
+    /// Moves within this percentage of the prediction price are neutral
+    pub neutral_band_pct: f64,
+}
+
+impl Default for AccuracySettings {
+    fn default() -> Self {
+        Self {
+            horizons: Horizons::default(),
+            neutral_band_pct: 0.1,
+        }
+    }
+}
+
+/// Outcome of a directional prediction given the price after its horizon
+pub fn outcome(
+    direction: SignalDirection,
+    price: f64,
+    later: f64,
+    neutral_band_pct: f64,
+) -> PredictionOutcome {
+    let change_pct = (later - price) / price * 100.0;
+    let bullish = matches!(direction, SignalDirection::StrongBuy | SignalDirection::Buy);
+    if change_pct.abs() <= neutral_band_pct || direction == SignalDirection::Neutral {
+        PredictionOutcome::Neutral
+    } else if bullish == (change_pct > 0.0) {
+        PredictionOutcome::Correct
+    } else {
+        PredictionOutcome::Incorrect
+    }
+}
+
+/// Whether the newest candle is recent enough to record predictions at its
+/// close: it has to have closed less than one candle interval before `now`
+///
+/// The interval is taken from the last two candles, so a single candle is
+/// never current.
+fn is_current(candles: &[OhlcPoint], now: i64) -> bool {
+    let [.., previous, last] = candles else {
+        return false;
+    };
+    // Candle times are Unix seconds
+    let interval = (last.time - previous.time) * 1000;
+    interval > 0 && now < last.time * 1000 + 2 * interval
+}
+
+/// Store an enum as its serde name
+fn to_text<T: Serialize>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(s)) => s,
+        _ => String::new(),
+    }
+}
+
+/// Read an enum back from its serde name
+fn from_text<T: DeserializeOwned>(text: &str) -> Option<T> {
+    serde_json::from_value(serde_json::Value::String(text.to_string())).ok()
+}
+
+/// A prediction awaiting its price
+struct Pending {
+    id: String,
+    direction: SignalDirection,
+    price: f64,
+    due_at: i64,
+    horizon_ms: i64,
+}
+
+impl Pending {
+    /// Latest time a price may settle this prediction
+    fn deadline(&self) -> i64 {
+        self.due_at + self.horizon_ms / 2
+    }
+}
+
+/// Which predictions to list
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    All,
+    Validated,
+    Pending,
+}
+
+impl Status {
+    pub fn parse(status: Option<&str>) -> Result<Self, AccuracyError> {
+        match status.unwrap_or("all") {
+            "all" => Ok(Self::All),
+            "validated" => Ok(Self::Validated),
+            "pending" => Ok(Self::Pending),
+            other => Err(AccuracyError::UnknownStatus(other.to_string())),
+        }
+    }
+}
+
+/// Counts shared by every accuracy grouping; the caller adds GROUP BY
+const ACCURACY_SELECT: &str = "SELECT symbol, timeframe, COUNT(*),
+        SUM(outcome = 'correct'), SUM(outcome = 'incorrect'), SUM(outcome = 'neutral'),
+        MAX(validated_at)";
+
+/// The prediction database
+pub struct AccuracyTracker {
+    conn: Connection,
+    /// Earliest due time of each symbol's open predictions, so price ticks
+    /// for symbols with nothing due skip the database
+    next_due: HashMap<String, i64>,
+}
+
+impl AccuracyTracker {
+    pub fn open(path: &Path) -> Result<Self, AccuracyError> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    fn with_connection(mut conn: Connection) -> Result<Self, AccuracyError> {
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        migrate(&mut conn, MIGRATIONS, AccuracyError::FutureSchema)?;
+        let next_due = {
+            let mut statement = conn.prepare(
+                "SELECT symbol, MIN(due_at) FROM predictions
+                 WHERE outcome IS NULL GROUP BY symbol",
+            )?;
+            let rows = statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect::<Result<_, _>>()?
+        };
+        Ok(Self { conn, next_due })
+    }
+
+    /// Symbols with open predictions
+    pub fn symbols(&self) -> BTreeSet<String> {
+        self.next_due.keys().cloned().collect()
+    }
+
+    fn refresh_next_due(&mut self, symbol: &str) -> Result<(), AccuracyError> {
+        let due: Option<i64> = self.conn.query_row(
+            "SELECT MIN(due_at) FROM predictions WHERE symbol = ?1 AND outcome IS NULL",
+            params![symbol],
+            |row| row.get(0),
+        )?;
+        match due {
+            Some(due) => self.next_due.insert(symbol.to_string(), due),
+            None => self.next_due.remove(symbol),
+        };
+        Ok(())
+    }
+
+    fn pending(&self, symbol: &str, now: i64) -> Result<Vec<Pending>, AccuracyError> {
+        let mut statement = self.conn.prepare_cached(
+            "SELECT id, direction, price, due_at, horizon_ms FROM predictions
+             WHERE symbol = ?1 AND outcome IS NULL AND due_at <= ?2 ORDER BY due_at",
+        )?;
+        let rows = statement.query_map(params![symbol, now], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get(2)?,
+                row.get(3)?,
+                row.get(4)?,
+            ))
+        })?;
+        let mut pending = Vec::new();
+        for row in rows {
+            let (id, direction, price, due_at, horizon_ms) = row?;
+            if let Some(direction) = from_text(&direction) {
+                pending.push(Pending {
+                    id,
+                    direction,
+                    price,
+                    due_at,
+                    horizon_ms,
+                });
+            }
+        }
+        Ok(pending)
+    }
+
+    /// Settle due predictions for a symbol with `price_at`, which gives the
+    /// first price and its time at or after a due time, if any
+    ///
+    /// Predictions past their deadline with no price are dropped. Returns
+    /// how many were settled.
+    fn settle(
+        &mut self,
+        symbol: &str,
+        now: i64,
+        neutral_band_pct: f64,
+        price_at: impl Fn(i64) -> Option<(f64, i64)>,
+    ) -> Result<usize, AccuracyError> {
+        let pending = self.pending(symbol, now)?;
+        if pending.is_empty() {
+            return Ok(0);
+        }
+        let mut settled = 0;
+        let tx = self.conn.transaction()?;
+        {
+            let mut update = tx.prepare_cached(
+                "UPDATE predictions SET price_after = ?2, outcome = ?3, validated_at = ?4
+                 WHERE id = ?1",
+            )?;
+            let mut expire = tx.prepare_cached("DELETE FROM predictions WHERE id = ?1")?;
+            for prediction in pending {
+                match price_at(prediction.due_at).filter(|(_, at)| *at <= prediction.deadline()) {
+                    Some((price, at)) => {
+                        let result = outcome(
+                            prediction.direction,
+                            prediction.price,
+                            price,
+                            neutral_band_pct,
+                        );
+                        update.execute(params![prediction.id, price, to_text(&result), at])?;
+                        settled += 1;
+                    }
+                    None if prediction.deadline() < now => {
+                        expire.execute(params![prediction.id])?;
+                    }
+                    None => {}
+                }
+            }
+        }
+        tx.commit()?;
+        self.refresh_next_due(symbol)?;
+        Ok(settled)
+    }
+
+    /// Settle a symbol's due predictions with a live price
+    pub fn on_price(
+        &mut self,
+        symbol: &str,
+        price: f64,
+        now: i64,
+        neutral_band_pct: f64,
+    ) -> Result<usize, AccuracyError> {
+        let symbol = symbol.to_uppercase();
+        if !self.next_due.get(&symbol).is_some_and(|due| *due <= now) {
+            return Ok(0);
+        }
+        self.settle(&symbol, now, neutral_band_pct, |_| Some((price, now)))
+    }
+
+    /// Settle a symbol's due predictions from candles, each at the close of
+    /// the first candle starting at or after its due time
+    pub fn on_candles(
+        &mut self,
+        symbol: &str,
+        candles: &[OhlcPoint],
+        now: i64,
+        neutral_band_pct: f64,
+    ) -> Result<usize, AccuracyError> {
+        // Candle times are Unix seconds
+        self.settle(&symbol.to_uppercase(), now, neutral_band_pct, |due| {
+            candles
+                .iter()
+                .find(|c| c.time * 1000 >= due)
+                .map(|c| (c.close, c.time * 1000))
+        })
+    }
+
+    /// Record a prediction for each directional signal without an open one
+    ///
+    /// Returns how many were recorded.
+    pub fn record(
+        &mut self,
+        signals: &SymbolSignals,
+        price: f64,
+        horizon: Horizon,
+    ) -> Result<usize, AccuracyError> {
+        if !(price.is_finite() && price > 0.0) {
+            return Ok(0);
+        }
+        let symbol = signals.symbol.to_uppercase();
+        let now = signals.timestamp;
+        let timeframe = signals.timeframe.as_str();
+        let mut recorded = 0;
+        let tx = self.conn.transaction()?;
+        {
+            // Open predictions that can no longer be settled would block
+            // new ones for good
+            tx.execute(
+                "DELETE FROM predictions WHERE symbol = ?1 AND outcome IS NULL
+                 AND due_at + horizon_ms / 2 < ?2",
+                params![symbol, now],
+            )?;
+            let mut insert = tx.prepare_cached(
+                "INSERT INTO predictions
+                 (id, symbol, indicator, timeframe, direction, score, price, created_at,
+                  horizon_ms, due_at)
+                 SELECT ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10
+                 WHERE NOT EXISTS (
+                    SELECT 1 FROM predictions WHERE symbol = ?2 AND indicator = ?3
+                    AND timeframe = ?4 AND outcome IS NULL
+                 )",
+            )?;
+            for signal in &signals.signals {
+                if signal.direction == SignalDirection::Neutral {
+                    continue;
+                }
+                recorded += insert.execute(params![
+                    new_id(),
+                    symbol,
+                    signal.name,
+                    timeframe,
+                    to_text(&signal.direction),
+                    signal.score,
+                    price,
+                    now,
+                    horizon.millis(),
+                    now + horizon.millis(),
+                ])?;
+            }
+        }
+        tx.commit()?;
+        self.refresh_next_due(&symbol)?;
+        Ok(recorded)
+    }
+
+    fn accuracies(
+        &self,
+        indicator: &str,
+        filter: &str,
+        group: &str,
+        param: Option<&str>,
+    ) -> Result<Vec<SignalAccuracy>, AccuracyError> {
+        let sql = format!(
+            "{}, {} FROM predictions WHERE outcome IS NOT NULL {} GROUP BY {} ORDER BY {}",
+            ACCURACY_SELECT, indicator, filter, group, group
+        );
+        let mut statement = self.conn.prepare_cached(&sql)?;
+        let map = |row: &rusqlite::Row<'_>| {
+            let correct: i64 = row.get(3)?;
+            let incorrect: i64 = row.get(4)?;
+            let decided = correct + incorrect;
+            Ok(SignalAccuracy {
+                indicator: row.get(7)?,
+                symbol: row.get(0)?,
+                timeframe: row.get(1)?,
+                total_predictions: row.get::<_, i64>(2)? as u64,
+                correct_predictions: correct as u64,
+                incorrect_predictions: incorrect as u64,
+                neutral_predictions: row.get::<_, i64>(5)? as u64,
+                accuracy_pct: if decided > 0 {
+                    correct as f64 / decided as f64 * 100.0
+                } else {
+                    0.0
+                },
+                last_updated: row.get(6)?,
+            })
+        };
+        let rows = match param {
+            Some(param) => statement.query_map(params![param], map)?,
+            None => statement.query_map([], map)?,
+        };
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Accuracy of each indicator and timeframe for a symbol
+    pub fn symbol_accuracy(&self, symbol: &str) -> Result<Vec<SignalAccuracy>, AccuracyError> {
+        self.accuracies(
+            "indicator",
+            "AND symbol = ?1",
+            "indicator, timeframe",
+            Some(&symbol.to_uppercase()),
+        )
+    }
+
+    /// Accuracy of an indicator for each symbol and timeframe
+    pub fn indicator_accuracy(
+        &self,
+        indicator: &str,
+    ) -> Result<Vec<SignalAccuracy>, AccuracyError> {
+        self.accuracies(
+            "indicator",
+            "AND lower(indicator) = lower(?1)",
+            "symbol, timeframe",
+            Some(indicator),
+        )
+    }
+
+    /// Accuracy across all indicators for each symbol and timeframe, with
+    /// `indicator` set to "all"
+    pub fn overall_accuracy(&self) -> Result<Vec<SignalAccuracy>, AccuracyError> {
+        self.accuracies("'all'", "", "symbol, timeframe", None)
+    }
+
+    /// A symbol's predictions, newest first
+    pub fn predictions(
+        &self,
+        symbol: &str,
+        status: Status,
+        limit: Option<u32>,
+    ) -> Result<Vec<SignalPrediction>, AccuracyError> {
+        let filter = match status {
+            Status::All => "",
+            Status::Validated => "AND outcome IS NOT NULL",
+            Status::Pending => "AND outcome IS NULL",
+        };
+        let sql = format!(
+            "SELECT id, symbol, indicator, direction, score, price, created_at, horizon_ms,
+             price_after, outcome FROM predictions WHERE symbol = ?1 {}
+             ORDER BY created_at DESC, rowid DESC LIMIT ?2",
+            filter
+        );
+        let limit = limit
+            .unwrap_or(DEFAULT_PREDICTION_LIMIT)
+            .min(MAX_PREDICTION_LIMIT);
+        let mut statement = self.conn.prepare_cached(&sql)?;
+        let rows = statement.query_map(params![symbol.to_uppercase(), limit], |row| {
+            Ok((
+                SignalPrediction {
+                    id: row.get(0)?,
+                    symbol: row.get(1)?,
+                    indicator: row.get(2)?,
+                    direction: SignalDirection::Neutral,
+                    score: row.get(4)?,
+                    price_at_prediction: row.get(5)?,
+                    timestamp: row.get(6)?,
+                    validated: false,
+                    price_after_5m: None,
+                    price_after_1h: None,
+                    price_after_4h: None,
+                    price_after_24h: None,
+                    outcome_5m: None,
+                    outcome_1h: None,
+                    outcome_4h: None,
+                    outcome_24h: None,
+                },
+                row.get::<_, String>(3)?,
+                row.get::<_, i64>(7)?,
+                row.get::<_, Option<f64>>(8)?,
+                row.get::<_, Option<String>>(9)?,
+            ))
+        })?;
+
+        let mut predictions = Vec::new();
+        for row in rows {
+            let (mut prediction, direction, horizon_ms, price_after, result) = row?;
+            let Some(direction) = from_text(&direction) else {
+                continue;
+            };
+            prediction.direction = direction;
+            let result = result.as_deref().and_then(from_text);
+            prediction.validated = result.is_some();
+            let (price, outcome) = match Horizon::from_millis(horizon_ms) {
+                Some(Horizon::FiveMinutes) => {
+                    (&mut prediction.price_after_5m, &mut prediction.outcome_5m)
+                }
+                Some(Horizon::OneHour) => {
+                    (&mut prediction.price_after_1h, &mut prediction.outcome_1h)
+                }
+                Some(Horizon::FourHours) => {
+                    (&mut prediction.price_after_4h, &mut prediction.outcome_4h)
+                }
+                Some(Horizon::OneDay) | None => {
+                    (&mut prediction.price_after_24h, &mut prediction.outcome_24h)
+                }
+            };
+            *price = price_after;
+            *outcome = result;
+            predictions.push(prediction);
+        }
+        Ok(predictions)
+    }
+
+    pub fn clear(&mut self) -> Result<(), AccuracyError> {
+        self.conn.execute("DELETE FROM predictions", [])?;
+        self.next_due.clear();
+        Ok(())
+    }
+}
+
+/// Managed state holding the open tracker and its settings
+#[derive(Default)]
+pub struct AccuracyState {
+    tracker: Mutex<Option<AccuracyTracker>>,
+    settings: Mutex<AccuracySettings>,
+    data_dir: Mutex<Option<PathBuf>>,
+    /// Symbols this module holds socket subscriptions for
+    subscribed: Mutex<BTreeSet<String>>,
+}
+
+impl AccuracyState {
+    fn with<T>(
+        &self,
+        f: impl FnOnce(&mut AccuracyTracker) -> Result<T, AccuracyError>,
+    ) -> Result<T, AccuracyError> {
+        let mut guard = self.tracker.lock().unwrap();
+        f(guard.as_mut().ok_or(AccuracyError::NotOpen)?)
+    }
+
+    fn save(&self) -> std::io::Result<()> {
+        let Some(dir) = self.data_dir.lock().unwrap().clone() else {
+            return Ok(());
+        };
+        let json = serde_json::to_vec_pretty(&*self.settings.lock().unwrap())
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join(SETTINGS_FILE), json)
+    }
+
+    /// Hold socket subscriptions for exactly the symbols with open
+    /// predictions
+    fn sync_subscriptions(&self, socket: &HauntSocket) {
+        let wanted = self
+            .with(|tracker| Ok(tracker.symbols()))
+            .unwrap_or_default();
+        let mut subscribed = self.subscribed.lock().unwrap();
+
+        let added: Vec<String> = wanted.difference(&subscribed).cloned().collect();
+        let removed: Vec<String> = subscribed.difference(&wanted).cloned().collect();
+        if !added.is_empty() {
+            socket.subscribe(&added);
+        }
+        if !removed.is_empty() {
+            socket.unsubscribe(Some(&removed));
+        }
+        *subscribed = wanted;
+    }
+
+    /// Settle, record and annotate a freshly computed set of signals
+    ///
+    /// Due predictions are settled from `candles` first, then the signals
+    /// are recorded at the latest close and given the accuracy their
+    /// indicator has reached for this symbol and timeframe. Signals from
+    /// candles that have gone stale, e.g. a cached chart served offline, are
+    /// not recorded. Failures are logged; the signals are still returned.
+    pub fn observe(
+        &self,
+        socket: &HauntSocket,
+        signals: &mut SymbolSignals,
+        candles: &[OhlcPoint],
+    ) {
+        let Some(price) = candles.last().map(|c| c.close) else {
+            return;
+        };
+        let settings = self.settings.lock().unwrap().clone();
+        let horizon = settings.horizons.get(signals.timeframe);
+        let result = self.with(|tracker| {
+            tracker.on_candles(
+                &signals.symbol,
+                candles,
+                signals.timestamp,
+                settings.neutral_band_pct,
+            )?;
+            if is_current(candles, signals.timestamp) {
+                tracker.record(signals, price, horizon)?;
+            }
+            tracker.symbol_accuracy(&signals.symbol)
+        });
+        self.sync_subscriptions(socket);
+        let accuracies = match result {
+            Ok(accuracies) => accuracies,
+            Err(e) => {
+                eprintln!("Failed to track signal accuracy: {}", e);
+                return;
+            }
+        };
+        let timeframe = signals.timeframe.as_str();
+        for signal in &mut signals.signals {
+            if let Some(accuracy) = accuracies
+                .iter()
+                .find(|a| a.indicator == signal.name && a.timeframe == timeframe)
+            {
+                signal.accuracy = Some(accuracy.accuracy_pct);
+                signal.sample_size = Some(accuracy.total_predictions);
+            }
+        }
+    }
+}
+
+/// Open the prediction store and settle predictions from the price stream
+pub fn init(app: &tauri::AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+    let data_dir = app.path().app_data_dir()?;
+    fs::create_dir_all(&data_dir)?;
+    let settings = match fs::read(data_dir.join(SETTINGS_FILE)) {
+        Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+            eprintln!("Failed to parse accuracy settings: {}", e);
+            AccuracySettings::default()
+        }),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => AccuracySettings::default(),
+        Err(e) => return Err(e.into()),
+    };
+
+    let state = app.state::<AccuracyState>();
+    *state.settings.lock().unwrap() = settings;
+    *state.tracker.lock().unwrap() = Some(AccuracyTracker::open(&data_dir.join(DB_FILE))?);
+    *state.data_dir.lock().unwrap() = Some(data_dir);
+    state.sync_subscriptions(&app.state::<HauntSocket>());
+
+    let mut messages = app.state::<HauntSocket>().messages();
+    let handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let state = handle.state::<AccuracyState>();
+        loop {
+            let message = match messages.recv().await {
+                Ok(message) => message,
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            };
+            let WsMessage::PriceUpdate { data } = &*message else {
+                continue;
+            };
+            let band = state.settings.lock().unwrap().neutral_band_pct;
+            match state
+                .with(|tracker| tracker.on_price(&data.symbol, data.price, now_millis(), band))
+            {
+                Ok(0) => {}
+                Ok(_) => state.sync_subscriptions(&handle.state::<HauntSocket>()),
+                Err(e) => eprintln!("Failed to validate signal predictions: {}", e),
+            }
+        }
+    });
+    Ok(())
+}
+
+// ========== Commands ==========
+
+/// Tauri command: Get local signal accuracy for a symbol
+#[tauri::command]
+pub fn local_signal_accuracy(
+    state: State<'_, AccuracyState>,
+    symbol: String,
+) -> Result<AccuracyResponse, String> {
+    let accuracies = state
+        .with(|tracker| tracker.symbol_accuracy(&symbol))
+        .map_err(|e| e.to_string())?;
+    Ok(AccuracyResponse {
+        symbol: symbol.to_uppercase(),
+        accuracies,
+        timestamp: now_millis(),
+    })
+}
+
+/// Tauri command: Get local accuracy of an indicator across symbols
+#[tauri::command]
+pub fn local_indicator_accuracy(
+    state: State<'_, AccuracyState>,
+    indicator: String,
+) -> Result<Vec<SignalAccuracy>, String> {
+    state
+        .with(|tracker| tracker.indicator_accuracy(&indicator))
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Get local accuracy of all indicators for each symbol
+#[tauri::command]
+pub fn local_symbol_accuracy(
+    state: State<'_, AccuracyState>,
+) -> Result<Vec<SignalAccuracy>, String> {
+    state
+        .with(|tracker| tracker.overall_accuracy())
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Get local predictions; `status` is "all", "validated" or
+/// "pending"
+#[tauri::command]
+pub fn local_signal_predictions(
+    state: State<'_, AccuracyState>,
+    symbol: String,
+    status: Option<String>,
+    limit: Option<u32>,
+) -> Result<PredictionsResponse, String> {
+    let status = Status::parse(status.as_deref()).map_err(|e| e.to_string())?;
+    let predictions = state
+        .with(|tracker| tracker.predictions(&symbol, status, limit))
+        .map_err(|e| e.to_string())?;
+    Ok(PredictionsResponse {
+        symbol: symbol.to_uppercase(),
+        predictions,
+        timestamp: now_millis(),
+    })
+}
+
+/// Tauri command: Get signal accuracy settings
+#[tauri::command]
+pub fn local_signal_accuracy_get_settings(state: State<'_, AccuracyState>) -> AccuracySettings {
+    state.settings.lock().unwrap().clone()
+}
+
+/// Tauri command: Update signal accuracy settings
+///
+/// Open predictions keep the horizon they were recorded with.
+#[tauri::command]
+pub fn local_signal_accuracy_set_settings(
+    state: State<'_, AccuracyState>,
+    settings: AccuracySettings,
+) -> Result<AccuracySettings, String> {
+    if !(settings.neutral_band_pct.is_finite() && settings.neutral_band_pct >= 0.0) {
+        return Err("Neutral band must be a non-negative percentage".to_string());
+    }
+    *state.settings.lock().unwrap() = settings.clone();
+    state.save().map_err(|e| e.to_string())?;
+    Ok(settings)
+}
+
+/// Tauri command: Delete all local predictions
+#[tauri::command]
+pub fn local_signal_accuracy_clear(
+    state: State<'_, AccuracyState>,
+    socket: State<'_, HauntSocket>,
+) -> Result<(), String> {
+    state
+        .with(|tracker| tracker.clear())
+        .map_err(|e| e.to_string())?;
+    state.sync_subscriptions(&socket);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::haunt::models::{SignalCategory, SignalOutput};
+
+    const HOUR: i64 = 60 * 60 * 1000;
+
+    fn tracker() -> AccuracyTracker {
+        AccuracyTracker::with_connection(Connection::open_in_memory().unwrap()).unwrap()
+    }
+
+    fn signals(symbol: &str, now: i64, calls: &[(&str, SignalDirection)]) -> SymbolSignals {
+        SymbolSignals {
+            symbol: symbol.to_string(),
+            timeframe: TradingTimeframe::DayTrading,
+            signals: calls
+                .iter()
+                .map(|(name, direction)| SignalOutput {
+                    name: name.to_string(),
+                    category: SignalCategory::Momentum,
+                    value: 0.0,
+                    score: 0.0,
+                    direction: *direction,
+                    accuracy: None,
+                    sample_size: None,
+                    timestamp: now,
+                })
+                .collect(),
+            trend_score: 0.0,
+            momentum_score: 0.0,
+            volatility_score: 0.0,
+            volume_score: 0.0,
+            composite_score: 0.0,
+            direction: SignalDirection::Neutral,
+            timestamp: now,
+        }
+    }
+
+    #[test]
+    fn outcomes_follow_direction_and_threshold() {
+        use PredictionOutcome::{Correct, Incorrect};
+        use SignalDirection::{Buy, Sell, StrongBuy, StrongSell};
+        assert_eq!(outcome(Buy, 100.0, 101.0, 0.1), Correct);
+        assert_eq!(outcome(StrongBuy, 100.0, 99.0, 0.1), Incorrect);
+        assert_eq!(outcome(Sell, 100.0, 99.0, 0.1), Correct);
+        assert_eq!(outcome(StrongSell, 100.0, 101.0, 0.1), Incorrect);
+        assert_eq!(outcome(Buy, 100.0, 100.05, 0.1), PredictionOutcome::Neutral);
+        assert_eq!(
+            outcome(SignalDirection::Neutral, 100.0, 110.0, 0.1),
+            PredictionOutcome::Neutral
+        );
+    }
+
+    #[test]
+    fn records_one_open_prediction_per_indicator() {
+        let mut tracker = tracker();
+        let calls = [
+            ("RSI (14)", SignalDirection::Buy),
+            ("MACD", SignalDirection::Sell),
+            ("OBV", SignalDirection::Neutral),
+        ];
+        assert_eq!(
+            tracker
+                .record(&signals("btc", 0, &calls), 100.0, Horizon::OneHour)
+                .unwrap(),
+            2
+        );
+        assert_eq!(
+            tracker
+                .record(&signals("BTC", 1000, &calls), 100.0, Horizon::OneHour)
+                .unwrap(),
+            0
+        );
+        let mut other = signals("BTC", 1000, &calls);
+        other.timeframe = TradingTimeframe::Scalping;
+        assert_eq!(
+            tracker.record(&other, 100.0, Horizon::FiveMinutes).unwrap(),
+            2
+        );
+        assert_eq!(tracker.next_due.get("BTC"), Some(&(1000 + 5 * 60 * 1000)));
+        assert_eq!(tracker.symbols().into_iter().collect::<Vec<_>>(), ["BTC"]);
+    }
+
+    #[test]
+    fn live_price_settles_due_predictions() {
+        let mut tracker = tracker();
+        let calls = [
+            ("RSI (14)", SignalDirection::Buy),
+            ("MACD", SignalDirection::Sell),
+        ];
+        tracker
+            .record(&signals("BTC", 0, &calls), 100.0, Horizon::OneHour)
+            .unwrap();
+
+        // Not yet due
+        assert_eq!(tracker.on_price("btc", 110.0, HOUR - 1, 0.1).unwrap(), 0);
+        assert_eq!(tracker.on_price("BTC", 110.0, HOUR, 0.1).unwrap(), 2);
+        assert!(tracker.next_due.is_empty());
+
+        let accuracy = tracker.symbol_accuracy("btc").unwrap();
+        assert_eq!(accuracy.len(), 2);
+        let rsi = accuracy.iter().find(|a| a.indicator == "RSI (14)").unwrap();
+        assert_eq!((rsi.correct_predictions, rsi.accuracy_pct), (1, 100.0));
+        assert_eq!(rsi.timeframe, "day_trading");
+        assert_eq!(rsi.last_updated, HOUR);
+        let macd = accuracy.iter().find(|a| a.indicator == "MACD").unwrap();
+        assert_eq!((macd.incorrect_predictions, macd.accuracy_pct), (1, 0.0));
+
+        // Settled predictions free the slot for a new one
+        assert_eq!(
+            tracker
+                .record(&signals("BTC", HOUR, &calls), 110.0, Horizon::OneHour)
+                .unwrap(),
+            2
+        );
+    }
+
+    #[test]
+    fn stale_predictions_are_dropped() {
+        let mut tracker = tracker();
+        let calls = [("RSI (14)", SignalDirection::Buy)];
+        tracker
+            .record(&signals("BTC", 0, &calls), 100.0, Horizon::OneHour)
+            .unwrap();
+
+        // Past due plus half a horizon, the price says nothing about the call
+        assert_eq!(
+            tracker
+                .on_price("BTC", 120.0, HOUR + HOUR / 2 + 1, 0.1)
+                .unwrap(),
+            0
+        );
+        assert!(tracker
+            .predictions("BTC", Status::All, None)
+            .unwrap()
+            .is_empty());
+        assert!(tracker.symbol_accuracy("BTC").unwrap().is_empty());
+    }
+
+    #[test]
+    fn candles_settle_at_first_close_after_due() {
+        let mut tracker = tracker();
+        let calls = [("RSI (14)", SignalDirection::Sell)];
+        tracker
+            .record(&signals("ETH", 0, &calls), 100.0, Horizon::OneHour)
+            .unwrap();
+
+        let candle = |minute: i64, close: f64| OhlcPoint {
+            time: minute * 60,
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: None,
+        };
+        let candles = [candle(30, 120.0), candle(60, 95.0), candle(90, 130.0)];
+        assert_eq!(
+            tracker.on_candles("ETH", &candles, 2 * HOUR, 0.1).unwrap(),
+            1
+        );
+
+        let predictions = tracker.predictions("ETH", Status::Validated, None).unwrap();
+        assert_eq!(predictions.len(), 1);
+        assert!(predictions[0].validated);
+        assert_eq!(predictions[0].price_after_1h, Some(95.0));
+        assert_eq!(predictions[0].outcome_1h, Some(PredictionOutcome::Correct));
+        assert_eq!(predictions[0].outcome_5m, None);
+    }
+
+    #[test]
+    fn only_current_candles_are_recorded() {
+        let candle = |minute: i64| OhlcPoint {
+            time: minute * 60,
+            open: 100.0,
+            high: 100.0,
+            low: 100.0,
+            close: 100.0,
+            volume: None,
+        };
+        let minute = 60 * 1000;
+        let candles = [candle(0), candle(5)];
+        // The last candle closes at minute 10
+        assert!(is_current(&candles, 9 * minute));
+        assert!(is_current(&candles, 14 * minute));
+        assert!(!is_current(&candles, 15 * minute));
+        assert!(!is_current(&candles[1..], 6 * minute));
+        assert!(!is_current(&[], 0));
+    }
+
+    #[test]
+    fn accuracy_groupings() {
+        let mut tracker = tracker();
+        let calls = [
+            ("RSI (14)", SignalDirection::Buy),
+            ("MACD", SignalDirection::Buy),
+        ];
+        tracker
+            .record(&signals("BTC", 0, &calls), 100.0, Horizon::OneHour)
+            .unwrap();
+        tracker.on_price("BTC", 100.05, HOUR, 0.1).unwrap();
+        tracker
+            .record(&signals("ETH", 0, &calls[..1]), 100.0, Horizon::OneHour)
+            .unwrap();
+        tracker.on_price("ETH", 90.0, HOUR, 0.1).unwrap();
+
+        let rsi = tracker.indicator_accuracy("rsi (14)").unwrap();
+        assert_eq!(
+            rsi.iter().map(|a| a.symbol.as_str()).collect::<Vec<_>>(),
+            ["BTC", "ETH"]
+        );
+        // A neutral outcome counts toward the sample but not the percentage
+        assert_eq!(rsi[0].neutral_predictions, 1);
+        assert_eq!(rsi[0].accuracy_pct, 0.0);
+        assert_eq!(rsi[1].incorrect_predictions, 1);
+
+        let overall = tracker.overall_accuracy().unwrap();
+        assert_eq!(overall.len(), 2);
+        assert_eq!(overall[0].indicator, "all");
+        assert_eq!(overall[0].total_predictions, 2);
+        assert_eq!(overall[1].total_predictions, 1);
+    }
+
+    #[test]
+    fn prediction_listing() {
+        let mut tracker = tracker();
+        tracker
+            .record(
+                &signals("BTC", 0, &[("RSI (14)", SignalDirection::Buy)]),
+                100.0,
+                Horizon::FiveMinutes,
+            )
+            .unwrap();
+        tracker.on_price("BTC", 101.0, 5 * 60 * 1000, 0.1).unwrap();
+        tracker
+            .record(
+                &signals("BTC", 10, &[("MACD", SignalDirection::Sell)]),
+                100.0,
+                Horizon::FiveMinutes,
+            )
+            .unwrap();
+
+        let all = tracker.predictions("BTC", Status::All, None).unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].indicator, "MACD");
+        let pending = tracker.predictions("BTC", Status::Pending, None).unwrap();
+        assert_eq!(pending.len(), 1);
+        assert!(!pending[0].validated);
+        assert_eq!(
+            tracker
+                .predictions("BTC", Status::All, Some(1))
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(matches!(
+            Status::parse(Some("later")),
+            Err(AccuracyError::UnknownStatus(_))
+        ));
+    }
+
+    #[test]
+    fn reopen_restores_next_due() {
+        let path = std::env::temp_dir().join(format!(
+            "wraith-accuracy-reopen-{}.sqlite3",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        {
+            let mut tracker = AccuracyTracker::open(&path).unwrap();
+            tracker
+                .record(
+                    &signals("BTC", 0, &[("RSI (14)", SignalDirection::Buy)]),
+                    100.0,
+                    Horizon::OneHour,
+                )
+                .unwrap();
+        }
+        let tracker = AccuracyTracker::open(&path).unwrap();
+        assert_eq!(tracker.next_due.get("BTC"), Some(&HOUR));
+        drop(tracker);
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+}
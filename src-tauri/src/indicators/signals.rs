@@ -0,0 +1,399 @@
+//! Indicator scores and composites
+//!
+//! Each indicator's latest value is scored from -100 (strong sell) to +100
+//! (strong buy). Trend followers score price against the indicator,
+//! oscillators score contrarian around their midpoint, and volume
+//! indicators score the direction money is flowing. Category scores are
+//! the mean of their indicators, and the composite weights the categories
+//! by timeframe: momentum and volume lead for scalping, trend for position
+//! trades. Indicators without enough history are left out, along with
+//! volume-based ones when the candles carry no volume.
+
+use crate::haunt::models::{
+    OhlcPoint, SignalCategory, SignalDirection, SignalOutput, SymbolSignals, TradingTimeframe,
+};
+
+/// Candles OBV's change and ATR's expansion are measured over
+const LOOKBACK: usize = 14;
+
+/// Errors produced while computing signals
+#[derive(Debug, thiserror::Error)]
+pub enum SignalError {
+    #[error("Not enough price history to compute signals")]
+    NotEnoughHistory,
+}
+
+/// Direction for a score from -100 to +100
+pub fn direction(score: f64) -> SignalDirection {
+    if score >= 50.0 {
+        SignalDirection::StrongBuy
+    } else if score >= 15.0 {
+        SignalDirection::Buy
+    } else if score <= -50.0 {
+        SignalDirection::StrongSell
+    } else if score <= -15.0 {
+        SignalDirection::Sell
+    } else {
+        SignalDirection::Neutral
+    }
+}
+
+/// Category weights in the composite: trend, momentum, volatility, volume
+fn weights(timeframe: TradingTimeframe) -> [f64; 4] {
+    match timeframe {
+        TradingTimeframe::Scalping => [0.15, 0.40, 0.15, 0.30],
+        TradingTimeframe::DayTrading => [0.25, 0.35, 0.15, 0.25],
+        TradingTimeframe::SwingTrading => [0.35, 0.30, 0.15, 0.20],
+        TradingTimeframe::PositionTrading => [0.45, 0.25, 0.10, 0.20],
+    }
+}
+
+fn clamp(score: f64) -> f64 {
+    score.clamp(-100.0, 100.0)
+}
+
+/// Price above a moving average is bullish; 5% away scores the maximum
+fn score_average(close: f64, average: f64) -> f64 {
+    if average == 0.0 {
+        return 0.0;
+    }
+    clamp((close - average) / average * 2000.0)
+}
+
+/// Oscillator scored contrarian around its midpoint: `span` below reads
+/// +100 and `span` above -100
+fn score_oscillator(value: f64, mid: f64, span: f64) -> f64 {
+    clamp((mid - value) / span * 100.0)
+}
+
+/// Latest value of a series
+fn last(series: &[Option<f64>]) -> Option<f64> {
+    *series.last()?
+}
+
+/// Value `back` candles before the latest
+fn back(series: &[Option<f64>], back: usize) -> Option<f64> {
+    *series.get(series.len().checked_sub(back + 1)?)?
+}
+
+/// Compute the indicator set and composite scores for the latest candle
+pub fn compute(
+    symbol: &str,
+    timeframe: TradingTimeframe,
+    candles: &[OhlcPoint],
+    now: i64,
+) -> Result<SymbolSignals, SignalError> {
+    let closes: Vec<f64> = candles.iter().map(|c| c.close).collect();
+    let close = *closes.last().ok_or(SignalError::NotEnoughHistory)?;
+    let mut signals = Vec::new();
+    let mut push = |name: &str, category, value: f64, score: f64| {
+        if value.is_finite() && score.is_finite() {
+            signals.push(SignalOutput {
+                name: name.to_string(),
+                category,
+                value,
+                score,
+                direction: direction(score),
+                accuracy: None,
+                sample_size: None,
+                timestamp: now,
+            });
+        }
+    };
+
+    // Trend
+    for (name, series) in [
+        ("SMA (20)", super::sma(&closes, 20)),
+        ("SMA (50)", super::sma(&closes, 50)),
+        ("EMA (12)", super::ema(&closes, 12)),
+        ("EMA (26)", super::ema(&closes, 26)),
+    ] {
+        if let Some(average) = last(&series) {
+            push(
+                name,
+                SignalCategory::Trend,
+                average,
+                score_average(close, average),
+            );
+        }
+    }
+    let macd = super::macd(&closes, 12, 26, 9);
+    if let Some(histogram) = last(&macd.histogram) {
+        // A histogram of 1% of price scores the maximum
+        let score = if close == 0.0 {
+            0.0
+        } else {
+            clamp(histogram / close * 10_000.0)
+        };
+        push("MACD", SignalCategory::Trend, histogram, score);
+    }
+    let adx = super::adx(candles, 14);
+    if let (Some(strength), Some(plus), Some(minus)) =
+        (last(&adx.adx), last(&adx.plus_di), last(&adx.minus_di))
+    {
+        // The DI spread gives the direction, ADX how far to trust it; full
+        // weight from 25, the usual threshold for a trending market
+        let spread = if plus + minus > 0.0 {
+            (plus - minus) / (plus + minus)
+        } else {
+            0.0
+        };
+        let score = clamp(spread * (strength / 25.0).min(1.0) * 100.0);
+        push("ADX (14)", SignalCategory::Trend, strength, score);
+    }
+
+    // Momentum
+    if let Some(rsi) = last(&super::rsi(&closes, 14)) {
+        push(
+            "RSI (14)",
+            SignalCategory::Momentum,
+            rsi,
+            score_oscillator(rsi, 50.0, 40.0),
+        );
+    }
+    let stochastic = super::stochastic(candles, 14, 3);
+    if let Some(k) = last(&stochastic.k) {
+        push(
+            "Stochastic",
+            SignalCategory::Momentum,
+            k,
+            score_oscillator(k, 50.0, 50.0),
+        );
+    }
+    if let Some(cci) = last(&super::cci(candles, 20)) {
+        push(
+            "CCI (20)",
+            SignalCategory::Momentum,
+            cci,
+            score_oscillator(cci, 0.0, 200.0),
+        );
+    }
+    if let Some(mfi) = last(&super::mfi(candles, 14)) {
+        push(
+            "MFI (14)",
+            SignalCategory::Momentum,
+            mfi,
+            score_oscillator(mfi, 50.0, 40.0),
+        );
+    }
+
+    // Volatility
+    let bands = super::bollinger(&closes, 20, 2.0);
+    if let (Some(upper), Some(lower)) = (last(&bands.upper), last(&bands.lower)) {
+        // %B: 0 at the lower band, 1 at the upper
+        let percent_b = if upper > lower {
+            (close - lower) / (upper - lower)
+        } else {
+            0.5
+        };
+        push(
+            "Bollinger Bands",
+            SignalCategory::Volatility,
+            percent_b,
+            score_oscillator(percent_b, 0.5, 0.5),
+        );
+    }
+    let atr = super::atr(candles, 14);
+    if let (Some(current), Some(before)) = (last(&atr), back(&atr, LOOKBACK)) {
+        // Expanding ranges confirm the move they come with; contracting
+        // ones score neutral. Doubling the ATR scores the maximum.
+        let moved = close - closes[closes.len() - 1 - LOOKBACK];
+        let expansion = if before > 0.0 {
+            (current / before - 1.0).max(0.0)
+        } else {
+            0.0
+        };
+        let score = clamp(moved.signum() * expansion * 100.0);
+        push("ATR (14)", SignalCategory::Volatility, current, score);
+    }
+
+    // Volume
+    if let Some(obv) = super::obv(candles).filter(|obv| obv.len() > LOOKBACK) {
+        // Net volume over the lookback as a share of all volume traded
+        let recent = &candles[candles.len() - LOOKBACK..];
+        let traded: f64 = recent.iter().filter_map(|c| c.volume).sum();
+        let current = obv[obv.len() - 1];
+        let score = if traded > 0.0 {
+            clamp((current - obv[obv.len() - 1 - LOOKBACK]) / traded * 100.0)
+        } else {
+            0.0
+        };
+        push("OBV", SignalCategory::Volume, current, score);
+    }
+    if let Some(vwap) = last(&super::vwap(candles)) {
+        push(
+            "VWAP",
+            SignalCategory::Volume,
+            vwap,
+            score_average(close, vwap),
+        );
+    }
+
+    if signals.is_empty() {
+        return Err(SignalError::NotEnoughHistory);
+    }
+
+    let category_score = |category| {
+        let scores: Vec<f64> = signals
+            .iter()
+            .filter(|s| s.category == category)
+            .map(|s| s.score)
+            .collect();
+        (!scores.is_empty()).then(|| scores.iter().sum::<f64>() / scores.len() as f64)
+    };
+    let categories = [
+        category_score(SignalCategory::Trend),
+        category_score(SignalCategory::Momentum),
+        category_score(SignalCategory::Volatility),
+        category_score(SignalCategory::Volume),
+    ];
+    // Categories with no indicators drop out and the rest are reweighted
+    let (weighted, total) = categories
+        .iter()
+        .zip(weights(timeframe))
+        .filter_map(|(score, weight)| Some((score.as_ref()? * weight, weight)))
+        .fold((0.0, 0.0), |(sum, total), (score, weight)| {
+            (sum + score, total + weight)
+        });
+    let composite_score = weighted / total;
+    let [trend, momentum, volatility, volume] = categories.map(|s| s.unwrap_or(0.0));
+
+    Ok(SymbolSignals {
+        symbol: symbol.to_string(),
+        timeframe,
+        signals,
+        trend_score: trend,
+        momentum_score: momentum,
+        volatility_score: volatility,
+        volume_score: volume,
+        composite_score,
+        direction: direction(composite_score),
+        timestamp: now,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn series(closes: impl Iterator<Item = f64>, volume: Option<f64>) -> Vec<OhlcPoint> {
+        closes
+            .enumerate()
+            .map(|(i, close)| OhlcPoint {
+                time: i as i64 * 60_000,
+                open: close,
+                high: close * 1.01,
+                low: close * 0.99,
+                close,
+                volume,
+            })
+            .collect()
+    }
+
+    fn names(signals: &SymbolSignals) -> Vec<&str> {
+        signals.signals.iter().map(|s| s.name.as_str()).collect()
+    }
+
+    #[test]
+    fn full_history_yields_every_indicator() {
+        let candles = series(
+            (0..120).map(|i| 100.0 + (i as f64 * 0.2).sin() * 3.0),
+            Some(10.0),
+        );
+        let result = compute("BTC", TradingTimeframe::SwingTrading, &candles, 7).unwrap();
+        // The names the webview's indicator explanations are keyed by
+        assert_eq!(
+            names(&result),
+            [
+                "SMA (20)",
+                "SMA (50)",
+                "EMA (12)",
+                "EMA (26)",
+                "MACD",
+                "ADX (14)",
+                "RSI (14)",
+                "Stochastic",
+                "CCI (20)",
+                "MFI (14)",
+                "Bollinger Bands",
+                "ATR (14)",
+                "OBV",
+                "VWAP",
+            ]
+        );
+        assert!(result
+            .signals
+            .iter()
+            .all(|s| (-100.0..=100.0).contains(&s.score) && s.timestamp == 7));
+        assert_eq!(result.timeframe, TradingTimeframe::SwingTrading);
+        assert_eq!(result.symbol, "BTC");
+    }
+
+    #[test]
+    fn trend_direction() {
+        let up = series((0..80).map(|i| 100.0 * 1.01f64.powi(i)), Some(10.0));
+        let result = compute("BTC", TradingTimeframe::PositionTrading, &up, 0).unwrap();
+        assert!(result.trend_score > 50.0);
+        assert!(result.volume_score > 50.0);
+        // A steady climb leaves the oscillators overbought
+        assert!(result.momentum_score < 0.0);
+        assert!(result.composite_score > 15.0);
+        assert!(matches!(
+            result.direction,
+            SignalDirection::Buy | SignalDirection::StrongBuy
+        ));
+
+        let down = series((0..80).map(|i| 100.0 * 0.99f64.powi(i)), Some(10.0));
+        let result = compute("BTC", TradingTimeframe::PositionTrading, &down, 0).unwrap();
+        assert!(result.trend_score < -50.0);
+        assert!(result.composite_score < -15.0);
+    }
+
+    #[test]
+    fn timeframe_weights_shift_composite() {
+        let up = series((0..80).map(|i| 100.0 * 1.01f64.powi(i)), Some(10.0));
+        let scalping = compute("BTC", TradingTimeframe::Scalping, &up, 0).unwrap();
+        let position = compute("BTC", TradingTimeframe::PositionTrading, &up, 0).unwrap();
+        // Trend outweighs the overbought oscillators more for position trades
+        assert!(position.composite_score > scalping.composite_score);
+        assert_eq!(position.trend_score, scalping.trend_score);
+    }
+
+    #[test]
+    fn missing_volume_drops_volume_indicators() {
+        let candles = series((0..60).map(|i| 100.0 + i as f64), None);
+        let result = compute("AAPL", TradingTimeframe::DayTrading, &candles, 0).unwrap();
+        let names = names(&result);
+        assert!(!names.contains(&"OBV"));
+        assert!(!names.contains(&"VWAP"));
+        assert!(!names.contains(&"MFI (14)"));
+        assert_eq!(result.volume_score, 0.0);
+        assert!(result.composite_score.is_finite());
+    }
+
+    #[test]
+    fn short_history() {
+        let candles = series((0..25).map(|i| 100.0 + i as f64), Some(1.0));
+        let result = compute("BTC", TradingTimeframe::DayTrading, &candles, 0).unwrap();
+        let names = names(&result);
+        assert!(names.contains(&"SMA (20)"));
+        assert!(!names.contains(&"SMA (50)"));
+        assert!(!names.contains(&"MACD"));
+
+        assert!(matches!(
+            compute("BTC", TradingTimeframe::DayTrading, &[], 0),
+            Err(SignalError::NotEnoughHistory)
+        ));
+        let few = series((0..5).map(f64::from), None);
+        assert!(compute("BTC", TradingTimeframe::DayTrading, &few, 0).is_err());
+    }
+
+    #[test]
+    fn direction_thresholds() {
+        assert_eq!(direction(50.0), SignalDirection::StrongBuy);
+        assert_eq!(direction(15.0), SignalDirection::Buy);
+        assert_eq!(direction(0.0), SignalDirection::Neutral);
+        assert_eq!(direction(-15.0), SignalDirection::Sell);
+        assert_eq!(direction(-80.0), SignalDirection::StrongSell);
+    }
+}
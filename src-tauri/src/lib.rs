@@ -4,68 +4,48 @@
 //! It provides native functionality like system tray, notifications,
 //! auto-updates, and deep linking.
 
-use tauri::{
-    menu::{MenuBuilder, MenuItemBuilder},
-    tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
-    Manager,
-};
+mod alerts;
+mod backtest;
+mod cache;
+mod deep_link;
+mod haunt;
+mod identity;
+mod indicators;
+mod notifications;
+mod recorder;
+mod replay;
+mod scripting;
+mod trade_confirm;
+mod tray;
+mod updater;
+mod vault;
+mod wallet;
 
-/// System tray command handler
-fn handle_tray_menu_event(app: &tauri::AppHandle, id: &str) {
-    match id {
-        "show" => {
-            if let Some(window) = app.get_webview_window("main") {
-                let _ = window.show();
-                let _ = window.set_focus();
-            }
-        }
-        "hide" => {
-            if let Some(window) = app.get_webview_window("main") {
-                let _ = window.hide();
-            }
-        }
-        "quit" => {
-            app.exit(0);
-        }
-        _ => {}
+use rand_core::{OsRng, RngCore};
+use tauri::webview::PageLoadEvent;
+use tauri::Manager;
+
+/// Bring the main window to the front, restoring it from the tray or dock
+pub(crate) fn focus_main_window(app: &tauri::AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
     }
 }
 
-/// Initialize the system tray
-fn setup_system_tray(app: &tauri::AppHandle) -> Result<(), Box<dyn std::error::Error>> {
-    let show_item = MenuItemBuilder::with_id("show", "Show Wraith").build(app)?;
-    let hide_item = MenuItemBuilder::with_id("hide", "Hide").build(app)?;
-    let quit_item = MenuItemBuilder::with_id("quit", "Quit").build(app)?;
-
-    let menu = MenuBuilder::new(app)
-        .item(&show_item)
-        .item(&hide_item)
-        .separator()
-        .item(&quit_item)
-        .build()?;
-
-    let _tray = TrayIconBuilder::new()
-        .menu(&menu)
-        .on_menu_event(move |app, event| {
-            handle_tray_menu_event(app, event.id().as_ref());
-        })
-        .on_tray_icon_event(|tray, event| {
-            if let TrayIconEvent::Click {
-                button: MouseButton::Left,
-                button_state: MouseButtonState::Up,
-                ..
-            } = event
-            {
-                let app = tray.app_handle();
-                if let Some(window) = app.get_webview_window("main") {
-                    let _ = window.show();
-                    let _ = window.set_focus();
-                }
-            }
-        })
-        .build(app)?;
+/// Current time in Unix milliseconds
+pub(crate) fn now_millis() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as i64)
+}
 
-    Ok(())
+/// Random hex id for records kept by the app
+pub(crate) fn new_id() -> String {
+    let mut bytes = [0u8; 16];
+    OsRng.fill_bytes(&mut bytes);
+    hex::encode(bytes)
 }
 
 /// Tauri command: Get system information
@@ -78,81 +58,288 @@ fn get_system_info() -> serde_json::Value {
     })
 }
 
-/// Tauri command: Check for updates
-#[tauri::command]
-async fn check_for_updates(app: tauri::AppHandle) -> Result<bool, String> {
-    let updater = app.updater().map_err(|e| e.to_string())?;
-
-    match updater.check().await {
-        Ok(Some(_update)) => Ok(true),
-        Ok(None) => Ok(false),
-        Err(e) => Err(e.to_string()),
-    }
-}
-
-/// Tauri command: Install update and restart
-#[tauri::command]
-async fn install_update(app: tauri::AppHandle) -> Result<(), String> {
-    let updater = app.updater().map_err(|e| e.to_string())?;
-
-    if let Ok(Some(update)) = updater.check().await {
-        update
-            .download_and_install(|_chunk, _total| {}, || {})
-            .await
-            .map_err(|e| e.to_string())?;
-    }
-
-    Ok(())
-}
-
 /// Tauri command: Show notification
 #[tauri::command]
 fn show_notification(app: tauri::AppHandle, title: String, body: String) -> Result<(), String> {
-    use tauri_plugin_notification::NotificationExt;
-
-    app.notification()
-        .builder()
-        .title(&title)
-        .body(&body)
-        .show()
-        .map_err(|e| e.to_string())?;
-
+    notifications::show(&app, notifications::Notification::new(title, body))?;
     Ok(())
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    tauri::Builder::default()
+    let mut builder = tauri::Builder::default();
+    #[cfg(desktop)]
+    {
+        // Registered first so a second launch hands over its arguments and
+        // exits before anything else starts; links among them are routed by
+        // the deep-link plugin in this instance
+        builder = builder.plugin(tauri_plugin_single_instance::init(|app, _argv, _cwd| {
+            focus_main_window(app);
+        }));
+    }
+
+    builder
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_window_state::Builder::default().build())
         .plugin(tauri_plugin_notification::init())
         .plugin(tauri_plugin_updater::Builder::default().build())
         .plugin(tauri_plugin_deep_link::init())
+        .plugin(tauri_plugin_dialog::init())
+        .manage(deep_link::DeepLinkState::default())
+        .manage(vault::VaultState::default())
+        .manage(identity::IdentityState::default())
+        .manage(cache::CacheState::default())
+        .manage(haunt::HauntClient::default())
+        .manage(haunt::HauntSocket::default())
+        .manage(haunt::MeshState::default())
+        .manage(recorder::RecorderState::default())
+        .manage(replay::ReplayState::default())
+        .manage(alerts::AlertState::default())
+        .manage(notifications::NotificationState::default())
+        .manage(tray::TrayState::default())
+        .manage(trade_confirm::TradeConfirmState::default())
+        .manage(indicators::accuracy::AccuracyState::default())
+        .manage(scripting::ScriptState::default())
+        .manage(updater::UpdaterState::default())
+        .on_page_load(|webview, payload| {
+            if webview.label() == "main" && payload.event() == PageLoadEvent::Finished {
+                updater::webview_loaded(webview.app_handle());
+            }
+        })
         .setup(|app| {
+            // First, so a launch that fails anywhere after counts against
+            // a freshly installed update
+            if let Err(e) = updater::init(app.handle()) {
+                eprintln!("Failed to load updater settings: {}", e);
+            }
+            if let Err(e) = notifications::init(app.handle()) {
+                eprintln!("Failed to load notification history: {}", e);
+            }
+            if let Err(e) = vault::init(app.handle()) {
+                eprintln!("Failed to load key vault: {}", e);
+            }
+            if let Err(e) = cache::init(app.handle()) {
+                eprintln!("Failed to open market cache: {}", e);
+            }
+            if let Err(e) = haunt::mesh::init(app.handle()) {
+                eprintln!("Failed to load server settings: {}", e);
+            }
+            haunt::socket::init(app.handle());
+            if let Err(e) = recorder::init(app.handle()) {
+                eprintln!("Failed to start tick recorder: {}", e);
+            }
+            if let Err(e) = alerts::init(app.handle()) {
+                eprintln!("Failed to load alerts: {}", e);
+            }
+            if let Err(e) = indicators::accuracy::init(app.handle()) {
+                eprintln!("Failed to open signal accuracy store: {}", e);
+            }
+
             #[cfg(desktop)]
             {
                 // Setup system tray
-                if let Err(e) = setup_system_tray(app.handle()) {
+                if let Err(e) = tray::init(app.handle()) {
                     eprintln!("Failed to setup system tray: {}", e);
                 }
             }
 
-            // Handle deep links
-            let handle = app.handle().clone();
-            tauri_plugin_deep_link::register("wraith", move |request| {
-                // Handle deep link - emit event to frontend
-                let _ = handle.emit("deep-link", request);
-            })
-            .ok();
+            if let Err(e) = trade_confirm::init(app.handle()) {
+                eprintln!("Failed to open trade audit log: {}", e);
+            }
+            if let Err(e) = deep_link::init(app.handle()) {
+                eprintln!("Failed to read launch deep link: {}", e);
+            }
 
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             get_system_info,
-            check_for_updates,
-            install_update,
+            updater::check_for_updates,
+            updater::updater_get_settings,
+            updater::updater_set_settings,
+            updater::update_status,
+            updater::update_download,
+            updater::update_set_install_on_quit,
+            updater::install_update,
+            updater::update_history,
+            updater::update_health,
+            updater::update_rollback,
             show_notification,
+            deep_link::deep_link_parse,
+            deep_link::deep_link_launch_routes,
+            notifications::notification_show,
+            notifications::notification_center_history,
+            notifications::notification_center_get_settings,
+            notifications::notification_center_set_settings,
+            notifications::notification_center_mark_read,
+            notifications::notification_center_mark_all_read,
+            notifications::notification_center_clear,
+            notifications::notification_center_sync,
+            tray::tray_get_settings,
+            tray::tray_set_settings,
+            tray::tray_set_session,
+            trade_confirm::trade_confirm_audit,
+            vault::vault_status,
+            vault::vault_create,
+            vault::vault_unlock,
+            vault::vault_lock,
+            vault::vault_change_passphrase,
+            vault::vault_set_idle_timeout,
+            wallet::wallet_public_key,
+            wallet::wallet_derive_public_key,
+            wallet::wallet_sign,
+            wallet::wallet_sign_challenge,
+            wallet::wallet_verify,
+            identity::identity_list,
+            identity::identity_create,
+            identity::identity_import,
+            identity::identity_rename,
+            identity::identity_remove,
+            identity::identity_switch,
+            identity::identity_set_session,
+            identity::identity_get_session,
+            identity::identity_clear_session,
+            haunt::commands::haunt_base_url,
+            haunt::commands::haunt_set_base_url,
+            haunt::commands::haunt_get_listings,
+            haunt::commands::haunt_get_asset,
+            haunt::commands::haunt_get_quotes,
+            haunt::commands::haunt_search,
+            haunt::commands::haunt_get_global_metrics,
+            haunt::commands::haunt_get_fear_greed,
+            haunt::commands::haunt_get_chart,
+            haunt::commands::haunt_seed_symbol,
+            haunt::commands::haunt_get_symbol_source_stats,
+            haunt::commands::haunt_get_movers,
+            haunt::commands::haunt_get_stats,
+            haunt::commands::haunt_get_confidence,
+            haunt::commands::haunt_get_exchange_dominance,
+            haunt::commands::haunt_health,
+            haunt::commands::haunt_get_order_book,
+            haunt::commands::haunt_get_funding_rates,
+            haunt::commands::haunt_get_funding_history,
+            haunt::commands::haunt_get_signals,
+            haunt::commands::haunt_get_signal_accuracy,
+            haunt::commands::haunt_get_signal_predictions,
+            haunt::commands::haunt_get_indicator_accuracy,
+            haunt::commands::haunt_get_recommendation,
+            haunt::commands::haunt_generate_predictions,
+            haunt::commands::haunt_list_portfolios,
+            haunt::commands::haunt_get_portfolio,
+            haunt::commands::haunt_get_portfolio_summary,
+            haunt::commands::haunt_create_portfolio,
+            haunt::commands::haunt_reset_portfolio,
+            haunt::commands::haunt_get_holdings,
+            haunt::commands::haunt_get_performance,
+            haunt::commands::haunt_get_portfolio_settings,
+            haunt::commands::haunt_update_portfolio_settings,
+            haunt::commands::haunt_get_portfolio_stats,
+            haunt::commands::haunt_get_drawdown_history,
+            haunt::commands::haunt_get_positions,
+            haunt::commands::haunt_get_position,
+            haunt::commands::haunt_get_orders,
+            haunt::commands::haunt_get_trades,
+            haunt::commands::haunt_place_order,
+            haunt::commands::haunt_cancel_order,
+            haunt::commands::haunt_modify_order,
+            haunt::commands::haunt_cancel_all_orders,
+            haunt::commands::haunt_close_position,
+            haunt::commands::haunt_modify_position,
+            haunt::commands::haunt_add_margin,
+            haunt::commands::haunt_get_challenge,
+            haunt::commands::haunt_verify,
+            haunt::commands::haunt_get_me,
+            haunt::commands::haunt_update_profile,
+            haunt::commands::haunt_update_username,
+            haunt::commands::haunt_update_leaderboard_visibility,
+            haunt::commands::haunt_logout,
+            haunt::commands::haunt_get_peers,
+            haunt::commands::haunt_get_peer,
+            haunt::commands::haunt_get_sync_health,
+            haunt::commands::haunt_get_leaderboard,
+            haunt::commands::haunt_get_trader_stats,
+            haunt::commands::haunt_get_my_rank,
+            haunt::commands::haunt_get_alerts,
+            haunt::commands::haunt_create_alert,
+            haunt::commands::haunt_delete_alert,
+            haunt::commands::haunt_get_account_summary,
+            haunt::commands::haunt_get_transactions,
+            haunt::commands::haunt_get_trading_stats,
+            haunt::commands::haunt_get_trade_history,
+            haunt::commands::haunt_start_rat,
+            haunt::commands::haunt_stop_rat,
+            haunt::commands::haunt_get_rat_status,
+            haunt::commands::haunt_update_rat_config,
+            haunt::commands::haunt_get_grid_config,
+            haunt::commands::haunt_get_grid_state,
+            haunt::commands::haunt_get_grid_positions,
+            haunt::commands::haunt_get_grid_stats,
+            haunt::commands::haunt_place_grid_trade,
+            haunt::commands::haunt_get_notifications,
+            haunt::commands::haunt_mark_all_notifications_read,
+            haunt::commands::haunt_mark_notifications_read,
+            haunt::commands::haunt_clear_notifications,
+            haunt::socket::haunt_ws_status,
+            haunt::socket::haunt_ws_subscribe,
+            haunt::socket::haunt_ws_unsubscribe,
+            haunt::socket::haunt_ws_set_throttle,
+            haunt::socket::haunt_ws_set_peers,
+            haunt::socket::haunt_ws_set_portfolio,
+            haunt::socket::haunt_ws_subscribe_gridline,
+            haunt::socket::haunt_ws_unsubscribe_gridline,
+            haunt::socket::haunt_ws_set_url,
+            haunt::socket::haunt_ws_reconnect,
+            haunt::mesh::mesh_status,
+            haunt::mesh::mesh_set_active,
+            haunt::mesh::mesh_set_auto_fastest,
+            haunt::mesh::mesh_refresh,
+            haunt::mesh::mesh_discover,
+            cache::cache_get_listings,
+            cache::cache_get_chart,
+            cache::cache_store_listings,
+            cache::cache_store_chart,
+            cache::cache_clear,
+            recorder::recorder_get_settings,
+            recorder::recorder_set_settings,
+            recorder::recorder_list_recordings,
+            recorder::recorder_delete_recording,
+            replay::replay_status,
+            replay::replay_start,
+            replay::replay_pause,
+            replay::replay_resume,
+            replay::replay_seek,
+            replay::replay_step,
+            replay::replay_set_speed,
+            replay::replay_stop,
+            alerts::local_alerts_list,
+            alerts::local_alerts_create,
+            alerts::local_alerts_delete,
+            alerts::local_alerts_set_enabled,
+            alerts::local_alerts_check_rule,
+            alerts::local_alerts_rule_fields,
+            indicators::indicators_compute,
+            indicators::indicators_compute_for_asset,
+            indicators::accuracy::local_signal_accuracy,
+            indicators::accuracy::local_indicator_accuracy,
+            indicators::accuracy::local_symbol_accuracy,
+            indicators::accuracy::local_signal_predictions,
+            indicators::accuracy::local_signal_accuracy_get_settings,
+            indicators::accuracy::local_signal_accuracy_set_settings,
+            indicators::accuracy::local_signal_accuracy_clear,
+            backtest::backtest_run,
+            backtest::backtest_run_for_asset,
+            scripting::script_check,
+            scripting::script_backtest,
+            scripting::script_backtest_for_asset,
+            scripting::script_start_live,
+            scripting::script_stop,
+            scripting::script_runs,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running Wraith desktop application");
+        .build(tauri::generate_context!())
+        .expect("error while building Wraith desktop application")
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                updater::install_on_quit(app);
+            }
+        });
 }
@@ -0,0 +1,562 @@
+//! Notification center
+//!
+//! Every notification passes through the center before it reaches the
+//! desktop. It keeps a history of what was delivered, folds bursts of one
+//! category into a single summary, caps how many notifications are shown
+//! per minute and holds everything but critical notifications back during
+//! do-not-disturb and quiet hours. Entries synced from `/api/notifications`
+//! keep their server id so read state can flow both ways.
+
+use std::collections::{BTreeMap, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+use super::{Notification, NotificationCategory, NotificationPriority};
+use crate::haunt::models::BackendNotification;
+use crate::new_id;
+
+const MINUTES_PER_DAY: u16 = 24 * 60;
+const RATE_WINDOW_MS: i64 = 60_000;
+/// Titles listed in a summary body before it says "and N more"
+const SUMMARY_LINES: usize = 3;
+
+/// Local time range during which only critical notifications are shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuietHours {
+    /// Minutes after local midnight
+    pub start: u16,
+    /// Minutes after local midnight; before `start` to wrap past midnight
+    pub end: u16,
+}
+
+impl QuietHours {
+    fn contains(self, minute: u16) -> bool {
+        if self.start <= self.end {
+            (self.start..self.end).contains(&minute)
+        } else {
+            minute >= self.start || minute < self.end
+        }
+    }
+}
+
+/// Notification center settings, persisted to `notifications.json`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CenterSettings {
+    /// Hold back everything but critical notifications
+    pub do_not_disturb: bool,
+    pub quiet_hours: Option<QuietHours>,
+    /// Notifications arriving this soon after one of the same category was
+    /// shown are folded into a summary
+    pub group_window_secs: u32,
+    /// Most notifications shown per minute, summaries included
+    pub max_per_minute: u32,
+    /// Entries kept in history
+    pub history_limit: usize,
+}
+
+impl Default for CenterSettings {
+    fn default() -> Self {
+        Self {
+            do_not_disturb: false,
+            quiet_hours: None,
+            group_window_secs: 30,
+            max_per_minute: 6,
+            history_limit: 500,
+        }
+    }
+}
+
+impl CenterSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(quiet) = self.quiet_hours {
+            if quiet.start >= MINUTES_PER_DAY || quiet.end >= MINUTES_PER_DAY {
+                return Err("Quiet hours must be minutes within a day".to_string());
+            }
+        }
+        if self.max_per_minute == 0 {
+            return Err("At least one notification per minute must be allowed".to_string());
+        }
+        if self.history_limit == 0 {
+            return Err("History must keep at least one notification".to_string());
+        }
+        Ok(())
+    }
+
+    /// Whether non-critical notifications are held back at `minute` after
+    /// local midnight
+    pub fn is_quiet(&self, minute: u16) -> bool {
+        self.do_not_disturb || self.quiet_hours.is_some_and(|quiet| quiet.contains(minute))
+    }
+
+    fn group_window_ms(&self) -> i64 {
+        i64::from(self.group_window_secs) * 1000
+    }
+}
+
+/// How a history entry reached the user
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Delivery {
+    /// Shown on the desktop
+    Shown,
+    /// Held back by a burst or the rate limit and folded into a summary
+    Grouped,
+    /// Held back by do-not-disturb or quiet hours
+    Quiet,
+    /// Synced from the server; never shown by this app
+    Remote,
+}
+
+/// A delivered notification
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEntry {
+    pub id: String,
+    pub title: String,
+    pub body: String,
+    pub category: NotificationCategory,
+    pub priority: NotificationPriority,
+    pub target: Option<String>,
+    pub created_at: i64,
+    pub read: bool,
+    pub delivery: Delivery,
+    /// Server id for entries synced from `/api/notifications`
+    pub remote_id: Option<String>,
+}
+
+/// Burst tracking for one category
+#[derive(Debug, Default)]
+struct Group {
+    last_shown: Option<i64>,
+    held: Vec<(String, Notification)>,
+}
+
+/// History, grouping, rate limiting and do-not-disturb
+#[derive(Debug, Default)]
+pub struct NotificationCenter {
+    settings: CenterSettings,
+    /// Oldest first
+    history: Vec<HistoryEntry>,
+    groups: BTreeMap<NotificationCategory, Group>,
+    /// When notifications were shown within the last minute
+    shown: VecDeque<i64>,
+}
+
+impl NotificationCenter {
+    pub fn load(&mut self, settings: CenterSettings, history: Vec<HistoryEntry>) {
+        self.settings = settings;
+        self.history = history;
+        self.trim();
+    }
+
+    pub fn settings(&self) -> &CenterSettings {
+        &self.settings
+    }
+
+    pub fn set_settings(&mut self, settings: CenterSettings) -> Result<(), String> {
+        settings.validate()?;
+        self.settings = settings;
+        self.trim();
+        Ok(())
+    }
+
+    pub fn history(&self) -> &[HistoryEntry] {
+        &self.history
+    }
+
+    pub fn unread(&self) -> usize {
+        self.history.iter().filter(|entry| !entry.read).count()
+    }
+
+    /// Record a notification and decide whether to show it now; `minute`
+    /// is the local time of day used for quiet hours
+    pub fn submit(&mut self, id: &str, notification: &Notification, now: i64, minute: u16) -> bool {
+        let category = notification.category;
+        let delivery = if notification.priority == NotificationPriority::Critical {
+            Delivery::Shown
+        } else if self.settings.is_quiet(minute) {
+            Delivery::Quiet
+        } else {
+            let limited = self.rate_limited(now);
+            let window = self.settings.group_window_ms();
+            let group = self.groups.entry(category).or_default();
+            let burst = group.last_shown.is_some_and(|at| now - at < window);
+            if burst || limited || !group.held.is_empty() {
+                group.held.push((id.to_string(), notification.clone()));
+                Delivery::Grouped
+            } else {
+                Delivery::Shown
+            }
+        };
+        if delivery == Delivery::Shown {
+            self.mark_shown(category, now);
+        }
+
+        self.history.push(HistoryEntry {
+            id: id.to_string(),
+            title: notification.title.clone(),
+            body: notification.body.clone(),
+            category,
+            priority: notification.priority,
+            target: notification.target.clone(),
+            created_at: now,
+            read: false,
+            delivery,
+            remote_id: None,
+        });
+        self.trim();
+        delivery == Delivery::Shown
+    }
+
+    /// Release held notifications whose category has been calm for the
+    /// group window, as one summary per category, within the rate limit
+    pub fn flush(&mut self, now: i64) -> Vec<(String, Notification)> {
+        let window = self.settings.group_window_ms();
+        let due: Vec<NotificationCategory> = self
+            .groups
+            .iter()
+            .filter(|(_, group)| {
+                !group.held.is_empty() && !group.last_shown.is_some_and(|at| now - at < window)
+            })
+            .map(|(category, _)| *category)
+            .collect();
+
+        let mut released = Vec::new();
+        for category in due {
+            if self.rate_limited(now) {
+                break;
+            }
+            let held = std::mem::take(&mut self.groups.entry(category).or_default().held);
+            released.push(summarize(category, held));
+            self.mark_shown(category, now);
+        }
+        released
+    }
+
+    /// Mark entries read; returns the server ids among them
+    pub fn mark_read(&mut self, ids: &[String]) -> Vec<String> {
+        let mut remote = Vec::new();
+        for entry in &mut self.history {
+            if ids.contains(&entry.id) && !entry.read {
+                entry.read = true;
+                remote.extend(entry.remote_id.clone());
+            }
+        }
+        remote
+    }
+
+    pub fn mark_all_read(&mut self) {
+        for entry in &mut self.history {
+            entry.read = true;
+        }
+    }
+
+    /// Drop the history and anything still held back
+    pub fn clear(&mut self) {
+        self.history.clear();
+        for group in self.groups.values_mut() {
+            group.held.clear();
+        }
+    }
+
+    /// Merge a page of server notifications into history. Read state wins
+    /// on either side; returns the server ids read here but not there.
+    pub fn merge_remote(&mut self, remote: &[BackendNotification]) -> Vec<String> {
+        let mut unsynced = Vec::new();
+        for notification in remote {
+            let existing = self
+                .history
+                .iter_mut()
+                .find(|entry| entry.remote_id.as_deref() == Some(notification.id.as_str()));
+            match existing {
+                Some(entry) if notification.read => entry.read = true,
+                Some(entry) if entry.read => unsynced.push(notification.id.clone()),
+                Some(_) => {}
+                None => self.history.push(HistoryEntry {
+                    id: notification.id.clone(),
+                    title: notification.title.clone(),
+                    body: notification.message.clone().unwrap_or_default(),
+                    category: NotificationCategory::System,
+                    priority: NotificationPriority::Normal,
+                    target: None,
+                    created_at: notification.timestamp,
+                    read: notification.read,
+                    delivery: Delivery::Remote,
+                    remote_id: Some(notification.id.clone()),
+                }),
+            }
+        }
+        self.history.sort_by_key(|entry| entry.created_at);
+        self.trim();
+        unsynced
+    }
+
+    fn rate_limited(&mut self, now: i64) -> bool {
+        while self
+            .shown
+            .front()
+            .is_some_and(|at| now - at >= RATE_WINDOW_MS)
+        {
+            self.shown.pop_front();
+        }
+        self.shown.len() >= self.settings.max_per_minute as usize
+    }
+
+    fn mark_shown(&mut self, category: NotificationCategory, now: i64) {
+        self.shown.push_back(now);
+        self.groups.entry(category).or_default().last_shown = Some(now);
+    }
+
+    fn trim(&mut self) {
+        let excess = self
+            .history
+            .len()
+            .saturating_sub(self.settings.history_limit);
+        self.history.drain(..excess);
+    }
+}
+
+/// One notification standing in for everything held in a category; a
+/// single held notification is released as it was
+fn summarize(
+    category: NotificationCategory,
+    mut held: Vec<(String, Notification)>,
+) -> (String, Notification) {
+    if held.len() == 1 {
+        return held.remove(0);
+    }
+
+    let first_target = held[0].1.target.clone();
+    let target = first_target.filter(|target| {
+        held.iter()
+            .all(|(_, n)| n.target.as_deref() == Some(target.as_str()))
+    });
+    let titles: Vec<&str> = held
+        .iter()
+        .take(SUMMARY_LINES)
+        .map(|(_, n)| n.title.as_str())
+        .collect();
+    let mut body = titles.join("\n");
+    if held.len() > SUMMARY_LINES {
+        body.push_str(&format!("\n…and {} more", held.len() - SUMMARY_LINES));
+    }
+
+    let summary = Notification {
+        category,
+        target,
+        ..Notification::new(format!("{} {}", held.len(), category.plural()), body)
+    };
+    (new_id(), summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOON: u16 = 12 * 60;
+
+    fn alert(title: &str) -> Notification {
+        Notification {
+            category: NotificationCategory::Alert,
+            target: Some("wraith://asset/BTC".to_string()),
+            ..Notification::new(title, "body")
+        }
+    }
+
+    fn with_settings(settings: CenterSettings) -> NotificationCenter {
+        let mut center = NotificationCenter::default();
+        center.load(settings, Vec::new());
+        center
+    }
+
+    fn remote(id: &str, read: bool, timestamp: i64) -> BackendNotification {
+        BackendNotification {
+            id: id.to_string(),
+            user_id: "user".to_string(),
+            kind: "info".to_string(),
+            title: format!("Server {}", id),
+            message: Some("message".to_string()),
+            read,
+            timestamp,
+        }
+    }
+
+    #[test]
+    fn groups_bursts_by_category() {
+        let mut center = with_settings(CenterSettings::default());
+
+        assert!(center.submit("a", &alert("BTC above 70k"), 0, NOON));
+        assert!(!center.submit("b", &alert("BTC above 71k"), 1_000, NOON));
+        assert!(!center.submit("c", &alert("BTC above 72k"), 2_000, NOON));
+        // Another category isn't part of the burst
+        let fill = Notification {
+            category: NotificationCategory::Trade,
+            ..Notification::new("Order filled", "body")
+        };
+        assert!(center.submit("d", &fill, 3_000, NOON));
+
+        assert!(center.flush(29_999).is_empty());
+        let released = center.flush(30_000);
+        assert_eq!(released.len(), 1);
+        let (_, summary) = &released[0];
+        assert_eq!(summary.title, "2 alerts");
+        assert_eq!(summary.body, "BTC above 71k\nBTC above 72k");
+        assert_eq!(summary.target.as_deref(), Some("wraith://asset/BTC"));
+
+        let deliveries: Vec<Delivery> = center.history().iter().map(|e| e.delivery).collect();
+        assert_eq!(
+            deliveries,
+            [
+                Delivery::Shown,
+                Delivery::Grouped,
+                Delivery::Grouped,
+                Delivery::Shown
+            ]
+        );
+        assert_eq!(center.unread(), 4);
+    }
+
+    #[test]
+    fn single_held_notification_is_released_unchanged() {
+        let mut center = with_settings(CenterSettings::default());
+        center.submit("a", &alert("first"), 0, NOON);
+        center.submit("b", &alert("second"), 1_000, NOON);
+
+        let released = center.flush(30_000);
+        assert_eq!(released, [("b".to_string(), alert("second"))]);
+        // The release restarts the window
+        assert!(!center.submit("c", &alert("third"), 31_000, NOON));
+    }
+
+    #[test]
+    fn rate_limits_floods_across_categories() {
+        let mut center = with_settings(CenterSettings {
+            max_per_minute: 2,
+            ..CenterSettings::default()
+        });
+        let categories = [
+            NotificationCategory::Alert,
+            NotificationCategory::Trade,
+            NotificationCategory::Risk,
+        ];
+        let shown: Vec<bool> = categories
+            .iter()
+            .enumerate()
+            .map(|(i, category)| {
+                let notification = Notification {
+                    category: *category,
+                    ..Notification::new("t", "b")
+                };
+                center.submit(&i.to_string(), &notification, i as i64, NOON)
+            })
+            .collect();
+        assert_eq!(shown, [true, true, false]);
+
+        // Held until a slot frees up, even after the group window
+        assert!(center.flush(59_999).is_empty());
+        assert_eq!(center.flush(60_000).len(), 1);
+    }
+
+    #[test]
+    fn quiet_hours_let_critical_through() {
+        let mut center = with_settings(CenterSettings {
+            quiet_hours: Some(QuietHours {
+                start: 22 * 60,
+                end: 7 * 60,
+            }),
+            ..CenterSettings::default()
+        });
+        let liquidation = Notification {
+            category: NotificationCategory::Risk,
+            priority: NotificationPriority::Critical,
+            ..Notification::new("Liquidation warning", "body")
+        };
+
+        assert!(!center.submit("a", &alert("quiet"), 0, 23 * 60));
+        assert!(!center.submit("b", &alert("quiet"), 0, 6 * 60 + 59));
+        assert!(center.submit("c", &liquidation, 0, 3 * 60));
+        // Critical notifications skip grouping too
+        assert!(center.submit("d", &liquidation, 1, 3 * 60));
+        assert_eq!(center.history()[0].delivery, Delivery::Quiet);
+        assert!(center.flush(60_000).is_empty());
+
+        assert!(center.submit("e", &alert("awake"), 0, 7 * 60));
+
+        let mut dnd = with_settings(CenterSettings {
+            do_not_disturb: true,
+            ..CenterSettings::default()
+        });
+        assert!(!dnd.submit("a", &alert("quiet"), 0, NOON));
+    }
+
+    #[test]
+    fn validates_settings() {
+        assert!(CenterSettings::default().validate().is_ok());
+        let invalid = [
+            CenterSettings {
+                quiet_hours: Some(QuietHours {
+                    start: 0,
+                    end: 1440,
+                }),
+                ..CenterSettings::default()
+            },
+            CenterSettings {
+                max_per_minute: 0,
+                ..CenterSettings::default()
+            },
+            CenterSettings {
+                history_limit: 0,
+                ..CenterSettings::default()
+            },
+        ];
+        for settings in invalid {
+            assert!(settings.validate().is_err());
+        }
+    }
+
+    #[test]
+    fn caps_history() {
+        let mut center = with_settings(CenterSettings {
+            history_limit: 2,
+            do_not_disturb: true,
+            ..CenterSettings::default()
+        });
+        for id in ["a", "b", "c"] {
+            center.submit(id, &alert(id), 0, NOON);
+        }
+        let ids: Vec<&str> = center.history().iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, ["b", "c"]);
+    }
+
+    #[test]
+    fn syncs_read_state_with_the_server() {
+        let mut center = with_settings(CenterSettings::default());
+        center.submit("local", &alert("local"), 500, NOON);
+
+        let unsynced = center.merge_remote(&[remote("r1", false, 100), remote("r2", true, 900)]);
+        assert!(unsynced.is_empty());
+        let ids: Vec<&str> = center.history().iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, ["r1", "local", "r2"]);
+        assert_eq!(center.unread(), 2);
+
+        assert_eq!(
+            center.mark_read(&["r1".to_string(), "local".to_string()]),
+            ["r1"]
+        );
+        assert!(center.mark_read(&["r1".to_string()]).is_empty());
+
+        // Still unread on the server, so it needs pushing; a server-side read
+        // of something unread here is taken as is
+        center
+            .history
+            .iter_mut()
+            .find(|e| e.id == "r2")
+            .unwrap()
+            .read = false;
+        let unsynced = center.merge_remote(&[remote("r1", false, 100), remote("r2", true, 900)]);
+        assert_eq!(unsynced, ["r1"]);
+        assert_eq!(center.unread(), 0);
+        assert_eq!(center.history().len(), 3);
+    }
+}
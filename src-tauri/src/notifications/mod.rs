@@ -0,0 +1,623 @@
+//! Actionable desktop notifications
+//!
+//! Notifications with an icon, a category, a `wraith://` click target and
+//! action buttons. On Linux they go straight to the freedesktop
+//! notification service, which reports clicks and button presses back:
+//! a click focuses the main window and routes the target like any other
+//! deep link, and a button press is emitted as [`ACTION_EVENT`]. Other
+//! platforms show the same title, body and icon through the notification
+//! plugin, which cannot report interactions on desktop.
+//!
+//! Everything shown goes through the [`center`] first, which records it in
+//! history and decides whether it reaches the desktop now, later as part
+//! of a summary, or not at all.
+
+mod center;
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::Timelike;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::deep_link::{self, DeepLink};
+use crate::haunt::HauntClient;
+use crate::{new_id, now_millis};
+pub use center::{CenterSettings, HistoryEntry, NotificationCenter};
+
+/// Event carrying a [`NotificationAction`] when a button is pressed
+pub const ACTION_EVENT: &str = "notification-action";
+/// Event carrying a [`NotificationAction`] when a notification is clicked
+pub const CLICKED_EVENT: &str = "notification-clicked";
+/// Event carrying the unread count whenever the history changes
+pub const HISTORY_EVENT: &str = "notification-history-changed";
+
+const SETTINGS_FILE: &str = "notifications.json";
+const HISTORY_FILE: &str = "notification-history.json";
+/// How often held notifications are checked for release
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+/// Server notifications fetched per sync
+const SYNC_PAGE_SIZE: u32 = 100;
+
+/// Most buttons a notification can carry; servers drop the rest
+const MAX_ACTIONS: usize = 3;
+/// Whether this platform reports clicks and button presses back
+const INTERACTIVE: bool = cfg!(all(unix, not(target_os = "macos")));
+
+/// Freedesktop action key for clicking the notification body
+#[cfg(all(unix, not(target_os = "macos")))]
+const DEFAULT_ACTION: &str = "default";
+/// notify-rust's key for a notification closed without an action
+#[cfg(all(unix, not(target_os = "macos")))]
+const CLOSED_ACTION: &str = "__closed";
+
+/// What a notification is about
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationCategory {
+    Alert,
+    /// Fills and other order updates
+    Trade,
+    /// Liquidation and margin warnings
+    Risk,
+    Update,
+    #[default]
+    System,
+}
+
+impl NotificationCategory {
+    /// Vendor category for the freedesktop `category` hint
+    #[cfg(all(unix, not(target_os = "macos")))]
+    fn freedesktop(self) -> &'static str {
+        match self {
+            Self::Alert => "x-wraith.alert",
+            Self::Trade => "x-wraith.trade",
+            Self::Risk => "x-wraith.risk",
+            Self::Update => "x-wraith.update",
+            Self::System => "x-wraith.system",
+        }
+    }
+
+    /// Noun for a summary title such as "3 alerts"
+    fn plural(self) -> &'static str {
+        match self {
+            Self::Alert => "alerts",
+            Self::Trade => "trade updates",
+            Self::Risk => "risk warnings",
+            Self::Update => "updates",
+            Self::System => "notifications",
+        }
+    }
+}
+
+/// Critical notifications bypass do-not-disturb, grouping and the rate limit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationPriority {
+    #[default]
+    Normal,
+    Critical,
+}
+
+/// A button on a notification
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NotificationButton {
+    /// Returned in [`NotificationAction::action`]
+    pub id: String,
+    pub label: String,
+}
+
+/// A notification to show
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Notification {
+    pub title: String,
+    pub body: String,
+    /// Icon name or image path
+    pub icon: Option<String>,
+    #[serde(default)]
+    pub category: NotificationCategory,
+    #[serde(default)]
+    pub priority: NotificationPriority,
+    /// `wraith://` link opened when the notification is clicked
+    pub target: Option<String>,
+    #[serde(default)]
+    pub actions: Vec<NotificationButton>,
+}
+
+/// Returned by [`notification_show`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShownNotification {
+    pub id: String,
+    /// Whether clicks and button presses will be reported; when false the
+    /// notification's target and actions are not shown
+    pub interactive: bool,
+}
+
+/// Payload of [`ACTION_EVENT`] and [`CLICKED_EVENT`]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationAction {
+    /// Id returned when the notification was shown
+    pub notification_id: String,
+    /// Button id; absent for a click
+    pub action: Option<String>,
+    pub category: NotificationCategory,
+    pub target: Option<String>,
+}
+
+impl Notification {
+    /// A plain notification with just a title and body
+    pub fn new(title: impl Into<String>, body: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            body: body.into(),
+            ..Default::default()
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.title.trim().is_empty() {
+            return Err("Notification title is required".to_string());
+        }
+        if let Some(target) = &self.target {
+            DeepLink::parse(target).map_err(|e| format!("Invalid notification target: {}", e))?;
+        }
+        if self.actions.len() > MAX_ACTIONS {
+            return Err(format!(
+                "Notifications can have at most {} actions",
+                MAX_ACTIONS
+            ));
+        }
+        for (i, action) in self.actions.iter().enumerate() {
+            if action.id.trim().is_empty() || action.label.trim().is_empty() {
+                return Err("Notification actions need an id and a label".to_string());
+            }
+            if matches!(action.id.as_str(), "default" | "__closed") {
+                return Err(format!(
+                    "Notification action id \"{}\" is reserved",
+                    action.id
+                ));
+            }
+            if self.actions[..i].iter().any(|a| a.id == action.id) {
+                return Err(format!("Duplicate notification action \"{}\"", action.id));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether anything is listening for the user's response
+    #[cfg(all(unix, not(target_os = "macos")))]
+    fn interactive(&self) -> bool {
+        self.target.is_some() || !self.actions.is_empty()
+    }
+}
+
+/// How the user responded to a notification
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(not(all(unix, not(target_os = "macos"))), allow(dead_code))]
+enum Response<'a> {
+    Clicked,
+    Action(&'a str),
+    Closed,
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn parse_response(action: &str) -> Response<'_> {
+    match action {
+        DEFAULT_ACTION => Response::Clicked,
+        CLOSED_ACTION => Response::Closed,
+        action => Response::Action(action),
+    }
+}
+
+#[cfg_attr(not(all(unix, not(target_os = "macos"))), allow(dead_code))]
+fn respond(app: &AppHandle, id: &str, notification: &Notification, response: Response<'_>) {
+    let payload = |action: Option<&str>| NotificationAction {
+        notification_id: id.to_string(),
+        action: action.map(str::to_string),
+        category: notification.category,
+        target: notification.target.clone(),
+    };
+    match response {
+        Response::Clicked => {
+            let state = app.state::<NotificationState>();
+            state.center.lock().unwrap().mark_read(&[id.to_string()]);
+            state.changed(app);
+            crate::focus_main_window(app);
+            if let Some(target) = &notification.target {
+                deep_link::route(app, target);
+            }
+            let _ = app.emit(CLICKED_EVENT, payload(None));
+        }
+        Response::Action(action) => {
+            let _ = app.emit(ACTION_EVENT, payload(Some(action)));
+        }
+        Response::Closed => {}
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn show_native(app: &AppHandle, id: String, notification: Notification) -> Result<(), String> {
+    use notify_rust::Hint;
+
+    let mut native = notify_rust::Notification::new();
+    native
+        .appname(&app.package_info().name)
+        .summary(&notification.title)
+        .body(&notification.body)
+        .hint(Hint::Category(
+            notification.category.freedesktop().to_string(),
+        ))
+        .hint(Hint::DesktopEntry(app.config().identifier.clone()));
+    if notification.priority == NotificationPriority::Critical {
+        native.urgency(notify_rust::Urgency::Critical);
+    }
+    if let Some(icon) = &notification.icon {
+        native.icon(icon);
+    }
+    if notification.target.is_some() {
+        native.action(DEFAULT_ACTION, "Open");
+    }
+    for action in &notification.actions {
+        native.action(&action.id, &action.label);
+    }
+
+    let handle = native.show().map_err(|e| e.to_string())?;
+    if notification.interactive() {
+        // Blocks until the notification is acted on or closed
+        let app = app.clone();
+        std::thread::spawn(move || {
+            handle.wait_for_action(|action| {
+                respond(&app, &id, &notification, parse_response(action));
+            });
+        });
+    }
+    Ok(())
+}
+
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+fn show_native(app: &AppHandle, _id: String, notification: Notification) -> Result<(), String> {
+    use tauri_plugin_notification::NotificationExt;
+
+    let mut builder = app
+        .notification()
+        .builder()
+        .title(&notification.title)
+        .body(&notification.body);
+    if let Some(icon) = notification.icon {
+        builder = builder.icon(icon);
+    }
+    builder.show().map_err(|e| e.to_string())
+}
+
+/// Minutes after local midnight, for quiet hours
+fn local_minute() -> u16 {
+    let now = chrono::Local::now();
+    (now.hour() * 60 + now.minute()) as u16
+}
+
+/// Managed state for the notification center
+#[derive(Default)]
+pub struct NotificationState {
+    center: Mutex<NotificationCenter>,
+    data_dir: Mutex<Option<PathBuf>>,
+}
+
+impl NotificationState {
+    fn save(&self, file: &str, value: &impl Serialize) {
+        let Some(dir) = self.data_dir.lock().unwrap().clone() else {
+            return;
+        };
+        let written = fs::create_dir_all(&dir).and_then(|_| {
+            let json = serde_json::to_vec_pretty(value)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+            fs::write(dir.join(file), json)
+        });
+        if let Err(e) = written {
+            eprintln!("Failed to save {}: {}", file, e);
+        }
+    }
+
+    /// Persist the history and send the frontend the new unread count
+    fn changed(&self, app: &AppHandle) {
+        let (history, unread) = {
+            let center = self.center.lock().unwrap();
+            (center.history().to_vec(), center.unread())
+        };
+        self.save(HISTORY_FILE, &history);
+        let _ = app.emit(HISTORY_EVENT, unread);
+    }
+}
+
+fn read_json<T: serde::de::DeserializeOwned + Default>(
+    path: &std::path::Path,
+) -> Result<T, Box<dyn std::error::Error>> {
+    match fs::read(path) {
+        Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(T::default()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Start releasing held notifications and load history and settings
+pub fn init(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+    let handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(FLUSH_INTERVAL);
+        loop {
+            interval.tick().await;
+            let released = handle
+                .state::<NotificationState>()
+                .center
+                .lock()
+                .unwrap()
+                .flush(now_millis());
+            for (id, notification) in released {
+                if let Err(e) = show_native(&handle, id, notification) {
+                    eprintln!("Failed to show notification: {}", e);
+                }
+            }
+        }
+    });
+
+    let data_dir = app.path().app_data_dir()?;
+    let settings = read_json::<CenterSettings>(&data_dir.join(SETTINGS_FILE))
+        .and_then(|settings| {
+            settings.validate()?;
+            Ok(settings)
+        })
+        .unwrap_or_else(|e| {
+            eprintln!("Failed to load notification settings: {}", e);
+            CenterSettings::default()
+        });
+    let history = read_json(&data_dir.join(HISTORY_FILE)).unwrap_or_else(|e| {
+        eprintln!("Failed to load notification history: {}", e);
+        Default::default()
+    });
+
+    let state = app.state::<NotificationState>();
+    state.center.lock().unwrap().load(settings, history);
+    *state.data_dir.lock().unwrap() = Some(data_dir);
+    Ok(())
+}
+
+/// Show a notification; works while the main window is hidden. It is
+/// recorded in history and may be held back by the notification center.
+/// Returns the id reported back in interaction events.
+pub fn show(app: &AppHandle, notification: Notification) -> Result<String, String> {
+    notification.validate()?;
+    let id = new_id();
+    let state = app.state::<NotificationState>();
+    let visible =
+        state
+            .center
+            .lock()
+            .unwrap()
+            .submit(&id, &notification, now_millis(), local_minute());
+    state.changed(app);
+    if visible {
+        show_native(app, id.clone(), notification)?;
+    }
+    Ok(id)
+}
+
+// ========== Commands ==========
+
+/// Tauri command: Show a notification with an icon, category, click target
+/// and action buttons; returns its id
+///
+/// Only Linux reports interactions back. On macOS and Windows the click
+/// target and buttons are dropped, which the returned `interactive` flag
+/// signals so callers can offer another route to the same action.
+#[tauri::command]
+pub fn notification_show(
+    app: AppHandle,
+    notification: Notification,
+) -> Result<ShownNotification, String> {
+    let id = show(&app, notification)?;
+    Ok(ShownNotification {
+        id,
+        interactive: INTERACTIVE,
+    })
+}
+
+/// Tauri command: Get notification history, oldest first
+#[tauri::command]
+pub fn notification_center_history(state: State<'_, NotificationState>) -> Vec<HistoryEntry> {
+    state.center.lock().unwrap().history().to_vec()
+}
+
+/// Tauri command: Get notification center settings
+#[tauri::command]
+pub fn notification_center_get_settings(state: State<'_, NotificationState>) -> CenterSettings {
+    state.center.lock().unwrap().settings().clone()
+}
+
+/// Tauri command: Update do-not-disturb, quiet hours, grouping and rate
+/// limit settings
+#[tauri::command]
+pub fn notification_center_set_settings(
+    app: AppHandle,
+    state: State<'_, NotificationState>,
+    settings: CenterSettings,
+) -> Result<(), String> {
+    state
+        .center
+        .lock()
+        .unwrap()
+        .set_settings(settings.clone())?;
+    state.save(SETTINGS_FILE, &settings);
+    state.changed(&app);
+    Ok(())
+}
+
+/// Tauri command: Mark notifications read, on the server too when a token
+/// is given
+#[tauri::command]
+pub async fn notification_center_mark_read(
+    app: AppHandle,
+    state: State<'_, NotificationState>,
+    client: State<'_, HauntClient>,
+    ids: Vec<String>,
+    token: Option<String>,
+) -> Result<(), String> {
+    let remote = state.center.lock().unwrap().mark_read(&ids);
+    state.changed(&app);
+    if let Some(token) = token.filter(|_| !remote.is_empty()) {
+        client
+            .mark_notifications_read(&token, &remote)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Tauri command: Mark all notifications read, on the server too when a
+/// token is given
+#[tauri::command]
+pub async fn notification_center_mark_all_read(
+    app: AppHandle,
+    state: State<'_, NotificationState>,
+    client: State<'_, HauntClient>,
+    token: Option<String>,
+) -> Result<(), String> {
+    state.center.lock().unwrap().mark_all_read();
+    state.changed(&app);
+    if let Some(token) = token {
+        client
+            .mark_all_notifications_read(&token)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Tauri command: Clear notification history, on the server too when a
+/// token is given
+#[tauri::command]
+pub async fn notification_center_clear(
+    app: AppHandle,
+    state: State<'_, NotificationState>,
+    client: State<'_, HauntClient>,
+    token: Option<String>,
+) -> Result<(), String> {
+    state.center.lock().unwrap().clear();
+    state.changed(&app);
+    if let Some(token) = token {
+        client
+            .clear_notifications(&token)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Tauri command: Merge server notifications into history and push read
+/// marks the server is missing; returns the merged history
+#[tauri::command]
+pub async fn notification_center_sync(
+    app: AppHandle,
+    state: State<'_, NotificationState>,
+    client: State<'_, HauntClient>,
+    token: String,
+) -> Result<Vec<HistoryEntry>, String> {
+    let page = client
+        .get_notifications(&token, 1, SYNC_PAGE_SIZE, false)
+        .await
+        .map_err(|e| e.to_string())?
+        .data;
+    let unsynced = state
+        .center
+        .lock()
+        .unwrap()
+        .merge_remote(&page.notifications);
+    state.changed(&app);
+    if !unsynced.is_empty() {
+        client
+            .mark_notifications_read(&token, &unsynced)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(state.center.lock().unwrap().history().to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn button(id: &str) -> NotificationButton {
+        NotificationButton {
+            id: id.to_string(),
+            label: id.to_uppercase(),
+        }
+    }
+
+    #[test]
+    fn deserializes_frontend_requests() {
+        let notification: Notification = serde_json::from_value(serde_json::json!({
+            "title": "BTC above $70,000",
+            "body": "BTC is at $70,120.00",
+            "category": "alert",
+            "target": "wraith://asset/BTC",
+            "actions": [{ "id": "snooze", "label": "Snooze" }],
+        }))
+        .unwrap();
+        assert_eq!(notification.category, NotificationCategory::Alert);
+        assert_eq!(
+            notification.actions,
+            [NotificationButton {
+                id: "snooze".to_string(),
+                label: "Snooze".to_string(),
+            }]
+        );
+        assert!(notification.validate().is_ok());
+
+        let plain: Notification =
+            serde_json::from_value(serde_json::json!({ "title": "t", "body": "b" })).unwrap();
+        assert_eq!(plain, Notification::new("t", "b"));
+    }
+
+    #[test]
+    fn validates_targets_and_actions() {
+        let with = |target: Option<&str>, actions: &[&str]| Notification {
+            target: target.map(str::to_string),
+            actions: actions.iter().map(|id| button(id)).collect(),
+            ..Notification::new("Title", "Body")
+        };
+
+        assert!(with(Some("wraith://portfolio/abc"), &["close", "snooze"])
+            .validate()
+            .is_ok());
+        assert!(Notification::new(" ", "Body").validate().is_err());
+        assert!(with(Some("https://example.com"), &[]).validate().is_err());
+        assert!(with(Some("not a url"), &[]).validate().is_err());
+        assert!(with(None, &["a", "b", "c", "d"]).validate().is_err());
+        assert!(with(None, &["default"]).validate().is_err());
+        assert!(with(None, &["__closed"]).validate().is_err());
+        assert!(with(None, &["a", "a"]).validate().is_err());
+        assert!(with(None, &[""]).validate().is_err());
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[test]
+    fn maps_freedesktop_responses() {
+        assert_eq!(parse_response("default"), Response::Clicked);
+        assert_eq!(parse_response("__closed"), Response::Closed);
+        assert_eq!(parse_response("snooze"), Response::Action("snooze"));
+
+        assert!(!Notification::new("t", "b").interactive());
+        let clickable = Notification {
+            target: Some("wraith://asset/BTC".to_string()),
+            ..Notification::new("t", "b")
+        };
+        assert!(clickable.interactive());
+        assert_eq!(NotificationCategory::Alert.freedesktop(), "x-wraith.alert");
+    }
+}
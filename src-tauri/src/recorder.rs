@@ -0,0 +1,746 @@
+//! Tick recorder
+//!
+//! Optionally appends the live `price_update` and `market_update` stream to
+//! JSON-lines files under `recordings/` in the app data directory, so market
+//! sessions can be replayed later. One file per UTC day
+//! (`ticks-2026-01-31.jsonl`); a day that outgrows the per-file cap continues
+//! in numbered parts (`ticks-2026-01-31.1.jsonl`), and the oldest files are
+//! deleted once the total cap is exceeded.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{Manager, State};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::haunt::socket::{MarketUpdate, PriceUpdate, WsMessage};
+use crate::haunt::HauntSocket;
+use crate::now_millis;
+
+const SETTINGS_FILE: &str = "recorder.json";
+const RECORDINGS_DIR: &str = "recordings";
+const FILE_PREFIX: &str = "ticks-";
+const FILE_SUFFIX: &str = ".jsonl";
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+/// Buffered ticks are written out at least this often
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// One recorded message, tagged like the wire format
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Tick {
+    PriceUpdate {
+        /// Unix milliseconds when the message arrived
+        at: i64,
+        data: PriceUpdate,
+    },
+    MarketUpdate {
+        at: i64,
+        data: MarketUpdate,
+    },
+}
+
+impl Tick {
+    /// The socket message this tick was recorded from
+    pub fn to_message(&self) -> WsMessage {
+        match self {
+            Self::PriceUpdate { data, .. } => WsMessage::PriceUpdate { data: data.clone() },
+            Self::MarketUpdate { data, .. } => WsMessage::MarketUpdate { data: data.clone() },
+        }
+    }
+
+    /// The recordable part of a socket message
+    pub fn from_message(message: &WsMessage, at: i64) -> Option<Self> {
+        match message {
+            WsMessage::PriceUpdate { data } => Some(Self::PriceUpdate {
+                at,
+                data: data.clone(),
+            }),
+            WsMessage::MarketUpdate { data } => Some(Self::MarketUpdate {
+                at,
+                data: data.clone(),
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn at(&self) -> i64 {
+        match self {
+            Self::PriceUpdate { at, .. } | Self::MarketUpdate { at, .. } => *at,
+        }
+    }
+}
+
+/// What gets recorded, persisted to `recorder.json`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RecorderSettings {
+    pub enabled: bool,
+    /// Symbols to record, lowercase; empty records every symbol
+    pub symbols: Vec<String>,
+    pub record_market: bool,
+    /// Size at which a day's file continues in a new part
+    pub max_file_bytes: u64,
+    /// Size of all recordings at which the oldest are deleted
+    pub max_total_bytes: u64,
+}
+
+impl Default for RecorderSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            symbols: Vec::new(),
+            record_market: true,
+            max_file_bytes: 64 * 1024 * 1024,
+            max_total_bytes: 1024 * 1024 * 1024,
+        }
+    }
+}
+
+impl RecorderSettings {
+    fn normalized(mut self) -> Self {
+        self.symbols = self
+            .symbols
+            .iter()
+            .map(|s| s.trim().to_lowercase())
+            .filter(|s| !s.is_empty())
+            .collect();
+        self.symbols.sort();
+        self.symbols.dedup();
+        self.max_file_bytes = self.max_file_bytes.max(1);
+        self
+    }
+
+    fn accepts(&self, tick: &Tick) -> bool {
+        match tick {
+            Tick::PriceUpdate { data, .. } => {
+                self.symbols.is_empty() || self.symbols.contains(&data.symbol.to_lowercase())
+            }
+            Tick::MarketUpdate { .. } => self.record_market,
+        }
+    }
+}
+
+/// A recording file on disk
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Recording {
+    pub name: String,
+    /// UTC day, `YYYY-MM-DD`
+    pub date: String,
+    pub part: u32,
+    pub size_bytes: u64,
+    /// Currently being written to
+    pub active: bool,
+}
+
+/// `YYYY-MM-DD` for days since the Unix epoch
+fn format_day(days: i64) -> String {
+    // Howard Hinnant's civil_from_days
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+fn file_name(date: &str, part: u32) -> String {
+    if part == 0 {
+        format!("{}{}{}", FILE_PREFIX, date, FILE_SUFFIX)
+    } else {
+        format!("{}{}.{}{}", FILE_PREFIX, date, part, FILE_SUFFIX)
+    }
+}
+
+/// Date and part of a recording file name; `None` for anything else
+pub fn parse_name(name: &str) -> Option<(String, u32)> {
+    let stem = name.strip_prefix(FILE_PREFIX)?.strip_suffix(FILE_SUFFIX)?;
+    let (date, part) = match stem.split_once('.') {
+        Some((date, part)) if !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()) => {
+            (date, part.parse().ok().filter(|&p| p > 0)?)
+        }
+        Some(_) => return None,
+        None => (stem, 0),
+    };
+
+    let bytes = date.as_bytes();
+    let valid = bytes.len() == 10
+        && bytes.iter().enumerate().all(|(i, b)| match i {
+            4 | 7 => *b == b'-',
+            _ => b.is_ascii_digit(),
+        });
+    valid.then(|| (date.to_string(), part))
+}
+
+/// Recording files in `dir`, oldest first
+pub fn list_recordings(dir: &Path) -> io::Result<Vec<Recording>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut recordings = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let Some((date, part)) = parse_name(&name) else {
+            continue;
+        };
+        recordings.push(Recording {
+            name,
+            date,
+            part,
+            size_bytes: entry.metadata()?.len(),
+            active: false,
+        });
+    }
+    recordings.sort_by(|a, b| (&a.date, a.part).cmp(&(&b.date, b.part)));
+    Ok(recordings)
+}
+
+/// Read the ticks in a recording, skipping lines that do not parse (such
+/// as a tail cut short by a crash)
+pub fn read_recording(path: &Path) -> io::Result<Vec<Tick>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut ticks = Vec::new();
+    let mut skipped = 0usize;
+    for line in reader.lines() {
+        match serde_json::from_str(&line?) {
+            Ok(tick) => ticks.push(tick),
+            Err(_) => skipped += 1,
+        }
+    }
+    if skipped > 0 {
+        eprintln!("Skipped {} unreadable ticks in {}", skipped, path.display());
+    }
+    Ok(ticks)
+}
+
+struct OpenFile {
+    name: String,
+    day: i64,
+    part: u32,
+    writer: BufWriter<File>,
+    size: u64,
+}
+
+/// Append-only writer over the recordings directory
+pub struct Recorder {
+    dir: PathBuf,
+    settings: RecorderSettings,
+    file: Option<OpenFile>,
+}
+
+impl Recorder {
+    pub fn new(dir: PathBuf, settings: RecorderSettings) -> Self {
+        Self {
+            dir,
+            settings: settings.normalized(),
+            file: None,
+        }
+    }
+
+    pub fn settings(&self) -> &RecorderSettings {
+        &self.settings
+    }
+
+    pub fn set_settings(&mut self, settings: RecorderSettings) -> io::Result<()> {
+        self.settings = settings.normalized();
+        if !self.settings.enabled {
+            self.close()?;
+        }
+        Ok(())
+    }
+
+    /// Append a tick if the settings allow it
+    pub fn record(&mut self, tick: &Tick) -> io::Result<()> {
+        if !self.settings.enabled || !self.settings.accepts(tick) {
+            return Ok(());
+        }
+
+        let mut line = serde_json::to_vec(tick)?;
+        line.push(b'\n');
+        let day = tick.at().div_euclid(DAY_MS);
+
+        let fits = self.file.as_ref().is_some_and(|file| {
+            file.day == day && file.size + line.len() as u64 <= self.settings.max_file_bytes
+        });
+        if !fits {
+            self.open(day, line.len() as u64)?;
+        }
+
+        let file = self.file.as_mut().expect("recording file is open");
+        file.writer.write_all(&line)?;
+        file.size += line.len() as u64;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        match self.file.as_mut() {
+            Some(file) => file.writer.flush(),
+            None => Ok(()),
+        }
+    }
+
+    fn close(&mut self) -> io::Result<()> {
+        match self.file.take() {
+            Some(mut file) => file.writer.flush(),
+            None => Ok(()),
+        }
+    }
+
+    /// Open the first part for `day` with room for `needed` more bytes,
+    /// continuing an existing file where possible
+    fn open(&mut self, day: i64, needed: u64) -> io::Result<()> {
+        let previous = self.file.as_ref().map(|file| (file.day, file.part));
+        self.close()?;
+        fs::create_dir_all(&self.dir)?;
+
+        let date = format_day(day);
+        let mut part = match previous {
+            Some((previous_day, part)) if previous_day == day => part + 1,
+            _ => 0,
+        };
+        let (name, size) = loop {
+            let name = file_name(&date, part);
+            let size = match fs::metadata(self.dir.join(&name)) {
+                Ok(metadata) => metadata.len(),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => 0,
+                Err(e) => return Err(e),
+            };
+            // An oversized tick still goes into an empty file
+            if size == 0 || size + needed <= self.settings.max_file_bytes {
+                break (name, size);
+            }
+            part += 1;
+        };
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.dir.join(&name))?;
+        self.file = Some(OpenFile {
+            name,
+            day,
+            part,
+            writer: BufWriter::new(file),
+            size,
+        });
+        self.prune(needed)
+    }
+
+    /// Delete the oldest recordings until the total, plus `needed` bytes
+    /// about to be written, fits the cap
+    fn prune(&mut self, needed: u64) -> io::Result<()> {
+        let active = self.file.as_ref().map(|file| file.name.as_str());
+        let recordings = list_recordings(&self.dir)?;
+        let mut total: u64 = recordings.iter().map(|r| r.size_bytes).sum::<u64>() + needed;
+
+        for recording in recordings {
+            if total <= self.settings.max_total_bytes {
+                break;
+            }
+            if Some(recording.name.as_str()) == active {
+                continue;
+            }
+            fs::remove_file(self.dir.join(&recording.name))?;
+            total -= recording.size_bytes;
+        }
+        Ok(())
+    }
+
+    /// Recording files, oldest first, with buffered bytes flushed so sizes
+    /// are current
+    pub fn recordings(&mut self) -> io::Result<Vec<Recording>> {
+        self.flush()?;
+        let active = self.file.as_ref().map(|file| file.name.clone());
+        let mut recordings = list_recordings(&self.dir)?;
+        for recording in &mut recordings {
+            recording.active = Some(&recording.name) == active.as_ref();
+        }
+        Ok(recordings)
+    }
+
+    pub fn delete(&mut self, name: &str) -> io::Result<()> {
+        let path = self.path(name)?;
+        if self.file.as_ref().is_some_and(|file| file.name == name) {
+            self.close()?;
+        }
+        fs::remove_file(path)
+    }
+
+    /// Path of a recording, with anything buffered for it written out
+    pub fn path(&mut self, name: &str) -> io::Result<PathBuf> {
+        if parse_name(name).is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Not a recording: {}", name),
+            ));
+        }
+        self.flush()?;
+        Ok(self.dir.join(name))
+    }
+}
+
+/// Managed state for the tick recorder
+#[derive(Default)]
+pub struct RecorderState {
+    recorder: Mutex<Option<Recorder>>,
+    data_dir: Mutex<Option<PathBuf>>,
+}
+
+impl RecorderState {
+    fn with<T>(&self, f: impl FnOnce(&mut Recorder) -> io::Result<T>) -> Result<T, String> {
+        let mut recorder = self.recorder.lock().unwrap();
+        let recorder = recorder
+            .as_mut()
+            .ok_or_else(|| "Tick recorder is not running".to_string())?;
+        f(recorder).map_err(|e| e.to_string())
+    }
+
+    /// Ticks from the named recordings, in time order
+    pub fn load(&self, names: &[String]) -> Result<Vec<Tick>, String> {
+        let mut ticks = Vec::new();
+        for name in names {
+            let path = self.with(|recorder| recorder.path(name))?;
+            ticks.extend(read_recording(&path).map_err(|e| format!("{}: {}", name, e))?);
+        }
+        ticks.sort_by_key(Tick::at);
+        Ok(ticks)
+    }
+
+    fn record(&self, tick: &Tick) {
+        if let Err(e) = self.with(|recorder| recorder.record(tick)) {
+            eprintln!("Failed to record tick: {}", e);
+        }
+    }
+
+    fn flush(&self) {
+        if let Err(e) = self.with(|recorder| recorder.flush()) {
+            eprintln!("Failed to flush recording: {}", e);
+        }
+    }
+
+    fn save(&self, settings: &RecorderSettings) -> io::Result<()> {
+        let Some(dir) = self.data_dir.lock().unwrap().clone() else {
+            return Ok(());
+        };
+        fs::create_dir_all(&dir)?;
+        let json = serde_json::to_vec_pretty(settings)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        fs::write(dir.join(SETTINGS_FILE), json)
+    }
+}
+
+/// Load the settings and start recording from the socket stream
+pub fn init(app: &tauri::AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+    let data_dir = app.path().app_data_dir()?;
+    let settings = match fs::read(data_dir.join(SETTINGS_FILE)) {
+        Ok(bytes) => serde_json::from_slice(&bytes)?,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => RecorderSettings::default(),
+        Err(e) => return Err(e.into()),
+    };
+
+    let state = app.state::<RecorderState>();
+    *state.recorder.lock().unwrap() = Some(Recorder::new(data_dir.join(RECORDINGS_DIR), settings));
+    *state.data_dir.lock().unwrap() = Some(data_dir);
+
+    let mut messages = app.state::<HauntSocket>().messages();
+    let handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let state = handle.state::<RecorderState>();
+        let mut flush = tokio::time::interval(FLUSH_INTERVAL);
+        loop {
+            tokio::select! {
+                message = messages.recv() => match message {
+                    Ok(message) => {
+                        if let Some(tick) = Tick::from_message(&message, now_millis()) {
+                            state.record(&tick);
+                        }
+                    }
+                    Err(RecvError::Lagged(skipped)) => {
+                        eprintln!("Tick recorder fell behind; {} messages not recorded", skipped);
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+                _ = flush.tick() => state.flush(),
+            }
+        }
+        state.flush();
+    });
+
+    Ok(())
+}
+
+// ========== Commands ==========
+
+/// Tauri command: Get the tick recorder settings
+#[tauri::command]
+pub fn recorder_get_settings(state: State<'_, RecorderState>) -> Result<RecorderSettings, String> {
+    state.with(|recorder| Ok(recorder.settings().clone()))
+}
+
+/// Tauri command: Change what the tick recorder records
+#[tauri::command]
+pub fn recorder_set_settings(
+    state: State<'_, RecorderState>,
+    settings: RecorderSettings,
+) -> Result<RecorderSettings, String> {
+    let settings = state.with(|recorder| {
+        recorder.set_settings(settings)?;
+        Ok(recorder.settings().clone())
+    })?;
+    state.save(&settings).map_err(|e| e.to_string())?;
+    Ok(settings)
+}
+
+/// Tauri command: List recordings, oldest first, with their sizes
+#[tauri::command]
+pub fn recorder_list_recordings(state: State<'_, RecorderState>) -> Result<Vec<Recording>, String> {
+    state.with(|recorder| recorder.recordings())
+}
+
+/// Tauri command: Delete a recording
+#[tauri::command]
+pub fn recorder_delete_recording(
+    state: State<'_, RecorderState>,
+    name: String,
+) -> Result<(), String> {
+    state.with(|recorder| recorder.delete(&name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("wraith-recorder-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn price(symbol: &str, at: i64) -> Tick {
+        Tick::PriceUpdate {
+            at,
+            data: PriceUpdate {
+                id: 1,
+                symbol: symbol.to_string(),
+                price: 100.0,
+                previous_price: None,
+                change_24h: None,
+                volume_24h: None,
+                trade_direction: None,
+                source: Some("binance".to_string()),
+                sources: None,
+                timestamp: at.to_string(),
+            },
+        }
+    }
+
+    fn market(at: i64) -> Tick {
+        Tick::MarketUpdate {
+            at,
+            data: MarketUpdate {
+                total_market_cap: 1.0,
+                total_volume_24h: 2.0,
+                btc_dominance: 50.0,
+                timestamp: at.to_string(),
+            },
+        }
+    }
+
+    fn enabled() -> RecorderSettings {
+        RecorderSettings {
+            enabled: true,
+            ..Default::default()
+        }
+    }
+
+    fn read_ticks(path: &Path) -> Vec<Tick> {
+        fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn formats_and_parses_names() {
+        assert_eq!(format_day(0), "1970-01-01");
+        assert_eq!(format_day(19_723), "2024-01-01");
+        assert_eq!(format_day(19_782), "2024-02-29");
+        assert_eq!(file_name("2024-02-29", 0), "ticks-2024-02-29.jsonl");
+        assert_eq!(file_name("2024-02-29", 3), "ticks-2024-02-29.3.jsonl");
+
+        assert_eq!(
+            parse_name("ticks-2024-02-29.jsonl"),
+            Some(("2024-02-29".to_string(), 0))
+        );
+        assert_eq!(
+            parse_name("ticks-2024-02-29.12.jsonl"),
+            Some(("2024-02-29".to_string(), 12))
+        );
+        for bad in [
+            "ticks-2024-02-29.0.jsonl",
+            "ticks-2024-02-29..jsonl",
+            "ticks-../x.jsonl",
+            "ticks-2024-02-29.jsonl.tmp",
+            "recorder.json",
+        ] {
+            assert_eq!(parse_name(bad), None, "{}", bad);
+        }
+    }
+
+    #[test]
+    fn ticks_use_the_wire_tags() {
+        let json = serde_json::to_value(price("btc", 5)).unwrap();
+        assert_eq!(json["type"], "price_update");
+        assert_eq!(json["at"], 5);
+        assert_eq!(json["data"]["source"], "binance");
+        assert_eq!(json["data"]["tradeDirection"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn disabled_recorder_writes_nothing() {
+        let dir = temp_dir("disabled");
+        let mut recorder = Recorder::new(dir.clone(), RecorderSettings::default());
+        recorder.record(&price("btc", 0)).unwrap();
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn filters_symbols_and_market_updates() {
+        let dir = temp_dir("filters");
+        let settings = RecorderSettings {
+            symbols: vec![" BTC ".to_string()],
+            record_market: false,
+            ..enabled()
+        };
+        let mut recorder = Recorder::new(dir.clone(), settings);
+        recorder.record(&price("btc", 0)).unwrap();
+        recorder.record(&price("eth", 1)).unwrap();
+        recorder.record(&market(2)).unwrap();
+        recorder.flush().unwrap();
+
+        let ticks = read_ticks(&dir.join("ticks-1970-01-01.jsonl"));
+        assert_eq!(ticks.len(), 1);
+        assert!(matches!(&ticks[0], Tick::PriceUpdate { data, .. } if data.symbol == "btc"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn rotates_daily() {
+        let dir = temp_dir("daily");
+        let mut recorder = Recorder::new(dir.clone(), enabled());
+        recorder.record(&price("btc", DAY_MS - 1)).unwrap();
+        recorder.record(&market(DAY_MS)).unwrap();
+
+        let recordings = recorder.recordings().unwrap();
+        let names: Vec<_> = recordings.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, ["ticks-1970-01-01.jsonl", "ticks-1970-01-02.jsonl"]);
+        assert!(!recordings[0].active);
+        assert!(recordings[1].active);
+        assert!(recordings.iter().all(|r| r.size_bytes > 0));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn size_cap_continues_in_parts_and_after_restart() {
+        let dir = temp_dir("parts");
+        let line = serde_json::to_vec(&price("btc", 0)).unwrap().len() as u64 + 1;
+        let settings = RecorderSettings {
+            max_file_bytes: line * 2,
+            ..enabled()
+        };
+
+        let mut recorder = Recorder::new(dir.clone(), settings.clone());
+        for _ in 0..3 {
+            recorder.record(&price("btc", 0)).unwrap();
+        }
+        drop(recorder);
+
+        // The first part is full, so a restarted recorder appends to the second
+        let mut recorder = Recorder::new(dir.clone(), settings);
+        recorder.record(&price("btc", 0)).unwrap();
+        let sizes: Vec<_> = recorder
+            .recordings()
+            .unwrap()
+            .iter()
+            .map(|r| (r.part, r.size_bytes))
+            .collect();
+        assert_eq!(sizes, [(0, line * 2), (1, line * 2)]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn total_cap_deletes_oldest() {
+        let dir = temp_dir("total");
+        let line = serde_json::to_vec(&price("btc", 3 * DAY_MS)).unwrap().len() as u64 + 1;
+        let settings = RecorderSettings {
+            max_total_bytes: line * 2,
+            ..enabled()
+        };
+
+        let mut recorder = Recorder::new(dir.clone(), settings);
+        for day in 0..4 {
+            recorder.record(&price("btc", day * DAY_MS)).unwrap();
+        }
+        let names: Vec<_> = recorder
+            .recordings()
+            .unwrap()
+            .into_iter()
+            .map(|r| r.name)
+            .collect();
+        assert_eq!(names, ["ticks-1970-01-03.jsonl", "ticks-1970-01-04.jsonl"]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn reads_back_skipping_truncated_lines() {
+        let dir = temp_dir("read");
+        let mut recorder = Recorder::new(dir.clone(), enabled());
+        recorder.record(&price("btc", 0)).unwrap();
+        recorder.record(&market(1)).unwrap();
+        let path = recorder.path("ticks-1970-01-01.jsonl").unwrap();
+        drop(recorder);
+
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"{\"type\":\"price_upd").unwrap();
+
+        let ticks = read_recording(&path).unwrap();
+        assert_eq!(ticks.iter().map(Tick::at).collect::<Vec<_>>(), [0, 1]);
+        assert!(matches!(
+            ticks[1].to_message().event(),
+            Some(("haunt-market-update", _))
+        ));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn delete_rejects_other_files() {
+        let dir = temp_dir("delete");
+        let mut recorder = Recorder::new(dir.clone(), enabled());
+        recorder.record(&price("btc", 0)).unwrap();
+
+        assert!(recorder.delete("../recorder.json").is_err());
+        recorder.delete("ticks-1970-01-01.jsonl").unwrap();
+        assert!(recorder.recordings().unwrap().is_empty());
+
+        // Recording carries on in a fresh file
+        recorder.record(&price("btc", 1)).unwrap();
+        assert_eq!(recorder.recordings().unwrap().len(), 1);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
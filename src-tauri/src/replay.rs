@@ -0,0 +1,546 @@
+//! Session replay
+//!
+//! Plays recorded ticks back through the same `haunt-price-update` and
+//! `haunt-market-update` events the live stream uses, so the frontend can
+//! be demoed and debugged without a Haunt server. Live data events are
+//! muted while a replay is loaded; the only difference the UI sees is
+//! [`ReplayStatus::active`].
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::sync::Notify;
+
+use crate::haunt::HauntSocket;
+use crate::recorder::{RecorderState, Tick};
+
+/// Event carrying [`ReplayStatus`] whenever playback changes
+pub const STATUS_EVENT: &str = "replay-status";
+
+/// Ticks emitted per batch at max speed before yielding
+const MAX_BATCH: usize = 256;
+
+/// Playback rate
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReplaySpeed {
+    #[default]
+    #[serde(rename = "1x")]
+    Realtime,
+    #[serde(rename = "10x")]
+    Fast,
+    /// As fast as ticks can be emitted
+    #[serde(rename = "max")]
+    Max,
+}
+
+impl ReplaySpeed {
+    fn factor(self) -> Option<f64> {
+        match self {
+            Self::Realtime => Some(1.0),
+            Self::Fast => Some(10.0),
+            Self::Max => None,
+        }
+    }
+}
+
+/// Playback state reported to the frontend
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayStatus {
+    /// A replay is standing in for the live stream
+    pub active: bool,
+    pub playing: bool,
+    pub speed: ReplaySpeed,
+    pub recordings: Vec<String>,
+    /// Recording time reached, Unix milliseconds
+    pub position: Option<i64>,
+    pub start: Option<i64>,
+    pub end: Option<i64>,
+    /// Ticks emitted so far
+    pub index: usize,
+    pub total: usize,
+}
+
+/// Playback over a loaded set of ticks
+///
+/// Tick times are mapped to the wall clock from an anchor taken whenever
+/// playback starts or changes speed, so timer jitter does not accumulate.
+struct Replay {
+    ticks: Vec<Tick>,
+    recordings: Vec<String>,
+    /// Next tick to emit
+    index: usize,
+    /// Recording time reached
+    position: i64,
+    speed: ReplaySpeed,
+    playing: bool,
+    anchor: (Instant, i64),
+    stopped: bool,
+}
+
+impl Replay {
+    fn new(ticks: Vec<Tick>, recordings: Vec<String>, speed: ReplaySpeed, now: Instant) -> Self {
+        let position = ticks.first().map_or(0, Tick::at);
+        Self {
+            ticks,
+            recordings,
+            index: 0,
+            position,
+            speed,
+            playing: false,
+            anchor: (now, position),
+            stopped: false,
+        }
+    }
+
+    fn status(&self) -> ReplayStatus {
+        ReplayStatus {
+            active: !self.stopped,
+            playing: self.playing,
+            speed: self.speed,
+            recordings: self.recordings.clone(),
+            position: (!self.ticks.is_empty()).then_some(self.position),
+            start: self.ticks.first().map(Tick::at),
+            end: self.ticks.last().map(Tick::at),
+            index: self.index,
+            total: self.ticks.len(),
+        }
+    }
+
+    /// Recording time the wall clock has reached, never past the next tick
+    fn clock(&self, now: Instant) -> i64 {
+        let Some(factor) = self.speed.factor().filter(|_| self.playing) else {
+            return self.position;
+        };
+        let (instant, at) = self.anchor;
+        let elapsed = now.saturating_duration_since(instant).as_micros() as f64 / 1000.0 * factor;
+        let clock = at + elapsed as i64;
+        match self.ticks.get(self.index) {
+            Some(next) => clock.min(next.at()),
+            None => clock,
+        }
+    }
+
+    fn play(&mut self, now: Instant) {
+        if self.index >= self.ticks.len() {
+            self.seek(i64::MIN, now);
+        }
+        self.playing = !self.ticks.is_empty();
+        self.anchor = (now, self.position);
+    }
+
+    fn pause(&mut self, now: Instant) {
+        self.position = self.clock(now);
+        self.playing = false;
+    }
+
+    fn set_speed(&mut self, speed: ReplaySpeed, now: Instant) {
+        self.position = self.clock(now);
+        self.speed = speed;
+        self.anchor = (now, self.position);
+    }
+
+    /// Move to recording time `at`; the next tick emitted is the first at
+    /// or after it
+    fn seek(&mut self, at: i64, now: Instant) {
+        let (Some(first), Some(last)) = (self.ticks.first(), self.ticks.last()) else {
+            return;
+        };
+        self.position = at.clamp(first.at(), last.at());
+        self.index = self.ticks.partition_point(|tick| tick.at() < self.position);
+        self.anchor = (now, self.position);
+    }
+
+    /// Pause and emit the next tick
+    fn step(&mut self, now: Instant) -> Option<Tick> {
+        self.pause(now);
+        let tick = self.ticks.get(self.index)?.clone();
+        self.index += 1;
+        self.position = tick.at();
+        Some(tick)
+    }
+
+    /// When the next tick is due, if playing
+    fn due(&self) -> Option<Instant> {
+        if !self.playing {
+            return None;
+        }
+        let next = self.ticks.get(self.index)?;
+        let (instant, at) = self.anchor;
+        let Some(factor) = self.speed.factor() else {
+            return Some(instant);
+        };
+        let wait = (next.at() - at).max(0) as f64 / factor;
+        Some(instant + Duration::from_micros((wait * 1000.0) as u64))
+    }
+
+    /// Ticks due by `now`, at most `limit`; playback pauses at the end
+    fn take_due(&mut self, now: Instant, limit: usize) -> Vec<Tick> {
+        let mut due = Vec::new();
+        while due.len() < limit && self.due().is_some_and(|at| at <= now) {
+            let tick = self.ticks[self.index].clone();
+            self.index += 1;
+            self.position = tick.at();
+            due.push(tick);
+        }
+        if self.index >= self.ticks.len() {
+            self.playing = false;
+        }
+        due
+    }
+}
+
+struct Session {
+    replay: Arc<Mutex<Replay>>,
+    wake: Arc<Notify>,
+}
+
+/// Managed state for replay mode
+#[derive(Default)]
+pub struct ReplayState {
+    session: Mutex<Option<Session>>,
+}
+
+impl ReplayState {
+    pub fn status(&self) -> ReplayStatus {
+        match self.session.lock().unwrap().as_ref() {
+            Some(session) => session.replay.lock().unwrap().status(),
+            None => ReplayStatus::default(),
+        }
+    }
+
+    /// Change the loaded replay and wake its task
+    fn update<T>(&self, f: impl FnOnce(&mut Replay, Instant) -> T) -> Result<T, String> {
+        let session = self.session.lock().unwrap();
+        let session = session
+            .as_ref()
+            .ok_or_else(|| "No replay loaded".to_string())?;
+        let result = f(&mut session.replay.lock().unwrap(), Instant::now());
+        session.wake.notify_one();
+        Ok(result)
+    }
+
+    fn stop(&self) -> bool {
+        let Some(session) = self.session.lock().unwrap().take() else {
+            return false;
+        };
+        session.replay.lock().unwrap().stopped = true;
+        session.wake.notify_one();
+        true
+    }
+}
+
+fn emit_tick(app: &AppHandle, tick: &Tick) {
+    if let Some((event, payload)) = tick.to_message().event() {
+        let _ = app.emit(event, payload);
+    }
+}
+
+fn emit_status(app: &AppHandle, status: &ReplayStatus) {
+    let _ = app.emit(STATUS_EVENT, status);
+}
+
+/// Emit ticks as they fall due until the replay is stopped
+async fn run(app: AppHandle, replay: Arc<Mutex<Replay>>, wake: Arc<Notify>) {
+    loop {
+        let (due, stopped) = {
+            let replay = replay.lock().unwrap();
+            (replay.due(), replay.stopped)
+        };
+        if stopped {
+            return;
+        }
+
+        match due {
+            Some(due) => {
+                tokio::select! {
+                    _ = tokio::time::sleep_until(due.into()) => {}
+                    _ = wake.notified() => continue,
+                }
+            }
+            None => {
+                wake.notified().await;
+                continue;
+            }
+        }
+
+        let (ticks, finished) = {
+            let mut replay = replay.lock().unwrap();
+            let ticks = replay.take_due(Instant::now(), MAX_BATCH);
+            let finished = (!replay.playing).then(|| replay.status());
+            (ticks, finished)
+        };
+        for tick in &ticks {
+            emit_tick(&app, tick);
+        }
+        if let Some(status) = finished {
+            emit_status(&app, &status);
+        }
+        tokio::task::yield_now().await;
+    }
+}
+
+// ========== Commands ==========
+
+/// Tauri command: Get the replay status
+#[tauri::command]
+pub fn replay_status(replay: State<'_, ReplayState>) -> ReplayStatus {
+    replay.status()
+}
+
+/// Tauri command: Load recordings and start playing them in place of the
+/// live stream
+#[tauri::command]
+pub fn replay_start(
+    app: AppHandle,
+    replay: State<'_, ReplayState>,
+    recorder: State<'_, RecorderState>,
+    recordings: Vec<String>,
+    speed: Option<ReplaySpeed>,
+) -> Result<ReplayStatus, String> {
+    let ticks = recorder.load(&recordings)?;
+    if ticks.is_empty() {
+        return Err("The selected recordings contain no ticks".to_string());
+    }
+
+    replay.stop();
+    let now = Instant::now();
+    let mut session = Replay::new(ticks, recordings, speed.unwrap_or_default(), now);
+    session.play(now);
+    let status = session.status();
+
+    let session = Session {
+        replay: Arc::new(Mutex::new(session)),
+        wake: Arc::new(Notify::new()),
+    };
+    tauri::async_runtime::spawn(run(
+        app.clone(),
+        session.replay.clone(),
+        session.wake.clone(),
+    ));
+    *replay.session.lock().unwrap() = Some(session);
+
+    app.state::<HauntSocket>().set_muted(true);
+    emit_status(&app, &status);
+    Ok(status)
+}
+
+/// Tauri command: Pause playback
+#[tauri::command]
+pub fn replay_pause(
+    app: AppHandle,
+    replay: State<'_, ReplayState>,
+) -> Result<ReplayStatus, String> {
+    let status = replay.update(|replay, now| {
+        replay.pause(now);
+        replay.status()
+    })?;
+    emit_status(&app, &status);
+    Ok(status)
+}
+
+/// Tauri command: Resume playback, restarting if it reached the end
+#[tauri::command]
+pub fn replay_resume(
+    app: AppHandle,
+    replay: State<'_, ReplayState>,
+) -> Result<ReplayStatus, String> {
+    let status = replay.update(|replay, now| {
+        replay.play(now);
+        replay.status()
+    })?;
+    emit_status(&app, &status);
+    Ok(status)
+}
+
+/// Tauri command: Jump to a recording time (Unix milliseconds)
+#[tauri::command]
+pub fn replay_seek(
+    app: AppHandle,
+    replay: State<'_, ReplayState>,
+    position: i64,
+) -> Result<ReplayStatus, String> {
+    let status = replay.update(|replay, now| {
+        replay.seek(position, now);
+        replay.status()
+    })?;
+    emit_status(&app, &status);
+    Ok(status)
+}
+
+/// Tauri command: Pause and emit the next tick
+#[tauri::command]
+pub fn replay_step(app: AppHandle, replay: State<'_, ReplayState>) -> Result<ReplayStatus, String> {
+    let (tick, status) = replay.update(|replay, now| (replay.step(now), replay.status()))?;
+    if let Some(tick) = tick {
+        emit_tick(&app, &tick);
+    }
+    emit_status(&app, &status);
+    Ok(status)
+}
+
+/// Tauri command: Change the playback speed
+#[tauri::command]
+pub fn replay_set_speed(
+    app: AppHandle,
+    replay: State<'_, ReplayState>,
+    speed: ReplaySpeed,
+) -> Result<ReplayStatus, String> {
+    let status = replay.update(|replay, now| {
+        replay.set_speed(speed, now);
+        replay.status()
+    })?;
+    emit_status(&app, &status);
+    Ok(status)
+}
+
+/// Tauri command: Unload the replay and go back to live data
+#[tauri::command]
+pub fn replay_stop(app: AppHandle, replay: State<'_, ReplayState>) -> ReplayStatus {
+    if replay.stop() {
+        app.state::<HauntSocket>().set_muted(false);
+    }
+    let status = replay.status();
+    emit_status(&app, &status);
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::haunt::socket::MarketUpdate;
+
+    fn tick(at: i64) -> Tick {
+        Tick::MarketUpdate {
+            at,
+            data: MarketUpdate {
+                total_market_cap: 0.0,
+                total_volume_24h: 0.0,
+                btc_dominance: 0.0,
+                timestamp: at.to_string(),
+            },
+        }
+    }
+
+    fn replay(times: &[i64], speed: ReplaySpeed, now: Instant) -> Replay {
+        let ticks = times.iter().map(|&at| tick(at)).collect();
+        Replay::new(
+            ticks,
+            vec!["ticks-1970-01-01.jsonl".to_string()],
+            speed,
+            now,
+        )
+    }
+
+    fn times(ticks: &[Tick]) -> Vec<i64> {
+        ticks.iter().map(Tick::at).collect()
+    }
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn speed_uses_wire_names() {
+        assert_eq!(serde_json::to_value(ReplaySpeed::Realtime).unwrap(), "1x");
+        assert_eq!(
+            serde_json::from_value::<ReplaySpeed>("10x".into()).unwrap(),
+            ReplaySpeed::Fast
+        );
+        assert_eq!(serde_json::to_value(ReplaySpeed::Max).unwrap(), "max");
+    }
+
+    #[test]
+    fn plays_in_real_time() {
+        let t0 = Instant::now();
+        let mut replay = replay(&[1000, 1500, 3000], ReplaySpeed::Realtime, t0);
+        assert_eq!(replay.due(), None);
+
+        replay.play(t0);
+        assert_eq!(times(&replay.take_due(t0, MAX_BATCH)), [1000]);
+        assert_eq!(replay.due(), Some(t0 + ms(500)));
+        assert!(replay.take_due(t0 + ms(499), MAX_BATCH).is_empty());
+        assert_eq!(
+            times(&replay.take_due(t0 + ms(2000), MAX_BATCH)),
+            [1500, 3000]
+        );
+
+        let status = replay.status();
+        assert!(status.active);
+        assert!(!status.playing);
+        assert_eq!((status.index, status.total), (3, 3));
+        assert_eq!(status.position, Some(3000));
+    }
+
+    #[test]
+    fn fast_and_max_speeds() {
+        let t0 = Instant::now();
+        let mut replay = replay(&[0, 1000, 2000], ReplaySpeed::Fast, t0);
+        replay.play(t0);
+        assert_eq!(times(&replay.take_due(t0 + ms(100), MAX_BATCH)), [0, 1000]);
+
+        // Switching speed keeps the place reached
+        replay.set_speed(ReplaySpeed::Max, t0 + ms(150));
+        assert_eq!(replay.due(), Some(t0 + ms(150)));
+        assert_eq!(times(&replay.take_due(t0 + ms(150), 1)), [2000]);
+    }
+
+    #[test]
+    fn speed_change_keeps_elapsed_time() {
+        let t0 = Instant::now();
+        let mut replay = replay(&[0, 1000], ReplaySpeed::Realtime, t0);
+        replay.play(t0);
+        replay.take_due(t0, MAX_BATCH);
+
+        // Half way to the next tick at 1x, the rest takes 50ms at 10x
+        replay.set_speed(ReplaySpeed::Fast, t0 + ms(500));
+        assert_eq!(replay.due(), Some(t0 + ms(550)));
+    }
+
+    #[test]
+    fn pause_resume_and_step() {
+        let t0 = Instant::now();
+        let mut replay = replay(&[0, 1000, 2000], ReplaySpeed::Realtime, t0);
+        replay.play(t0);
+        replay.take_due(t0, MAX_BATCH);
+
+        replay.pause(t0 + ms(400));
+        assert_eq!(replay.due(), None);
+        assert_eq!(replay.status().position, Some(400));
+
+        // Resuming an hour later still has 600ms to wait
+        let later = t0 + Duration::from_secs(3600);
+        replay.play(later);
+        assert_eq!(replay.due(), Some(later + ms(600)));
+
+        assert_eq!(replay.step(later).map(|t| t.at()), Some(1000));
+        assert!(!replay.playing);
+        assert_eq!(replay.step(later).map(|t| t.at()), Some(2000));
+        assert!(replay.step(later).is_none());
+
+        // Playing from the end starts over
+        replay.play(later);
+        assert_eq!(replay.status().index, 0);
+        assert_eq!(times(&replay.take_due(later, MAX_BATCH)), [0]);
+    }
+
+    #[test]
+    fn seek_lands_on_the_next_tick() {
+        let t0 = Instant::now();
+        let mut replay = replay(&[0, 1000, 2000], ReplaySpeed::Realtime, t0);
+        replay.seek(1500, t0);
+        assert_eq!(replay.status().index, 2);
+        assert_eq!(replay.status().position, Some(1500));
+
+        replay.play(t0);
+        assert_eq!(replay.due(), Some(t0 + ms(500)));
+
+        replay.seek(-5, t0);
+        assert_eq!(replay.status().index, 0);
+        assert_eq!(replay.status().position, Some(0));
+        replay.seek(i64::MAX, t0);
+        assert_eq!(replay.status().index, 2);
+    }
+}
@@ -0,0 +1,629 @@
+//! Scriptable strategies
+//!
+//! Strategies too involved for [`crate::backtest::strategy`]'s conditions
+//! can be written as [Rhai](https://rhai.rs) scripts and run in a
+//! [`sandbox`] against a backtest or the live price stream. Each script
+//! trades a [`paper`] account, so nothing it does reaches a real order.
+//!
+//! Runs happen on their own threads and report through events: every log
+//! line, fill and error as a [`ScriptLog`] on [`LOG_EVENT`], and the run's
+//! progress as a [`ScriptStatus`] on [`STATUS_EVENT`]. A backtest finishes
+//! with a [`BacktestResult`]; a live run keeps going until it is stopped or
+//! fails, then reports the result of its paper trading so far.
+//!
+//! Live runs build candles from ticks: the series is seeded from the chart
+//! when an asset id is given, and each tick updates the latest candle or
+//! starts a new one at the seeded interval, calling `on_bar` either way.
+//! Orders fill at the next tick.
+
+pub mod paper;
+pub mod sandbox;
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::{Emitter, Manager, State};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::backtest::{BacktestConfig, BacktestResult};
+use crate::cache::CacheState;
+use crate::haunt::models::OhlcPoint;
+use crate::haunt::socket::WsMessage;
+use crate::haunt::{HauntClient, HauntSocket};
+use crate::indicators;
+use crate::{new_id, now_millis};
+use sandbox::{Diagnostic, LogLevel, Logger, Script, ScriptError};
+
+/// Event carrying a [`ScriptLog`]
+pub const LOG_EVENT: &str = "script-log";
+/// Event carrying a [`ScriptStatus`]
+pub const STATUS_EVENT: &str = "script-status";
+
+/// Chart range backtested when none is given
+const DEFAULT_RANGE: &str = "1m";
+/// Chart range that seeds a live run's history
+const LIVE_SEED_RANGE: &str = "1d";
+/// Candle interval for live runs without seed history
+const DEFAULT_INTERVAL_SECS: i64 = 60;
+/// Wall-clock time a whole backtest may take
+const BACKTEST_TIMEOUT: Duration = Duration::from_secs(60);
+const MAX_LIVE_RUNS: usize = 4;
+/// Backtests are CPU-bound, so more at once only slows each one down
+const MAX_BACKTEST_RUNS: usize = 2;
+/// Log lines emitted per run before the rest are dropped
+const MAX_LOG_LINES: usize = 1000;
+/// Ticks buffered for a live run that is busy with an earlier one
+const TICK_BUFFER: usize = 256;
+/// How often an idle live run checks whether it was stopped
+const STOP_POLL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RunMode {
+    Backtest,
+    Live,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RunState {
+    Running,
+    Finished,
+    Stopped,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunInfo {
+    pub id: String,
+    pub mode: RunMode,
+    pub symbol: String,
+    pub started_at: i64,
+}
+
+/// Payload of [`LOG_EVENT`]
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptLog {
+    pub run_id: String,
+    pub level: LogLevel,
+    pub message: String,
+    /// Script line the output came from, when known
+    pub line: Option<usize>,
+    pub timestamp: i64,
+}
+
+/// Payload of [`STATUS_EVENT`]
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptStatus {
+    pub run_id: String,
+    pub state: RunState,
+    pub error: Option<Diagnostic>,
+    /// Set when a run ends, except for a backtest that failed
+    pub result: Option<BacktestResult>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RunError {
+    #[error("No price history to test against")]
+    NoHistory,
+    #[error("At most {MAX_LIVE_RUNS} live scripts can run at once")]
+    TooManyLiveRuns,
+    #[error("At most {MAX_BACKTEST_RUNS} backtests can run at once")]
+    TooManyBacktests,
+    #[error("No script run with that id")]
+    NotFound,
+}
+
+struct Run {
+    info: RunInfo,
+    cancel: Arc<AtomicBool>,
+}
+
+/// A live run's price subscription, released when the run is done with it
+/// or never starts
+struct Subscription {
+    app: tauri::AppHandle,
+    symbol: String,
+}
+
+impl Subscription {
+    fn new(app: &tauri::AppHandle, symbol: &str) -> Self {
+        let symbol = symbol.to_string();
+        app.state::<HauntSocket>()
+            .subscribe(std::slice::from_ref(&symbol));
+        Self {
+            app: app.clone(),
+            symbol,
+        }
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        // Subscriptions are reference counted, so this only releases ours
+        self.app
+            .state::<HauntSocket>()
+            .unsubscribe(Some(std::slice::from_ref(&self.symbol)));
+    }
+}
+
+/// Managed state for script runs
+#[derive(Default)]
+pub struct ScriptState {
+    runs: Mutex<HashMap<String, Run>>,
+}
+
+/// Replay candles through a script, filling orders at each next open
+pub fn backtest(script: &mut Script, candles: &[OhlcPoint]) -> Result<BacktestResult, ScriptError> {
+    let Some((last, rest)) = candles.split_last() else {
+        return Ok(script.finish(0.0, 0));
+    };
+    for candle in rest {
+        // Candle times are Unix seconds
+        let time = candle.time * 1000;
+        script.fill(candle.open, time);
+        script.on_bar(candle)?;
+        script.mark(candle.close, time);
+    }
+    let time = last.time * 1000;
+    script.fill(last.open, time);
+    script.on_bar(last)?;
+    Ok(script.finish(last.close, time))
+}
+
+/// Fold a tick into the latest candle, or start the next one
+fn advance(last: Option<&OhlcPoint>, price: f64, time: i64, interval: i64) -> OhlcPoint {
+    let start = time / 1000 / interval * interval;
+    match last {
+        Some(last) if last.time >= start => OhlcPoint {
+            high: last.high.max(price),
+            low: last.low.min(price),
+            close: price,
+            ..*last
+        },
+        _ => OhlcPoint {
+            time: start,
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            volume: None,
+        },
+    }
+}
+
+/// Seconds between the last two candles
+fn interval(candles: &[OhlcPoint]) -> i64 {
+    match candles {
+        [.., a, b] if b.time > a.time => b.time - a.time,
+        _ => DEFAULT_INTERVAL_SECS,
+    }
+}
+
+/// Feed ticks to a script until it is stopped or fails
+fn live(script: &mut Script, ticks: mpsc::Receiver<(f64, i64)>, interval: i64) -> RunOutcome {
+    let mut price = script.last_bar().map_or(0.0, |c| c.close);
+    let mut time = now_millis();
+    let error = loop {
+        let (tick, at) = match ticks.recv_timeout(STOP_POLL) {
+            Ok(tick) => tick,
+            Err(RecvTimeoutError::Timeout) => {
+                if script.stopped() {
+                    break None;
+                }
+                continue;
+            }
+            Err(RecvTimeoutError::Disconnected) => break None,
+        };
+        let last = script.last_bar();
+        script.fill(tick, at);
+        let candle = advance(last.as_ref(), tick, at, interval);
+        if let Some(last) = last.filter(|last| last.time != candle.time) {
+            script.mark(last.close, last.time * 1000);
+        }
+        (price, time) = (tick, at);
+        match script.on_bar(&candle) {
+            Ok(()) => {}
+            Err(ScriptError::Stopped) => break None,
+            Err(e) => break Some(e),
+        }
+    };
+    let result = script.finish(price, time);
+    match error {
+        None => RunOutcome::Stopped(Some(result)),
+        Some(e) => RunOutcome::Failed(e, Some(result)),
+    }
+}
+
+enum RunOutcome {
+    Finished(BacktestResult),
+    /// Live runs report what they traded before the stop
+    Stopped(Option<BacktestResult>),
+    Failed(ScriptError, Option<BacktestResult>),
+}
+
+/// Emits a run's log lines, dropping them past [`MAX_LOG_LINES`]
+fn logger(app: &tauri::AppHandle, run_id: &str) -> Logger {
+    let (app, run_id) = (app.clone(), run_id.to_string());
+    let emitted = AtomicUsize::new(0);
+    Arc::new(move |level, message, line| {
+        let n = emitted.fetch_add(1, Ordering::Relaxed);
+        let (level, message, line) = match n.cmp(&MAX_LOG_LINES) {
+            std::cmp::Ordering::Less => (level, message, line),
+            std::cmp::Ordering::Equal => (
+                LogLevel::Warn,
+                "Log limit reached, further output is dropped".to_string(),
+                None,
+            ),
+            std::cmp::Ordering::Greater => return,
+        };
+        let log = ScriptLog {
+            run_id: run_id.clone(),
+            level,
+            message,
+            line,
+            timestamp: now_millis(),
+        };
+        let _ = app.emit(LOG_EVENT, log);
+    })
+}
+
+fn report(
+    app: &tauri::AppHandle,
+    run_id: &str,
+    state: RunState,
+    error: Option<&ScriptError>,
+    result: Option<BacktestResult>,
+) {
+    let status = ScriptStatus {
+        run_id: run_id.to_string(),
+        state,
+        error: error.map(ScriptError::diagnostic),
+        result,
+    };
+    let _ = app.emit(STATUS_EVENT, status);
+}
+
+impl ScriptState {
+    /// Register a run and start `body` on its own thread with a compiled
+    /// script, reporting how it ends
+    fn start(
+        &self,
+        app: &tauri::AppHandle,
+        mode: RunMode,
+        symbol: String,
+        source: String,
+        config: BacktestConfig,
+        body: impl FnOnce(&mut Script) -> RunOutcome + Send + 'static,
+    ) -> Result<String, String> {
+        config.validate().map_err(|e| e.to_string())?;
+        let id = new_id();
+        let cancel = Arc::new(AtomicBool::new(false));
+        {
+            let mut runs = self.runs.lock().unwrap();
+            let running = runs.values().filter(|r| r.info.mode == mode).count();
+            let (limit, error) = match mode {
+                RunMode::Live => (MAX_LIVE_RUNS, RunError::TooManyLiveRuns),
+                RunMode::Backtest => (MAX_BACKTEST_RUNS, RunError::TooManyBacktests),
+            };
+            if running >= limit {
+                return Err(error.to_string());
+            }
+            let info = RunInfo {
+                id: id.clone(),
+                mode,
+                symbol: symbol.clone(),
+                started_at: now_millis(),
+            };
+            let cancel = Arc::clone(&cancel);
+            runs.insert(id.clone(), Run { info, cancel });
+        }
+
+        let (app, run_id) = (app.clone(), id.clone());
+        std::thread::spawn(move || {
+            let log = logger(&app, &run_id);
+            report(&app, &run_id, RunState::Running, None, None);
+            let outcome = match Script::new(&source, &symbol, config, cancel, Arc::clone(&log)) {
+                Ok(mut script) => body(&mut script),
+                Err(e) => RunOutcome::Failed(e, None),
+            };
+            match outcome {
+                RunOutcome::Finished(result) => {
+                    report(&app, &run_id, RunState::Finished, None, Some(result))
+                }
+                RunOutcome::Stopped(result) => {
+                    report(&app, &run_id, RunState::Stopped, None, result)
+                }
+                RunOutcome::Failed(e, result) => {
+                    let diagnostic = e.diagnostic();
+                    log(LogLevel::Error, e.to_string(), diagnostic.line);
+                    report(&app, &run_id, RunState::Failed, Some(&e), result);
+                }
+            }
+            app.state::<ScriptState>()
+                .runs
+                .lock()
+                .unwrap()
+                .remove(&run_id);
+        });
+        Ok(id)
+    }
+
+    fn start_backtest(
+        &self,
+        app: &tauri::AppHandle,
+        symbol: String,
+        candles: Vec<OhlcPoint>,
+        source: String,
+        config: BacktestConfig,
+    ) -> Result<String, String> {
+        if candles.is_empty() {
+            return Err(RunError::NoHistory.to_string());
+        }
+        self.start(
+            app,
+            RunMode::Backtest,
+            symbol,
+            source,
+            config,
+            move |script| {
+                script.set_time_limit(BACKTEST_TIMEOUT);
+                match backtest(script, &candles) {
+                    Ok(result) => RunOutcome::Finished(result),
+                    Err(ScriptError::Stopped) => RunOutcome::Stopped(None),
+                    Err(e) => RunOutcome::Failed(e, None),
+                }
+            },
+        )
+    }
+}
+
+// ========== Commands ==========
+
+/// Tauri command: Compile a script without running it, returning its errors
+#[tauri::command]
+pub fn script_check(source: String) -> Vec<Diagnostic> {
+    sandbox::check(&source)
+}
+
+/// Tauri command: Backtest a script over candles supplied by the webview
+///
+/// Returns the run id at once; logs and the result arrive as events.
+#[tauri::command]
+pub fn script_backtest(
+    app: tauri::AppHandle,
+    state: State<'_, ScriptState>,
+    symbol: String,
+    candles: Vec<OhlcPoint>,
+    source: String,
+    config: Option<BacktestConfig>,
+) -> Result<String, String> {
+    state.start_backtest(&app, symbol, candles, source, config.unwrap_or_default())
+}
+
+/// Tauri command: Backtest a script over an asset's chart
+///
+/// Fetches the range from Haunt, falling back to the cached series when it
+/// cannot be reached; `cached` skips the fetch.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn script_backtest_for_asset(
+    app: tauri::AppHandle,
+    state: State<'_, ScriptState>,
+    client: State<'_, HauntClient>,
+    cache: State<'_, CacheState>,
+    id: i64,
+    symbol: String,
+    range: Option<String>,
+    source: String,
+    config: Option<BacktestConfig>,
+    cached: Option<bool>,
+) -> Result<String, String> {
+    let range = range.as_deref().unwrap_or(DEFAULT_RANGE);
+    let candles = if cached.unwrap_or(false) {
+        cache
+            .cached_chart(id, range)
+            .ok_or_else(|| RunError::NoHistory.to_string())?
+    } else {
+        indicators::load_candles(&client, &cache, id, range).await?
+    };
+    state.start_backtest(&app, symbol, candles, source, config.unwrap_or_default())
+}
+
+/// Tauri command: Run a script against the live price stream
+///
+/// With an asset id, the last day of the chart seeds the script's history
+/// and sets the candle interval. Runs until stopped, holding a price
+/// subscription for the symbol meanwhile.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn script_start_live(
+    app: tauri::AppHandle,
+    state: State<'_, ScriptState>,
+    client: State<'_, HauntClient>,
+    cache: State<'_, CacheState>,
+    socket: State<'_, HauntSocket>,
+    id: Option<i64>,
+    symbol: String,
+    source: String,
+    config: Option<BacktestConfig>,
+) -> Result<String, String> {
+    let seed = match id {
+        Some(id) => indicators::load_candles(&client, &cache, id, LIVE_SEED_RANGE).await?,
+        None => Vec::new(),
+    };
+    let interval = interval(&seed);
+
+    // Ticks are forwarded only while the run's thread takes them
+    let (sender, ticks) = mpsc::sync_channel(TICK_BUFFER);
+    let mut messages = socket.messages();
+    let subscription = Subscription::new(&app, &symbol);
+    let run_id = state.start(
+        &app,
+        RunMode::Live,
+        symbol.clone(),
+        source,
+        config.unwrap_or_default(),
+        move |script| {
+            let _subscription = subscription;
+            script.seed(&seed);
+            live(script, ticks, interval)
+        },
+    )?;
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let message = match messages.recv().await {
+                Ok(message) => message,
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            };
+            let WsMessage::PriceUpdate { data } = &*message else {
+                continue;
+            };
+            if !data.symbol.eq_ignore_ascii_case(&symbol) {
+                continue;
+            }
+            match sender.try_send((data.price, now_millis())) {
+                Ok(()) | Err(TrySendError::Full(_)) => {}
+                Err(TrySendError::Disconnected(_)) => break,
+            }
+        }
+    });
+    Ok(run_id)
+}
+
+/// Tauri command: Stop a running script
+#[tauri::command]
+pub fn script_stop(state: State<'_, ScriptState>, run_id: String) -> Result<(), String> {
+    let runs = state.runs.lock().unwrap();
+    let run = runs
+        .get(&run_id)
+        .ok_or_else(|| RunError::NotFound.to_string())?;
+    run.cancel.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Tauri command: List running scripts
+#[tauri::command]
+pub fn script_runs(state: State<'_, ScriptState>) -> Vec<RunInfo> {
+    let mut runs: Vec<RunInfo> = state
+        .runs
+        .lock()
+        .unwrap()
+        .values()
+        .map(|r| r.info.clone())
+        .collect();
+    runs.sort_by_key(|r| r.started_at);
+    runs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candle(time: i64, open: f64, close: f64) -> OhlcPoint {
+        OhlcPoint {
+            time,
+            open,
+            high: open.max(close),
+            low: open.min(close),
+            close,
+            volume: None,
+        }
+    }
+
+    fn script(source: &str) -> Script {
+        let config = BacktestConfig {
+            initial_balance: 1000.0,
+            fee_pct: 0.0,
+            slippage_pct: 0.0,
+        };
+        let log: Logger = Arc::new(|_, _, _| {});
+        Script::new(source, "BTC", config, Arc::new(AtomicBool::new(false)), log).unwrap()
+    }
+
+    #[test]
+    fn backtest_fills_at_next_open() {
+        let mut script = script(
+            r#"
+            fn on_bar(bar) {
+                if position() == 0.0 && bar.close > bar.open { buy(1); }
+                else if position() > 0.0 && bar.close < bar.open { sell(1); }
+            }
+            "#,
+        );
+        let candles = [
+            candle(1, 100.0, 101.0),
+            candle(2, 102.0, 110.0),
+            candle(3, 111.0, 105.0),
+            candle(4, 104.0, 100.0),
+        ];
+        let result = backtest(&mut script, &candles).unwrap();
+        assert_eq!(result.trades.len(), 2);
+        assert_eq!(result.trades[0].price, 102.0);
+        assert_eq!(result.trades[0].executed_at, 2000);
+        assert_eq!(result.trades[1].price, 104.0);
+        assert_eq!(result.summary.end_value, 1002.0);
+        assert_eq!(result.equity.len(), candles.len());
+        assert_eq!(result.equity[1].value, 1008.0);
+    }
+
+    #[test]
+    fn backtest_closes_at_the_end() {
+        let mut script = script("fn on_bar(bar) { if position() == 0.0 { buy(1); } }");
+        let candles = [candle(1, 100.0, 100.0), candle(2, 100.0, 120.0)];
+        let result = backtest(&mut script, &candles).unwrap();
+        assert_eq!(result.trades.len(), 2);
+        assert_eq!(result.trades[1].price, 120.0);
+        assert_eq!(result.summary.total_pnl, 20.0);
+    }
+
+    #[test]
+    fn ticks_build_candles() {
+        let first = advance(None, 100.0, 61_500, 60);
+        assert_eq!((first.time, first.open, first.close), (60, 100.0, 100.0));
+        let same = advance(Some(&first), 90.0, 119_000, 60);
+        assert_eq!(
+            (same.time, same.open, same.low, same.close),
+            (60, 100.0, 90.0, 90.0)
+        );
+        let next = advance(Some(&same), 95.0, 120_000, 60);
+        assert_eq!((next.time, next.open), (120, 95.0));
+
+        assert_eq!(interval(&[candle(0, 1.0, 1.0), candle(300, 1.0, 1.0)]), 300);
+        assert_eq!(interval(&[]), DEFAULT_INTERVAL_SECS);
+    }
+
+    #[test]
+    fn live_runs_until_the_ticks_stop() {
+        let mut script = script(
+            r#"
+            fn on_bar(bar) {
+                if this.bars == () { this.bars = 0; }
+                this.bars += 1;
+                if position() == 0.0 { buy(1); }
+            }
+            "#,
+        );
+        let (sender, ticks) = mpsc::sync_channel(8);
+        for (price, time) in [(100.0, 60_000), (105.0, 61_000), (110.0, 120_000)] {
+            sender.send((price, time)).unwrap();
+        }
+        drop(sender);
+        let RunOutcome::Stopped(Some(result)) = live(&mut script, ticks, 60) else {
+            panic!("expected the run to stop");
+        };
+        // Bought at the second tick, closed at the last
+        assert_eq!(result.trades[0].price, 105.0);
+        assert_eq!(result.summary.total_pnl, 5.0);
+        // One mark when the first candle closed, one at the end
+        assert_eq!(result.equity.len(), 2);
+    }
+}
@@ -0,0 +1,321 @@
+//! Paper account behind a script's order API
+//!
+//! Scripts trade one symbol with market orders. Orders are queued when the
+//! script places them and filled at the next price the run sees: the next
+//! candle's open in a backtest, the next tick on the live stream. Fills use
+//! the [`BacktestConfig`] fee and slippage assumptions.
+//!
+//! The account holds one net position. Buying while short reduces the short
+//! before opening a long, and each reducing fill books its share of the
+//! round trip's P&L, net of the entry and exit fees. Positions are
+//! unleveraged, so orders that would take the position past current equity
+//! are cut down to fit.
+
+use crate::backtest::{slip, BacktestConfig, BacktestResult, Curve};
+use crate::haunt::models::{OrderSide, Trade};
+use crate::new_id;
+
+/// Position sizes this small are treated as flat
+const DUST: f64 = 1e-12;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Order {
+    /// Signed quantity: positive buys, negative sells
+    Market(f64),
+    /// Close whatever position is open when the order fills
+    Flatten,
+}
+
+/// What became of a queued order
+#[derive(Debug, Clone)]
+pub enum Fill {
+    Filled {
+        trade: Trade,
+        requested: f64,
+    },
+    /// Nothing could be filled within current equity
+    Rejected {
+        requested: f64,
+    },
+}
+
+pub struct PaperAccount {
+    symbol: String,
+    config: BacktestConfig,
+    cash: f64,
+    /// Signed: positive long, negative short
+    size: f64,
+    /// Average fill price of the open position
+    entry: f64,
+    /// Entry fees not yet charged to a round trip
+    open_fees: f64,
+    pending: Vec<Order>,
+    trades: Vec<Trade>,
+    fees: f64,
+    /// Net P&L of each reducing fill
+    results: Vec<f64>,
+    curve: Curve,
+}
+
+impl PaperAccount {
+    pub fn new(symbol: &str, config: BacktestConfig) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            cash: config.initial_balance,
+            curve: Curve::new(config.initial_balance),
+            config,
+            size: 0.0,
+            entry: 0.0,
+            open_fees: 0.0,
+            pending: Vec::new(),
+            trades: Vec::new(),
+            fees: 0.0,
+            results: Vec::new(),
+        }
+    }
+
+    pub fn size(&self) -> f64 {
+        self.size
+    }
+
+    /// Average entry price, `None` while flat
+    pub fn entry(&self) -> Option<f64> {
+        (self.size != 0.0).then_some(self.entry)
+    }
+
+    pub fn cash(&self) -> f64 {
+        self.cash
+    }
+
+    pub fn equity(&self, price: f64) -> f64 {
+        self.cash + self.size * (price - self.entry)
+    }
+
+    pub fn place(&mut self, order: Order) {
+        self.pending.push(order);
+    }
+
+    /// Fill queued orders, in the order they were placed, at `price`
+    pub fn fill(&mut self, price: f64, time: i64) -> Vec<Fill> {
+        let pending = std::mem::take(&mut self.pending);
+        pending
+            .into_iter()
+            .filter_map(|order| {
+                let requested = match order {
+                    Order::Market(size) => size,
+                    // Nothing to close
+                    Order::Flatten if self.size == 0.0 => return None,
+                    Order::Flatten => -self.size,
+                };
+                Some(match self.execute(requested, price, time) {
+                    Some(trade) => Fill::Filled { trade, requested },
+                    None => Fill::Rejected { requested },
+                })
+            })
+            .collect()
+    }
+
+    fn execute(&mut self, requested: f64, price: f64, time: i64) -> Option<Trade> {
+        if !(requested.is_finite() && requested.abs() > DUST && price > 0.0) {
+            return None;
+        }
+        let side = if requested > 0.0 {
+            OrderSide::Buy
+        } else {
+            OrderSide::Sell
+        };
+        let fill = slip(price, side, self.config.slippage_pct);
+
+        // Only growth beyond the current position is capped, so a reduction
+        // always goes through
+        let held = self.size;
+        let mut target = held + requested;
+        let limit = (self.equity(fill) / fill).max(0.0);
+        let cap = if target.signum() == held.signum() {
+            limit.max(held.abs())
+        } else {
+            limit
+        };
+        if target.abs() > cap {
+            target = target.signum() * cap;
+        }
+        let quantity = target - held;
+        if quantity.abs() <= DUST || quantity.signum() != requested.signum() {
+            return None;
+        }
+
+        let fee = quantity.abs() * fill * self.config.fee_pct / 100.0;
+        let closing = if held * quantity < 0.0 {
+            quantity.abs().min(held.abs())
+        } else {
+            0.0
+        };
+        let opening = quantity.abs() - closing;
+        let closing_fee = fee * closing / quantity.abs();
+
+        let mut pnl = 0.0;
+        if closing > 0.0 {
+            let entry_fees = self.open_fees * closing / held.abs();
+            self.open_fees -= entry_fees;
+            let gross = closing * (fill - self.entry) * held.signum();
+            pnl = gross - entry_fees - closing_fee;
+            self.cash += gross;
+            self.results.push(pnl);
+        }
+        if opening > 0.0 {
+            // A flip has closed the old position entirely
+            let kept = if closing > 0.0 { 0.0 } else { held.abs() };
+            self.entry = (kept * self.entry + opening * fill) / (kept + opening);
+            self.open_fees += fee - closing_fee;
+        }
+        self.cash -= fee;
+        self.fees += fee;
+        self.size = target;
+        if self.size.abs() <= DUST {
+            self.size = 0.0;
+            self.entry = 0.0;
+            self.open_fees = 0.0;
+        }
+
+        let trade = Trade {
+            id: new_id(),
+            symbol: self.symbol.clone(),
+            side,
+            size: quantity.abs(),
+            price: fill,
+            fee,
+            pnl,
+            executed_at: time,
+        };
+        self.trades.push(trade.clone());
+        Some(trade)
+    }
+
+    /// Record equity at `price` on the curve
+    pub fn mark(&mut self, price: f64, time: i64) {
+        let value = self.equity(price);
+        self.curve.mark(time, value);
+    }
+
+    /// Close any open position at `price`, mark the final equity and
+    /// summarise the run
+    pub fn finish(&mut self, price: f64, time: i64) -> BacktestResult {
+        self.pending.clear();
+        self.execute(-self.size, price, time);
+        let end_value = self.cash;
+        self.curve.mark(time, end_value);
+        let curve = std::mem::replace(&mut self.curve, Curve::new(end_value));
+        curve.finish(
+            &self.symbol,
+            end_value,
+            std::mem::take(&mut self.trades),
+            &std::mem::take(&mut self.results),
+            self.fees,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frictionless(balance: f64) -> PaperAccount {
+        PaperAccount::new(
+            "BTC",
+            BacktestConfig {
+                initial_balance: balance,
+                fee_pct: 0.0,
+                slippage_pct: 0.0,
+            },
+        )
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn orders_fill_when_the_next_price_arrives() {
+        let mut account = frictionless(1000.0);
+        account.place(Order::Market(2.0));
+        assert_eq!(account.size(), 0.0);
+
+        let fills = account.fill(100.0, 1);
+        assert!(matches!(&fills[..], [Fill::Filled { trade, .. }] if trade.price == 100.0));
+        assert_eq!(account.size(), 2.0);
+        assert_eq!(account.entry(), Some(100.0));
+        assert!(close(account.equity(110.0), 1020.0));
+        assert!(account.fill(110.0, 2).is_empty());
+    }
+
+    #[test]
+    fn netting_and_flips() {
+        let mut account = frictionless(1000.0);
+        account.place(Order::Market(2.0));
+        account.fill(100.0, 1);
+        account.place(Order::Market(2.0));
+        account.fill(110.0, 2);
+        assert!(close(account.entry().unwrap(), 105.0));
+
+        // Sell 6: closes the 4 long at 120 and opens 2 short
+        account.place(Order::Market(-6.0));
+        let fills = account.fill(120.0, 3);
+        let Fill::Filled { trade, .. } = &fills[0] else {
+            panic!("expected a fill");
+        };
+        assert!(close(trade.pnl, 60.0));
+        assert_eq!(account.size(), -2.0);
+        assert_eq!(account.entry(), Some(120.0));
+
+        account.place(Order::Flatten);
+        account.fill(100.0, 4);
+        assert_eq!(account.size(), 0.0);
+        assert_eq!(account.entry(), None);
+        assert!(close(account.cash(), 1100.0));
+    }
+
+    #[test]
+    fn orders_are_capped_at_equity() {
+        let mut account = frictionless(1000.0);
+        account.place(Order::Market(50.0));
+        let fills = account.fill(100.0, 1);
+        assert!(matches!(
+            &fills[..],
+            [Fill::Filled { trade, requested }] if close(trade.size, 10.0) && *requested == 50.0
+        ));
+
+        // Already at the cap: more is rejected, a reduction is not
+        account.place(Order::Market(1.0));
+        account.place(Order::Market(-4.0));
+        let fills = account.fill(100.0, 2);
+        assert!(matches!(fills[0], Fill::Rejected { .. }));
+        assert!(matches!(&fills[1], Fill::Filled { trade, .. } if close(trade.size, 4.0)));
+    }
+
+    #[test]
+    fn fees_slippage_and_summary() {
+        let mut account = PaperAccount::new(
+            "BTC",
+            BacktestConfig {
+                initial_balance: 1000.0,
+                fee_pct: 1.0,
+                slippage_pct: 1.0,
+            },
+        );
+        account.place(Order::Market(1.0));
+        account.fill(100.0, 1);
+        assert_eq!(account.entry(), Some(101.0));
+        account.mark(100.0, 1);
+
+        let result = account.finish(200.0, 2);
+        // Exit slips to 198: gross 97, fees 1.01 and 1.98
+        let exit = &result.trades[1];
+        assert_eq!(exit.side, OrderSide::Sell);
+        assert!(close(exit.price, 198.0));
+        assert!(close(exit.pnl, 97.0 - 1.01 - 1.98));
+        assert!(close(result.summary.end_value, 1000.0 + exit.pnl));
+        assert_eq!(result.summary.total_trades, 1);
+        assert_eq!(result.summary.winning_trades, 1);
+        assert!(close(result.summary.total_fees, 2.99));
+    }
+}
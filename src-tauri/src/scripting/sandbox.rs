@@ -0,0 +1,719 @@
+//! The script sandbox
+//!
+//! Each script gets its own Rhai [`Engine`] with no module loading, no
+//! `eval` and no `sleep`, so the only way out of the sandbox is the API
+//! registered here. Resources are capped per handler call: operations count
+//! against a budget, wall-clock time against a deadline, and strings, arrays,
+//! maps, call depth and variable counts against fixed sizes.
+//!
+//! Scripts define `fn on_bar(bar)`, called with each candle as a map of
+//! `time` (Unix milliseconds), `open`, `high`, `low`, `close` and `volume`.
+//! Functions cannot see top-level variables in Rhai, so state that should
+//! survive between bars lives on `this`, an object map that starts empty.
+//!
+//! The API:
+//! - market data: `symbol()`, `time()`, `price()`, `bars(n)`, `closes(n)`
+//! - indicators on the latest candle, `()` until there is enough history:
+//!   `sma`, `ema`, `rsi`, `cci`, `mfi`, `atr`, `adx` and `stochastic`
+//!   taking a period, `macd(fast, slow, signal)` and
+//!   `bollinger(period, width)` returning maps
+//! - paper orders: `buy(size)`, `sell(size)`, `close_position()`,
+//!   `position()`, `entry_price()`, `cash()` and `equity()`
+//! - `print` and `debug` go to the run's log
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use rhai::module_resolvers::DummyModuleResolver;
+use rhai::{
+    Array, CallFnOptions, Dynamic, Engine, EvalAltResult, Map, Position, Scope, AST, FLOAT, INT,
+};
+use serde::Serialize;
+
+use super::paper::{Fill, Order, PaperAccount};
+use crate::backtest::{BacktestConfig, BacktestResult};
+use crate::haunt::models::{OhlcPoint, OrderSide};
+use crate::indicators;
+
+/// Function called for every bar
+pub const HANDLER: &str = "on_bar";
+
+/// Longest script accepted, in bytes
+pub const MAX_SOURCE_SIZE: usize = 64 * 1024;
+/// Operations one handler call may run
+const MAX_OPERATIONS: u64 = 1_000_000;
+/// Wall-clock time one handler call, or the top level, may run
+const CALL_TIMEOUT: Duration = Duration::from_millis(250);
+const MAX_STRING_SIZE: usize = 64 * 1024;
+const MAX_ARRAY_SIZE: usize = 10_000;
+const MAX_MAP_SIZE: usize = 1_000;
+const MAX_CALL_LEVELS: usize = 32;
+const MAX_EXPR_DEPTH: usize = 64;
+const MAX_VARIABLES: usize = 256;
+const MAX_FUNCTIONS: usize = 256;
+/// Candles kept for market data and indicators; older ones are dropped
+pub const HISTORY: usize = 500;
+/// Operations between deadline checks
+const CHECK_EVERY: u64 = 256;
+
+const STOPPED: &str = "stopped";
+const TIMED_OUT: &str = "timed out";
+
+/// A problem in a script, with its position when known
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnostic {
+    pub message: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
+impl Diagnostic {
+    fn new(message: impl Into<String>, position: Position) -> Self {
+        Self {
+            message: message.into(),
+            line: position.line(),
+            column: position.position(),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)?;
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => write!(f, " (line {}, column {})", line, column),
+            (Some(line), None) => write!(f, " (line {})", line),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Errors from compiling or running a script
+#[derive(Debug, thiserror::Error)]
+pub enum ScriptError {
+    #[error("Script is larger than 64 KiB")]
+    TooLarge,
+    #[error("{0}")]
+    Compile(Diagnostic),
+    #[error("Scripts must define fn on_bar(bar)")]
+    MissingHandler,
+    #[error("{0}")]
+    Runtime(Diagnostic),
+    #[error("Script ran past its time limit")]
+    TimedOut,
+    #[error("Script was stopped")]
+    Stopped,
+}
+
+impl ScriptError {
+    /// The error as a diagnostic, positioned where the script is at fault
+    pub fn diagnostic(&self) -> Diagnostic {
+        match self {
+            Self::Compile(diagnostic) | Self::Runtime(diagnostic) => diagnostic.clone(),
+            other => Diagnostic::new(other.to_string(), Position::NONE),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Info,
+    Debug,
+    Warn,
+    Error,
+}
+
+/// Receives a run's log lines with the script line they came from, if any
+pub type Logger = Arc<dyn Fn(LogLevel, String, Option<usize>) + Send + Sync>;
+
+/// What the API functions read and trade against
+struct Session {
+    symbol: String,
+    candles: Vec<OhlcPoint>,
+    account: PaperAccount,
+}
+
+impl Session {
+    fn last_close(&self) -> Option<f64> {
+        self.candles.last().map(|c| c.close)
+    }
+}
+
+/// An engine with the sandbox's limits and nothing registered
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_module_resolver(DummyModuleResolver::new())
+        .disable_symbol("eval")
+        .set_max_operations(MAX_OPERATIONS)
+        .set_max_string_size(MAX_STRING_SIZE)
+        .set_max_array_size(MAX_ARRAY_SIZE)
+        .set_max_map_size(MAX_MAP_SIZE)
+        .set_max_call_levels(MAX_CALL_LEVELS)
+        .set_max_expr_depths(MAX_EXPR_DEPTH, MAX_EXPR_DEPTH)
+        .set_max_variables(MAX_VARIABLES)
+        .set_max_functions(MAX_FUNCTIONS)
+        .set_max_modules(0)
+        .on_print(|_| {})
+        .on_debug(|_, _, _| {});
+    // Blocking would stall the run without counting operations
+    engine.register_fn("sleep", |_: INT| -> Result<(), Box<EvalAltResult>> {
+        Err("sleep is not available to scripts".into())
+    });
+    engine.register_fn("sleep", |_: FLOAT| -> Result<(), Box<EvalAltResult>> {
+        Err("sleep is not available to scripts".into())
+    });
+    engine
+}
+
+fn compile(engine: &Engine, source: &str) -> Result<AST, ScriptError> {
+    if source.len() > MAX_SOURCE_SIZE {
+        return Err(ScriptError::TooLarge);
+    }
+    let ast = engine.compile(source).map_err(|e| {
+        ScriptError::Compile(Diagnostic::new(e.err_type().to_string(), e.position()))
+    })?;
+    let has_handler = ast
+        .iter_functions()
+        .any(|f| f.name == HANDLER && f.params.len() == 1);
+    if has_handler {
+        Ok(ast)
+    } else {
+        Err(ScriptError::MissingHandler)
+    }
+}
+
+/// Problems that stop a script from compiling; empty when it is fine
+pub fn check(source: &str) -> Vec<Diagnostic> {
+    match compile(&engine(), source) {
+        Ok(_) => Vec::new(),
+        Err(e) => vec![e.diagnostic()],
+    }
+}
+
+fn bar_map(candle: &OhlcPoint) -> Map {
+    let mut bar = Map::new();
+    bar.insert("time".into(), Dynamic::from_int(candle.time * 1000));
+    bar.insert("open".into(), Dynamic::from_float(candle.open));
+    bar.insert("high".into(), Dynamic::from_float(candle.high));
+    bar.insert("low".into(), Dynamic::from_float(candle.low));
+    bar.insert("close".into(), Dynamic::from_float(candle.close));
+    bar.insert(
+        "volume".into(),
+        candle.volume.map_or(Dynamic::UNIT, Dynamic::from_float),
+    );
+    bar
+}
+
+fn period(period: INT) -> Result<usize, Box<EvalAltResult>> {
+    usize::try_from(period)
+        .ok()
+        .filter(|p| (1..=HISTORY).contains(p))
+        .ok_or_else(|| format!("Indicator periods must be between 1 and {}", HISTORY).into())
+}
+
+/// A series' value at the latest candle, or `()`
+fn latest(series: &[Option<f64>]) -> Dynamic {
+    series
+        .last()
+        .copied()
+        .flatten()
+        .map_or(Dynamic::UNIT, Dynamic::from_float)
+}
+
+fn closes(candles: &[OhlcPoint]) -> Vec<f64> {
+    candles.iter().map(|c| c.close).collect()
+}
+
+fn order_size(size: FLOAT) -> Result<FLOAT, Box<EvalAltResult>> {
+    if size.is_finite() && size > 0.0 {
+        Ok(size)
+    } else {
+        Err("Order size must be a positive number".into())
+    }
+}
+
+/// An indicator computed from candles with one period
+type Series = fn(&[OhlcPoint], usize) -> Vec<Option<f64>>;
+
+fn register_api(engine: &mut Engine, session: &Arc<Mutex<Session>>) {
+    let shared = || Arc::clone(session);
+
+    let s = shared();
+    engine.register_fn("symbol", move || s.lock().unwrap().symbol.clone());
+    let s = shared();
+    engine.register_fn("time", move || {
+        s.lock()
+            .unwrap()
+            .candles
+            .last()
+            .map_or(Dynamic::UNIT, |c| Dynamic::from_int(c.time * 1000))
+    });
+    let s = shared();
+    engine.register_fn("price", move || {
+        s.lock()
+            .unwrap()
+            .last_close()
+            .map_or(Dynamic::UNIT, Dynamic::from_float)
+    });
+    let s = shared();
+    engine.register_fn("bars", move |n: INT| -> Array {
+        let session = s.lock().unwrap();
+        let n = usize::try_from(n).unwrap_or(0).min(session.candles.len());
+        session.candles[session.candles.len() - n..]
+            .iter()
+            .map(|c| Dynamic::from_map(bar_map(c)))
+            .collect()
+    });
+    let s = shared();
+    engine.register_fn("closes", move |n: INT| -> Array {
+        let session = s.lock().unwrap();
+        let n = usize::try_from(n).unwrap_or(0).min(session.candles.len());
+        session.candles[session.candles.len() - n..]
+            .iter()
+            .map(|c| Dynamic::from_float(c.close))
+            .collect()
+    });
+
+    let single: [(&str, Series); 8] = [
+        ("sma", |c, p| indicators::sma(&closes(c), p)),
+        ("ema", |c, p| indicators::ema(&closes(c), p)),
+        ("rsi", |c, p| indicators::rsi(&closes(c), p)),
+        ("cci", indicators::cci),
+        ("mfi", indicators::mfi),
+        ("atr", indicators::atr),
+        ("adx", |c, p| indicators::adx(c, p).adx),
+        ("stochastic", |c, p| indicators::stochastic(c, p, 1).k),
+    ];
+    for (name, series) in single {
+        let s = shared();
+        engine.register_fn(name, move |p: INT| -> Result<Dynamic, Box<EvalAltResult>> {
+            let p = period(p)?;
+            Ok(latest(&series(&s.lock().unwrap().candles, p)))
+        });
+    }
+    let s = shared();
+    engine.register_fn(
+        "macd",
+        move |fast: INT, slow: INT, signal: INT| -> Result<Dynamic, Box<EvalAltResult>> {
+            let (fast, slow, signal) = (period(fast)?, period(slow)?, period(signal)?);
+            let macd = indicators::macd(&closes(&s.lock().unwrap().candles), fast, slow, signal);
+            let mut map = Map::new();
+            map.insert("macd".into(), latest(&macd.macd));
+            map.insert("signal".into(), latest(&macd.signal));
+            map.insert("histogram".into(), latest(&macd.histogram));
+            Ok(Dynamic::from_map(map))
+        },
+    );
+    let bollinger = {
+        let s = shared();
+        move |p: INT, width: FLOAT| -> Result<Dynamic, Box<EvalAltResult>> {
+            let p = period(p)?;
+            let bands = indicators::bollinger(&closes(&s.lock().unwrap().candles), p, width);
+            let mut map = Map::new();
+            map.insert("upper".into(), latest(&bands.upper));
+            map.insert("middle".into(), latest(&bands.middle));
+            map.insert("lower".into(), latest(&bands.lower));
+            Ok(Dynamic::from_map(map))
+        }
+    };
+    let by_int = bollinger.clone();
+    engine.register_fn("bollinger", bollinger);
+    engine.register_fn("bollinger", move |p: INT, width: INT| {
+        by_int(p, width as FLOAT)
+    });
+
+    let place = |sign: FLOAT| {
+        let s = shared();
+        move |size: FLOAT| -> Result<(), Box<EvalAltResult>> {
+            let size = order_size(size)?;
+            s.lock().unwrap().account.place(Order::Market(sign * size));
+            Ok(())
+        }
+    };
+    for (name, sign) in [("buy", 1.0), ("sell", -1.0)] {
+        let by_float = place(sign);
+        let by_int = place(sign);
+        engine.register_fn(name, by_float);
+        engine.register_fn(name, move |size: INT| by_int(size as FLOAT));
+    }
+    let s = shared();
+    engine.register_fn("close_position", move || {
+        s.lock().unwrap().account.place(Order::Flatten);
+    });
+    let s = shared();
+    engine.register_fn("position", move || s.lock().unwrap().account.size());
+    let s = shared();
+    engine.register_fn("entry_price", move || {
+        s.lock()
+            .unwrap()
+            .account
+            .entry()
+            .map_or(Dynamic::UNIT, Dynamic::from_float)
+    });
+    let s = shared();
+    engine.register_fn("cash", move || s.lock().unwrap().account.cash());
+    let s = shared();
+    engine.register_fn("equity", move || {
+        let session = s.lock().unwrap();
+        match session.last_close() {
+            Some(price) => session.account.equity(price),
+            None => session.account.cash(),
+        }
+    });
+}
+
+/// A compiled script with its sandbox, history and paper account
+pub struct Script {
+    engine: Engine,
+    ast: AST,
+    scope: Scope<'static>,
+    /// Bound as `this` in the handler
+    state: Dynamic,
+    session: Arc<Mutex<Session>>,
+    /// When the running call must end
+    deadline: Arc<Mutex<Instant>>,
+    /// When the whole run must end, if it is bounded
+    run_deadline: Option<Instant>,
+    cancel: Arc<AtomicBool>,
+    log: Logger,
+}
+
+impl Script {
+    /// Compile `source` and run its top level once
+    pub fn new(
+        source: &str,
+        symbol: &str,
+        config: BacktestConfig,
+        cancel: Arc<AtomicBool>,
+        log: Logger,
+    ) -> Result<Self, ScriptError> {
+        let mut engine = engine();
+        let ast = compile(&engine, source)?;
+
+        let session = Arc::new(Mutex::new(Session {
+            symbol: symbol.to_string(),
+            candles: Vec::new(),
+            account: PaperAccount::new(symbol, config),
+        }));
+        register_api(&mut engine, &session);
+
+        let deadline = Arc::new(Mutex::new(Instant::now()));
+        let (limit, stop) = (Arc::clone(&deadline), Arc::clone(&cancel));
+        engine.on_progress(move |operations| {
+            if operations % CHECK_EVERY != 0 {
+                None
+            } else if stop.load(Ordering::Relaxed) {
+                Some(STOPPED.into())
+            } else if Instant::now() >= *limit.lock().unwrap() {
+                Some(TIMED_OUT.into())
+            } else {
+                None
+            }
+        });
+        let printer = Arc::clone(&log);
+        engine.on_print(move |text| printer(LogLevel::Info, text.to_string(), None));
+        let printer = Arc::clone(&log);
+        engine.on_debug(move |text, _, position| {
+            printer(LogLevel::Debug, text.to_string(), position.line())
+        });
+
+        let mut script = Self {
+            engine,
+            ast,
+            scope: Scope::new(),
+            state: Dynamic::from_map(Map::new()),
+            session,
+            deadline,
+            run_deadline: None,
+            cancel,
+            log,
+        };
+        script.arm()?;
+        let ran = script
+            .engine
+            .run_ast_with_scope(&mut script.scope, &script.ast);
+        ran.map_err(|e| Self::error(*e))?;
+        Ok(script)
+    }
+
+    /// Bound the whole run, on top of the per-call limit
+    pub fn set_time_limit(&mut self, limit: Duration) {
+        self.run_deadline = Some(Instant::now() + limit);
+    }
+
+    /// Whether the run was asked to stop
+    pub fn stopped(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+
+    /// Set the deadline for the next call, failing if the run is already over
+    fn arm(&mut self) -> Result<(), ScriptError> {
+        if self.stopped() {
+            return Err(ScriptError::Stopped);
+        }
+        let now = Instant::now();
+        let mut deadline = now + CALL_TIMEOUT;
+        if let Some(end) = self.run_deadline {
+            if now >= end {
+                return Err(ScriptError::TimedOut);
+            }
+            deadline = deadline.min(end);
+        }
+        *self.deadline.lock().unwrap() = deadline;
+        Ok(())
+    }
+
+    fn error(mut err: EvalAltResult) -> ScriptError {
+        if let EvalAltResult::ErrorTerminated(reason, _) = err.unwrap_inner() {
+            return if reason.to_string() == STOPPED {
+                ScriptError::Stopped
+            } else {
+                ScriptError::TimedOut
+            };
+        }
+        let position = err.take_position();
+        ScriptError::Runtime(Diagnostic::new(err.to_string(), position))
+    }
+
+    /// Add candles to the history without calling the handler
+    pub fn seed(&mut self, candles: &[OhlcPoint]) {
+        let mut session = self.session.lock().unwrap();
+        session.candles.extend_from_slice(candles);
+        let excess = session.candles.len().saturating_sub(HISTORY);
+        session.candles.drain(..excess);
+    }
+
+    /// The latest candle in the history
+    pub fn last_bar(&self) -> Option<OhlcPoint> {
+        self.session.lock().unwrap().candles.last().copied()
+    }
+
+    /// Fill queued orders at `price` and log what happened to them
+    pub fn fill(&mut self, price: f64, time: i64) {
+        let (symbol, fills) = {
+            let mut session = self.session.lock().unwrap();
+            let fills = session.account.fill(price, time);
+            (session.symbol.clone(), fills)
+        };
+        for fill in fills {
+            let (level, message) = match fill {
+                Fill::Filled { trade, requested } => {
+                    let verb = match trade.side {
+                        OrderSide::Buy => "Bought",
+                        OrderSide::Sell => "Sold",
+                    };
+                    let mut message =
+                        format!("{} {} {} at {}", verb, trade.size, symbol, trade.price);
+                    if trade.size < requested.abs() * (1.0 - 1e-9) {
+                        message.push_str(&format!(
+                            ", cut down from {} to stay within equity",
+                            requested.abs()
+                        ));
+                    }
+                    (LogLevel::Info, message)
+                }
+                Fill::Rejected { requested } => (
+                    LogLevel::Warn,
+                    format!(
+                        "Order for {} {} rejected: no equity left to cover it",
+                        requested.abs(),
+                        symbol
+                    ),
+                ),
+            };
+            (self.log)(level, message, None);
+        }
+    }
+
+    /// Add a candle, or replace the latest one if it has the same time, and
+    /// call the handler with it
+    pub fn on_bar(&mut self, candle: &OhlcPoint) -> Result<(), ScriptError> {
+        {
+            let mut session = self.session.lock().unwrap();
+            match session.candles.last_mut() {
+                Some(last) if last.time == candle.time => *last = *candle,
+                _ => {
+                    session.candles.push(*candle);
+                    if session.candles.len() > HISTORY {
+                        session.candles.remove(0);
+                    }
+                }
+            }
+        }
+        self.arm()?;
+        let options = CallFnOptions::new()
+            .eval_ast(false)
+            .bind_this_ptr(&mut self.state);
+        let called = self.engine.call_fn_with_options::<Dynamic>(
+            options,
+            &mut self.scope,
+            &self.ast,
+            HANDLER,
+            (Dynamic::from_map(bar_map(candle)),),
+        );
+        called.map(|_| ()).map_err(|e| Self::error(*e))
+    }
+
+    /// Record equity at `price` on the run's curve
+    pub fn mark(&mut self, price: f64, time: i64) {
+        self.session.lock().unwrap().account.mark(price, time);
+    }
+
+    /// Close any open position at `price` and summarise the run
+    pub fn finish(&mut self, price: f64, time: i64) -> BacktestResult {
+        self.session.lock().unwrap().account.finish(price, time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candle(time: i64, close: f64) -> OhlcPoint {
+        OhlcPoint {
+            time,
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: None,
+        }
+    }
+
+    fn load(source: &str) -> (Result<Script, ScriptError>, Arc<Mutex<Vec<String>>>) {
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&lines);
+        let log: Logger = Arc::new(move |_, message, _| sink.lock().unwrap().push(message));
+        let config = BacktestConfig {
+            initial_balance: 1000.0,
+            fee_pct: 0.0,
+            slippage_pct: 0.0,
+        };
+        let script = Script::new(source, "BTC", config, Arc::new(AtomicBool::new(false)), log);
+        (script, lines)
+    }
+
+    #[test]
+    fn compile_errors_have_positions() {
+        let diagnostics = check("fn on_bar(bar) {\n    let x = ;\n}");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].line, Some(2));
+        assert!(diagnostics[0].column.is_some());
+
+        assert!(check("fn on_bar(bar) { print(bar.close); }").is_empty());
+        assert_eq!(
+            check("fn on_tick(bar) {}")[0].message,
+            ScriptError::MissingHandler.to_string()
+        );
+        assert!(!check("eval(\"1\"); fn on_bar(bar) {}").is_empty());
+        assert!(matches!(
+            compile(&engine(), &" ".repeat(MAX_SOURCE_SIZE + 1)),
+            Err(ScriptError::TooLarge)
+        ));
+    }
+
+    #[test]
+    fn print_and_state_between_bars() {
+        let (script, lines) = load(
+            r#"
+            print("loaded");
+            fn on_bar(bar) {
+                if this.count == () { this.count = 0; }
+                this.count += 1;
+                print(`${this.count}: ${bar.close}`);
+            }
+            "#,
+        );
+        let mut script = script.unwrap();
+        script.on_bar(&candle(1, 10.0)).unwrap();
+        script.on_bar(&candle(2, 11.0)).unwrap();
+        assert_eq!(*lines.lock().unwrap(), ["loaded", "1: 10.0", "2: 11.0"]);
+    }
+
+    #[test]
+    fn enforces_limits() {
+        let (script, _) = load("fn on_bar(bar) { loop {} }");
+        let mut script = script.unwrap();
+        assert!(matches!(
+            script.on_bar(&candle(1, 1.0)),
+            Err(ScriptError::Runtime(_) | ScriptError::TimedOut)
+        ));
+
+        let (script, _) = load("fn on_bar(bar) { let s = \"x\"; loop { s += s; } }");
+        let err = script.unwrap().on_bar(&candle(1, 1.0)).unwrap_err();
+        assert!(err.to_string().contains("too large"), "{}", err);
+
+        let (script, _) = load("fn on_bar(bar) { sleep(10); }");
+        assert!(script.unwrap().on_bar(&candle(1, 1.0)).is_err());
+
+        // A top level that never finishes fails to load
+        let (script, _) = load("loop {} fn on_bar(bar) {}");
+        assert!(script.is_err());
+
+        let (script, _) = load("fn on_bar(bar) {}");
+        let mut script = script.unwrap();
+        script.cancel.store(true, Ordering::Relaxed);
+        assert!(matches!(
+            script.on_bar(&candle(1, 1.0)),
+            Err(ScriptError::Stopped)
+        ));
+    }
+
+    #[test]
+    fn runtime_errors_have_positions() {
+        let (script, _) = load("fn on_bar(bar) {\n    let x = bar.close / \"a\";\n}");
+        let err = script.unwrap().on_bar(&candle(1, 1.0)).unwrap_err();
+        let ScriptError::Runtime(diagnostic) = err else {
+            panic!("expected a runtime error, got {}", err);
+        };
+        assert!(diagnostic.line.is_some());
+    }
+
+    #[test]
+    fn market_data_and_indicators() {
+        let (script, lines) = load(
+            r#"
+            fn on_bar(bar) {
+                print(`${symbol()} ${time()} ${price()} ${closes(10).len()} ${sma(3)}`);
+                let bands = bollinger(3, 2);
+                print(`${bars(1)[0].close} ${bands.middle}`);
+            }
+            "#,
+        );
+        let mut script = script.unwrap();
+        script.seed(&[candle(1, 1.0), candle(2, 2.0)]);
+        script.on_bar(&candle(3, 3.0)).unwrap();
+        assert_eq!(*lines.lock().unwrap(), ["BTC 3000 3.0 3 2.0", "3.0 2.0"]);
+
+        let (script, _) = load("fn on_bar(bar) { sma(0); }");
+        assert!(script.unwrap().on_bar(&candle(1, 1.0)).is_err());
+    }
+
+    #[test]
+    fn paper_orders() {
+        let (script, lines) = load(
+            r#"
+            fn on_bar(bar) {
+                if position() == 0.0 && bar.close < 100.0 { buy(2); }
+                if position() > 0.0 && bar.close > 100.0 { close_position(); }
+            }
+            "#,
+        );
+        let mut script = script.unwrap();
+        for (time, price) in [(1, 90.0), (2, 95.0), (3, 110.0), (4, 120.0)] {
+            script.fill(price, time * 1000);
+            script.on_bar(&candle(time, price)).unwrap();
+        }
+        let result = script.finish(120.0, 4000);
+        assert_eq!(result.trades.len(), 2);
+        assert_eq!(result.trades[0].price, 95.0);
+        assert_eq!(result.trades[1].price, 120.0);
+        assert_eq!(result.summary.end_value, 1050.0);
+        assert_eq!(lines.lock().unwrap()[0], "Bought 2 BTC at 95");
+    }
+}
@@ -0,0 +1,466 @@
+//! Confirm-before-execute for trade deep links
+//!
+//! A `wraith://trade` link never places an order by itself. The parsed
+//! order is shown in a native dialog for the active portfolio of the
+//! signed-in session, and is placed only once the user approves it, with
+//! the session of the identity that is active at that moment. Only
+//! one trade link is up for confirmation at a time; links arriving
+//! meanwhile are dropped rather than queued behind it.
+//!
+//! Every trade link is recorded in `trade-audit.jsonl`, one JSON line per
+//! step: when it arrives, and how it ended (invalid, dropped, signed out,
+//! declined, placed or failed). Session tokens are never written.
+
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+use crate::alerts::format_price;
+use crate::deep_link::{DeepLinkError, TradeLink};
+use crate::focus_main_window;
+use crate::haunt::models::{AssetClass, OrderSide, OrderType, PlaceOrderRequest};
+use crate::haunt::HauntClient;
+use crate::identity;
+use crate::notifications::{self, Notification, NotificationCategory};
+use crate::tray;
+use crate::{new_id, now_millis};
+
+const AUDIT_FILE: &str = "trade-audit.jsonl";
+/// Characters of a link kept in the audit log
+const MAX_AUDIT_LINK_LEN: usize = 2048;
+const DEFAULT_AUDIT_LIMIT: usize = 100;
+
+/// What happened to a trade link
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditEvent {
+    /// A valid link arrived and is up for confirmation
+    Received,
+    /// The link failed to parse
+    Invalid,
+    /// Another trade link was awaiting confirmation
+    Dropped,
+    /// No session to place the order with
+    SignedOut,
+    Declined,
+    Placed,
+    Failed,
+}
+
+/// A line of the audit log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    pub at: i64,
+    /// Shared by all entries for the same link
+    pub request_id: String,
+    pub event: AuditEvent,
+    pub link: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order: Option<PlaceOrderRequest>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl AuditEntry {
+    fn new(request_id: &str, event: AuditEvent, link: &str) -> Self {
+        Self {
+            at: now_millis(),
+            request_id: request_id.to_string(),
+            event,
+            link: link.chars().take(MAX_AUDIT_LINK_LEN).collect(),
+            order: None,
+            order_id: None,
+            error: None,
+        }
+    }
+}
+
+/// Managed state for trade link confirmation
+#[derive(Default)]
+pub struct TradeConfirmState {
+    /// Request id of the link awaiting confirmation
+    open: Mutex<Option<String>>,
+    /// Serializes appends to the audit log
+    audit_lock: Mutex<()>,
+    data_dir: Mutex<Option<PathBuf>>,
+}
+
+impl TradeConfirmState {
+    fn audit(&self, entry: &AuditEntry) {
+        let Some(dir) = self.data_dir.lock().unwrap().clone() else {
+            return;
+        };
+        let _guard = self.audit_lock.lock().unwrap();
+        if let Err(e) = append_audit(&dir, entry) {
+            eprintln!("Failed to write trade audit log: {}", e);
+        }
+    }
+
+    /// Claim the confirmation dialog for `request_id`
+    fn begin(&self, request_id: &str) -> bool {
+        let mut open = self.open.lock().unwrap();
+        if open.is_some() {
+            return false;
+        }
+        *open = Some(request_id.to_string());
+        true
+    }
+
+    fn end(&self) {
+        *self.open.lock().unwrap() = None;
+    }
+}
+
+fn append_audit(dir: &Path, entry: &AuditEntry) -> std::io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let mut line =
+        serde_json::to_vec(entry).map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+    line.push(b'\n');
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(AUDIT_FILE))?;
+    file.write_all(&line)
+}
+
+/// The newest `limit` entries, newest first; unreadable lines are skipped
+fn read_audit(dir: &Path, limit: usize) -> std::io::Result<Vec<AuditEntry>> {
+    let file = match fs::File::open(dir.join(AUDIT_FILE)) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut entries = Vec::new();
+    for line in BufReader::new(file).lines() {
+        if let Ok(entry) = serde_json::from_str::<AuditEntry>(&line?) {
+            entries.push(entry);
+        }
+    }
+    entries.reverse();
+    entries.truncate(limit);
+    Ok(entries)
+}
+
+/// Text of the confirmation dialog
+fn describe(order: &PlaceOrderRequest, identity: &str) -> String {
+    let side = match order.side {
+        OrderSide::Buy => "Buy",
+        OrderSide::Sell => "Sell",
+    };
+    let market = match order.asset_class {
+        AssetClass::CryptoSpot => "Spot",
+        AssetClass::Perp => "Perpetual",
+        AssetClass::Stock => "Stock",
+        AssetClass::Etf => "ETF",
+        AssetClass::Option => "Option",
+    };
+    let price = |price: Option<f64>| price.map(format_price).unwrap_or_default();
+    let order_type = match order.order_type {
+        OrderType::Limit => format!("Limit at {}", price(order.price)),
+        OrderType::StopLimit => format!(
+            "Stop limit at {}, triggered at {}",
+            price(order.price),
+            price(order.stop_price)
+        ),
+        _ => "Market".to_string(),
+    };
+
+    let mut lines = vec![
+        format!("{} {} {}", side, order.quantity, order.symbol),
+        String::new(),
+        format!("Type: {}", order_type),
+        format!("Market: {}", market),
+    ];
+    if let Some(leverage) = order.leverage {
+        lines.push(format!("Leverage: {}x", leverage));
+    }
+    if let Some(stop_loss) = order.stop_loss {
+        lines.push(format!("Stop loss: {}", format_price(stop_loss)));
+    }
+    if let Some(take_profit) = order.take_profit {
+        lines.push(format!("Take profit: {}", format_price(take_profit)));
+    }
+    lines.push(format!("Portfolio: {}", order.portfolio_id));
+    lines.push(format!("Identity: {}", identity));
+    lines.push(String::new());
+    lines.push("This order was requested by a link. Place it only if you meant to.".to_string());
+    lines.join("\n")
+}
+
+/// Ask for approval; runs the dialog off the async runtime
+async fn approve(app: &AppHandle, order: &PlaceOrderRequest, identity: &str) -> bool {
+    let dialog = app
+        .dialog()
+        .message(describe(order, identity))
+        .title("Confirm Order")
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            "Place Order".to_string(),
+            "Cancel".to_string(),
+        ));
+    tauri::async_runtime::spawn_blocking(move || dialog.blocking_show())
+        .await
+        .unwrap_or(false)
+}
+
+fn notify(app: &AppHandle, title: &str, body: String) {
+    let notification = Notification {
+        category: NotificationCategory::Trade,
+        ..Notification::new(title, body)
+    };
+    let _ = notifications::show(app, notification);
+}
+
+/// Record a link that found no session to place its order with
+fn signed_out(app: &AppHandle, entry: &AuditEntry) {
+    app.state::<TradeConfirmState>().audit(entry);
+    notify(
+        app,
+        "Order link ignored",
+        "Sign in to Wraith, then open the link again.".to_string(),
+    );
+}
+
+/// Record a trade link that failed to parse
+pub fn audit_invalid(app: &AppHandle, link: &str, error: &DeepLinkError) {
+    let entry = AuditEntry {
+        error: Some(error.to_string()),
+        ..AuditEntry::new(&new_id(), AuditEvent::Invalid, link)
+    };
+    app.state::<TradeConfirmState>().audit(&entry);
+}
+
+/// Ask to place the order a trade link carries
+pub fn request(app: &AppHandle, link: &str, trade: TradeLink) {
+    let app = app.clone();
+    let link = link.to_string();
+    tauri::async_runtime::spawn(async move { confirm_and_place(&app, &link, trade).await });
+}
+
+async fn confirm_and_place(app: &AppHandle, link: &str, trade: TradeLink) {
+    let state = app.state::<TradeConfirmState>();
+    let request_id = new_id();
+
+    let signed_in = identity::active_session(app).zip(tray::portfolio_id(app));
+    let Some(((identity, _), portfolio_id)) = signed_in else {
+        signed_out(
+            app,
+            &AuditEntry::new(&request_id, AuditEvent::SignedOut, link),
+        );
+        return;
+    };
+    let order = trade.order(&portfolio_id);
+    let entry = AuditEntry {
+        order: Some(order.clone()),
+        ..AuditEntry::new(&request_id, AuditEvent::Received, link)
+    };
+    state.audit(&entry);
+
+    if !state.begin(&request_id) {
+        state.audit(&AuditEntry {
+            event: AuditEvent::Dropped,
+            at: now_millis(),
+            ..entry
+        });
+        notify(
+            app,
+            "Order link ignored",
+            "Another order from a link is waiting for confirmation.".to_string(),
+        );
+        return;
+    }
+    focus_main_window(app);
+    let approved = approve(app, &order, &identity).await;
+    state.end();
+
+    if !approved {
+        state.audit(&AuditEntry {
+            event: AuditEvent::Declined,
+            at: now_millis(),
+            ..entry
+        });
+        return;
+    }
+
+    // The identity may have been switched or signed out while the dialog
+    // was open; never place the order with another identity's session
+    let token = match identity::active_session(app) {
+        Some((active, session)) if active == identity => session.session_token,
+        _ => {
+            let entry = AuditEntry {
+                event: AuditEvent::SignedOut,
+                at: now_millis(),
+                ..entry
+            };
+            signed_out(app, &entry);
+            return;
+        }
+    };
+
+    let client = app.state::<HauntClient>();
+    match client.place_order(&token, &order).await {
+        Ok(placed) => {
+            let placed = placed.data;
+            state.audit(&AuditEntry {
+                event: AuditEvent::Placed,
+                at: now_millis(),
+                order_id: Some(placed.id),
+                ..entry
+            });
+            notify(
+                app,
+                "Order placed",
+                format!("{} {}", placed.size, placed.symbol),
+            );
+        }
+        Err(e) => {
+            state.audit(&AuditEntry {
+                event: AuditEvent::Failed,
+                at: now_millis(),
+                error: Some(e.to_string()),
+                ..entry
+            });
+            notify(app, "Order failed", e.to_string());
+        }
+    }
+}
+
+pub fn init(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+    let dir = app.path().app_data_dir()?;
+    *app.state::<TradeConfirmState>().data_dir.lock().unwrap() = Some(dir);
+    Ok(())
+}
+
+// ========== Commands ==========
+
+/// Tauri command: Get the newest entries of the trade link audit log
+#[tauri::command]
+pub fn trade_confirm_audit(
+    state: State<'_, TradeConfirmState>,
+    limit: Option<usize>,
+) -> Result<Vec<AuditEntry>, String> {
+    let Some(dir) = state.data_dir.lock().unwrap().clone() else {
+        return Ok(Vec::new());
+    };
+    let _guard = state.audit_lock.lock().unwrap();
+    read_audit(&dir, limit.unwrap_or(DEFAULT_AUDIT_LIMIT)).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deep_link::DeepLink;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "wraith-trade-confirm-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn parsed_order(link: &str) -> PlaceOrderRequest {
+        match DeepLink::parse(link).unwrap() {
+            DeepLink::Trade(trade) => trade.order("p1"),
+            route => panic!("not a trade link: {:?}", route),
+        }
+    }
+
+    #[test]
+    fn describes_the_parsed_order() {
+        let order = parsed_order(
+            "wraith://trade?symbol=btc&side=buy&size=0.5&type=limit&price=60000&leverage=10&sl=55000&tp=70000",
+        );
+        assert_eq!(order.asset_class, AssetClass::Perp);
+        assert_eq!(order.order_type, OrderType::Limit);
+        assert_eq!(
+            describe(&order, "Trading"),
+            "Buy 0.5 BTC\n\
+             \n\
+             Type: Limit at $60,000.00\n\
+             Market: Perpetual\n\
+             Leverage: 10x\n\
+             Stop loss: $55,000.00\n\
+             Take profit: $70,000.00\n\
+             Portfolio: p1\n\
+             Identity: Trading\n\
+             \n\
+             This order was requested by a link. Place it only if you meant to."
+        );
+
+        let order = parsed_order("wraith://trade?symbol=eth&side=sell&size=2");
+        assert!(describe(&order, "Trading")
+            .starts_with("Sell 2 ETH\n\nType: Market\nMarket: Spot\nPortfolio: p1"));
+    }
+
+    #[test]
+    fn audit_log_round_trips_newest_first() {
+        let dir = temp_dir("audit");
+        assert!(read_audit(&dir, 10).unwrap().is_empty());
+
+        let link = "wraith://trade?symbol=BTC&side=buy&size=1";
+        let received = AuditEntry {
+            order: Some(parsed_order(link)),
+            ..AuditEntry::new("r1", AuditEvent::Received, link)
+        };
+        append_audit(&dir, &received).unwrap();
+        append_audit(
+            &dir,
+            &AuditEntry {
+                event: AuditEvent::Placed,
+                order_id: Some("o1".to_string()),
+                ..received.clone()
+            },
+        )
+        .unwrap();
+        // A torn line from a crash doesn't hide the rest
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(dir.join(AUDIT_FILE))
+            .unwrap();
+        file.write_all(b"{\"at\":1,\n").unwrap();
+        append_audit(
+            &dir,
+            &AuditEntry::new("r2", AuditEvent::Invalid, "wraith://trade"),
+        )
+        .unwrap();
+
+        let entries = read_audit(&dir, 10).unwrap();
+        let events: Vec<(&str, AuditEvent)> = entries
+            .iter()
+            .map(|e| (e.request_id.as_str(), e.event))
+            .collect();
+        assert_eq!(
+            events,
+            [
+                ("r2", AuditEvent::Invalid),
+                ("r1", AuditEvent::Placed),
+                ("r1", AuditEvent::Received),
+            ]
+        );
+        assert_eq!(entries[1].order_id.as_deref(), Some("o1"));
+        assert_eq!(entries[2].order.as_ref().unwrap().quantity, 1.0);
+        assert_eq!(read_audit(&dir, 1).unwrap().len(), 1);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn one_confirmation_at_a_time() {
+        let state = TradeConfirmState::default();
+        assert!(state.begin("a"));
+        assert!(!state.begin("b"));
+        state.end();
+        assert!(state.begin("b"));
+    }
+}
@@ -0,0 +1,1075 @@
+//! System tray
+//!
+//! The tray carries a configurable ticker, prices for a few symbols and
+//! today's P&L of the active portfolio, in its title (where the platform
+//! has one) and tooltip, plus a Watchlist submenu of pinned symbols with
+//! their last price and 24h change. Stream updates only mark the ticker
+//! dirty; a timer applies them, touching just the labels that changed, and
+//! the menu itself is rebuilt only when the settings change.
+//!
+//! Once the frontend hands over a session, the menu also switches the
+//! active portfolio and offers quick trading actions: pausing the RAT
+//! auto-trader, cancelling all open orders and toggling drawdown
+//! protection. Anything that can't be undone from the tray asks for
+//! confirmation in a native dialog first. Position and order updates from
+//! the stream trigger a debounced refetch, and the menu is rebuilt when the
+//! portfolios, positions or orders it shows have changed.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::menu::{
+    CheckMenuItemBuilder, Menu, MenuBuilder, MenuItem, MenuItemBuilder, Submenu, SubmenuBuilder,
+};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
+use tauri::{AppHandle, Emitter, Manager, State, Wry};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::Notify;
+
+use crate::alerts::format_price;
+use crate::deep_link;
+use crate::focus_main_window;
+use crate::haunt::socket::{PriceUpdate, WsMessage};
+use crate::haunt::{HauntClient, HauntSocket};
+use crate::notifications::{self, Notification, NotificationCategory};
+
+const TRAY_ID: &str = "main";
+const SETTINGS_FILE: &str = "tray.json";
+/// How often pending ticker changes are applied to the tray
+const REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+/// How often today's P&L is fetched; portfolio updates move it in between
+const PNL_POLL_INTERVAL: Duration = Duration::from_secs(60);
+const MAX_TICKER_SYMBOLS: usize = 3;
+const MAX_WATCHLIST: usize = 20;
+/// Menu id prefix of watchlist entries
+const WATCH_PREFIX: &str = "watch:";
+/// Menu id prefix of portfolio entries
+const PORTFOLIO_PREFIX: &str = "portfolio:";
+/// How long to wait for a burst of position and order updates to settle
+const TRADING_DEBOUNCE: Duration = Duration::from_secs(1);
+
+/// Event carrying the portfolio id picked from the tray
+pub const PORTFOLIO_EVENT: &str = "tray-portfolio-selected";
+
+/// What the tray shows, persisted to `tray.json`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TraySettings {
+    /// Symbols shown in the tray title and tooltip
+    pub ticker: Vec<String>,
+    /// Show today's P&L of the active portfolio
+    pub show_pnl: bool,
+    /// Pinned symbols listed in the Watchlist submenu
+    pub watchlist: Vec<String>,
+}
+
+impl Default for TraySettings {
+    fn default() -> Self {
+        Self {
+            ticker: vec!["btc".to_string()],
+            show_pnl: true,
+            watchlist: vec!["btc".to_string(), "eth".to_string(), "sol".to_string()],
+        }
+    }
+}
+
+impl TraySettings {
+    /// Lowercase, deduplicated symbols within the size limits
+    fn normalized(mut self) -> Result<Self, String> {
+        normalize_symbols(&mut self.ticker)?;
+        normalize_symbols(&mut self.watchlist)?;
+        if self.ticker.len() > MAX_TICKER_SYMBOLS {
+            return Err(format!(
+                "The ticker shows at most {} symbols",
+                MAX_TICKER_SYMBOLS
+            ));
+        }
+        if self.watchlist.len() > MAX_WATCHLIST {
+            return Err(format!(
+                "The watchlist holds at most {} symbols",
+                MAX_WATCHLIST
+            ));
+        }
+        Ok(self)
+    }
+
+    /// Symbols the tray needs prices for
+    fn symbols(&self) -> BTreeSet<String> {
+        self.ticker.iter().chain(&self.watchlist).cloned().collect()
+    }
+
+    fn wants(&self, symbol: &str) -> bool {
+        self.ticker
+            .iter()
+            .chain(&self.watchlist)
+            .any(|s| s == symbol)
+    }
+}
+
+fn normalize_symbols(symbols: &mut Vec<String>) -> Result<(), String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for symbol in symbols.drain(..) {
+        let symbol = symbol.trim().to_lowercase();
+        if symbol.is_empty() || !symbol.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(format!("Invalid symbol \"{}\"", symbol));
+        }
+        if !normalized.contains(&symbol) {
+            normalized.push(symbol);
+        }
+    }
+    *symbols = normalized;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Quote {
+    price: f64,
+    change_24h: Option<f64>,
+}
+
+/// Today's P&L as last fetched, moved along by live portfolio values
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct DayPnl {
+    pnl: f64,
+    value: f64,
+    live_value: f64,
+}
+
+/// Latest prices and P&L, and whether the tray is behind them
+#[derive(Debug, Default)]
+struct Ticker {
+    quotes: HashMap<String, Quote>,
+    pnl: Option<DayPnl>,
+    dirty: bool,
+}
+
+impl Ticker {
+    fn on_price(&mut self, update: &PriceUpdate) {
+        let symbol = update.symbol.to_lowercase();
+        // Not every update carries the 24h change
+        let change_24h = update
+            .change_24h
+            .or_else(|| self.quotes.get(&symbol).and_then(|q| q.change_24h));
+        self.quotes.insert(
+            symbol,
+            Quote {
+                price: update.price,
+                change_24h,
+            },
+        );
+        self.dirty = true;
+    }
+
+    fn set_day_pnl(&mut self, pnl: Option<(f64, f64)>) {
+        self.pnl = pnl.map(|(pnl, value)| DayPnl {
+            pnl,
+            value,
+            live_value: value,
+        });
+        self.dirty = true;
+    }
+
+    fn on_portfolio(&mut self, total_value: f64) {
+        if let Some(pnl) = &mut self.pnl {
+            pnl.live_value = total_value;
+            self.dirty = true;
+        }
+    }
+
+    fn day_pnl(&self) -> Option<f64> {
+        self.pnl.map(|p| p.pnl + p.live_value - p.value)
+    }
+
+    /// "BTC $67,120.00", or a dash until a price arrives
+    fn price_text(&self, symbol: &str) -> String {
+        match self.quotes.get(symbol) {
+            Some(quote) => format!("{} {}", symbol.to_uppercase(), format_price(quote.price)),
+            None => format!("{} —", symbol.to_uppercase()),
+        }
+    }
+
+    /// Price with the 24h change when known
+    fn quote_text(&self, symbol: &str) -> String {
+        let price = self.price_text(symbol);
+        match self.quotes.get(symbol).and_then(|q| q.change_24h) {
+            Some(change) => format!("{} ({:+.2}%)", price, change),
+            None => price,
+        }
+    }
+
+    fn title(&self, settings: &TraySettings) -> String {
+        let mut parts: Vec<String> = settings
+            .ticker
+            .iter()
+            .map(|symbol| self.price_text(symbol))
+            .collect();
+        if let Some(pnl) = self.day_pnl().filter(|_| settings.show_pnl) {
+            parts.push(format!("P&L {}", format_signed(pnl)));
+        }
+        parts.join(" · ")
+    }
+
+    fn tooltip(&self, settings: &TraySettings) -> String {
+        let mut lines = vec!["Wraith".to_string()];
+        lines.extend(settings.ticker.iter().map(|symbol| self.quote_text(symbol)));
+        if let Some(pnl) = self.day_pnl().filter(|_| settings.show_pnl) {
+            lines.push(format!("Today's P&L {}", format_signed(pnl)));
+        }
+        lines.join("\n")
+    }
+}
+
+fn format_signed(value: f64) -> String {
+    if value >= 0.0 {
+        format!("+{}", format_price(value))
+    } else {
+        format_price(value)
+    }
+}
+
+/// Portfolio the tray reports P&L for
+#[derive(Debug, Clone)]
+struct Session {
+    token: String,
+    portfolio_id: String,
+    /// Portfolio the frontend handed over, which its socket subscription
+    /// streams values for
+    streamed_id: String,
+}
+
+impl Session {
+    fn new(token: String, portfolio_id: String) -> Self {
+        Self {
+            token,
+            streamed_id: portfolio_id.clone(),
+            portfolio_id,
+        }
+    }
+
+    /// Whether live portfolio values on a socket subscribed with
+    /// `socket_token` belong to the portfolio the tray reports on
+    fn streams_live(&self, socket_token: Option<&str>) -> bool {
+        socket_token == Some(self.token.as_str()) && self.portfolio_id == self.streamed_id
+    }
+}
+
+/// What a menu entry does
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum MenuAction {
+    Show,
+    Hide,
+    Quit,
+    Watch(String),
+    SelectPortfolio(String),
+    ToggleRat,
+    CancelOrders,
+    ToggleDrawdown,
+}
+
+fn parse_menu_id(id: &str) -> Option<MenuAction> {
+    let action = match id {
+        "show" => MenuAction::Show,
+        "hide" => MenuAction::Hide,
+        "quit" => MenuAction::Quit,
+        "rat-toggle" => MenuAction::ToggleRat,
+        "cancel-orders" => MenuAction::CancelOrders,
+        "drawdown-toggle" => MenuAction::ToggleDrawdown,
+        _ => {
+            if let Some(symbol) = id.strip_prefix(WATCH_PREFIX) {
+                MenuAction::Watch(symbol.to_string())
+            } else if let Some(portfolio_id) = id.strip_prefix(PORTFOLIO_PREFIX) {
+                MenuAction::SelectPortfolio(portfolio_id.to_string())
+            } else {
+                return None;
+            }
+        }
+    };
+    Some(action)
+}
+
+/// Native prompt shown before a destructive action
+#[derive(Debug, PartialEq, Eq)]
+struct Confirmation {
+    message: String,
+    confirm: &'static str,
+    dismiss: &'static str,
+}
+
+/// Trading state the menu reflects for the active portfolio
+#[derive(Debug, Clone, Default, PartialEq)]
+struct TradingMenu {
+    /// Id and name of each of the user's portfolios
+    portfolios: Vec<(String, String)>,
+    open_positions: usize,
+    open_orders: usize,
+    /// Whether RAT is trading; unknown if its status couldn't be fetched
+    rat_active: Option<bool>,
+    drawdown_protection: Option<bool>,
+}
+
+impl TradingMenu {
+    fn rat_label(&self) -> &'static str {
+        match self.rat_active {
+            Some(true) => "Pause Auto-Trader",
+            Some(false) => "Resume Auto-Trader",
+            None => "Auto-Trader Unavailable",
+        }
+    }
+
+    fn orders_label(&self) -> String {
+        match self.open_orders {
+            0 => "No Open Orders".to_string(),
+            1 => "Cancel 1 Open Order…".to_string(),
+            n => format!("Cancel All {} Open Orders…", n),
+        }
+    }
+
+    fn positions_label(&self) -> String {
+        match self.open_positions {
+            1 => "1 open position".to_string(),
+            n => format!("{} open positions", n),
+        }
+    }
+
+    /// Prompt for actions that can't be undone from the tray
+    fn confirmation(&self, action: &MenuAction) -> Option<Confirmation> {
+        match action {
+            MenuAction::CancelOrders => Some(Confirmation {
+                message: match self.open_orders {
+                    1 => "Cancel the open order in this portfolio?".to_string(),
+                    n => format!("Cancel all {} open orders in this portfolio?", n),
+                },
+                confirm: "Cancel Orders",
+                dismiss: "Keep Orders",
+            }),
+            MenuAction::ToggleDrawdown if self.drawdown_protection == Some(true) => {
+                Some(Confirmation {
+                    message: "Turn off drawdown protection? Trading will no longer stop \
+                              at the drawdown limit."
+                        .to_string(),
+                    confirm: "Turn Off",
+                    dismiss: "Keep On",
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A watchlist menu entry and the label it currently shows
+struct WatchItem {
+    symbol: String,
+    item: MenuItem<Wry>,
+    label: String,
+}
+
+/// Managed state for the system tray
+#[derive(Default)]
+pub struct TrayState {
+    settings: Mutex<TraySettings>,
+    ticker: Mutex<Ticker>,
+    session: Mutex<Option<Session>>,
+    watch_items: Mutex<Vec<WatchItem>>,
+    trading: Mutex<TradingMenu>,
+    /// Signalled when positions or orders change on the stream
+    trading_changed: Notify,
+    /// Symbols this module holds socket subscriptions for
+    subscribed: Mutex<BTreeSet<String>>,
+    data_dir: Mutex<Option<PathBuf>>,
+}
+
+impl TrayState {
+    fn save(&self) {
+        let Some(dir) = self.data_dir.lock().unwrap().clone() else {
+            return;
+        };
+        let settings = self.settings.lock().unwrap().clone();
+        let written = fs::create_dir_all(&dir).and_then(|_| {
+            let json = serde_json::to_vec_pretty(&settings)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+            fs::write(dir.join(SETTINGS_FILE), json)
+        });
+        if let Err(e) = written {
+            eprintln!("Failed to save tray settings: {}", e);
+        }
+    }
+
+    /// Hold socket subscriptions for exactly the symbols the tray shows
+    fn sync_subscriptions(&self, socket: &HauntSocket) {
+        let wanted = self.settings.lock().unwrap().symbols();
+        let mut subscribed = self.subscribed.lock().unwrap();
+
+        let added: Vec<String> = wanted.difference(&subscribed).cloned().collect();
+        let removed: Vec<String> = subscribed.difference(&wanted).cloned().collect();
+        if !added.is_empty() {
+            socket.subscribe(&added);
+        }
+        if !removed.is_empty() {
+            socket.unsubscribe(Some(&removed));
+        }
+        *subscribed = wanted;
+    }
+}
+
+/// Active portfolio of the session the frontend handed over
+pub(crate) fn portfolio_id(app: &AppHandle) -> Option<String> {
+    let state = app.state::<TrayState>();
+    let session = state.session.lock().unwrap();
+    session.as_ref().map(|session| session.portfolio_id.clone())
+}
+
+/// Drop the session behind the tray until the frontend hands over the next
+/// one, e.g. when the identity it belonged to stops being active
+pub(crate) fn sign_out(app: &AppHandle) {
+    let state = app.state::<TrayState>();
+    if state.session.lock().unwrap().take().is_none() {
+        return;
+    }
+    state.ticker.lock().unwrap().set_day_pnl(None);
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move { refresh_trading(&app, true).await });
+}
+
+/// System tray command handler
+fn handle_tray_menu_event(app: &AppHandle, id: &str) {
+    let Some(action) = parse_menu_id(id) else {
+        return;
+    };
+    match action {
+        MenuAction::Show => focus_main_window(app),
+        MenuAction::Hide => {
+            if let Some(window) = app.get_webview_window("main") {
+                let _ = window.hide();
+            }
+        }
+        MenuAction::Quit => {
+            app.exit(0);
+        }
+        MenuAction::Watch(symbol) => {
+            focus_main_window(app);
+            deep_link::route(app, &format!("wraith://asset/{}", symbol));
+        }
+        action => {
+            tauri::async_runtime::spawn(run_action(app.clone(), action));
+        }
+    }
+}
+
+fn build_menu(app: &AppHandle) -> tauri::Result<Menu<Wry>> {
+    let state = app.state::<TrayState>();
+    let watchlist = state.settings.lock().unwrap().watchlist.clone();
+    let labels: Vec<String> = {
+        let ticker = state.ticker.lock().unwrap();
+        watchlist.iter().map(|s| ticker.quote_text(s)).collect()
+    };
+
+    let mut submenu = SubmenuBuilder::new(app, "Watchlist");
+    let mut watch_items = Vec::new();
+    for (symbol, label) in watchlist.into_iter().zip(labels) {
+        let item =
+            MenuItemBuilder::with_id(format!("{}{}", WATCH_PREFIX, symbol), &label).build(app)?;
+        submenu = submenu.item(&item);
+        watch_items.push(WatchItem {
+            symbol,
+            item,
+            label,
+        });
+    }
+    if watch_items.is_empty() {
+        let empty = MenuItemBuilder::with_id("watch-empty", "No pinned symbols")
+            .enabled(false)
+            .build(app)?;
+        submenu = submenu.item(&empty);
+    }
+
+    let show_item = MenuItemBuilder::with_id("show", "Show Wraith").build(app)?;
+    let hide_item = MenuItemBuilder::with_id("hide", "Hide").build(app)?;
+    let quit_item = MenuItemBuilder::with_id("quit", "Quit").build(app)?;
+    let mut menu = MenuBuilder::new(app)
+        .item(&show_item)
+        .item(&hide_item)
+        .separator()
+        .item(&submenu.build()?);
+    let active = state
+        .session
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|session| session.portfolio_id.clone());
+    if let Some(active) = active {
+        let trading = state.trading.lock().unwrap().clone();
+        let (portfolios, actions) = trading_submenus(app, &trading, &active)?;
+        menu = menu.item(&portfolios).item(&actions);
+    }
+    let menu = menu.separator().item(&quit_item).build()?;
+
+    *state.watch_items.lock().unwrap() = watch_items;
+    Ok(menu)
+}
+
+/// Portfolio switcher and trading actions for a signed-in session
+fn trading_submenus(
+    app: &AppHandle,
+    trading: &TradingMenu,
+    active: &str,
+) -> tauri::Result<(Submenu<Wry>, Submenu<Wry>)> {
+    let mut portfolios = SubmenuBuilder::new(app, "Portfolio");
+    for (id, name) in &trading.portfolios {
+        let item = CheckMenuItemBuilder::with_id(format!("{}{}", PORTFOLIO_PREFIX, id), name)
+            .checked(id == active)
+            .build(app)?;
+        portfolios = portfolios.item(&item);
+    }
+    if trading.portfolios.is_empty() {
+        let empty = MenuItemBuilder::with_id("portfolio-empty", "No portfolios")
+            .enabled(false)
+            .build(app)?;
+        portfolios = portfolios.item(&empty);
+    }
+
+    let positions = MenuItemBuilder::with_id("positions", trading.positions_label())
+        .enabled(false)
+        .build(app)?;
+    let rat = MenuItemBuilder::with_id("rat-toggle", trading.rat_label())
+        .enabled(trading.rat_active.is_some())
+        .build(app)?;
+    let orders = MenuItemBuilder::with_id("cancel-orders", trading.orders_label())
+        .enabled(trading.open_orders > 0)
+        .build(app)?;
+    let drawdown = CheckMenuItemBuilder::with_id("drawdown-toggle", "Drawdown Protection")
+        .checked(trading.drawdown_protection == Some(true))
+        .enabled(trading.drawdown_protection.is_some())
+        .build(app)?;
+    let actions = SubmenuBuilder::new(app, "Trading")
+        .item(&positions)
+        .separator()
+        .item(&rat)
+        .item(&orders)
+        .item(&drawdown)
+        .build()?;
+
+    Ok((portfolios.build()?, actions))
+}
+
+/// Apply pending ticker changes to the title, tooltip and watchlist labels
+fn refresh(app: &AppHandle) {
+    let state = app.state::<TrayState>();
+    let settings = state.settings.lock().unwrap().clone();
+    let (title, tooltip, labels) = {
+        let mut ticker = state.ticker.lock().unwrap();
+        if !std::mem::take(&mut ticker.dirty) {
+            return;
+        }
+        let labels: HashMap<String, String> = settings
+            .watchlist
+            .iter()
+            .map(|symbol| (symbol.clone(), ticker.quote_text(symbol)))
+            .collect();
+        (ticker.title(&settings), ticker.tooltip(&settings), labels)
+    };
+
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    let _ = tray.set_title(Some(title).filter(|t| !t.is_empty()));
+    let _ = tray.set_tooltip(Some(tooltip));
+    for watch in state.watch_items.lock().unwrap().iter_mut() {
+        if let Some(label) = labels.get(&watch.symbol) {
+            if *label != watch.label && watch.item.set_text(label).is_ok() {
+                watch.label = label.clone();
+            }
+        }
+    }
+}
+
+/// Rebuild the menu after the settings changed
+fn rebuild(app: &AppHandle) -> tauri::Result<()> {
+    let menu = build_menu(app)?;
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        tray.set_menu(Some(menu))?;
+    }
+    app.state::<TrayState>().ticker.lock().unwrap().dirty = true;
+    refresh(app);
+    Ok(())
+}
+
+/// Fetch today's P&L for the session's portfolio
+async fn fetch_pnl(app: &AppHandle) -> Result<(), String> {
+    let session = app.state::<TrayState>().session.lock().unwrap().clone();
+    let Some(session) = session else {
+        return Ok(());
+    };
+    let performance = app
+        .state::<HauntClient>()
+        .get_performance(&session.token, &session.portfolio_id, "1d")
+        .await
+        .map_err(|e| e.to_string())?
+        .data;
+    app.state::<TrayState>()
+        .ticker
+        .lock()
+        .unwrap()
+        .set_day_pnl(Some((performance.total_pnl, performance.end_value)));
+    Ok(())
+}
+
+/// Fetch what the trading menu shows; anything that fails stays unknown
+async fn fetch_trading(client: &HauntClient, session: &Session) -> TradingMenu {
+    let (token, portfolio_id) = (session.token.as_str(), session.portfolio_id.as_str());
+    let (portfolios, positions, orders, rat, settings) = tokio::join!(
+        client.list_portfolios(token, None),
+        client.get_positions(token, portfolio_id),
+        client.get_orders(token, portfolio_id, "open"),
+        client.get_rat_status(token, portfolio_id),
+        client.get_portfolio_settings(token, portfolio_id),
+    );
+
+    TradingMenu {
+        portfolios: portfolios
+            .map(|r| r.data.into_iter().map(|p| (p.id, p.name)).collect())
+            .unwrap_or_default(),
+        open_positions: positions.map_or(0, |r| r.data.len()),
+        open_orders: orders.map_or(0, |r| r.data.len()),
+        rat_active: rat.ok().and_then(|r| {
+            r.data
+                .get("status")
+                .and_then(Value::as_str)
+                .map(|status| status == "active")
+        }),
+        drawdown_protection: settings.ok().map(|r| r.data.drawdown_protection.enabled),
+    }
+}
+
+/// Refetch the trading menu and rebuild if it changed, or regardless when
+/// `force` is set
+async fn refresh_trading(app: &AppHandle, force: bool) {
+    let session = app.state::<TrayState>().session.lock().unwrap().clone();
+    let trading = match session {
+        Some(session) => fetch_trading(&app.state::<HauntClient>(), &session).await,
+        None => TradingMenu::default(),
+    };
+    let changed = {
+        let state = app.state::<TrayState>();
+        let mut current = state.trading.lock().unwrap();
+        let changed = *current != trading;
+        *current = trading;
+        changed
+    };
+    if changed || force {
+        if let Err(e) = rebuild(app) {
+            eprintln!("Failed to rebuild tray menu: {}", e);
+        }
+    }
+}
+
+/// Ask before a destructive action; runs the dialog off the async runtime
+async fn confirm(app: &AppHandle, confirmation: Confirmation) -> bool {
+    let dialog = app
+        .dialog()
+        .message(confirmation.message)
+        .title("Wraith")
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            confirmation.confirm.to_string(),
+            confirmation.dismiss.to_string(),
+        ));
+    tauri::async_runtime::spawn_blocking(move || dialog.blocking_show())
+        .await
+        .unwrap_or(false)
+}
+
+async fn perform(app: &AppHandle, action: &MenuAction) -> Result<(), String> {
+    let state = app.state::<TrayState>();
+    let Some(session) = state.session.lock().unwrap().clone() else {
+        return Ok(());
+    };
+    let trading = state.trading.lock().unwrap().clone();
+    if let Some(confirmation) = trading.confirmation(action) {
+        if !confirm(app, confirmation).await {
+            return Ok(());
+        }
+    }
+
+    let client = app.state::<HauntClient>();
+    let (token, portfolio_id) = (session.token.as_str(), session.portfolio_id.as_str());
+    match action {
+        MenuAction::SelectPortfolio(id) => {
+            if let Some(session) = state.session.lock().unwrap().as_mut() {
+                session.portfolio_id = id.clone();
+            }
+            // The last P&L and live values belong to the old portfolio
+            state.ticker.lock().unwrap().set_day_pnl(None);
+            let _ = app.emit(PORTFOLIO_EVENT, id);
+            fetch_pnl(app).await?;
+        }
+        MenuAction::ToggleRat => {
+            let result = if trading.rat_active == Some(true) {
+                client.stop_rat(token, portfolio_id).await
+            } else {
+                client.start_rat(token, portfolio_id, None).await
+            };
+            result.map_err(|e| e.to_string())?;
+        }
+        MenuAction::CancelOrders => {
+            let cancelled = client
+                .cancel_all_orders(token, portfolio_id, None)
+                .await
+                .map_err(|e| e.to_string())?
+                .data;
+            let notification = Notification {
+                category: NotificationCategory::Trade,
+                ..Notification::new("Orders cancelled", cancelled.message)
+            };
+            notifications::show(app, notification)?;
+        }
+        MenuAction::ToggleDrawdown => {
+            let enabled = trading.drawdown_protection != Some(true);
+            client
+                .update_portfolio_settings(
+                    token,
+                    portfolio_id,
+                    &json!({ "drawdownProtection": { "enabled": enabled } }),
+                )
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+        MenuAction::Show | MenuAction::Hide | MenuAction::Quit | MenuAction::Watch(_) => {}
+    }
+    Ok(())
+}
+
+/// Run a trading action from the menu and report failures
+async fn run_action(app: AppHandle, action: MenuAction) {
+    if let Err(e) = perform(&app, &action).await {
+        let notification = Notification {
+            category: NotificationCategory::Trade,
+            ..Notification::new("Tray action failed", e)
+        };
+        let _ = notifications::show(&app, notification);
+    }
+    // Check items toggle themselves on click; rebuild to show the real state
+    refresh_trading(&app, true).await;
+}
+
+fn load_settings(dir: &Path) -> Result<TraySettings, Box<dyn std::error::Error>> {
+    let settings: TraySettings = match fs::read(dir.join(SETTINGS_FILE)) {
+        Ok(bytes) => serde_json::from_slice(&bytes)?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => TraySettings::default(),
+        Err(e) => return Err(e.into()),
+    };
+    Ok(settings.normalized()?)
+}
+
+/// Initialize the system tray and keep its ticker current
+pub fn init(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+    let data_dir = app.path().app_data_dir()?;
+    let settings = load_settings(&data_dir).unwrap_or_else(|e| {
+        eprintln!("Failed to load tray settings: {}", e);
+        TraySettings::default()
+    });
+    let state = app.state::<TrayState>();
+    *state.settings.lock().unwrap() = settings;
+    *state.data_dir.lock().unwrap() = Some(data_dir);
+
+    let menu = build_menu(app)?;
+    let _tray = TrayIconBuilder::with_id(TRAY_ID)
+        .menu(&menu)
+        .on_menu_event(move |app, event| {
+            handle_tray_menu_event(app, event.id().as_ref());
+        })
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                focus_main_window(tray.app_handle());
+            }
+        })
+        .build(app)?;
+    state.sync_subscriptions(&app.state::<HauntSocket>());
+
+    let mut messages = app.state::<HauntSocket>().messages();
+    let handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let state = handle.state::<TrayState>();
+        loop {
+            let message = match messages.recv().await {
+                Ok(message) => message,
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            };
+            match &*message {
+                WsMessage::PriceUpdate { data } => {
+                    let wanted = state
+                        .settings
+                        .lock()
+                        .unwrap()
+                        .wants(&data.symbol.to_lowercase());
+                    if wanted {
+                        state.ticker.lock().unwrap().on_price(data);
+                    }
+                }
+                WsMessage::PortfolioUpdate { data } => {
+                    let token = handle.state::<HauntSocket>().portfolio_token();
+                    let live = state
+                        .session
+                        .lock()
+                        .unwrap()
+                        .as_ref()
+                        .is_some_and(|session| session.streams_live(token.as_deref()));
+                    if live {
+                        state.ticker.lock().unwrap().on_portfolio(data.total_value);
+                    }
+                }
+                WsMessage::PositionUpdate { .. } | WsMessage::OrderUpdate { .. } => {
+                    state.trading_changed.notify_one();
+                }
+                _ => {}
+            }
+        }
+    });
+
+    let handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(REFRESH_INTERVAL);
+        loop {
+            interval.tick().await;
+            refresh(&handle);
+        }
+    });
+
+    let handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(PNL_POLL_INTERVAL);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            // Failures keep the last known P&L; the next poll retries
+            let _ = fetch_pnl(&handle).await;
+            refresh_trading(&handle, false).await;
+        }
+    });
+
+    let handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let state = handle.state::<TrayState>();
+        loop {
+            state.trading_changed.notified().await;
+            tokio::time::sleep(TRADING_DEBOUNCE).await;
+            refresh_trading(&handle, false).await;
+        }
+    });
+
+    Ok(())
+}
+
+// ========== Commands ==========
+
+/// Tauri command: Get tray ticker and watchlist settings
+#[tauri::command]
+pub fn tray_get_settings(state: State<'_, TrayState>) -> TraySettings {
+    state.settings.lock().unwrap().clone()
+}
+
+/// Tauri command: Update tray ticker and watchlist settings
+#[tauri::command]
+pub fn tray_set_settings(
+    app: AppHandle,
+    state: State<'_, TrayState>,
+    settings: TraySettings,
+) -> Result<TraySettings, String> {
+    let settings = settings.normalized()?;
+    *state.settings.lock().unwrap() = settings.clone();
+    state.save();
+    state.sync_subscriptions(&app.state::<HauntSocket>());
+    rebuild(&app).map_err(|e| e.to_string())?;
+    Ok(settings)
+}
+
+/// Tauri command: Set the session and active portfolio behind the tray's
+/// P&L and trading menu; `None` signs the tray out
+#[tauri::command]
+pub async fn tray_set_session(
+    app: AppHandle,
+    state: State<'_, TrayState>,
+    token: Option<String>,
+    portfolio_id: Option<String>,
+) -> Result<(), String> {
+    let session = token
+        .zip(portfolio_id)
+        .map(|(token, portfolio_id)| Session::new(token, portfolio_id));
+    let signed_in = session.is_some();
+    *state.session.lock().unwrap() = session;
+    refresh_trading(&app, true).await;
+    if !signed_in {
+        state.ticker.lock().unwrap().set_day_pnl(None);
+        return Ok(());
+    }
+    fetch_pnl(&app).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn price(symbol: &str, price: f64, change_24h: Option<f64>) -> PriceUpdate {
+        PriceUpdate {
+            id: 1,
+            symbol: symbol.to_string(),
+            price,
+            previous_price: None,
+            change_24h,
+            volume_24h: None,
+            trade_direction: None,
+            source: None,
+            sources: None,
+            timestamp: "2024-01-01T00:00:00Z".to_string(),
+        }
+    }
+
+    #[test]
+    fn normalizes_settings() {
+        let settings = TraySettings {
+            ticker: vec![" BTC ".to_string(), "btc".to_string()],
+            show_pnl: false,
+            watchlist: vec!["Eth".to_string(), "SOL".to_string()],
+        }
+        .normalized()
+        .unwrap();
+        assert_eq!(settings.ticker, ["btc"]);
+        assert_eq!(settings.watchlist, ["eth", "sol"]);
+        assert_eq!(
+            settings.symbols().into_iter().collect::<Vec<_>>(),
+            ["btc", "eth", "sol"]
+        );
+        assert!(settings.wants("eth") && !settings.wants("doge"));
+
+        let too_many = TraySettings {
+            ticker: ["a", "b", "c", "d"].map(String::from).to_vec(),
+            ..TraySettings::default()
+        };
+        assert!(too_many.normalized().is_err());
+        let invalid = TraySettings {
+            watchlist: vec!["btc/usd".to_string()],
+            ..TraySettings::default()
+        };
+        assert!(invalid.normalized().is_err());
+    }
+
+    #[test]
+    fn formats_ticker_text() {
+        let settings = TraySettings::default();
+        let mut ticker = Ticker::default();
+        assert_eq!(ticker.title(&settings), "BTC —");
+
+        ticker.on_price(&price("BTC", 67120.5, Some(2.314)));
+        ticker.on_price(&price("btc", 67200.0, None));
+        assert!(ticker.dirty);
+        assert_eq!(ticker.quote_text("btc"), "BTC $67,200.00 (+2.31%)");
+        assert_eq!(ticker.quote_text("eth"), "ETH —");
+
+        ticker.set_day_pnl(Some((120.5, 10_000.0)));
+        assert_eq!(ticker.title(&settings), "BTC $67,200.00 · P&L +$120.50");
+        ticker.on_portfolio(9_800.0);
+        assert_eq!(
+            ticker.tooltip(&settings),
+            "Wraith\nBTC $67,200.00 (+2.31%)\nToday's P&L -$79.50"
+        );
+
+        let hidden = TraySettings {
+            show_pnl: false,
+            ..TraySettings::default()
+        };
+        assert_eq!(ticker.title(&hidden), "BTC $67,200.00");
+    }
+
+    #[test]
+    fn parses_menu_ids() {
+        assert_eq!(parse_menu_id("show"), Some(MenuAction::Show));
+        assert_eq!(
+            parse_menu_id("watch:btc"),
+            Some(MenuAction::Watch("btc".to_string()))
+        );
+        assert_eq!(
+            parse_menu_id("portfolio:abc"),
+            Some(MenuAction::SelectPortfolio("abc".to_string()))
+        );
+        assert_eq!(
+            parse_menu_id("cancel-orders"),
+            Some(MenuAction::CancelOrders)
+        );
+        assert_eq!(parse_menu_id("positions"), None);
+    }
+
+    #[test]
+    fn confirms_destructive_actions() {
+        let mut trading = TradingMenu {
+            open_orders: 3,
+            rat_active: Some(true),
+            drawdown_protection: Some(true),
+            ..TradingMenu::default()
+        };
+        let cancel = trading.confirmation(&MenuAction::CancelOrders).unwrap();
+        assert_eq!(
+            cancel.message,
+            "Cancel all 3 open orders in this portfolio?"
+        );
+        assert!(trading.confirmation(&MenuAction::ToggleDrawdown).is_some());
+        assert!(trading.confirmation(&MenuAction::ToggleRat).is_none());
+        assert!(trading
+            .confirmation(&MenuAction::SelectPortfolio("abc".to_string()))
+            .is_none());
+
+        // Turning protection back on is not destructive
+        trading.drawdown_protection = Some(false);
+        assert!(trading.confirmation(&MenuAction::ToggleDrawdown).is_none());
+    }
+
+    #[test]
+    fn labels_trading_items() {
+        let mut trading = TradingMenu::default();
+        assert_eq!(trading.rat_label(), "Auto-Trader Unavailable");
+        assert_eq!(trading.orders_label(), "No Open Orders");
+        assert_eq!(trading.positions_label(), "0 open positions");
+
+        trading.rat_active = Some(false);
+        trading.open_orders = 1;
+        trading.open_positions = 1;
+        assert_eq!(trading.rat_label(), "Resume Auto-Trader");
+        assert_eq!(trading.orders_label(), "Cancel 1 Open Order…");
+        assert_eq!(trading.positions_label(), "1 open position");
+
+        trading.open_orders = 4;
+        assert_eq!(trading.orders_label(), "Cancel All 4 Open Orders…");
+    }
+
+    #[test]
+    fn ignores_portfolio_values_without_a_baseline() {
+        let mut ticker = Ticker::default();
+        ticker.on_portfolio(10_000.0);
+        assert_eq!(ticker.day_pnl(), None);
+        assert!(!ticker.dirty);
+    }
+
+    #[test]
+    fn streams_live_values_only_for_the_subscribed_portfolio() {
+        let mut session = Session::new("token".to_string(), "main".to_string());
+        assert!(session.streams_live(Some("token")));
+        assert!(!session.streams_live(Some("other")));
+        assert!(!session.streams_live(None));
+
+        // Picking another portfolio in the tray leaves the socket on the old one
+        session.portfolio_id = "hedge".to_string();
+        assert!(!session.streams_live(Some("token")));
+        session.portfolio_id = "main".to_string();
+        assert!(session.streams_live(Some("token")));
+    }
+}
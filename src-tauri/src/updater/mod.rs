@@ -0,0 +1,884 @@
+//! App updates
+//!
+//! `check_for_updates` caches the update it finds, so downloading and
+//! installing act on exactly the release the user was shown. Downloading is
+//! separate from installing: progress is emitted as [`PROGRESS_EVENT`] and
+//! the verified package is kept in memory until it is installed, either
+//! right away (with a restart) or when Wraith next quits.
+//!
+//! Updates come from a release channel picked in settings and stored in
+//! `updater.json`. Each channel has a static manifest at
+//! `{base}/{channel}/latest.json` in the format the Tauri updater reads,
+//! plus an optional `rollout` percentage. Every install draws a random
+//! bucket from 0 to 99 once and keeps it, and sees a release only if its
+//! bucket is below the rollout, so raising the percentage only ever adds
+//! installs. For testing, point `baseUrl` at a local static file server
+//! (plain http works in debug builds), e.g. `http://localhost:8000`
+//! serving `beta/latest.json`.
+//!
+//! Installing keeps a copy of the current version and watches the new one
+//! start; see [`rollback`]. A launch counts as healthy once the main
+//! webview has loaded and the Haunt socket has connected. A version rolled
+//! back from isn't offered again.
+
+mod rollback;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+use tauri_plugin_updater::{Update, UpdaterExt};
+use url::Url;
+
+use crate::haunt::HauntSocket;
+use crate::notifications::{self, Notification, NotificationCategory};
+use crate::now_millis;
+use rollback::LaunchCheck;
+pub use rollback::{HistoryEntry, HistoryEvent, LaunchHealth};
+
+/// Event carrying [`DownloadProgress`]
+pub const PROGRESS_EVENT: &str = "update-progress";
+/// Event carrying the [`UpdateInfo`] of a downloaded update
+pub const DOWNLOADED_EVENT: &str = "update-downloaded";
+
+/// Bytes between progress events when the size is unknown
+const UNKNOWN_SIZE_STEP: u64 = 1024 * 1024;
+const SETTINGS_FILE: &str = "updater.json";
+const DEFAULT_BASE_URL: &str = "https://releases.wraith.dev/tauri";
+const ROLLOUT_BUCKETS: u8 = 100;
+/// How often a pending update's launch is checked for health
+const HEALTH_POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// How long a launch that loaded waits for the Haunt socket before it is
+/// verified anyway, e.g. when offline
+const HEALTH_SOCKET_TIMEOUT: Duration = Duration::from_secs(60);
+const DEFAULT_HISTORY_LIMIT: usize = 100;
+
+/// Release channel to take updates from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Channel {
+    #[default]
+    Stable,
+    Beta,
+    Nightly,
+}
+
+impl Channel {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Stable => "stable",
+            Self::Beta => "beta",
+            Self::Nightly => "nightly",
+        }
+    }
+}
+
+/// Update settings, persisted to `updater.json`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdaterSettings {
+    #[serde(default)]
+    pub channel: Channel,
+    /// Where channel manifests are served from, instead of the release
+    /// server
+    #[serde(default)]
+    pub base_url: Option<String>,
+    /// Rollout bucket of this install, drawn once
+    #[serde(default = "new_bucket")]
+    pub bucket: u8,
+    /// Version rolled back from, which isn't offered again
+    #[serde(default)]
+    pub skipped_version: Option<String>,
+}
+
+impl Default for UpdaterSettings {
+    fn default() -> Self {
+        Self {
+            channel: Channel::default(),
+            base_url: None,
+            bucket: new_bucket(),
+            skipped_version: None,
+        }
+    }
+}
+
+impl UpdaterSettings {
+    /// An http(s) base URL without a trailing slash, and a bucket in range
+    fn normalized(mut self) -> Result<Self, String> {
+        if let Some(base_url) = self.base_url.take() {
+            let base_url = base_url.trim().trim_end_matches('/');
+            if !base_url.is_empty() {
+                let url = Url::parse(base_url).map_err(|e| format!("Invalid base URL: {}", e))?;
+                if !matches!(url.scheme(), "http" | "https") || url.query().is_some() {
+                    return Err("The base URL must be a plain http(s) URL".to_string());
+                }
+                self.base_url = Some(base_url.to_string());
+            }
+        }
+        self.bucket %= ROLLOUT_BUCKETS;
+        Ok(self)
+    }
+
+    /// Manifest URL of the selected channel
+    fn endpoint(&self) -> Result<Url, String> {
+        let base = self.base_url.as_deref().unwrap_or(DEFAULT_BASE_URL);
+        let endpoint = format!("{}/{}/latest.json", base, self.channel.as_str());
+        Url::parse(&endpoint).map_err(|e| e.to_string())
+    }
+}
+
+fn new_bucket() -> u8 {
+    (OsRng.next_u32() % ROLLOUT_BUCKETS as u32) as u8
+}
+
+/// Whether a release reaches `bucket`, given its manifest. A missing
+/// `rollout` means everyone; one that isn't a number means no one, so a
+/// broken manifest can't release to all installs at once.
+fn in_rollout(manifest: &Value, bucket: u8) -> bool {
+    let rollout = match manifest.get("rollout") {
+        None | Some(Value::Null) => return true,
+        Some(rollout) => rollout.as_f64(),
+    };
+    match rollout {
+        Some(percent) => f64::from(bucket) < percent,
+        None => {
+            eprintln!(
+                "Ignoring update with an invalid rollout: {}",
+                manifest["rollout"]
+            );
+            false
+        }
+    }
+}
+
+/// An available release
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateInfo {
+    pub version: String,
+    pub current_version: String,
+    /// Publish date in Unix milliseconds
+    pub date: Option<i64>,
+    /// Release notes
+    pub notes: Option<String>,
+}
+
+impl UpdateInfo {
+    fn new(update: &Update) -> Self {
+        Self {
+            version: update.version.clone(),
+            current_version: update.current_version.clone(),
+            date: update.date.map(|date| date.unix_timestamp() * 1000),
+            notes: update.body.clone(),
+        }
+    }
+}
+
+/// Payload of [`PROGRESS_EVENT`]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadProgress {
+    pub version: String,
+    pub downloaded: u64,
+    /// Package size, when the server reports it
+    pub total: Option<u64>,
+}
+
+/// Where the cached update stands
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateStatus {
+    pub update: Option<UpdateInfo>,
+    pub downloading: bool,
+    pub downloaded: bool,
+    pub install_on_quit: bool,
+}
+
+/// Decides which chunks are worth a progress event: each whole percent of a
+/// known size, or every [`UNKNOWN_SIZE_STEP`] bytes otherwise
+struct Progress {
+    version: String,
+    downloaded: u64,
+    reported: u64,
+}
+
+impl Progress {
+    fn new(version: &str) -> Self {
+        Self {
+            version: version.to_string(),
+            downloaded: 0,
+            reported: 0,
+        }
+    }
+
+    fn advance(&mut self, chunk: usize, total: Option<u64>) -> Option<DownloadProgress> {
+        self.downloaded += chunk as u64;
+        let step = match total {
+            Some(total) => (total / 100).max(1),
+            None => UNKNOWN_SIZE_STEP,
+        };
+        let finished = total.is_some_and(|total| self.downloaded >= total);
+        if self.downloaded - self.reported < step && !finished {
+            return None;
+        }
+        self.reported = self.downloaded;
+        Some(DownloadProgress {
+            version: self.version.clone(),
+            downloaded: self.downloaded,
+            total,
+        })
+    }
+}
+
+/// A verified package and the version it installs
+struct Downloaded {
+    version: String,
+    bytes: Vec<u8>,
+}
+
+/// Managed state for app updates
+#[derive(Default)]
+pub struct UpdaterState {
+    settings: Mutex<UpdaterSettings>,
+    /// The update found by the last check
+    update: Mutex<Option<Update>>,
+    downloaded: Mutex<Option<Downloaded>>,
+    downloading: Mutex<bool>,
+    install_on_quit: Mutex<bool>,
+    /// The last installed update, until a launch of it is healthy
+    health: Mutex<Option<LaunchHealth>>,
+    webview_loaded: Mutex<bool>,
+    /// Serializes appends to the history log
+    history_lock: Mutex<()>,
+    data_dir: Mutex<Option<PathBuf>>,
+}
+
+impl UpdaterState {
+    fn save(&self) {
+        let Some(dir) = self.data_dir.lock().unwrap().clone() else {
+            return;
+        };
+        let settings = self.settings.lock().unwrap().clone();
+        let written = fs::create_dir_all(&dir).and_then(|_| {
+            let json = serde_json::to_vec_pretty(&settings)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+            fs::write(dir.join(SETTINGS_FILE), json)
+        });
+        if let Err(e) = written {
+            eprintln!("Failed to save updater settings: {}", e);
+        }
+    }
+
+    fn save_health(&self) -> Result<(), String> {
+        let Some(dir) = self.data_dir.lock().unwrap().clone() else {
+            return Ok(());
+        };
+        let health = self.health.lock().unwrap().clone();
+        rollback::save_health(&dir, health.as_ref())
+            .map_err(|e| format!("Failed to save update health: {}", e))
+    }
+
+    fn record(
+        &self,
+        event: HistoryEvent,
+        version: &str,
+        from_version: &str,
+        error: Option<String>,
+    ) {
+        let Some(dir) = self.data_dir.lock().unwrap().clone() else {
+            return;
+        };
+        let entry = HistoryEntry {
+            at: now_millis(),
+            event,
+            version: version.to_string(),
+            from_version: from_version.to_string(),
+            error,
+        };
+        let _guard = self.history_lock.lock().unwrap();
+        if let Err(e) = rollback::append_history(&dir, &entry) {
+            eprintln!("Failed to write update history: {}", e);
+        }
+    }
+
+    /// Forget the cached update and its package
+    fn forget(&self) {
+        *self.update.lock().unwrap() = None;
+        *self.downloaded.lock().unwrap() = None;
+        *self.install_on_quit.lock().unwrap() = false;
+    }
+
+    fn status(&self) -> UpdateStatus {
+        let update = self.update.lock().unwrap();
+        let downloaded = self.downloaded.lock().unwrap();
+        UpdateStatus {
+            update: update.as_ref().map(UpdateInfo::new),
+            downloading: *self.downloading.lock().unwrap(),
+            downloaded: update.as_ref().is_some_and(|update| {
+                downloaded
+                    .as_ref()
+                    .is_some_and(|downloaded| downloaded.version == update.version)
+            }),
+            install_on_quit: *self.install_on_quit.lock().unwrap(),
+        }
+    }
+
+    fn cached(&self) -> Result<Update, String> {
+        self.update
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| "No update available; check for updates first".to_string())
+    }
+
+    /// Take the package for `update` if it has been downloaded
+    fn take_package(&self, update: &Update) -> Option<Vec<u8>> {
+        let mut downloaded = self.downloaded.lock().unwrap();
+        match downloaded.take() {
+            Some(package) if package.version == update.version => Some(package.bytes),
+            other => {
+                *downloaded = other;
+                None
+            }
+        }
+    }
+}
+
+/// Download and verify `update`, emitting progress along the way
+async fn download(app: &AppHandle, update: &Update) -> Result<(), String> {
+    let state = app.state::<UpdaterState>();
+    {
+        let mut downloading = state.downloading.lock().unwrap();
+        if *downloading {
+            return Err("The update is already downloading".to_string());
+        }
+        *downloading = true;
+    }
+
+    let mut progress = Progress::new(&update.version);
+    let emitter = app.clone();
+    let result = update
+        .download(
+            move |chunk, total| {
+                if let Some(progress) = progress.advance(chunk, total) {
+                    let _ = emitter.emit(PROGRESS_EVENT, progress);
+                }
+            },
+            || {},
+        )
+        .await;
+    *state.downloading.lock().unwrap() = false;
+
+    let bytes = result.map_err(|e| e.to_string())?;
+    *state.downloaded.lock().unwrap() = Some(Downloaded {
+        version: update.version.clone(),
+        bytes,
+    });
+    let info = UpdateInfo::new(update);
+    let _ = app.emit(DOWNLOADED_EVENT, &info);
+    let notification = Notification {
+        category: NotificationCategory::Update,
+        ..Notification::new(
+            "Update ready",
+            format!("Wraith {} is ready to install", info.version),
+        )
+    };
+    let _ = notifications::show(app, notification);
+    Ok(())
+}
+
+/// Install a downloaded update marked for install on quit; called as the
+/// app exits
+pub fn install_on_quit(app: &AppHandle) {
+    let state = app.state::<UpdaterState>();
+    if !*state.install_on_quit.lock().unwrap() {
+        return;
+    }
+    let Ok(update) = state.cached() else {
+        return;
+    };
+    let Some(bytes) = state.take_package(&update) else {
+        return;
+    };
+    if let Err(e) = install(app, &update, bytes) {
+        eprintln!("Failed to install update on quit: {}", e);
+    }
+}
+
+/// Keep a copy of the current version, then install `update` over it
+fn install(app: &AppHandle, update: &Update, bytes: Vec<u8>) -> Result<(), String> {
+    let state = app.state::<UpdaterState>();
+    let data_dir = state
+        .data_dir
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or_else(|| "Updates aren't ready yet".to_string())?;
+    let (version, from_version) = (update.version.as_str(), update.current_version.as_str());
+
+    let backup = rollback::install_target().and_then(|target| {
+        let backup = rollback::back_up(&data_dir, &target, from_version)?;
+        Ok((backup, target))
+    });
+    let (backup, target) = backup.map_err(|e| {
+        let error = format!("Failed to keep the current version: {}", e);
+        state.record(
+            HistoryEvent::InstallFailed,
+            version,
+            from_version,
+            Some(error.clone()),
+        );
+        error
+    })?;
+    *state.health.lock().unwrap() = Some(LaunchHealth {
+        version: version.to_string(),
+        previous_version: from_version.to_string(),
+        backup,
+        target,
+        launches: 0,
+        verified: false,
+    });
+    state.save_health()?;
+    state.record(HistoryEvent::Installing, version, from_version, None);
+
+    match update.install(bytes) {
+        Ok(()) => {
+            state.record(HistoryEvent::Installed, version, from_version, None);
+            Ok(())
+        }
+        Err(e) => {
+            state.record(
+                HistoryEvent::InstallFailed,
+                version,
+                from_version,
+                Some(e.to_string()),
+            );
+            *state.health.lock().unwrap() = None;
+            let _ = state.save_health();
+            Err(e.to_string())
+        }
+    }
+}
+
+/// Record that the main webview has loaded, half of a healthy launch
+pub fn webview_loaded(app: &AppHandle) {
+    *app.state::<UpdaterState>().webview_loaded.lock().unwrap() = true;
+}
+
+/// Stop counting this launch as failed once the webview has loaded, and
+/// mark the pending update healthy once the Haunt socket has connected too
+/// or [`HEALTH_SOCKET_TIMEOUT`] has passed
+async fn watch_health(app: AppHandle) {
+    let state = app.state::<UpdaterState>();
+    let mut interval = tokio::time::interval(HEALTH_POLL_INTERVAL);
+    while !*state.webview_loaded.lock().unwrap() {
+        interval.tick().await;
+    }
+    if let Some(health) = state.health.lock().unwrap().as_mut() {
+        health.loaded();
+    }
+    if let Err(e) = state.save_health() {
+        eprintln!("{}", e);
+    }
+
+    let connected = async {
+        while !app.state::<HauntSocket>().status().connected {
+            interval.tick().await;
+        }
+    };
+    let _ = tokio::time::timeout(HEALTH_SOCKET_TIMEOUT, connected).await;
+
+    let verified = {
+        let mut health = state.health.lock().unwrap();
+        // Already verified if the user chose to keep the version
+        health
+            .as_mut()
+            .filter(|health| !health.verified)
+            .map(|health| {
+                health.verify();
+                (health.version.clone(), health.previous_version.clone())
+            })
+    };
+    let Some((version, from_version)) = verified else {
+        return;
+    };
+    if let Err(e) = state.save_health() {
+        eprintln!("{}", e);
+    }
+    state.record(HistoryEvent::Verified, &version, &from_version, None);
+}
+
+/// Ask whether to go back to the previous version after failed launches
+async fn offer_rollback(app: AppHandle, health: LaunchHealth, failed: u32) {
+    let dialog = app
+        .dialog()
+        .message(format!(
+            "Wraith {} didn't start properly the last {} times. Roll back to {}?",
+            health.version, failed, health.previous_version
+        ))
+        .title("Wraith")
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            format!("Roll Back to {}", health.previous_version),
+            format!("Keep {}", health.version),
+        ));
+    let confirmed = tauri::async_runtime::spawn_blocking(move || dialog.blocking_show())
+        .await
+        .unwrap_or(false);
+    if !confirmed {
+        keep(&app);
+        return;
+    }
+    if let Err(e) = roll_back(&app) {
+        let notification = Notification {
+            category: NotificationCategory::Update,
+            ..Notification::new("Rollback failed", e)
+        };
+        let _ = notifications::show(&app, notification);
+    }
+}
+
+/// Keep the new version, so its launches stop offering a rollback
+fn keep(app: &AppHandle) {
+    let state = app.state::<UpdaterState>();
+    let declined = state.health.lock().unwrap().as_mut().map(|health| {
+        health.verify();
+        (health.version.clone(), health.previous_version.clone())
+    });
+    let Some((version, from_version)) = declined else {
+        return;
+    };
+    if let Err(e) = state.save_health() {
+        eprintln!("{}", e);
+    }
+    state.record(
+        HistoryEvent::RollbackDeclined,
+        &version,
+        &from_version,
+        None,
+    );
+}
+
+/// Restore the version the last update replaced and restart into it
+fn roll_back(app: &AppHandle) -> Result<(), String> {
+    let state = app.state::<UpdaterState>();
+    let health = state
+        .health
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or_else(|| "There's no previous version to roll back to".to_string())?;
+    let (version, from_version) = (&health.previous_version, &health.version);
+
+    if let Err(e) = rollback::restore(&health.backup, &health.target) {
+        let error = format!("Rollback failed: {}", e);
+        state.record(
+            HistoryEvent::RollbackFailed,
+            version,
+            from_version,
+            Some(error.clone()),
+        );
+        return Err(error);
+    }
+    state.record(HistoryEvent::RolledBack, version, from_version, None);
+    state.settings.lock().unwrap().skipped_version = Some(from_version.clone());
+    state.save();
+    *state.health.lock().unwrap() = None;
+    state.save_health()?;
+    app.restart();
+}
+
+fn load_settings(dir: &Path) -> Result<UpdaterSettings, Box<dyn std::error::Error>> {
+    let settings: UpdaterSettings = match fs::read(dir.join(SETTINGS_FILE)) {
+        Ok(bytes) => serde_json::from_slice(&bytes)?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => UpdaterSettings::default(),
+        Err(e) => return Err(e.into()),
+    };
+    Ok(settings.normalized()?)
+}
+
+/// Load the update settings, drawing and storing the rollout bucket on
+/// first run, and count this launch against the last update
+pub fn init(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+    let data_dir = app.path().app_data_dir()?;
+    let first_run = !data_dir.join(SETTINGS_FILE).exists();
+    let settings = load_settings(&data_dir).unwrap_or_else(|e| {
+        eprintln!("Failed to load update settings: {}", e);
+        UpdaterSettings::default()
+    });
+    let health = rollback::load_health(&data_dir).unwrap_or_else(|e| {
+        eprintln!("Failed to load launch health: {}", e);
+        None
+    });
+    let state = app.state::<UpdaterState>();
+    *state.settings.lock().unwrap() = settings;
+    *state.data_dir.lock().unwrap() = Some(data_dir);
+    if first_run {
+        state.save();
+    }
+
+    let Some(mut health) = health else {
+        return Ok(());
+    };
+    let check = health.launch(&app.package_info().version.to_string());
+    let (version, from_version) = (&health.version, &health.previous_version);
+    match check {
+        LaunchCheck::Idle => return Ok(()),
+        LaunchCheck::NotApplied => {
+            state.record(HistoryEvent::NotApplied, version, from_version, None);
+            state.save_health()?;
+            return Ok(());
+        }
+        LaunchCheck::Pending => {}
+        LaunchCheck::OfferRollback { failed } => {
+            state.record(HistoryEvent::RollbackOffered, version, from_version, None);
+            tauri::async_runtime::spawn(offer_rollback(app.clone(), health.clone(), failed));
+        }
+    }
+    *state.health.lock().unwrap() = Some(health);
+    state.save_health()?;
+    tauri::async_runtime::spawn(watch_health(app.clone()));
+    Ok(())
+}
+
+// ========== Commands ==========
+
+/// Tauri command: Check for updates, caching the one found
+#[tauri::command]
+pub async fn check_for_updates(
+    app: AppHandle,
+    state: State<'_, UpdaterState>,
+) -> Result<Option<UpdateInfo>, String> {
+    let settings = state.settings.lock().unwrap().clone();
+    let updater = app
+        .updater_builder()
+        .endpoints(vec![settings.endpoint()?])
+        .and_then(|builder| builder.build())
+        .map_err(|e| e.to_string())?;
+    let update = updater
+        .check()
+        .await
+        .map_err(|e| e.to_string())?
+        .filter(|update| settings.skipped_version.as_ref() != Some(&update.version))
+        .filter(|update| in_rollout(&update.raw_json, settings.bucket));
+
+    let info = update.as_ref().map(UpdateInfo::new);
+    let mut cached = state.update.lock().unwrap();
+    let mut downloaded = state.downloaded.lock().unwrap();
+    if downloaded.as_ref().map(|d| &d.version) != info.as_ref().map(|i| &i.version) {
+        *downloaded = None;
+    }
+    *cached = update;
+    Ok(info)
+}
+
+/// Tauri command: Get the update channel and rollout bucket
+#[tauri::command]
+pub fn updater_get_settings(state: State<'_, UpdaterState>) -> UpdaterSettings {
+    state.settings.lock().unwrap().clone()
+}
+
+/// Tauri command: Pick the update channel and manifest server; the
+/// rollout bucket stays as drawn
+#[tauri::command]
+pub fn updater_set_settings(
+    state: State<'_, UpdaterState>,
+    channel: Channel,
+    base_url: Option<String>,
+) -> Result<UpdaterSettings, String> {
+    let changed = {
+        let mut current = state.settings.lock().unwrap();
+        let settings = UpdaterSettings {
+            channel,
+            base_url,
+            bucket: current.bucket,
+            skipped_version: current.skipped_version.clone(),
+        }
+        .normalized()?;
+        std::mem::replace(&mut *current, settings) != *current
+    };
+    state.save();
+    if changed {
+        // An update from the old channel must not be installed
+        state.forget();
+    }
+    Ok(state.settings.lock().unwrap().clone())
+}
+
+/// Tauri command: Get the cached update and how far it has got
+#[tauri::command]
+pub fn update_status(state: State<'_, UpdaterState>) -> UpdateStatus {
+    state.status()
+}
+
+/// Tauri command: Download the cached update without installing it
+#[tauri::command]
+pub async fn update_download(app: AppHandle, state: State<'_, UpdaterState>) -> Result<(), String> {
+    let update = state.cached()?;
+    if state.status().downloaded {
+        return Ok(());
+    }
+    download(&app, &update).await
+}
+
+/// Tauri command: Install the cached update when Wraith next quits
+#[tauri::command]
+pub fn update_set_install_on_quit(
+    state: State<'_, UpdaterState>,
+    enabled: bool,
+) -> Result<UpdateStatus, String> {
+    if enabled {
+        state.cached()?;
+    }
+    *state.install_on_quit.lock().unwrap() = enabled;
+    Ok(state.status())
+}
+
+/// Tauri command: Install the cached update and restart, downloading it
+/// first if needed
+#[tauri::command]
+pub async fn install_update(app: AppHandle, state: State<'_, UpdaterState>) -> Result<(), String> {
+    let update = state.cached()?;
+    if !state.status().downloaded {
+        download(&app, &update).await?;
+    }
+    let bytes = state
+        .take_package(&update)
+        .ok_or_else(|| "The update is no longer available".to_string())?;
+    install(&app, &update, bytes)?;
+    app.restart();
+}
+
+/// Tauri command: Get the update history, newest first
+#[tauri::command]
+pub fn update_history(
+    state: State<'_, UpdaterState>,
+    limit: Option<usize>,
+) -> Result<Vec<HistoryEntry>, String> {
+    let Some(dir) = state.data_dir.lock().unwrap().clone() else {
+        return Ok(Vec::new());
+    };
+    let _guard = state.history_lock.lock().unwrap();
+    rollback::read_history(&dir, limit.unwrap_or(DEFAULT_HISTORY_LIMIT)).map_err(|e| e.to_string())
+}
+
+/// Tauri command: Get the health of the last installed update, if any
+#[tauri::command]
+pub fn update_health(state: State<'_, UpdaterState>) -> Option<LaunchHealth> {
+    state.health.lock().unwrap().clone()
+}
+
+/// Tauri command: Go back to the version the last update replaced and
+/// restart
+#[tauri::command]
+pub fn update_rollback(app: AppHandle) -> Result<(), String> {
+    roll_back(&app)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn settings(channel: Channel, base_url: Option<&str>) -> UpdaterSettings {
+        UpdaterSettings {
+            channel,
+            base_url: base_url.map(str::to_string),
+            bucket: 42,
+            skipped_version: None,
+        }
+        .normalized()
+        .unwrap()
+    }
+
+    #[test]
+    fn builds_channel_endpoints() {
+        assert_eq!(
+            settings(Channel::Stable, None).endpoint().unwrap().as_str(),
+            "https://releases.wraith.dev/tauri/stable/latest.json"
+        );
+        assert_eq!(
+            settings(Channel::Nightly, Some(" http://localhost:8000/ "))
+                .endpoint()
+                .unwrap()
+                .as_str(),
+            "http://localhost:8000/nightly/latest.json"
+        );
+        assert_eq!(settings(Channel::Beta, Some("")).base_url, None);
+        for base_url in ["file:///tmp", "localhost:8000", "https://a.dev/?x=1"] {
+            let settings = UpdaterSettings {
+                base_url: Some(base_url.to_string()),
+                ..UpdaterSettings::default()
+            };
+            assert!(settings.normalized().is_err(), "{}", base_url);
+        }
+    }
+
+    #[test]
+    fn keeps_the_bucket_once_drawn() {
+        let drawn = UpdaterSettings::default();
+        assert!(drawn.bucket < ROLLOUT_BUCKETS);
+        let stored: UpdaterSettings =
+            serde_json::from_slice(&serde_json::to_vec(&drawn).unwrap()).unwrap();
+        assert_eq!(stored, drawn);
+
+        let legacy: UpdaterSettings = serde_json::from_value(json!({ "channel": "beta" })).unwrap();
+        assert_eq!(legacy.channel, Channel::Beta);
+        assert!(legacy.bucket < ROLLOUT_BUCKETS);
+    }
+
+    #[test]
+    fn stages_rollouts_by_bucket() {
+        let manifest = |rollout: Value| json!({ "version": "1.2.0", "rollout": rollout });
+        assert!(in_rollout(&json!({ "version": "1.2.0" }), 99));
+        assert!(in_rollout(&manifest(Value::Null), 99));
+        assert!(in_rollout(&manifest(json!(100)), 99));
+        assert!(!in_rollout(&manifest(json!(0)), 0));
+        assert!(in_rollout(&manifest(json!(25)), 24));
+        assert!(!in_rollout(&manifest(json!(25)), 25));
+        assert!(in_rollout(&manifest(json!(0.5)), 0));
+        assert!(!in_rollout(&manifest(json!("50")), 0));
+        // Raising the percentage only adds buckets
+        for bucket in 0..ROLLOUT_BUCKETS {
+            let before = in_rollout(&manifest(json!(10)), bucket);
+            assert!(!before || in_rollout(&manifest(json!(50)), bucket));
+        }
+    }
+
+    #[test]
+    fn reports_each_percent_of_a_known_size() {
+        let mut progress = Progress::new("1.2.0");
+        let total = Some(1000);
+        assert_eq!(progress.advance(4, total), None);
+        assert_eq!(
+            progress.advance(6, total),
+            Some(DownloadProgress {
+                version: "1.2.0".to_string(),
+                downloaded: 10,
+                total,
+            })
+        );
+        assert_eq!(progress.advance(9, total), None);
+        let reported = (0..98).filter_map(|_| progress.advance(10, total)).count();
+        assert_eq!(reported, 98);
+        // The last chunk is always reported, however small
+        assert_eq!(progress.advance(1, total).unwrap().downloaded, 1000);
+    }
+
+    #[test]
+    fn reports_every_step_of_an_unknown_size() {
+        let mut progress = Progress::new("1.2.0");
+        let chunk = UNKNOWN_SIZE_STEP as usize / 4;
+        let reported: Vec<u64> = (0..8)
+            .filter_map(|_| progress.advance(chunk, None))
+            .map(|p| p.downloaded)
+            .collect();
+        assert_eq!(reported, [UNKNOWN_SIZE_STEP, 2 * UNKNOWN_SIZE_STEP]);
+    }
+}
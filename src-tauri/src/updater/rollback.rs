@@ -0,0 +1,410 @@
+//! Update rollback and launch health
+//!
+//! Before an update is installed, the current install (the AppImage, the
+//! `.app` bundle, or the executable elsewhere) is copied aside and a
+//! [`LaunchHealth`] record is written for the incoming version. A launch of
+//! that version counts as failed if it exits before its webview loads;
+//! after [`FAILED_LAUNCHES_BEFORE_ROLLBACK`] failed launches in a row, a
+//! rollback to the copy is offered. Every step is appended to the update
+//! history log.
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+pub const HEALTH_FILE: &str = "update-health.json";
+pub const HISTORY_FILE: &str = "update-history.jsonl";
+/// Directory under the app data dir holding the previous install
+const BACKUP_DIR: &str = "rollback";
+/// Failed launches of a new version before a rollback is offered
+pub const FAILED_LAUNCHES_BEFORE_ROLLBACK: u32 = 3;
+
+/// How the version installed by the last update is doing
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LaunchHealth {
+    /// Version installed by the update
+    pub version: String,
+    pub previous_version: String,
+    /// Copy of the previous install
+    pub backup: PathBuf,
+    /// Where the install lives; `backup` is restored here
+    pub target: PathBuf,
+    /// Launches of `version` in a row that didn't load the webview,
+    /// including the current one until it does
+    pub launches: u32,
+    /// Whether a launch of `version` has come up healthy
+    pub verified: bool,
+}
+
+/// What a launch should do about the last update
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LaunchCheck {
+    /// Nothing to verify
+    Idle,
+    /// The update isn't verified yet; this launch may verify it
+    Pending,
+    /// The update isn't verified and has failed to launch this many times
+    /// in a row
+    OfferRollback { failed: u32 },
+    /// The update never took effect
+    NotApplied,
+}
+
+impl LaunchHealth {
+    /// Count a launch of `current`
+    pub fn launch(&mut self, current: &str) -> LaunchCheck {
+        if self.version != current {
+            return if self.verified {
+                LaunchCheck::Idle
+            } else {
+                LaunchCheck::NotApplied
+            };
+        }
+        if self.verified {
+            return LaunchCheck::Idle;
+        }
+        let failed = self.launches;
+        self.launches += 1;
+        if failed >= FAILED_LAUNCHES_BEFORE_ROLLBACK {
+            LaunchCheck::OfferRollback { failed }
+        } else {
+            LaunchCheck::Pending
+        }
+    }
+
+    /// The current launch loaded its webview, so it didn't fail
+    pub fn loaded(&mut self) {
+        self.launches = 0;
+    }
+
+    pub fn verify(&mut self) {
+        self.verified = true;
+        self.launches = 0;
+    }
+}
+
+/// What happened during an update
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryEvent {
+    Installing,
+    Installed,
+    InstallFailed,
+    /// A launch of the new version came up healthy
+    Verified,
+    /// The installed version doesn't match the update
+    NotApplied,
+    RollbackOffered,
+    /// The user chose to keep the new version
+    RollbackDeclined,
+    RolledBack,
+    RollbackFailed,
+}
+
+/// A line of the update history log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEntry {
+    pub at: i64,
+    pub event: HistoryEvent,
+    /// Version being installed, or rolled back to
+    pub version: String,
+    pub from_version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+pub fn append_history(dir: &Path, entry: &HistoryEntry) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let mut line =
+        serde_json::to_vec(entry).map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+    line.push(b'\n');
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(HISTORY_FILE))?;
+    file.write_all(&line)
+}
+
+/// The newest `limit` entries, newest first; unreadable lines are skipped
+pub fn read_history(dir: &Path, limit: usize) -> io::Result<Vec<HistoryEntry>> {
+    let file = match fs::File::open(dir.join(HISTORY_FILE)) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut entries = Vec::new();
+    for line in BufReader::new(file).lines() {
+        if let Ok(entry) = serde_json::from_str::<HistoryEntry>(&line?) {
+            entries.push(entry);
+        }
+    }
+    entries.reverse();
+    entries.truncate(limit);
+    Ok(entries)
+}
+
+pub fn load_health(dir: &Path) -> Result<Option<LaunchHealth>, Box<dyn std::error::Error>> {
+    match fs::read(dir.join(HEALTH_FILE)) {
+        Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub fn save_health(dir: &Path, health: Option<&LaunchHealth>) -> io::Result<()> {
+    let path = dir.join(HEALTH_FILE);
+    let Some(health) = health else {
+        return match fs::remove_file(path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        };
+    };
+    fs::create_dir_all(dir)?;
+    let json =
+        serde_json::to_vec_pretty(health).map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+    fs::write(path, json)
+}
+
+/// The file or bundle an update replaces
+pub fn install_target() -> io::Result<PathBuf> {
+    #[cfg(target_os = "linux")]
+    if let Some(appimage) = std::env::var_os("APPIMAGE") {
+        return Ok(PathBuf::from(appimage));
+    }
+    let exe = std::env::current_exe()?;
+    #[cfg(target_os = "macos")]
+    if let Some(bundle) = exe
+        .ancestors()
+        .find(|path| path.extension().is_some_and(|ext| ext == "app"))
+    {
+        return Ok(bundle.to_path_buf());
+    }
+    Ok(exe)
+}
+
+/// Copy `target` into the backup directory under `data_dir`, replacing the
+/// copy kept from an earlier update
+pub fn back_up(data_dir: &Path, target: &Path, version: &str) -> io::Result<PathBuf> {
+    let name = target
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "install has no file name"))?;
+    let dir = data_dir.join(BACKUP_DIR);
+    remove(&dir)?;
+    let backup = dir.join(version).join(name);
+    fs::create_dir_all(backup.parent().unwrap_or(&dir))?;
+    if let Err(e) = copy_recursive(target, &backup) {
+        let _ = remove(&dir);
+        return Err(e);
+    }
+    Ok(backup)
+}
+
+/// Put `backup` back in place of `target`. The current install is moved
+/// aside rather than overwritten, which also works for a running
+/// executable, and is moved back if the copy fails.
+pub fn restore(backup: &Path, target: &Path) -> io::Result<()> {
+    if fs::symlink_metadata(backup).is_err() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "the previous version is no longer available",
+        ));
+    }
+    let mut displaced = target.as_os_str().to_owned();
+    displaced.push(".failed");
+    let displaced = PathBuf::from(displaced);
+    remove(&displaced)?;
+    fs::rename(target, &displaced)?;
+    if let Err(e) = copy_recursive(backup, target) {
+        let _ = remove(target);
+        let _ = fs::rename(&displaced, target);
+        return Err(e);
+    }
+    // A running executable may not be removable yet; it goes next time
+    let _ = remove(&displaced);
+    Ok(())
+}
+
+/// Copy a file, or a directory tree keeping its symlinks
+fn copy_recursive(from: &Path, to: &Path) -> io::Result<()> {
+    let metadata = fs::symlink_metadata(from)?;
+    if metadata.is_dir() {
+        fs::create_dir(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_recursive(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        fs::set_permissions(to, metadata.permissions())
+    } else if metadata.file_type().is_symlink() {
+        copy_symlink(from, to)
+    } else {
+        fs::copy(from, to).map(|_| ())
+    }
+}
+
+#[cfg(unix)]
+fn copy_symlink(from: &Path, to: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(fs::read_link(from)?, to)
+}
+
+#[cfg(not(unix))]
+fn copy_symlink(from: &Path, to: &Path) -> io::Result<()> {
+    fs::copy(from, to).map(|_| ())
+}
+
+/// Remove a file or directory tree if it exists
+fn remove(path: &Path) -> io::Result<()> {
+    let result = match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.is_dir() => fs::remove_dir_all(path),
+        Ok(_) => fs::remove_file(path),
+        Err(e) => Err(e),
+    };
+    match result {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("wraith-rollback-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn health() -> LaunchHealth {
+        LaunchHealth {
+            version: "1.1.0".to_string(),
+            previous_version: "1.0.0".to_string(),
+            backup: PathBuf::from("backup"),
+            target: PathBuf::from("target"),
+            launches: 0,
+            verified: false,
+        }
+    }
+
+    #[test]
+    fn offers_rollback_after_repeated_failed_launches() {
+        let mut health = health();
+        for _ in 0..FAILED_LAUNCHES_BEFORE_ROLLBACK {
+            assert_eq!(health.launch("1.1.0"), LaunchCheck::Pending);
+        }
+        assert_eq!(
+            health.launch("1.1.0"),
+            LaunchCheck::OfferRollback {
+                failed: FAILED_LAUNCHES_BEFORE_ROLLBACK
+            }
+        );
+        assert_eq!(
+            health.launch("1.1.0"),
+            LaunchCheck::OfferRollback {
+                failed: FAILED_LAUNCHES_BEFORE_ROLLBACK + 1
+            }
+        );
+
+        health.verify();
+        assert_eq!(health.launch("1.1.0"), LaunchCheck::Idle);
+        assert_eq!(health.launches, 0);
+    }
+
+    #[test]
+    fn launches_that_load_are_not_failures() {
+        let mut health = health();
+        for _ in 0..FAILED_LAUNCHES_BEFORE_ROLLBACK + 1 {
+            assert_eq!(health.launch("1.1.0"), LaunchCheck::Pending);
+            // Loaded, then quit before the socket connected
+            health.loaded();
+        }
+        assert!(!health.verified);
+        assert_eq!(health.launches, 0);
+    }
+
+    #[test]
+    fn notices_updates_that_never_applied() {
+        let mut health = health();
+        assert_eq!(health.launch("1.0.0"), LaunchCheck::NotApplied);
+        health.verify();
+        assert_eq!(health.launch("1.2.0"), LaunchCheck::Idle);
+    }
+
+    #[test]
+    fn backs_up_and_restores_an_install() {
+        let dir = temp_dir("restore");
+        let data_dir = dir.join("data");
+        let target = dir.join("Wraith.app");
+        fs::create_dir_all(target.join("Contents/MacOS")).unwrap();
+        fs::write(target.join("Contents/MacOS/wraith"), b"v1").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink("MacOS/wraith", target.join("Contents/current")).unwrap();
+
+        let backup = back_up(&data_dir, &target, "1.0.0").unwrap();
+        assert_eq!(backup, data_dir.join("rollback/1.0.0/Wraith.app"));
+
+        // The update replaces the bundle, then gets rolled back
+        fs::write(target.join("Contents/MacOS/wraith"), b"v2").unwrap();
+        fs::write(target.join("Contents/new"), b"").unwrap();
+        restore(&backup, &target).unwrap();
+        assert_eq!(
+            fs::read(target.join("Contents/MacOS/wraith")).unwrap(),
+            b"v1"
+        );
+        assert!(!target.join("Contents/new").exists());
+        assert!(!dir.join("Wraith.app.failed").exists());
+        #[cfg(unix)]
+        assert_eq!(
+            fs::read_link(target.join("Contents/current")).unwrap(),
+            Path::new("MacOS/wraith")
+        );
+
+        // A newer backup replaces the old one
+        let backup = back_up(&data_dir, &target, "1.1.0").unwrap();
+        assert!(backup.exists());
+        assert!(!data_dir.join("rollback/1.0.0").exists());
+
+        fs::remove_dir_all(&backup).unwrap();
+        assert!(restore(&backup, &target).is_err());
+        assert!(target.join("Contents/MacOS/wraith").exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn history_and_health_round_trip() {
+        let dir = temp_dir("history");
+        assert!(read_history(&dir, 10).unwrap().is_empty());
+        assert_eq!(load_health(&dir).unwrap(), None);
+
+        for event in [HistoryEvent::Installing, HistoryEvent::Installed] {
+            let entry = HistoryEntry {
+                at: 1,
+                event,
+                version: "1.1.0".to_string(),
+                from_version: "1.0.0".to_string(),
+                error: None,
+            };
+            append_history(&dir, &entry).unwrap();
+        }
+        let events: Vec<HistoryEvent> = read_history(&dir, 10)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.event)
+            .collect();
+        assert_eq!(events, [HistoryEvent::Installed, HistoryEvent::Installing]);
+
+        save_health(&dir, Some(&health())).unwrap();
+        assert_eq!(load_health(&dir).unwrap(), Some(health()));
+        save_health(&dir, None).unwrap();
+        save_health(&dir, None).unwrap();
+        assert_eq!(load_health(&dir).unwrap(), None);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
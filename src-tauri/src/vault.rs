@@ -0,0 +1,829 @@
+//! Encrypted key vault
+//!
+//! Wallet secrets are kept in `vault.json` in the app data dir, sealed with
+//! XChaCha20-Poly1305 under a key derived from the user's passphrase with
+//! Argon2id. The derived key is held in memory only while the vault is
+//! unlocked, and the vault locks itself after a period of inactivity.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use tauri::{Emitter, Manager, State};
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+use crate::now_millis;
+
+/// File name of the vault inside the app data dir
+const VAULT_FILE: &str = "vault.json";
+
+/// Plaintext key file written by builds that predate the vault
+const LEGACY_KEY_FILE: &str = "wallet.key";
+
+/// Vault entry name given to a migrated legacy key
+const LEGACY_KEY_NAME: &str = "default";
+
+/// Current on-disk format version
+const VAULT_VERSION: u32 = 1;
+
+/// Associated data bound to every ciphertext
+const VAULT_AAD: &[u8] = b"wraith-vault-v1";
+
+/// Lock after 15 minutes without vault access unless configured otherwise
+const DEFAULT_IDLE_TIMEOUT_SECS: u64 = 15 * 60;
+
+/// How often the idle watcher checks the vault
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+const MIN_PASSPHRASE_LEN: usize = 8;
+
+/// Errors produced by vault operations
+#[derive(Debug, thiserror::Error)]
+pub enum VaultError {
+    #[error("No vault has been created")]
+    NotFound,
+    #[error("A vault already exists")]
+    AlreadyExists,
+    #[error("Vault is locked")]
+    Locked,
+    #[error("Incorrect passphrase")]
+    WrongPassphrase,
+    #[error("Passphrase must be at least {MIN_PASSPHRASE_LEN} characters")]
+    WeakPassphrase,
+    #[error("Vault file is corrupt: {0}")]
+    Corrupt(String),
+    #[error("Vault storage error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Argon2id cost parameters, stored alongside the ciphertext
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KdfParams {
+    /// Memory cost in KiB
+    pub m_cost: u32,
+    /// Number of passes
+    pub t_cost: u32,
+    /// Degree of parallelism
+    pub p_cost: u32,
+}
+
+impl Default for KdfParams {
+    /// OWASP's recommended Argon2id baseline
+    fn default() -> Self {
+        Self {
+            m_cost: 19 * 1024,
+            t_cost: 2,
+            p_cost: 1,
+        }
+    }
+}
+
+/// On-disk vault representation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VaultFile {
+    version: u32,
+    kdf: KdfParams,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+    idle_timeout_secs: u64,
+}
+
+/// A named secret key stored in the vault
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultKey {
+    pub name: String,
+    pub secret_key: String,
+    /// Unix milliseconds when the key was added
+    #[serde(default)]
+    pub created_at: u64,
+}
+
+/// Decrypted vault payload
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
+pub struct VaultContents {
+    keys: Vec<VaultKey>,
+    /// Name of the key used for signing
+    #[serde(default)]
+    active: Option<String>,
+}
+
+impl VaultContents {
+    /// All stored keys, in insertion order
+    pub fn keys(&self) -> &[VaultKey] {
+        &self.keys
+    }
+
+    /// Hex secret key stored under `name`
+    pub fn key(&self, name: &str) -> Option<&str> {
+        self.keys
+            .iter()
+            .find(|k| k.name == name)
+            .map(|k| k.secret_key.as_str())
+    }
+
+    /// Insert or replace the secret key stored under `name`
+    pub fn set_key(&mut self, name: &str, secret_key: &str) {
+        match self.keys.iter_mut().find(|k| k.name == name) {
+            Some(existing) => {
+                existing.secret_key.zeroize();
+                existing.secret_key = secret_key.to_string();
+            }
+            None => self.keys.push(VaultKey {
+                name: name.to_string(),
+                secret_key: secret_key.to_string(),
+                created_at: now_millis() as u64,
+            }),
+        }
+    }
+
+    /// Rename a key, keeping it active if it was
+    pub fn rename_key(&mut self, name: &str, new_name: &str) -> bool {
+        let Some(key) = self.keys.iter_mut().find(|k| k.name == name) else {
+            return false;
+        };
+        key.name = new_name.to_string();
+        if self.active.as_deref() == Some(name) {
+            self.active = Some(new_name.to_string());
+        }
+        true
+    }
+
+    /// Remove the key stored under `name`, returning whether it existed
+    ///
+    /// Removing the active key makes the first remaining key active.
+    pub fn remove_key(&mut self, name: &str) -> bool {
+        let before = self.keys.len();
+        self.keys.retain(|k| k.name != name);
+        if self.active.as_deref() == Some(name) {
+            self.active = self.keys.first().map(|k| k.name.clone());
+        }
+        self.keys.len() != before
+    }
+
+    /// Name of the active key
+    pub fn active(&self) -> Option<&str> {
+        self.active.as_deref()
+    }
+
+    /// Make `name` the active key, returning false if it doesn't exist
+    pub fn set_active(&mut self, name: &str) -> bool {
+        if self.key(name).is_none() {
+            return false;
+        }
+        self.active = Some(name.to_string());
+        true
+    }
+}
+
+/// Derive the 256-bit vault key from a passphrase
+fn derive_key(
+    passphrase: &str,
+    salt: &[u8],
+    params: KdfParams,
+) -> Result<Zeroizing<[u8; 32]>, VaultError> {
+    let params = Params::new(params.m_cost, params.t_cost, params.p_cost, Some(32))
+        .map_err(|e| VaultError::Corrupt(e.to_string()))?;
+    let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+
+    let mut key = Zeroizing::new([0u8; 32]);
+    argon2
+        .hash_password_into(passphrase.as_bytes(), salt, key.as_mut_slice())
+        .map_err(|e| VaultError::Corrupt(e.to_string()))?;
+    Ok(key)
+}
+
+/// Encrypt the contents under `key`, returning hex nonce and ciphertext
+fn seal(key: &[u8; 32], contents: &VaultContents) -> Result<(String, String), VaultError> {
+    let plaintext = Zeroizing::new(
+        serde_json::to_vec(contents).map_err(|e| VaultError::Corrupt(e.to_string()))?,
+    );
+
+    let mut nonce = [0u8; 24];
+    OsRng.fill_bytes(&mut nonce);
+
+    let cipher = XChaCha20Poly1305::new(key.into());
+    let ciphertext = cipher
+        .encrypt(
+            XNonce::from_slice(&nonce),
+            Payload {
+                msg: &plaintext,
+                aad: VAULT_AAD,
+            },
+        )
+        .map_err(|_| VaultError::Corrupt("encryption failed".into()))?;
+
+    Ok((hex::encode(nonce), hex::encode(ciphertext)))
+}
+
+/// Decrypt vault contents; a failed tag check means a wrong passphrase
+fn open(key: &[u8; 32], nonce: &str, ciphertext: &str) -> Result<VaultContents, VaultError> {
+    let nonce = hex::decode(nonce).map_err(|e| VaultError::Corrupt(e.to_string()))?;
+    if nonce.len() != 24 {
+        return Err(VaultError::Corrupt("invalid nonce length".into()));
+    }
+    let ciphertext = hex::decode(ciphertext).map_err(|e| VaultError::Corrupt(e.to_string()))?;
+
+    let cipher = XChaCha20Poly1305::new(key.into());
+    let plaintext = Zeroizing::new(
+        cipher
+            .decrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: &ciphertext,
+                    aad: VAULT_AAD,
+                },
+            )
+            .map_err(|_| VaultError::WrongPassphrase)?,
+    );
+
+    serde_json::from_slice(&plaintext).map_err(|e| VaultError::Corrupt(e.to_string()))
+}
+
+fn check_passphrase(passphrase: &str) -> Result<(), VaultError> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+        return Err(VaultError::WeakPassphrase);
+    }
+    Ok(())
+}
+
+fn random_salt() -> [u8; 16] {
+    let mut salt = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    salt
+}
+
+/// Keys held while the vault is unlocked
+struct Session {
+    key: Zeroizing<[u8; 32]>,
+    contents: VaultContents,
+}
+
+/// An on-disk vault and, while unlocked, its decrypted contents
+pub struct Vault {
+    path: PathBuf,
+    file: VaultFile,
+    session: Option<Session>,
+    last_activity: Instant,
+}
+
+impl Vault {
+    /// Create a new vault at `path`, leaving it unlocked
+    pub fn create(
+        path: &Path,
+        passphrase: &str,
+        params: KdfParams,
+        contents: VaultContents,
+    ) -> Result<Self, VaultError> {
+        check_passphrase(passphrase)?;
+        if path.exists() {
+            return Err(VaultError::AlreadyExists);
+        }
+
+        let salt = random_salt();
+        let key = derive_key(passphrase, &salt, params)?;
+        let (nonce, ciphertext) = seal(&key, &contents)?;
+
+        let vault = Self {
+            path: path.to_path_buf(),
+            file: VaultFile {
+                version: VAULT_VERSION,
+                kdf: params,
+                salt: hex::encode(salt),
+                nonce,
+                ciphertext,
+                idle_timeout_secs: DEFAULT_IDLE_TIMEOUT_SECS,
+            },
+            session: Some(Session { key, contents }),
+            last_activity: Instant::now(),
+        };
+        vault.write()?;
+        Ok(vault)
+    }
+
+    /// Load a locked vault from `path`
+    pub fn load(path: &Path) -> Result<Self, VaultError> {
+        let data = match fs::read(path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Err(VaultError::NotFound),
+            Err(e) => return Err(e.into()),
+        };
+        let file: VaultFile =
+            serde_json::from_slice(&data).map_err(|e| VaultError::Corrupt(e.to_string()))?;
+        if file.version != VAULT_VERSION {
+            return Err(VaultError::Corrupt(format!(
+                "unsupported version {}",
+                file.version
+            )));
+        }
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            session: None,
+            last_activity: Instant::now(),
+        })
+    }
+
+    pub fn is_unlocked(&self) -> bool {
+        self.session.is_some()
+    }
+
+    pub fn idle_timeout(&self) -> Option<Duration> {
+        match self.file.idle_timeout_secs {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        }
+    }
+
+    /// Decrypt the vault with `passphrase`
+    pub fn unlock(&mut self, passphrase: &str) -> Result<(), VaultError> {
+        let salt = hex::decode(&self.file.salt).map_err(|e| VaultError::Corrupt(e.to_string()))?;
+        let key = derive_key(passphrase, &salt, self.file.kdf)?;
+        let contents = open(&key, &self.file.nonce, &self.file.ciphertext)?;
+
+        self.session = Some(Session { key, contents });
+        self.last_activity = Instant::now();
+        Ok(())
+    }
+
+    /// Drop the derived key and decrypted contents
+    pub fn lock(&mut self) {
+        self.session = None;
+    }
+
+    /// Re-encrypt the vault under a new passphrase with a fresh salt
+    pub fn change_passphrase(&mut self, current: &str, new: &str) -> Result<(), VaultError> {
+        check_passphrase(new)?;
+        self.unlock(current)?;
+
+        let salt = random_salt();
+        let params = KdfParams::default();
+        let key = derive_key(new, &salt, params)?;
+
+        let session = self.session.as_mut().ok_or(VaultError::Locked)?;
+        session.key = key;
+        self.file.kdf = params;
+        self.file.salt = hex::encode(salt);
+        self.save()
+    }
+
+    /// Change the idle-lock timeout; zero disables it
+    pub fn set_idle_timeout(&mut self, secs: u64) -> Result<(), VaultError> {
+        self.file.idle_timeout_secs = secs;
+        self.write()
+    }
+
+    /// Read the decrypted contents
+    ///
+    /// Reads don't count as activity, so polling can't hold the vault open.
+    pub fn read<T>(&self, f: impl FnOnce(&VaultContents) -> T) -> Result<T, VaultError> {
+        let session = self.session.as_ref().ok_or(VaultError::Locked)?;
+        Ok(f(&session.contents))
+    }
+
+    /// Restart the idle timer after an explicit user action
+    pub fn touch(&mut self) {
+        self.last_activity = Instant::now();
+    }
+
+    /// Modify the decrypted contents, persisting them only if `f` succeeds
+    /// and changed something; on failure the contents are left as they were
+    pub fn update<T, E: From<VaultError>>(
+        &mut self,
+        f: impl FnOnce(&mut VaultContents) -> Result<T, E>,
+    ) -> Result<T, E> {
+        let session = self.session.as_mut().ok_or(VaultError::Locked)?;
+        let before = session.contents.clone();
+        let result = match f(&mut session.contents) {
+            Ok(result) => result,
+            Err(e) => {
+                session.contents = before;
+                return Err(e);
+            }
+        };
+        self.last_activity = Instant::now();
+        if session.contents != before {
+            self.save()?;
+        }
+        Ok(result)
+    }
+
+    /// Whether the vault is unlocked and has been idle past its timeout
+    pub fn is_idle(&self, now: Instant) -> bool {
+        match self.idle_timeout() {
+            Some(timeout) => {
+                self.is_unlocked() && now.duration_since(self.last_activity) >= timeout
+            }
+            None => false,
+        }
+    }
+
+    /// Re-seal the unlocked contents and write them out
+    fn save(&mut self) -> Result<(), VaultError> {
+        let session = self.session.as_ref().ok_or(VaultError::Locked)?;
+        let (nonce, ciphertext) = seal(&session.key, &session.contents)?;
+        self.file.nonce = nonce;
+        self.file.ciphertext = ciphertext;
+        self.write()
+    }
+
+    fn write(&self) -> Result<(), VaultError> {
+        let data = serde_json::to_vec_pretty(&self.file)
+            .map_err(|e| VaultError::Corrupt(e.to_string()))?;
+        write_private(&self.path, &data)
+    }
+}
+
+/// Atomically write a file readable only by the current user where supported
+fn write_private(path: &Path, data: &[u8]) -> Result<(), VaultError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension("tmp");
+
+    #[cfg(unix)]
+    {
+        use std::io::Write;
+        use std::os::unix::fs::OpenOptionsExt;
+
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(&tmp)?;
+        file.write_all(data)?;
+        file.sync_all()?;
+    }
+
+    #[cfg(not(unix))]
+    fs::write(&tmp, data)?;
+
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Managed state holding the vault
+#[derive(Default)]
+pub struct VaultState {
+    data_dir: Mutex<Option<PathBuf>>,
+    vault: Mutex<Option<Vault>>,
+}
+
+impl VaultState {
+    /// Read the unlocked vault contents
+    pub fn read<T>(&self, f: impl FnOnce(&VaultContents) -> T) -> Result<T, VaultError> {
+        let vault = self.vault.lock().unwrap();
+        vault.as_ref().ok_or(VaultError::NotFound)?.read(f)
+    }
+
+    /// Restart the idle timer after an explicit user action
+    pub fn touch(&self) {
+        if let Some(vault) = self.vault.lock().unwrap().as_mut() {
+            vault.touch();
+        }
+    }
+
+    /// Modify the unlocked vault contents, persisting them if they changed
+    pub fn update<T, E: From<VaultError>>(
+        &self,
+        f: impl FnOnce(&mut VaultContents) -> Result<T, E>,
+    ) -> Result<T, E> {
+        let mut vault = self.vault.lock().unwrap();
+        vault.as_mut().ok_or(VaultError::NotFound)?.update(f)
+    }
+
+    /// Lock the vault if it has been idle too long, returning whether it locked
+    fn lock_if_idle(&self) -> bool {
+        let mut vault = self.vault.lock().unwrap();
+        match vault.as_mut() {
+            Some(v) if v.is_idle(Instant::now()) => {
+                v.lock();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn data_dir(&self) -> Result<PathBuf, VaultError> {
+        self.data_dir
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or(VaultError::NotFound)
+    }
+}
+
+/// Vault status reported to the frontend
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultStatus {
+    pub exists: bool,
+    pub unlocked: bool,
+    pub idle_timeout_secs: u64,
+}
+
+/// Load the vault (locked) and start the idle watcher during setup
+pub fn init(app: &tauri::AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+    let data_dir = app.path().app_data_dir()?;
+    let state = app.state::<VaultState>();
+
+    *state.data_dir.lock().unwrap() = Some(data_dir.clone());
+    match Vault::load(&data_dir.join(VAULT_FILE)) {
+        Ok(vault) => *state.vault.lock().unwrap() = Some(vault),
+        Err(VaultError::NotFound) => {}
+        Err(e) => return Err(e.into()),
+    }
+
+    let handle = app.clone();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(IDLE_CHECK_INTERVAL);
+        if handle.state::<VaultState>().lock_if_idle() {
+            let _ = handle.emit("vault-locked", ());
+        }
+    });
+
+    Ok(())
+}
+
+/// Tauri command: Get vault status
+#[tauri::command]
+pub fn vault_status(state: State<'_, VaultState>) -> VaultStatus {
+    let vault = state.vault.lock().unwrap();
+    VaultStatus {
+        exists: vault.is_some(),
+        unlocked: vault.as_ref().is_some_and(Vault::is_unlocked),
+        idle_timeout_secs: vault
+            .as_ref()
+            .map_or(DEFAULT_IDLE_TIMEOUT_SECS, |v| v.file.idle_timeout_secs),
+    }
+}
+
+/// Tauri command: Create the vault, migrating any legacy plaintext key
+#[tauri::command]
+pub fn vault_create(state: State<'_, VaultState>, passphrase: String) -> Result<(), String> {
+    let passphrase = Zeroizing::new(passphrase);
+    let data_dir = state.data_dir().map_err(|e| e.to_string())?;
+    let legacy_path = data_dir.join(LEGACY_KEY_FILE);
+
+    let mut contents = VaultContents::default();
+    if let Ok(secret) = fs::read_to_string(&legacy_path) {
+        let secret = Zeroizing::new(secret);
+        contents.set_key(LEGACY_KEY_NAME, secret.trim());
+        contents.set_active(LEGACY_KEY_NAME);
+    }
+
+    let mut vault = state.vault.lock().unwrap();
+    if vault.is_some() {
+        return Err(VaultError::AlreadyExists.to_string());
+    }
+    *vault = Some(
+        Vault::create(
+            &data_dir.join(VAULT_FILE),
+            &passphrase,
+            KdfParams::default(),
+            contents,
+        )
+        .map_err(|e| e.to_string())?,
+    );
+
+    if legacy_path.exists() {
+        fs::remove_file(&legacy_path).map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+/// Tauri command: Unlock the vault with its passphrase
+#[tauri::command]
+pub fn vault_unlock(state: State<'_, VaultState>, passphrase: String) -> Result<(), String> {
+    let passphrase = Zeroizing::new(passphrase);
+    let mut vault = state.vault.lock().unwrap();
+    vault
+        .as_mut()
+        .ok_or(VaultError::NotFound)
+        .and_then(|v| v.unlock(&passphrase))
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Lock the vault
+#[tauri::command]
+pub fn vault_lock(app: tauri::AppHandle, state: State<'_, VaultState>) {
+    if let Some(vault) = state.vault.lock().unwrap().as_mut() {
+        vault.lock();
+    }
+    let _ = app.emit("vault-locked", ());
+}
+
+/// Tauri command: Re-encrypt the vault under a new passphrase
+#[tauri::command]
+pub fn vault_change_passphrase(
+    state: State<'_, VaultState>,
+    current_passphrase: String,
+    new_passphrase: String,
+) -> Result<(), String> {
+    let current = Zeroizing::new(current_passphrase);
+    let new = Zeroizing::new(new_passphrase);
+    let mut vault = state.vault.lock().unwrap();
+    vault
+        .as_mut()
+        .ok_or(VaultError::NotFound)
+        .and_then(|v| v.change_passphrase(&current, &new))
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Set the idle-lock timeout in seconds (0 disables it)
+#[tauri::command]
+pub fn vault_set_idle_timeout(state: State<'_, VaultState>, seconds: u64) -> Result<(), String> {
+    let mut vault = state.vault.lock().unwrap();
+    vault
+        .as_mut()
+        .ok_or(VaultError::NotFound)
+        .and_then(|v| v.set_idle_timeout(seconds))
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Cheap parameters so the tests don't spend seconds in Argon2
+    const TEST_PARAMS: KdfParams = KdfParams {
+        m_cost: 64,
+        t_cost: 1,
+        p_cost: 1,
+    };
+
+    fn temp_vault_path(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("wraith-vault-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir.join(VAULT_FILE)
+    }
+
+    fn sample_contents() -> VaultContents {
+        let mut contents = VaultContents::default();
+        contents.set_key("default", &"11".repeat(32));
+        contents
+    }
+
+    #[test]
+    fn create_lock_unlock_round_trip() {
+        let path = temp_vault_path("round-trip");
+        let mut vault =
+            Vault::create(&path, "correct horse", TEST_PARAMS, sample_contents()).unwrap();
+        vault.lock();
+        assert!(matches!(vault.read(|_| ()), Err(VaultError::Locked)));
+
+        let mut vault = Vault::load(&path).unwrap();
+        assert!(!vault.is_unlocked());
+        vault.unlock("correct horse").unwrap();
+        let secret = vault
+            .read(|c| c.key("default").map(str::to_string))
+            .unwrap();
+        assert_eq!(secret.as_deref(), Some("11".repeat(32).as_str()));
+    }
+
+    #[test]
+    fn wrong_passphrase_is_rejected() {
+        let path = temp_vault_path("wrong");
+        Vault::create(&path, "correct horse", TEST_PARAMS, sample_contents()).unwrap();
+
+        let mut vault = Vault::load(&path).unwrap();
+        assert!(matches!(
+            vault.unlock("battery staple"),
+            Err(VaultError::WrongPassphrase)
+        ));
+        assert!(!vault.is_unlocked());
+    }
+
+    #[test]
+    fn secrets_are_not_stored_in_plaintext() {
+        let path = temp_vault_path("plaintext");
+        Vault::create(&path, "correct horse", TEST_PARAMS, sample_contents()).unwrap();
+
+        let raw = fs::read_to_string(&path).unwrap();
+        assert!(!raw.contains(&"11".repeat(32)));
+    }
+
+    #[test]
+    fn change_passphrase_reencrypts() {
+        let path = temp_vault_path("change");
+        let mut vault =
+            Vault::create(&path, "correct horse", TEST_PARAMS, sample_contents()).unwrap();
+        assert!(matches!(
+            vault.change_passphrase("nope nope", "battery staple"),
+            Err(VaultError::WrongPassphrase)
+        ));
+        vault
+            .change_passphrase("correct horse", "battery staple")
+            .unwrap();
+
+        let mut vault = Vault::load(&path).unwrap();
+        assert!(vault.unlock("correct horse").is_err());
+        vault.unlock("battery staple").unwrap();
+        assert!(vault.read(|c| c.key("default").is_some()).unwrap());
+    }
+
+    #[test]
+    fn updates_persist() {
+        let path = temp_vault_path("update");
+        let mut vault = Vault::create(
+            &path,
+            "correct horse",
+            TEST_PARAMS,
+            VaultContents::default(),
+        )
+        .unwrap();
+        vault
+            .update(|c| {
+                c.set_key("trading", &"22".repeat(32));
+                Ok::<_, VaultError>(())
+            })
+            .unwrap();
+
+        let mut vault = Vault::load(&path).unwrap();
+        vault.unlock("correct horse").unwrap();
+        assert!(vault.read(|c| c.key("trading").is_some()).unwrap());
+    }
+
+    #[test]
+    fn unchanged_or_failed_updates_are_not_written() {
+        let path = temp_vault_path("no-op");
+        let mut vault =
+            Vault::create(&path, "correct horse", TEST_PARAMS, sample_contents()).unwrap();
+        let written = fs::read(&path).unwrap();
+
+        assert!(!vault
+            .update(|c| Ok::<_, VaultError>(c.set_active("missing")))
+            .unwrap());
+        let failed = vault.update(|c| {
+            c.set_key("trading", &"22".repeat(32));
+            Err::<(), _>(VaultError::Locked)
+        });
+        assert!(failed.is_err());
+        assert!(vault.read(|c| c.key("trading").is_none()).unwrap());
+        assert_eq!(fs::read(&path).unwrap(), written);
+    }
+
+    #[test]
+    fn reads_do_not_reset_the_idle_timer() {
+        let path = temp_vault_path("idle-read");
+        let mut vault =
+            Vault::create(&path, "correct horse", TEST_PARAMS, sample_contents()).unwrap();
+        let started = vault.last_activity;
+        std::thread::sleep(Duration::from_millis(5));
+        vault.read(|_| ()).unwrap();
+        assert_eq!(vault.last_activity, started);
+
+        vault.touch();
+        assert!(vault.last_activity > started);
+    }
+
+    #[test]
+    fn tampered_ciphertext_fails_to_open() {
+        let path = temp_vault_path("tamper");
+        Vault::create(&path, "correct horse", TEST_PARAMS, sample_contents()).unwrap();
+
+        let mut file: VaultFile = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+        let mut bytes = hex::decode(&file.ciphertext).unwrap();
+        bytes[0] ^= 1;
+        file.ciphertext = hex::encode(bytes);
+        fs::write(&path, serde_json::to_vec(&file).unwrap()).unwrap();
+
+        let mut vault = Vault::load(&path).unwrap();
+        assert!(vault.unlock("correct horse").is_err());
+    }
+
+    #[test]
+    fn idle_timeout_locks() {
+        let path = temp_vault_path("idle");
+        let mut vault =
+            Vault::create(&path, "correct horse", TEST_PARAMS, sample_contents()).unwrap();
+        let later = Instant::now() + Duration::from_secs(DEFAULT_IDLE_TIMEOUT_SECS + 1);
+        assert!(!vault.is_idle(Instant::now()));
+        assert!(vault.is_idle(later));
+
+        vault.set_idle_timeout(0).unwrap();
+        assert!(!vault.is_idle(later));
+    }
+
+    #[test]
+    fn short_passphrase_is_rejected() {
+        let path = temp_vault_path("short");
+        assert!(matches!(
+            Vault::create(&path, "short", TEST_PARAMS, VaultContents::default()),
+            Err(VaultError::WeakPassphrase)
+        ));
+    }
+}
@@ -0,0 +1,245 @@
+//! Wallet keypair management
+//!
+//! Ed25519 keypairs used to authenticate against Haunt. Signing keys live
+//! only in the Rust backend, encrypted in the [vault](crate::vault); the
+//! webview receives public keys and signatures, never the secret. Signing
+//! commands use the active [identity](crate::identity).
+
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use rand_core::OsRng;
+use serde::Serialize;
+use tauri::State;
+use zeroize::Zeroizing;
+
+use crate::vault::{VaultError, VaultState};
+
+/// Errors produced by wallet operations
+#[derive(Debug, thiserror::Error)]
+pub enum WalletError {
+    #[error("Invalid private key format. Expected 64 hex characters.")]
+    InvalidSecretKey,
+    #[error("Invalid public key")]
+    InvalidPublicKey,
+    #[error("Invalid signature")]
+    InvalidSignature,
+    #[error("No active identity")]
+    NoWallet,
+    #[error(transparent)]
+    Vault(#[from] VaultError),
+}
+
+/// An ed25519 keypair
+pub struct Keypair {
+    signing_key: SigningKey,
+}
+
+impl Keypair {
+    /// Generate a new keypair from the OS random number generator
+    pub fn generate() -> Self {
+        Self {
+            signing_key: SigningKey::generate(&mut OsRng),
+        }
+    }
+
+    /// Build a keypair from a 32-byte secret seed encoded as hex
+    pub fn from_secret_hex(secret: &str) -> Result<Self, WalletError> {
+        let mut seed = Zeroizing::new([0u8; 32]);
+        hex::decode_to_slice(secret.trim(), seed.as_mut_slice())
+            .map_err(|_| WalletError::InvalidSecretKey)?;
+
+        Ok(Self {
+            signing_key: SigningKey::from_bytes(&seed),
+        })
+    }
+
+    /// Hex-encoded secret seed, for writing to storage
+    pub fn secret_hex(&self) -> Zeroizing<String> {
+        Zeroizing::new(hex::encode(self.signing_key.as_bytes()))
+    }
+
+    /// Hex-encoded public key
+    pub fn public_key_hex(&self) -> String {
+        hex::encode(self.signing_key.verifying_key().as_bytes())
+    }
+
+    /// Sign a message, returning the hex-encoded signature
+    pub fn sign(&self, message: &[u8]) -> String {
+        hex::encode(self.signing_key.sign(message).to_bytes())
+    }
+}
+
+/// Derive the hex public key for a hex secret seed
+pub fn derive_public_key(secret: &str) -> Result<String, WalletError> {
+    Ok(Keypair::from_secret_hex(secret)?.public_key_hex())
+}
+
+/// Verify a hex signature over `message` against a hex public key
+pub fn verify(public_key: &str, message: &[u8], signature: &str) -> Result<bool, WalletError> {
+    let mut key_bytes = [0u8; 32];
+    hex::decode_to_slice(public_key.trim(), &mut key_bytes)
+        .map_err(|_| WalletError::InvalidPublicKey)?;
+    let verifying_key =
+        VerifyingKey::from_bytes(&key_bytes).map_err(|_| WalletError::InvalidPublicKey)?;
+
+    let mut sig_bytes = [0u8; 64];
+    hex::decode_to_slice(signature.trim(), &mut sig_bytes)
+        .map_err(|_| WalletError::InvalidSignature)?;
+    let signature = Signature::from_bytes(&sig_bytes);
+
+    Ok(verifying_key.verify(message, &signature).is_ok())
+}
+
+/// Signed challenge, shaped like the `/api/auth/verify` request body
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignedChallenge {
+    pub public_key: String,
+    pub challenge: String,
+    pub signature: String,
+    pub timestamp: u64,
+}
+
+/// Load the active identity's keypair from the unlocked vault
+pub fn active_keypair(vault: &VaultState) -> Result<Keypair, WalletError> {
+    vault
+        .read(|contents| {
+            contents
+                .active()
+                .and_then(|name| contents.key(name))
+                .map(Keypair::from_secret_hex)
+        })?
+        .ok_or(WalletError::NoWallet)?
+}
+
+/// Tauri command: Get the active public key, if the vault is unlocked
+#[tauri::command]
+pub fn wallet_public_key(vault: State<'_, VaultState>) -> Option<String> {
+    active_keypair(&vault)
+        .ok()
+        .map(|keypair| keypair.public_key_hex())
+}
+
+/// Tauri command: Derive the public key for a hex secret key without storing it
+///
+/// Lets the import dialog preview the identity a pasted key maps to.
+#[tauri::command]
+pub fn wallet_derive_public_key(secret_key: String) -> Result<String, String> {
+    derive_public_key(&Zeroizing::new(secret_key)).map_err(|e| e.to_string())
+}
+
+/// Tauri command: Sign an arbitrary UTF-8 message
+#[tauri::command]
+pub fn wallet_sign(vault: State<'_, VaultState>, message: String) -> Result<String, String> {
+    vault.touch();
+    let keypair = active_keypair(&vault).map_err(|e| e.to_string())?;
+    Ok(keypair.sign(message.as_bytes()))
+}
+
+/// Tauri command: Sign an `/api/auth/challenge` payload
+#[tauri::command]
+pub fn wallet_sign_challenge(
+    vault: State<'_, VaultState>,
+    challenge: String,
+    timestamp: u64,
+) -> Result<SignedChallenge, String> {
+    vault.touch();
+    let keypair = active_keypair(&vault).map_err(|e| e.to_string())?;
+    Ok(SignedChallenge {
+        public_key: keypair.public_key_hex(),
+        signature: keypair.sign(challenge.as_bytes()),
+        challenge,
+        timestamp,
+    })
+}
+
+/// Tauri command: Verify a signature against a public key
+#[tauri::command]
+pub fn wallet_verify(
+    public_key: String,
+    message: String,
+    signature: String,
+) -> Result<bool, String> {
+    verify(&public_key, message.as_bytes(), &signature).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test vectors from RFC 8032, section 7.1
+    const VECTORS: &[(&str, &str, &str, &str)] = &[
+        (
+            "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+            "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+            "",
+            "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b",
+        ),
+        (
+            "4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb",
+            "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c",
+            "72",
+            "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00",
+        ),
+        (
+            "c5aa8df43f9f837bedb7442f31dcb7b166d38535076f094b85ce3a2e0b4458f7",
+            "fc51cd8e6218a1a38da47ed00230f0580816ed13ba3303ac5deb911548908025",
+            "af82",
+            "6291d657deec24024827e69c3abe01a30ce548a284743a445e3680d7db5ac3ac18ff9b538d16f290ae67f760984dc6594a7c15e9716ed28dc027beceea1ec40a",
+        ),
+    ];
+
+    #[test]
+    fn rfc8032_vectors() {
+        for (secret, public, message, signature) in VECTORS {
+            let message = hex::decode(message).unwrap();
+            let keypair = Keypair::from_secret_hex(secret).unwrap();
+
+            assert_eq!(keypair.public_key_hex(), *public);
+            assert_eq!(derive_public_key(secret).unwrap(), *public);
+            assert_eq!(keypair.sign(&message), *signature);
+            assert!(verify(public, &message, signature).unwrap());
+        }
+    }
+
+    #[test]
+    fn sign_verify_round_trip() {
+        let keypair = Keypair::generate();
+        let challenge = b"haunt-challenge-1700000000000";
+        let signature = keypair.sign(challenge);
+
+        assert!(verify(&keypair.public_key_hex(), challenge, &signature).unwrap());
+        assert!(!verify(&keypair.public_key_hex(), b"tampered", &signature).unwrap());
+
+        let other = Keypair::generate();
+        assert!(!verify(&other.public_key_hex(), challenge, &signature).unwrap());
+    }
+
+    #[test]
+    fn secret_hex_round_trip() {
+        let keypair = Keypair::generate();
+        let restored = Keypair::from_secret_hex(&keypair.secret_hex()).unwrap();
+        assert_eq!(restored.public_key_hex(), keypair.public_key_hex());
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        assert!(matches!(
+            Keypair::from_secret_hex("abcd"),
+            Err(WalletError::InvalidSecretKey)
+        ));
+        assert!(matches!(
+            Keypair::from_secret_hex(&"zz".repeat(32)),
+            Err(WalletError::InvalidSecretKey)
+        ));
+        assert!(matches!(
+            verify("00", b"", &"00".repeat(64)),
+            Err(WalletError::InvalidPublicKey)
+        ));
+
+        let keypair = Keypair::generate();
+        assert!(matches!(
+            verify(&keypair.public_key_hex(), b"", "00"),
+            Err(WalletError::InvalidSignature)
+        ));
+    }
+}
@@ -17,6 +17,72 @@ export type SystemInfo = {
   family: string;
 };
 
+export type UpdateInfo = {
+  version: string;
+  currentVersion: string;
+  /** Publish date in Unix milliseconds */
+  date: number | null;
+  /** Release notes */
+  notes: string | null;
+};
+
+export type UpdateChannel = "stable" | "beta" | "nightly";
+
+export type UpdaterSettings = {
+  channel: UpdateChannel;
+  /** Manifest server used instead of the release server */
+  baseUrl: string | null;
+  /** Staged rollout bucket of this install, 0-99 */
+  bucket: number;
+  /** Version rolled back from, which isn't offered again */
+  skippedVersion: string | null;
+};
+
+export type UpdateStatus = {
+  update: UpdateInfo | null;
+  downloading: boolean;
+  downloaded: boolean;
+  installOnQuit: boolean;
+};
+
+export type UpdateHistoryEvent =
+  | "installing"
+  | "installed"
+  | "install_failed"
+  | "verified"
+  | "not_applied"
+  | "rollback_offered"
+  | "rollback_declined"
+  | "rolled_back"
+  | "rollback_failed";
+
+export type UpdateHistoryEntry = {
+  /** Unix milliseconds */
+  at: number;
+  event: UpdateHistoryEvent;
+  /** Version being installed, or rolled back to */
+  version: string;
+  fromVersion: string;
+  error?: string;
+};
+
+export type LaunchHealth = {
+  /** Version installed by the last update */
+  version: string;
+  previousVersion: string;
+  backup: string;
+  target: string;
+  /** Launches in a row that didn't load */
+  launches: number;
+  verified: boolean;
+};
+
+export type UpdateProgress = {
+  version: string;
+  downloaded: number;
+  total: number | null;
+};
+
 export type TauriState = {
   /** Whether running in Tauri desktop environment */
   isTauri: boolean;
@@ -92,20 +158,123 @@ export function useTauri() {
   }, []);
 
   /**
-   * Check for application updates
+   * Check for application updates; the update found is kept for download and install
    */
-  const checkForUpdates = useCallback(async (): Promise<boolean> => {
+  const checkForUpdates = useCallback(async (): Promise<UpdateInfo | null> => {
     const invoke = getTauriInvoke();
-    if (!invoke) return false;
+    if (!invoke) return null;
 
     try {
-      return (await invoke("check_for_updates")) as boolean;
+      return (await invoke("check_for_updates")) as UpdateInfo | null;
     } catch (err) {
       console.error("Failed to check for updates:", err);
-      return false;
+      return null;
     }
   }, []);
 
+  /**
+   * Get the update channel and rollout bucket
+   */
+  const getUpdaterSettings = useCallback(async (): Promise<UpdaterSettings | null> => {
+    const invoke = getTauriInvoke();
+    if (!invoke) return null;
+
+    return (await invoke("updater_get_settings")) as UpdaterSettings;
+  }, []);
+
+  /**
+   * Pick the update channel, and optionally a manifest server to test against
+   */
+  const setUpdaterSettings = useCallback(
+    async (channel: UpdateChannel, baseUrl: string | null = null): Promise<UpdaterSettings | null> => {
+      const invoke = getTauriInvoke();
+      if (!invoke) return null;
+
+      return (await invoke("updater_set_settings", { channel, baseUrl })) as UpdaterSettings;
+    },
+    []
+  );
+
+  /**
+   * Download the update found by the last check without installing it
+   */
+  const downloadUpdate = useCallback(async (): Promise<void> => {
+    const invoke = getTauriInvoke();
+    if (!invoke) return;
+
+    await invoke("update_download");
+  }, []);
+
+  /**
+   * Install the downloaded update when the app next quits
+   */
+  const setInstallOnQuit = useCallback(async (enabled: boolean): Promise<void> => {
+    const invoke = getTauriInvoke();
+    if (!invoke) return;
+
+    await invoke("update_set_install_on_quit", { enabled });
+  }, []);
+
+  /**
+   * Get the cached update and how far it has got
+   */
+  const getUpdateStatus = useCallback(async (): Promise<UpdateStatus | null> => {
+    const invoke = getTauriInvoke();
+    if (!invoke) return null;
+
+    return (await invoke("update_status")) as UpdateStatus;
+  }, []);
+
+  /**
+   * Get the update history, newest first
+   */
+  const getUpdateHistory = useCallback(async (limit?: number): Promise<UpdateHistoryEntry[]> => {
+    const invoke = getTauriInvoke();
+    if (!invoke) return [];
+
+    return (await invoke("update_history", { limit })) as UpdateHistoryEntry[];
+  }, []);
+
+  /**
+   * Get the health of the last installed update, if any
+   */
+  const getUpdateHealth = useCallback(async (): Promise<LaunchHealth | null> => {
+    const invoke = getTauriInvoke();
+    if (!invoke) return null;
+
+    return (await invoke("update_health")) as LaunchHealth | null;
+  }, []);
+
+  /**
+   * Go back to the version the last update replaced and restart
+   */
+  const rollbackUpdate = useCallback(async (): Promise<void> => {
+    const invoke = getTauriInvoke();
+    if (!invoke) return;
+
+    await invoke("update_rollback");
+  }, []);
+
+  /**
+   * Listen for update download progress
+   */
+  const onUpdateProgress = useCallback((handler: (progress: UpdateProgress) => void): (() => void) => {
+    const listen = getTauriListen();
+    if (!listen) return () => {};
+
+    let unlisten: (() => void) | null = null;
+
+    listen("update-progress", (event) => {
+      handler(event.payload as UpdateProgress);
+    }).then((fn) => {
+      unlisten = fn;
+    });
+
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, []);
+
   /**
    * Install update and restart
    */
@@ -194,6 +363,15 @@ export function useTauri() {
   return {
     ...state,
     checkForUpdates,
+    getUpdaterSettings,
+    setUpdaterSettings,
+    downloadUpdate,
+    setInstallOnQuit,
+    getUpdateStatus,
+    getUpdateHistory,
+    getUpdateHealth,
+    rollbackUpdate,
+    onUpdateProgress,
     installUpdate,
     showNotification,
     onDeepLink,